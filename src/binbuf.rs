@@ -33,6 +33,17 @@ impl BinBuf {
             filename
         }
     } 
+    pub fn from_bytes(filename : &str, buf : Vec<u8>) -> BinBuf {
+        // e.g. a member object of a static archive
+        BinBuf {
+            size : buf.len() as u32,
+            buf,
+            filename : String::from(filename),
+        }
+    }
+    pub fn name(&self) -> &str {
+        &self.filename
+    }
     pub fn idx_to_string(&self, idx : usize) -> String {
         // find a Null-terminated string at specific index in binbuf
         let mut s = String::new();
@@ -48,4 +59,62 @@ impl BinBuf {
     pub fn get_content(&self, idx : usize, sz : usize) -> Vec<u8> {
         self.buf[idx..idx+sz].to_vec()
     }
-}
\ No newline at end of file
+}
+
+/*
+ bounded reads into a byte slice, None when the value does not fit
+ (multi-byte values are little-endian, as is every ELF file we parse)
+ */
+pub fn u16_at(buf : &[u8], off : usize) -> Option<u16> {
+    buf.get(off..off.checked_add(2)?).map(|b| u16::from_le_bytes(b.try_into().unwrap()))
+}
+
+pub fn u32_at(buf : &[u8], off : usize) -> Option<u32> {
+    buf.get(off..off.checked_add(4)?).map(|b| u32::from_le_bytes(b.try_into().unwrap()))
+}
+
+pub fn u64_at(buf : &[u8], off : usize) -> Option<u64> {
+    buf.get(off..off.checked_add(8)?).map(|b| u64::from_le_bytes(b.try_into().unwrap()))
+}
+
+pub fn word_at(buf : &[u8], off : usize, size : usize, big_endian : bool) -> Option<usize> {
+    // a size or an offset of any width and byte order, e.g. in an archive symbol index
+    let bytes = buf.get(off..off.checked_add(size)?)?;
+    let mut v : u64 = 0;
+    for i in 0..size {
+        let b = if big_endian { bytes[i] } else { bytes[size - 1 - i] };
+        v = v << 8 | b as u64;
+    }
+    usize::try_from(v).ok()
+}
+
+pub fn c_str(bytes : &[u8]) -> String {
+    // up to the first Null, or all of bytes if there is none
+    let end = bytes.iter().position(|&c| c == 0).unwrap_or(bytes.len());
+    String::from_utf8_lossy(&bytes[..end]).into_owned()
+}
+
+pub fn c_str_at(buf : &[u8], off : usize) -> Option<String> {
+    // Null-terminated string at off, e.g. in a string table
+    buf.get(off..).map(c_str)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn test_bounded_reads() {
+        let buf = [0x01, 0x02, 0x03, 0x04, b'a', b'b', 0, b'c'];
+        assert_eq!(u16_at(&buf, 0), Some(0x0201));
+        assert_eq!(u32_at(&buf, 0), Some(0x04030201));
+        assert_eq!(u32_at(&buf, 5), None);
+        assert_eq!(u64_at(&buf, 1), None);
+        assert_eq!(u32_at(&buf, usize::MAX), None);
+        assert_eq!(word_at(&buf, 0, 2, true), Some(0x0102));
+        assert_eq!(word_at(&buf, 0, 2, false), Some(0x0201));
+        // a string runs to its Null, or to the end of the buffer without one
+        assert_eq!(c_str_at(&buf, 4).as_deref(), Some("ab"));
+        assert_eq!(c_str_at(&buf, 7).as_deref(), Some("c"));
+        assert_eq!(c_str_at(&buf, 9), None);
+    }
+}
@@ -1,15 +1,171 @@
 mod parser;
 mod binbuf;
+mod json;
 
 use parser::Parser;
+use parser::bloat::GroupBy;
+use parser::demangle::NameStyle;
+use parser::archive::Archive;
+use parser::validate;
+use parser::ldcache::LdCache;
+use parser::deps::LD_SO_CACHE;
+use parser::patch::{parse_addr, parse_bytes};
+use binbuf::BinBuf;
 use std::env;
+use colored::Colorize;
 use clap::{arg, command, value_parser, ArgAction, Command, Arg};
 
-fn main() {
-    let matches = Command::new("ExParser")
+fn cli() -> Command {
+    Command::new("ExParser")
         .version("1.0")
         .author("Squ17. <ler2sq@gmail.com>")
         .about("Executable Parser")
+        .subcommand_negates_reqs(true)
+        .subcommand(Command::new("disasm")
+            .about("Disassemble a symbol, a section or a vaddr range")
+            .arg(Arg::new("file")
+                .value_name("path")
+                .help("executable path")
+                .required(true))
+            .arg(Arg::new("sym")
+                .long("sym")
+                .value_name("name")
+                .help("Disassemble the symbol (recovered names work on stripped binaries)"))
+            .arg(Arg::new("section")
+                .long("section")
+                .value_name("name")
+                .help("Disassemble a whole section [default: .text]"))
+            .arg(Arg::new("range")
+                .long("range")
+                .value_name("start-end")
+                .help("Disassemble the vaddr range, e.g. 0x401000-0x401050")))
+        .subcommand(Command::new("diff")
+            .about("Compare two ELF files, exit 0 if identical, 1 if they differ, 2 on error")
+            .arg(Arg::new("old")
+                .value_name("old")
+                .help("old executable path")
+                .required(true))
+            .arg(Arg::new("new")
+                .value_name("new")
+                .help("new executable path")
+                .required(true))
+            .arg(Arg::new("json")
+                .long("json")
+                .action(ArgAction::SetTrue)
+                .help("Print the report in machine-readable JSON")))
+        .subcommand(Command::new("bloat")
+            .about("Rank sections and symbols by size and group them by namespace or source file")
+            .arg(Arg::new("file")
+                .value_name("path")
+                .help("executable path")
+                .required(true))
+            .arg(Arg::new("top")
+                .long("top")
+                .value_name("n")
+                .value_parser(value_parser!(usize))
+                .default_value("20")
+                .help("Number of symbols and groups to list"))
+            .arg(Arg::new("by")
+                .long("by")
+                .value_parser(["namespace", "file"])
+                .default_value("namespace")
+                .help("Group symbols by demangled namespace/crate or by DWARF source file"))
+            .arg(Arg::new("json")
+                .long("json")
+                .action(ArgAction::SetTrue)
+                .help("Print the report in machine-readable JSON")))
+        .subcommand(Command::new("validate")
+            .about("Check the structure of an ELF file, exit 0 if no errors, 1 otherwise")
+            .arg(Arg::new("file")
+                .value_name("path")
+                .help("executable path")
+                .required(true))
+            .arg(Arg::new("json")
+                .long("json")
+                .action(ArgAction::SetTrue)
+                .help("Print the findings in machine-readable JSON")))
+        .subcommand(Command::new("core")
+            .about("Show the threads, signal, command line and mappings of a core dump")
+            .arg(Arg::new("file")
+                .value_name("path")
+                .help("core file path")
+                .required(true))
+            .arg(Arg::new("read")
+                .long("read")
+                .value_name("vaddr")
+                .help("Hex dump the process memory at vaddr instead"))
+            .arg(Arg::new("len")
+                .long("len")
+                .value_name("n")
+                .value_parser(value_parser!(usize))
+                .default_value("64")
+                .help("Number of bytes to dump with --read")))
+        .subcommand(Command::new("addr2sym")
+            .about("Symbolize hex addresses read from stdin or a file as symbol+offset (section) [file:line]")
+            .arg(Arg::new("file")
+                .value_name("path")
+                .help("executable path")
+                .required(true))
+            .arg(Arg::new("input")
+                .long("input")
+                .value_name("path")
+                .help("Read the addresses from a file instead of stdin"))
+            .arg(Arg::new("bias")
+                .long("bias")
+                .value_name("addr")
+                .default_value("0")
+                .help("Load bias subtracted from every address, e.g. the base a PIE was mapped at"))
+            .arg(Arg::new("addresses")
+                .short('a')
+                .long("addresses")
+                .action(ArgAction::SetTrue)
+                .help("Print each input address before its symbol")))
+        .subcommand(Command::new("deps")
+            .about("Resolve shared library dependencies like ldd without running the file, exit 0 if all resolve, 1 otherwise")
+            .arg(Arg::new("file")
+                .value_name("path")
+                .help("executable path")
+                .required(true))
+            .arg(Arg::new("ldcache")
+                .long("ldcache")
+                .value_name("path")
+                .help("Search this ld.so.cache instead of /etc/ld.so.cache, e.g. one copied from another machine")))
+        .subcommand(Command::new("linkcheck")
+            .about("Check every undefined dynamic symbol against the exports of the other files, exit 0 if all bind, 1 otherwise, 2 on error")
+            .arg(Arg::new("file")
+                .value_name("path")
+                .help("executable path")
+                .required(true))
+            .arg(Arg::new("libs")
+                .value_name("lib")
+                .num_args(1..)
+                .help("Shared objects to check against in load order [default: resolved like deps]"))
+            .arg(Arg::new("ldcache")
+                .long("ldcache")
+                .value_name("path")
+                .help("Search this ld.so.cache instead of /etc/ld.so.cache, e.g. one copied from another machine")
+                .conflicts_with("libs")))
+        .subcommand(Command::new("patch")
+            .about("Overwrite bytes at a virtual address or symbol, in place or into a copy")
+            .arg(Arg::new("file")
+                .value_name("path")
+                .help("executable path")
+                .required(true))
+            .arg(Arg::new("at")
+                .long("at")
+                .value_name("vaddr|sym+off")
+                .required(true)
+                .help("Where to write, e.g. 0x401136, main or main+0x8"))
+            .arg(Arg::new("bytes")
+                .long("bytes")
+                .value_name("hex")
+                .required(true)
+                .help("Bytes to write, e.g. \"90 90\" or e800000000"))
+            .arg(Arg::new("out")
+                .short('o')
+                .long("out")
+                .value_name("path")
+                .help("Write the patched file here [default: overwrite the input]")))
         // .arg(arg!(-b --bin <VALUE> "executable path").required(true))
         // // .arg(arg!(-o --out <VALUE> "modified file writeback to ram").required(false))
         // .arg(arg!(-h --file-header "Display the ELF file header").required(false)).t
@@ -20,7 +176,11 @@ fn main() {
             .long("bin")
             .value_name("path")
             .help("executable path")
-            .required(true))
+            .required_unless_present("ldcache"))
+        .arg(Arg::new("ldcache")
+            .long("ldcache")
+            .value_name("path")
+            .help("Display an ld.so.cache the way ldconfig -p does"))
         .arg(Arg::new("elf-header")
             .short('e')
             .long("file-header")
@@ -45,16 +205,170 @@ fn main() {
             .action(ArgAction::SetTrue)
             .required(false)
             .help("Display the section header's layout"))
-        .get_matches();
+        .arg(Arg::new("symbols")
+            .long("symbols")
+            .action(ArgAction::SetTrue)
+            .required(false)
+            .help("Display the symbol table (recovered functions if stripped)"))
+        .arg(Arg::new("dyn-syms")
+            .long("dyn-syms")
+            .action(ArgAction::SetTrue)
+            .required(false)
+            .help("Display the dynamic symbol table"))
+        .arg(Arg::new("relocs")
+            .short('r')
+            .long("relocs")
+            .action(ArgAction::SetTrue)
+            .required(false)
+            .help("Display the relocations"))
+        .arg(Arg::new("checksec")
+            .long("checksec")
+            .action(ArgAction::SetTrue)
+            .required(false)
+            .help("Display the hardening features (RELRO, canary, NX, PIE, ...)"))
+        .arg(Arg::new("debug-info")
+            .long("debug-info")
+            .action(ArgAction::SetTrue)
+            .required(false)
+            .help("Display DWARF compilation units and functions"))
+        .arg(Arg::new("lookup")
+            .long("lookup")
+            .value_name("name|vaddr")
+            .required(false)
+            .help("Find a symbol by name or address in .symtab and .dynsym"))
+        .arg(Arg::new("addr2line")
+            .long("addr2line")
+            .value_name("vaddr")
+            .required(false)
+            .help("Map a virtual address to function and file:line using DWARF"))
+        .arg(Arg::new("eh-frame")
+            .long("eh-frame")
+            .action(ArgAction::SetTrue)
+            .required(false)
+            .help("Display .eh_frame CIEs/FDEs and check the .eh_frame_hdr search table"))
+        .arg(Arg::new("section-groups")
+            .short('g')
+            .long("section-groups")
+            .action(ArgAction::SetTrue)
+            .required(false)
+            .help("Display the section groups (COMDAT) of a relocatable object"))
+        .arg(Arg::new("modinfo")
+            .long("modinfo")
+            .action(ArgAction::SetTrue)
+            .required(false)
+            .help("Display .modinfo, __versions CRCs, this_module and the signature of a kernel module"))
+        .arg(Arg::new("overlay")
+            .long("overlay")
+            .action(ArgAction::SetTrue)
+            .required(false)
+            .help("Report gaps and data appended after the ELF (overlay)"))
+        .arg(Arg::new("extract-overlay")
+            .long("extract-overlay")
+            .value_name("out")
+            .required(false)
+            .help("Write the overlay to the given file"))
+        .arg(Arg::new("rebuild-sections")
+            .long("rebuild-sections")
+            .value_name("out")
+            .required(false)
+            .help("Write a copy with section headers rebuilt from PT_DYNAMIC when the file has none"))
+        .arg(Arg::new("archive-index")
+            .long("archive-index")
+            .action(ArgAction::SetTrue)
+            .required(false)
+            .help("Display which member of a static archive defines each symbol"))
+        .arg(Arg::new("json")
+            .long("json")
+            .action(ArgAction::SetTrue)
+            .required(false)
+            .help("Print reports in machine-readable JSON"))
+        .arg(Arg::new("demangle")
+            .short('C')
+            .long("demangle")
+            .value_name("style")
+            .num_args(0..=1)
+            .require_equals(true)
+            .default_missing_value("demangled")
+            .value_parser(["mangled", "demangled", "both"])
+            .global(true)
+            .help("Demangle C++/Rust symbol names, --demangle=both also keeps the mangled form"))
+}
+
+fn main() {
+    let matches = cli().get_matches();
 
+    match matches.subcommand() {
+        Some(("disasm", sub)) => { disasm(sub); return; }
+        Some(("diff", sub))   => diff(sub),
+        Some(("bloat", sub))  => { bloat(sub); return; }
+        Some(("core", sub))   => { core(sub); return; }
+        Some(("validate", sub)) => validate(sub),
+        Some(("addr2sym", sub)) => { addr2sym(sub); return; }
+        Some(("deps", sub))   => deps(sub),
+        Some(("linkcheck", sub)) => linkcheck(sub),
+        Some(("patch", sub))  => { patch(sub); return; }
+        _ => {}
+    }
+
+    if let Some(path) = matches.get_one::<String>("ldcache") {
+        match LdCache::open(path) {
+            Some(cache) => cache.show(path),
+            None => {
+                eprintln!("{} is not an ld.so.cache", path);
+                std::process::exit(1);
+            }
+        }
+        if !matches.contains_id("bin") {
+            return;
+        }
+    }
 
     let path = matches.get_one::<String>("bin").expect("required");
-    
-    let parser = Parser::new(&path);
-    
-    // if let Some(_) = matches. {
 
-    // }
+    if let Some(archive) = Archive::open(path) {
+        if matches.get_flag("archive-index") {
+            archive.show_index(name_style(&matches));
+        }
+        // every ELF member gets the same views as a plain file
+        let views = VIEWS.iter().any(|id| matches.get_flag(id)) || matches.contains_id("lookup") || matches.contains_id("addr2line") || matches.contains_id("extract-overlay");
+        for member in archive.members.iter().filter(|_| views) {
+            match archive.parser(member) {
+                Some(mut parser) => {
+                    parser.set_name_style(name_style(&matches));
+                    println!("\n{} {}({})", "File:".red(), path, member.name);
+                    show(&parser, &matches);
+                }
+                None => eprintln!("{}({}): not an ELF object, skipped", path, member.name),
+            }
+        }
+        return;
+    }
+    if matches.get_flag("archive-index") {
+        eprintln!("{} is not an ar archive", path);
+        std::process::exit(1);
+    }
+
+    let mut parser = Parser::new(path);
+    parser.set_name_style(name_style(&matches));
+    show(&parser, &matches);
+
+    if let Some(out) = matches.get_one::<String>("rebuild-sections") {
+        if !parser.sections_rebuilt() {
+            eprintln!("{} has usable section headers, nothing to rebuild", path);
+            std::process::exit(1);
+        }
+        if let Err(e) = parser.writeback(out, true) {
+            eprintln!("{}: {}", out, e);
+            std::process::exit(1);
+        }
+        println!("wrote {} with rebuilt section headers", out);
+    }
+}
+
+const VIEWS : [&str; 13] = ["elf-header", "program-headers", "section-headers", "section-layout", "symbols",
+                            "dyn-syms", "relocs", "section-groups", "modinfo", "overlay", "checksec", "debug-info", "eh-frame"];
+
+fn show(parser : &Parser, matches : &clap::ArgMatches) {
     if matches.get_flag("elf-header") {
         parser.show_header();
     }
@@ -67,13 +381,272 @@ fn main() {
     if matches.get_flag("section-layout") {
         parser.show_layout();
     }
+    if matches.get_flag("symbols") {
+        parser.show_symbols();
+    }
+    if matches.get_flag("dyn-syms") {
+        parser.show_dyn_syms();
+    }
+    if matches.get_flag("relocs") {
+        parser.show_relocs();
+    }
+    if matches.get_flag("section-groups") {
+        parser.show_groups();
+    }
+    if matches.get_flag("modinfo") {
+        parser.show_module();
+    }
+    if matches.get_flag("overlay") {
+        parser.show_overlay();
+    }
+    if let Some(out) = matches.get_one::<String>("extract-overlay") {
+        parser.extract_overlay(out);
+    }
+    if matches.get_flag("checksec") {
+        parser.show_checksec(matches.get_flag("json"));
+    }
+    if matches.get_flag("debug-info") {
+        parser.show_debug_info();
+    }
+    if matches.get_flag("eh-frame") {
+        parser.show_eh_frame();
+    }
+    if let Some(query) = matches.get_one::<String>("lookup") {
+        parser.show_lookup(query, parse_addr(query));
+    }
+    if let Some(vaddr) = matches.get_one::<String>("addr2line") {
+        match parse_addr(vaddr) {
+            Some(vaddr) => { parser.show_source_for_vaddr(vaddr); }
+            None        => eprintln!("invalid address: {}", vaddr),
+        }
+    }
     // parser.show_segments().show_sections().show_layout();
 
     // if let Some(out) = matches.get_one::<String>("out"){
     //     parser.writeback(out);
     // };
+}
+
+fn disasm(matches : &clap::ArgMatches) {
+    let path = matches.get_one::<String>("file").expect("required");
+    let mut parser = Parser::new(path);
+    parser.set_name_style(name_style(matches));
+
+    // relocatable objects are disassembled by offset into the section of the symbol
+    let (range, shndx) = if let Some(name) = matches.get_one::<String>("sym") {
+        (parser.symbol_range(name).ok_or(format!("no symbol {}", name)), parser.symbol_shndx(name))
+    } else if let Some(range) = matches.get_one::<String>("range") {
+        match range.split_once('-').map(|(s, e)| (parse_addr(s), parse_addr(e))) {
+            Some((Some(start), Some(end))) if start < end => (Ok((start, end)), None),
+            _ => (Err(format!("invalid range: {}", range)), None),
+        }
+    } else {
+        let name = matches.get_one::<String>("section").map(|s| s.as_str()).unwrap_or(".text");
+        (parser.section_range(name).ok_or(format!("no section {}", name)), parser.section_shndx(name))
+    };
+
+    if let Err(e) = range.and_then(|(start, end)| parser.show_disasm(start, end, shndx).map(|_| ())) {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+}
+
+fn diff(matches : &clap::ArgMatches) -> ! {
+    let old = matches.get_one::<String>("old").expect("required");
+    let new = matches.get_one::<String>("new").expect("required");
+
+    // keep exit status 1 for "differs", unreadable or non-ELF input must not look like a diff
+    let mut old = open_elf(old);
+    old.set_name_style(name_style(matches));
+    let diff = old.diff(&open_elf(new));
+    if matches.get_flag("json") {
+        println!("{}", diff.to_json());
+    } else {
+        diff.show();
+    }
+    std::process::exit(if diff.is_empty() { 0 } else { 1 });
+}
+
+fn bloat(matches : &clap::ArgMatches) {
+    let path = matches.get_one::<String>("file").expect("required");
+    let top = *matches.get_one::<usize>("top").expect("default");
+    let by = match matches.get_one::<String>("by").map(|s| s.as_str()) {
+        Some("file") => GroupBy::File,
+        _            => GroupBy::Namespace,
+    };
+    Parser::new(path)
+        .set_name_style(name_style(matches))
+        .show_bloat(top, by, matches.get_flag("json"));
+}
+
+fn validate(matches : &clap::ArgMatches) -> ! {
+    // only the raw bytes, Parser::new would trust the very headers being checked
+    let path = matches.get_one::<String>("file").expect("required");
+    let report = validate::validate(&BinBuf::new(path).buf);
+    if matches.get_flag("json") {
+        println!("{}", report.to_json());
+    } else {
+        report.show();
+    }
+    std::process::exit(if report.is_valid() { 0 } else { 1 });
+}
+
+fn core(matches : &clap::ArgMatches) {
+    let path = matches.get_one::<String>("file").expect("required");
+    let parser = Parser::new(path);
+
+    if !parser.is_core() {
+        eprintln!("{} is not a core file", path);
+        std::process::exit(1);
+    }
+    match matches.get_one::<String>("read") {
+        Some(addr) => {
+            if let Err(e) = parser.show_memory(addr, *matches.get_one::<usize>("len").expect("default")) {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        }
+        None => { parser.show_core(); }
+    }
+}
+
+fn addr2sym(matches : &clap::ArgMatches) {
+    let path = matches.get_one::<String>("file").expect("required");
+    let bias = matches.get_one::<String>("bias").expect("default");
+    let bias = match parse_addr(bias) {
+        Some(bias) => bias,
+        None => {
+            eprintln!("invalid load bias: {}", bias);
+            std::process::exit(1);
+        }
+    };
+    let mut parser = Parser::new(path);
+    parser.set_name_style(name_style(matches));
+
+    let addresses = matches.get_flag("addresses");
+    let result = match matches.get_one::<String>("input") {
+        Some(input) => match std::fs::File::open(input) {
+            Ok(f)  => parser.show_addr2sym(std::io::BufReader::new(f), bias, addresses),
+            Err(e) => Err(e),
+        },
+        None => parser.show_addr2sym(std::io::stdin().lock(), bias, addresses),
+    };
+    // a closed pipe (| head) is not an error
+    if let Err(e) = result {
+        if e.kind() != std::io::ErrorKind::BrokenPipe {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn deps(matches : &clap::ArgMatches) -> ! {
+    let path = matches.get_one::<String>("file").expect("required");
+    let mut parser = Parser::new(path);
+    parser.set_name_style(name_style(matches));
+
+    let report = parser.resolve_deps(ld_cache(matches).as_ref());
+    report.show(path);
+    std::process::exit(if report.is_complete() { 0 } else { 1 });
+}
+
+fn linkcheck(matches : &clap::ArgMatches) -> ! {
+    // exit 1 is for symbols that do not bind, a file that is not ELF at all is an error
+    let path = matches.get_one::<String>("file").expect("required");
+    let parser = open_elf(path);
+
+    let libs : Vec<Parser> = match matches.get_many::<String>("libs") {
+        Some(libs) => libs.map(|lib| open_elf(lib)).collect(),
+        None => {
+            let (rows, loaded) = parser.map_deps(ld_cache(matches).as_ref());
+            for row in rows.iter().filter(|r| r.path.is_none()) {
+                eprintln!("{} not found, its symbols are missing", row.name);
+            }
+            loaded.into_iter().map(|(_, lib)| lib).collect()
+        }
+    };
+    let report = parser.check_links(&libs);
+    report.show();
+    std::process::exit(if report.errors() == 0 { 0 } else { 1 });
+}
+
+fn open_elf(path : &str) -> Parser {
+    // for commands whose exit 1 means something else, unusable input exits 2
+    Parser::open(path).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(2);
+    })
+}
+
+fn ld_cache(matches : &clap::ArgMatches) -> Option<LdCache> {
+    // --ldcache must parse, the system one may be missing (musl, containers)
+    let path = matches.get_one::<String>("ldcache").map(|s| s.as_str()).unwrap_or(LD_SO_CACHE);
+    let cache = LdCache::open(path);
+    if cache.is_none() && matches.contains_id("ldcache") {
+        eprintln!("{} is not an ld.so.cache", path);
+        std::process::exit(1);
+    }
+    cache
+}
+
+fn patch(matches : &clap::ArgMatches) {
+    let path = matches.get_one::<String>("file").expect("required");
+    let out = matches.get_one::<String>("out").unwrap_or(path);
+    let bytes = matches.get_one::<String>("bytes").expect("required");
+    let bytes = match parse_bytes(bytes) {
+        Some(bytes) => bytes,
+        None => {
+            eprintln!("invalid hex bytes: {}", bytes);
+            std::process::exit(1);
+        }
+    };
+    let mut parser = Parser::new(path);
+
+    let at = matches.get_one::<String>("at").expect("required");
+    let patch = match parser.resolve_target(at).and_then(|vaddr| parser.patch(vaddr, &bytes)) {
+        Ok(patch) => patch,
+        Err(e) => {
+            eprintln!("{}: {}", path, e);
+            std::process::exit(1);
+        }
+    };
+    if let Err(e) = parser.writeback(out, false) {
+        eprintln!("{}: {}", out, e);
+        std::process::exit(1);
+    }
+    let hex = |b : &[u8]| b.iter().map(|b| format!("{:02x}", b)).collect::<Vec<_>>().join(" ");
+    println!("{:#x} (offset {:#x}): {} -> {}", patch.vaddr, patch.offset, hex(&patch.old), hex(&patch.new).green());
+    println!("wrote {}", out);
+}
+
+fn name_style(matches : &clap::ArgMatches) -> NameStyle {
+    match matches.get_one::<String>("demangle").map(|s| s.as_str()) {
+        Some("demangled") => NameStyle::Demangled,
+        Some("both")      => NameStyle::Both,
+        _                 => NameStyle::Mangled,
+    }
+}
 
-    // if let Some(fh) = matches.
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn test_ldcache_scope() {
+        // deps and linkcheck search through their own --ldcache, the root one only displays a cache
+        let args = |args : &[&str]| cli().try_get_matches_from(std::iter::once("ExParser").chain(args.iter().copied()));
+        let m = args(&["deps", "--ldcache", "other.cache", "test/testlink"]).unwrap();
+        let deps = m.subcommand_matches("deps").unwrap();
+        assert_eq!(deps.get_one::<String>("ldcache").map(|s| s.as_str()), Some("other.cache"));
+        assert!(args(&["linkcheck", "--ldcache", "other.cache", "test/testlink"]).is_ok());
+        // explicit libs are never looked up, a cache would be ignored
+        assert!(args(&["linkcheck", "--ldcache", "other.cache", "test/testlink", "test/libtestbase.so.1"]).is_err());
+        assert!(args(&["patch", "--ldcache", "other.cache", "--at", "main", "--bytes", "90", "test/testgot"]).is_err());
+        assert!(args(&["diff", "--ldcache", "other.cache", "test/test", "test/testgot"]).is_err());
 
-    // println!("Hello, world!");
+        let m = args(&["--ldcache", "other.cache"]).unwrap();
+        assert!(m.subcommand().is_none());
+        // no longer global, a root --ldcache does not reach deps
+        let m = args(&["--ldcache", "other.cache", "deps", "test/testlink"]).unwrap();
+        assert!(m.subcommand_matches("deps").unwrap().get_one::<String>("ldcache").is_none());
+    }
 }
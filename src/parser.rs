@@ -1,4 +1,24 @@
 pub mod elf;
+pub mod checksec;
+pub mod dwarf;
+pub mod ehframe;
+pub mod symbols;
+pub mod disasm;
+pub mod diff;
+pub mod bloat;
+pub mod demangle;
+pub mod archive;
+pub mod relobj;
+pub mod coredump;
+pub mod kmod;
+pub mod overlay;
+pub mod validate;
+pub mod segonly;
+pub mod addr2sym;
+pub mod ldcache;
+pub mod deps;
+pub mod linkcheck;
+pub mod patch;
 use colored::Colorize;
 
 use crate::binbuf::BinBuf;
@@ -6,20 +26,26 @@ use crate::parser::elf::elf_struct::Elf64Ehdr;
 use crate::parser::elf::elf_struct::Elf64Phdr;
 use crate::parser::elf::elf_struct::Elf64Shdr;
 use crate::parser::elf::elf_struct::Elf64Sym;
-use crate::parser::elf::dyntable::DynSymTab;
-use crate::parser::elf::dyntable::DynSymTables;
-use crate::parser::elf::symtable::SymTables;
-use crate::parser::elf::symtable::Symbol;
+use crate::parser::elf::elf_struct::Elf64Dyn;
+use crate::parser::elf::elf_struct::Elf64Rela;
+use crate::parser::elf::elf_struct::{SHT_NOBITS, SHF_ALLOC, SHT_RELA, SHT_SYMTAB, SHT_DYNSYM, SHN_XINDEX, STT_SECTION};
+use crate::parser::elf::rela::{Rela, RelaTable, RelaTables};
+use crate::parser::elf::dynamic::DynEntries;
+use crate::parser::elf::dynamic::{DT_NULL, DT_STRTAB, DT_STRSZ};
+use crate::parser::elf::segments::segtype::SegmentType;
+use crate::parser::elf::symtable::{Symbol, SymbolTable, SymSource, extended_shndx};
 use crate::parser::elf::segments::Segments;
 use crate::parser::elf::segments::Segment;
 use crate::parser::elf::sections::Sections;
 use crate::parser::elf::sections::Section;
 use crate::parser::elf::relocations::Relocations;
 use crate::parser::elf::relocations::Relocation;
+use crate::parser::demangle::NameStyle;
+use crate::parser::dwarf::DebugInfo;
 use std::borrow::BorrowMut;
 use std::mem;
+use std::cell::OnceCell;
 
-use self::elf::dyntable;
 use self::elf::symtable;
 
 pub struct Parser {
@@ -27,9 +53,14 @@ pub struct Parser {
     ehdr       : Elf64Ehdr,
     segments   : Segments,
     sections   : Sections,
-    dynsymtabs : DynSymTables,
-    symtables  : Option<SymTables>,
-    relocs     : Option<Relocations>
+    dynsym     : SymbolTable,
+    symtab     : Option<SymbolTable>,
+    recovered  : OnceCell<SymbolTable>,    /* functions of a stripped file, rebuilt on first use */
+    dwarf      : OnceCell<Option<DebugInfo>>,   /* .debug_* parsed on first use, None without .debug_info */
+    relocs     : Option<Relocations>,
+    dynamic    : Option<DynEntries>,
+    relas      : RelaTables,
+    names      : NameStyle,
 }
 
 /*
@@ -46,6 +77,9 @@ impl Parser {
     }
 
     pub fn show_sections(&self) -> &Self {
+        if self.sections.synthetic {
+            println!("{}", "no usable section headers, sections rebuilt from PT_DYNAMIC".yellow());
+        }
         self.sections.show_shdrs();
         self
     }
@@ -58,6 +92,12 @@ impl Parser {
     // NOTE: debug info and symbol will not be loaded, i.e. not display in segment scope
     pub fn show_layout(&self) -> &Self {
 
+        if self.segments.segs.is_empty() {
+            return self.show_section_layout();
+        }
+
+        let index = self.symbol_index();
+
         for seg in &self.segments.segs {
 
             let seg_start = seg.phdr.p_offset;
@@ -75,7 +115,16 @@ impl Parser {
 
                 // TODO: maybe unsound
                 if sec_start >= seg_start && sec_end <= seg_end {
-                    println!("\t{:<20} 0x{:x}-0x{:x}", sec.name.blue(), sec_start, sec_end );
+                    let syms = match sec.shdr.sh_flags & SHF_ALLOC {
+                        0 => 0,
+                        _ => index.in_range(sec.shdr.sh_addr, sec.shdr.sh_addr + sec.shdr.sh_size).len(),
+                    };
+                    if syms != 0 {
+                        let plural = if syms == 1 { "" } else { "s" };
+                        println!("\t{:<20} 0x{:x}-0x{:x} {} symbol{}", sec.name.blue(), sec_start, sec_end, syms, plural);
+                    } else {
+                        println!("\t{:<20} 0x{:x}-0x{:x}", sec.name.blue(), sec_start, sec_end );
+                    }
                 }
             }
             println!("{:<022} {}", "END".to_string().red(), end.yellow() );
@@ -87,7 +136,7 @@ impl Parser {
     
     fn get_name(&self, shdr : &Elf64Shdr) -> String {
         // get name of given shdr
-        let shstridx = self.ehdr.e_shstrndx as usize;
+        let shstridx = self.ehdr.shstrndx(&self.binbuf.buf);
         let offset = shdr.sh_name;
         let i = self.sections[shstridx].shdr.sh_offset + offset as u64;
 
@@ -107,6 +156,16 @@ impl Parser {
         return None;
     }
 
+    pub fn section_data(&self, sname : &str) -> Option<&[u8]> {
+        // file content of section by name, None for missing or NOBITS section
+        let sec = self.sections.get_section(sname)?;
+        if sec.shdr.sh_type == SHT_NOBITS {
+            return None;
+        }
+        let start = sec.shdr.sh_offset as usize;
+        self.binbuf.buf.get(start..start.checked_add(sec.shdr.sh_size as usize)?)
+    }
+
     pub fn show_magic(&self) -> &Self {
         for i in 0..0x10 {
             print!("{:02x} ", self.binbuf.buf[i]);
@@ -116,10 +175,33 @@ impl Parser {
     }
 
     pub fn new(filename : &str) -> Parser {
+        Self::from_binbuf(BinBuf::new(filename))
+    }
+
+    pub fn check_elf(buf : &[u8]) -> Result<(), String> {
+        // what from_binbuf takes for granted: a whole little-endian ELF64 header
+        if buf.len() < mem::size_of::<Elf64Ehdr>() {
+            return Err(format!("{:#x} bytes, too small for an ELF header", buf.len()));
+        }
+        if !buf.starts_with(b"\x7fELF") {
+            return Err("not an ELF file".to_string());
+        }
+        if buf[4] != 2 || buf[5] != 1 {
+            return Err(format!("EI_CLASS {} / EI_DATA {}, only little-endian ELF64 is supported", buf[4], buf[5]));
+        }
+        Ok(())
+    }
+
+    pub fn open(filename : &str) -> Result<Parser, String> {
+        // like new, but an unreadable or non-ELF file is an error for the caller to report
+        let buf = std::fs::read(filename).map_err(|e| format!("{}: {}", filename, e))?;
+        Self::check_elf(&buf).map_err(|e| format!("{}: {}", filename, e))?;
+        Ok(Self::from_binbuf(BinBuf::from_bytes(filename, buf)))
+    }
+
+    pub fn from_binbuf(binbuf : BinBuf) -> Parser {
         // TODO: split it to diff parts
 
-        let binbuf = BinBuf::new(filename);
-        
         let idx = 0x0;
         let ehdr = Elf64Ehdr::new(binbuf.buf[idx..].as_ref());
 
@@ -127,90 +209,48 @@ impl Parser {
         let mut idx = ehdr.e_phoff as usize;
         let mut phdrs = vec![];
 
-        for _ in 0..ehdr.e_phnum {
-            let phdr = Elf64Phdr::new(binbuf.buf[idx..].as_ref());
+        for _ in 0..ehdr.phnum(&binbuf.buf) {
+            // a table running past EOF keeps only the entries that are in the file
+            let phdr = match binbuf.buf.get(idx..).filter(|b| b.len() >= mem::size_of::<Elf64Phdr>()) {
+                Some(b) => Elf64Phdr::new(b),
+                None    => break,
+            };
             idx += mem::size_of::<Elf64Phdr>();
             phdrs.push(phdr);
         }
 
-        /* parse sections */
-        let mut idx = ehdr.e_shoff as usize;
-        let mut shdrs = vec![];
-
-        for _ in 0..ehdr.e_shnum {
-            let shdr = Elf64Shdr::new(binbuf.buf[idx..].as_ref());
-            idx += mem::size_of::<Elf64Shdr>();
-            shdrs.push(shdr);
-        }
-
-        
-        // 1. find section header string index 
-        // 2. get offset of shstrtab in binary
-        let shstridx = ehdr.e_shstrndx as usize;
-        let offset = shdrs[shstridx].sh_offset as usize;
-        let end = (shdrs[shstridx].sh_offset + shdrs[shstridx].sh_size) as usize;
-        
         let segments = Segments::new(phdrs);
-        let sections = Sections::new(shdrs, &binbuf.buf[offset..end], shstridx);
-        
-        /* parse .dynsym */
-        let mut dynsyms = vec![];
-
-        let dynsym_section = sections.get_section(".dynsym").unwrap();
-        let mut offset = dynsym_section.shdr.sh_offset as usize;
-
-        let dynsym_size : u64 = mem::size_of::<Elf64Sym>() as u64;
-        let num_of_sym = dynsym_section.shdr.sh_size / dynsym_size;
-
-        let sym_section = sections.get_section(".dynstr").unwrap();
-        let sym_start_offset = sym_section.shdr.sh_offset as usize;
-
-        for _ in 0..num_of_sym {
-            let sym = Elf64Sym::new(binbuf.buf[offset..].as_ref());
-            offset += mem::size_of::<Elf64Sym>();
-
-            // NOTE: sym.st_name is offset in strtab here(maybe make it more human readable?)
-            let string = binbuf.idx_to_string(sym.st_name as usize + sym_start_offset);
-            
-            dynsyms.push(DynSymTab{
-                sym,
-                str : string
-            });
-        }
-
-        /* parse symbol (if exist) */
-        let symtables;
-
-        if let Some(sym_section) = sections.get_section(".symtab"){
-
-            let mut symbols = vec![];
-            
-            let mut offset = sym_section.shdr.sh_offset as usize;
 
-            let sym_size : u64 = mem::size_of::<Elf64Sym>() as u64;
-            let num_of_sym = sym_section.shdr.sh_size / sym_size;
-    
-            let sym_str_section = sections.get_section(".strtab").unwrap();
-            let sym_start_offset = sym_str_section.shdr.sh_offset as usize;
+        /* parse sections, stripped or corrupt headers are rebuilt from PT_DYNAMIC */
+        let sections = if Self::section_headers_ok(&ehdr, &binbuf.buf) {
+            let mut idx = ehdr.e_shoff as usize;
+            let mut shdrs = vec![];
 
-            for _ in 0..num_of_sym {
-                let sym = Elf64Sym::new(binbuf.buf[offset..].as_ref());
-                offset += mem::size_of::<Elf64Sym>();
-    
-                // NOTE: sym.st_name is offset in strtab here(maybe make it more human readable?)
-                let string = binbuf.idx_to_string(sym.st_name as usize + sym_start_offset);
-                
-                symbols.push(Symbol::new(
-                    sym,
-                    string,
-                ));
+            for _ in 0..ehdr.shnum(&binbuf.buf) {
+                let shdr = Elf64Shdr::new(binbuf.buf[idx..].as_ref());
+                idx += mem::size_of::<Elf64Shdr>();
+                shdrs.push(shdr);
             }
 
-            symtables = Some(SymTables::new(symbols));
+            // 1. find section header string index 
+            // 2. get offset of shstrtab in binary
+            let shstridx = ehdr.shstrndx(&binbuf.buf);
+            let offset = shdrs[shstridx].sh_offset as usize;
+            let end = offset + shdrs[shstridx].sh_size as usize;
 
+            Sections::new(shdrs, &binbuf.buf[offset..end], shstridx)
         } else {
-            symtables = None;
-        }
+            Self::sections_from_dynamic(&binbuf, &ehdr, &segments)
+        };
+        
+        /* parse .dynsym (static executables and objects have none) and .symtab (if not stripped) */
+        let dynsym = sections.secs.iter()
+            .position(|s| s.shdr.sh_type == SHT_DYNSYM)
+            .and_then(|i| SymbolTable::from_section(&binbuf, &sections, i))
+            .unwrap_or_else(|| SymbolTable::new(vec![], SymSource::Dynsym, 0));
+        let symtab = sections.secs.iter()
+            .position(|s| s.shdr.sh_type == SHT_SYMTAB)
+            .and_then(|i| SymbolTable::from_section(&binbuf, &sections, i));
         
         /* parse got(if dynamic) TODO: test statically */
 
@@ -246,20 +286,118 @@ impl Parser {
         }
 
 
+        /* parse dynamic entries (if dynamic) */
+        let dynamic = Self::parse_dynamic(&binbuf, &segments);
+
+        /* parse .rela.* */
+        let relas = Self::parse_relas(&binbuf, &sections);
+
         Parser {  
             binbuf,
             ehdr,
             segments ,
             sections ,
-            dynsymtabs : DynSymTables::new( dynsyms),
-            symtables,
-            relocs
+            dynsym,
+            symtab,
+            recovered : OnceCell::new(),
+            dwarf     : OnceCell::new(),
+            relocs,
+            dynamic,
+            relas,
+            names : NameStyle::Mangled,
+        }
+    }
+
+    fn parse_relas(binbuf : &BinBuf, sections : &Sections) -> RelaTables {
+        // every SHT_RELA section, symbol names come from the linked symbol table and its strtab
+        let mut tables = vec![];
+        let buf = &binbuf.buf;
+
+        for sec in &sections.secs {
+            if sec.shdr.sh_type != SHT_RELA {
+                continue;
+            }
+            let symtab = sections.secs.get(sec.shdr.sh_link as usize).map(|s| s.shdr);
+            let strtab = symtab.and_then(|s| sections.secs.get(s.sh_link as usize)).map(|s| s.shdr);
+
+            let sym_name = |idx : u32| -> String {
+                let (symtab, strtab) = match (symtab, strtab) {
+                    (Some(symtab), Some(strtab)) if idx != 0 => (symtab, strtab),
+                    _ => return String::new(),
+                };
+                let off = (symtab.sh_offset + idx as u64 * mem::size_of::<Elf64Sym>() as u64) as usize;
+                if off + mem::size_of::<Elf64Sym>() > buf.len() {
+                    return String::new();
+                }
+                let sym = Elf64Sym::new(&buf[off..]);
+                if sym.st_type() == STT_SECTION {
+                    // section symbols have no name, show the section instead
+                    let shndx = match sym.st_shndx {
+                        SHN_XINDEX => extended_shndx(buf, sections, sec.shdr.sh_link as usize, idx as usize).unwrap_or(0),
+                        n          => n as usize,
+                    };
+                    return sections.secs.get(shndx).map(|s| s.name.trim_end_matches('\x00').to_string()).unwrap_or_default();
+                }
+                let off = (strtab.sh_offset + sym.st_name as u64) as usize;
+                if off >= buf.len() {
+                    return String::new();
+                }
+                binbuf.idx_to_string(off)
+            };
+
+            let mut relas = vec![];
+            let sz = mem::size_of::<Elf64Rela>();
+            let mut idx = sec.shdr.sh_offset as usize;
+            let end = ((sec.shdr.sh_offset + sec.shdr.sh_size) as usize).min(buf.len());
+
+            while idx + sz <= end {
+                let rela = Elf64Rela::new(&buf[idx..]);
+                idx += sz;
+                relas.push(Rela {
+                    sym_name : sym_name(rela.r_sym()),
+                    rela,
+                });
+            }
+
+            tables.push(RelaTable {
+                name   : sec.name.trim_end_matches('\x00').to_string(),
+                target : sec.shdr.sh_info,
+                relas,
+            });
         }
+
+        RelaTables::new(tables)
     }
-    pub fn writeback(&self, path : &String) {
+    fn parse_dynamic(binbuf : &BinBuf, segments : &Segments) -> Option<DynEntries> {
+        // walk PT_DYNAMIC until DT_NULL, dynstr is located by DT_STRTAB/DT_STRSZ
+        let seg = segments.get_segment(SegmentType::PT_DYNAMIC)?;
+
+        let mut dyns = vec![];
+        let mut idx = seg.phdr.p_offset as usize;
+        let end = (seg.phdr.p_offset + seg.phdr.p_filesz) as usize;
+        let sz = mem::size_of::<Elf64Dyn>();
+
+        while idx + sz <= end.min(binbuf.buf.len()) {
+            let d = Elf64Dyn::new(binbuf.buf[idx..].as_ref());
+            idx += sz;
+            if d.d_tag == DT_NULL {
+                break;
+            }
+            dyns.push(d);
+        }
+
+        let find = |tag| dyns.iter().find(|d| d.d_tag == tag).map(|d| d.d_val);
 
-        unimplemented!()
+        let dynstr = match (find(DT_STRTAB), find(DT_STRSZ)) {
+            (Some(addr), Some(size)) => segments
+                .vaddr_to_offset(addr)
+                .and_then(|off| binbuf.buf.get(off as usize..(off + size) as usize)),
+            _ => None,
+        };
+
+        Some(DynEntries::new(dyns, dynstr))
     }
+
     pub fn add_new_section(&mut self, section : Section) -> &Self {
         /* 
          1. adjust ElfXX_Ehdr->e_shoff
@@ -278,7 +416,7 @@ impl Parser {
         let shift = 0x1000;
         
         let phdr_start_offset = self.ehdr.e_phoff;
-        let phdr_size = self.ehdr.e_phnum as u64;
+        let phdr_size = self.segments.segs.len() as u64;
         
         /*
         |------------------|
@@ -345,23 +483,23 @@ impl Parser {
                 sec.shdr.sh_offset += shift;
             }
         }
-        for dynsym in &mut self.dynsymtabs.tables {
+        let shift_sym = |sym : &mut Symbol| {
             // st_value is an virtual address of value in memory
-            if dynsym.sym.st_value >= from {
-                dynsym.sym.st_value += shift;
+            if sym.sym.st_value >= from {
+                sym.sym.st_value += shift;
             }
-            // TODO: fini_array
-        }
+        };
+        // TODO: fini_array
+        self.dynsym.update(shift_sym);
 
         // NOTE: need rewrite here
-        if let Some(symtable) = &mut self.symtables {
-
-            for sym in &mut symtable.syms {
-                if sym.sym.st_value >= from {
-                    sym.sym.st_value += shift;
-                }
-            }
+        if let Some(symtable) = &mut self.symtab {
+            symtable.update(shift_sym);
+        }
+        if let Some(recovered) = self.recovered.get_mut() {
+            recovered.update(shift_sym);
         }
+        self.dwarf.take();
 
         self.ehdr.e_entry += shift;
 
@@ -404,4 +542,91 @@ mod tests {
         assert_eq!(parser.sections[2].shdr.sh_name, sec.shdr.sh_name);
         assert_eq!(parser.sections[2].shdr.sh_offset, sec.shdr.sh_offset);
     }
-}
\ No newline at end of file
+
+    fn extended_numbering_elf() -> Vec<u8> {
+        // e_phnum, e_shnum and e_shstrndx all escaped to section header 0, like a file with more than 0xff00 sections
+        let put = |buf : &mut Vec<u8>, v : u64, n : usize| buf.extend_from_slice(&v.to_le_bytes()[..n]);
+        let shstrtab = b"\0.text\0.shstrtab\0.symtab\0.strtab\0.symtab_shndx\0";
+        let strtab = b"\0main\0";
+        let (text, shstr, symtab, str, xindex, shoff) = (0x80u64, 0x88u64, 0xc0u64, 0xf0u64, 0xf8u64, 0x100u64);
+
+        let mut buf = b"\x7fELF\x02\x01\x01".to_vec();
+        buf.resize(16, 0);
+        put(&mut buf, 2, 2);                        /* ET_EXEC */
+        put(&mut buf, 62, 2);
+        put(&mut buf, 1, 4);
+        put(&mut buf, 0x400000 + text, 8);
+        put(&mut buf, 0x40, 8);
+        put(&mut buf, shoff, 8);
+        put(&mut buf, 0, 4);
+        for v in [64, 56, 0xffff, 64, 0, 0xffff] {  /* PN_XNUM, 0 sections, SHN_XINDEX */
+            put(&mut buf, v, 2);
+        }
+
+        // one PT_LOAD R+X over the whole file
+        for (v, n) in [(1, 4), (5, 4), (0, 8), (0x400000, 8), (0x400000, 8), (shoff + 6 * 64, 8), (shoff + 6 * 64, 8), (0x1000, 8)] {
+            put(&mut buf, v, n);
+        }
+        buf.resize(text as usize, 0);
+        buf.extend_from_slice(&[0xc3; 8]);
+        buf.extend_from_slice(shstrtab);
+        buf.resize(symtab as usize, 0);
+        buf.resize(symtab as usize + 24, 0);
+        for (v, n) in [(1, 4), (0x12, 1), (0, 1), (0xffff, 2), (0x400000 + text, 8), (8, 8)] {
+            put(&mut buf, v, n);
+        }
+        buf.resize(str as usize, 0);
+        buf.extend_from_slice(strtab);
+        buf.resize(xindex as usize, 0);
+        put(&mut buf, 0, 4);
+        put(&mut buf, 1, 4);
+
+        // name, type, flags, addr, offset, size, link, info, addralign, entsize
+        let shdrs = [
+            [0, 0, 0, 0, 0, 6, 2, 1, 0, 0],
+            [1, 1, 6, 0x400000 + text, text, 8, 0, 0, 16, 0],
+            [7, 3, 0, 0, shstr, shstrtab.len() as u64, 0, 0, 1, 0],
+            [17, 2, 0, 0, symtab, 48, 4, 1, 8, 24],
+            [25, 3, 0, 0, str, strtab.len() as u64, 0, 0, 1, 0],
+            [33, 18, 0, 0, xindex, 8, 3, 0, 4, 4],
+        ];
+        for shdr in shdrs {
+            for (i, v) in shdr.into_iter().enumerate() {
+                put(&mut buf, v, if i < 2 || i == 6 || i == 7 { 4 } else { 8 });
+            }
+        }
+        buf
+    }
+
+    #[test]
+    fn test_extended_numbering() {
+        let buf = extended_numbering_elf();
+        let ehdr = Elf64Ehdr::new(&buf);
+        assert_eq!((ehdr.phnum(&buf), ehdr.shnum(&buf), ehdr.shstrndx(&buf)), (1, 6, 2));
+
+        let parser = Parser::from_binbuf(BinBuf::from_bytes("xnum", buf.clone()));
+        assert_eq!(parser.segments.segs.len(), 1);
+        assert_eq!(parser.section_name(5), ".symtab_shndx");
+
+        // main is SHN_XINDEX, .symtab_shndx puts it in .text
+        let main = &parser.symtab.as_ref().unwrap().get(1).unwrap();
+        assert_eq!(main.str, "main");
+        assert_eq!(main.sym.st_shndx, SHN_XINDEX);
+        assert_eq!(parser.section_name(main.shndx), ".text");
+
+        let v = validate::validate(&buf);
+        assert!(v.is_valid(), "{:?}", v.findings);
+    }
+    #[test]
+    fn test_section_data_overflow() {
+        // a section whose offset + size wraps is missing, not a panic
+        let orig = Parser::new("test/test");
+        let idx = orig.sections.index_of(".comment").unwrap();
+        let mut buf = orig.binbuf.buf.clone();
+        let off = orig.ehdr.e_shoff as usize + idx * mem::size_of::<Elf64Shdr>() + 0x18;
+        buf[off..off + 8].copy_from_slice(&(u64::MAX - 4).to_le_bytes());
+        let parser = Parser::from_binbuf(BinBuf::from_bytes("wrapped", buf));
+        assert!(orig.section_data(".comment").is_some());
+        assert!(parser.section_data(".comment").is_none());
+    }
+}
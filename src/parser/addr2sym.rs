@@ -0,0 +1,147 @@
+use std::io::{self, BufRead, Write};
+
+use crate::parser::Parser;
+use crate::parser::dwarf::{DebugInfo, SourceLoc};
+use crate::parser::elf::symindex::SymbolIndex;
+
+pub struct Symbolized {
+    pub symbol  : Option<(String, u64)>,   /* name and offset of vaddr into it */
+    pub section : Option<String>,
+    pub source  : Option<SourceLoc>,       /* None without DWARF or when no line covers vaddr */
+}
+
+pub struct Symbolizer<'a> {
+    // tables are built once, symbolizing is then a few binary searches per address
+    parser : &'a Parser,
+    index  : SymbolIndex,
+    dwarf  : Option<&'a DebugInfo>,
+}
+
+impl<'a> Symbolizer<'a> {
+    pub fn new(parser : &'a Parser) -> Self {
+        Symbolizer { parser, index : parser.symbol_index(), dwarf : parser.debug_info() }
+    }
+
+    pub fn has_dwarf(&self) -> bool {
+        self.dwarf.is_some()
+    }
+
+    pub fn symbolize(&self, vaddr : u64) -> Symbolized {
+        let section = self.parser.sections.index_at(vaddr);
+
+        // an unsized symbol (asm label) runs until the next symbol of its section
+        let sym = self.index.containing(vaddr).or_else(|| {
+            self.index.prev(vaddr).filter(|s| s.size() == 0 && section.is_some() && self.parser.sections.index_at(s.start) == section)
+        });
+        let mut symbol = sym.map(|s| (s.name.clone(), vaddr - s.start));
+
+        // a stripped binary may still have debug info
+        if symbol.is_none() {
+            if let Some(f) = self.dwarf.and_then(|d| d.find_function(vaddr)) {
+                let low = f.ranges.iter().find(|&&(l, h)| l <= vaddr && vaddr < h).unwrap().0;
+                symbol = Some((f.linkage_name.clone().unwrap_or(f.name.clone()), vaddr - low));
+            }
+        }
+
+        Symbolized {
+            symbol,
+            section : section.map(|i| self.parser.section_name(i).to_string()),
+            source  : self.dwarf.and_then(|d| d.find_location(vaddr)),
+        }
+    }
+
+    pub fn format(&self, s : &Symbolized) -> String {
+        // symbol+offset (section) [file:line], the location only when there is DWARF
+        let mut line = match &s.symbol {
+            Some((name, 0))   => self.parser.display_name(name),
+            Some((name, off)) => format!("{}+{:#x}", self.parser.display_name(name), off),
+            None              => "??".to_string(),
+        };
+        line += &format!(" ({})", s.section.as_deref().unwrap_or("??"));
+        if self.has_dwarf() {
+            match &s.source {
+                Some(loc) => line += &format!(" [{}:{}]", loc.file, loc.line),
+                None      => line += " [??:0]",
+            }
+        }
+        line
+    }
+}
+
+pub fn parse_hex(s : &str) -> Option<u64> {
+    // addresses are hex like addr2line takes them, with or without 0x
+    let hex = s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")).unwrap_or(s);
+    u64::from_str_radix(hex, 16).ok()
+}
+
+impl Parser {
+    pub fn symbolizer(&self) -> Symbolizer<'_> {
+        Symbolizer::new(self)
+    }
+
+    pub fn show_addr2sym(&self, input : impl BufRead, bias : u64, addresses : bool) -> io::Result<()> {
+        // one output line per address token, runtime addresses minus the load bias of a PIE
+        let symbolizer = self.symbolizer();
+        let mut out = io::BufWriter::new(io::stdout().lock());
+
+        for line in input.lines() {
+            for token in line?.split_whitespace() {
+                if addresses {
+                    write!(out, "{}: ", token)?;
+                }
+                match parse_hex(token) {
+                    Some(addr) => writeln!(out, "{}", symbolizer.format(&symbolizer.symbolize(addr.wrapping_sub(bias))))?,
+                    None => {
+                        eprintln!("invalid address: {}", token);
+                        writeln!(out, "??")?;
+                    }
+                }
+            }
+        }
+        out.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn test_symbolize() {
+        // test/testdwarf is a PIE: add at 0x1129 (20 bytes), square at 0x113d, main at 0x114c
+        let parser = Parser::new("test/testdwarf");
+        let symbolizer = parser.symbolizer();
+        let line = |runtime : u64| symbolizer.format(&symbolizer.symbolize(runtime - 0x5555_5555_4000));
+
+        assert!(line(0x5555_5555_5129).starts_with("add (.text) [") && line(0x5555_5555_5129).ends_with("testdwarf.c:2]"));
+        assert!(line(0x5555_5555_5131).starts_with("add+0x8 (.text) ["));
+        assert!(line(0x5555_5555_514c).starts_with("main (.text) ["));
+        assert_eq!(line(0x5555_5555_4000), "?? (??) [??:0]");
+
+        // no DWARF, no location
+        let parser = Parser::new("test/testgot");
+        let symbolizer = parser.symbolizer();
+        let main = parser.symbols_named("main")[0].1.sym.st_value;
+        assert_eq!(symbolizer.format(&symbolizer.symbolize(main + 1)), "main+0x1 (.text)");
+        assert_eq!(parse_hex("401136"), Some(0x401136));
+        assert_eq!(parse_hex("0x401136"), Some(0x401136));
+        assert_eq!(parse_hex("main"), None);
+    }
+
+    #[test]
+    fn test_symbolize_matches_addr2line() {
+        // every address of .text gets the file:line --addr2line prints for it
+        let parser = Parser::new("test/testdwarf");
+        let symbolizer = parser.symbolizer();
+        let (start, end) = parser.section_range(".text").unwrap();
+        for addr in start..end {
+            let source = symbolizer.symbolize(addr).source.map(|loc| (loc.file, loc.line));
+            let addr2line = parser.source_for_vaddr(addr).map(|loc| (loc.file, loc.line));
+            assert_eq!(source, addr2line, "{:#x}", addr);
+        }
+        for name in ["add", "square", "main"] {
+            let addr = parser.symbols_named(name)[0].1.sym.st_value;
+            assert_eq!(parser.source_for_vaddr(addr).unwrap().function.as_deref(), Some(name));
+            assert_eq!(symbolizer.symbolize(addr).symbol, Some((name.to_string(), 0)));
+        }
+    }
+}
@@ -0,0 +1,243 @@
+use colored::Colorize;
+
+use crate::binbuf::{BinBuf, word_at, c_str_at};
+use crate::parser::Parser;
+use crate::parser::demangle::{display_name, NameStyle};
+use crate::parser::elf::elf_struct::{SHN_UNDEF, STB_GLOBAL, STB_WEAK};
+
+const AR_MAGIC    : &[u8] = b"!<arch>\n";
+const AR_HDR_SIZE : usize = 60;     /* name[16] date[12] uid[6] gid[6] mode[8] size[10] fmag[2] */
+const ELF_MAGIC   : &[u8] = b"\x7fELF";
+
+pub struct Member {
+    pub name   : String,
+    pub offset : usize,     /* of the member header, what the symbol index points to */
+    pub data   : Vec<u8>,
+}
+
+pub struct Archive {
+    pub path    : String,
+    pub members : Vec<Member>,
+    pub index   : Vec<(String, usize)>,     /* symbol and header offset of the member defining it */
+}
+
+impl Member {
+    pub fn is_elf(&self) -> bool {
+        self.data.starts_with(ELF_MAGIC)
+    }
+}
+
+impl Archive {
+    pub fn is_archive(buf : &[u8]) -> bool {
+        buf.starts_with(AR_MAGIC)
+    }
+
+    pub fn open(path : &str) -> Option<Archive> {
+        // None when path is not an ar archive
+        let binbuf = BinBuf::new(path);
+        Self::parse(path, &binbuf.buf)
+    }
+
+    pub fn parse(path : &str, buf : &[u8]) -> Option<Archive> {
+        if !Self::is_archive(buf) {
+            return None;
+        }
+
+        let mut members = vec![];
+        let mut index = vec![];
+        let mut long_names : &[u8] = &[];
+        let mut pos = AR_MAGIC.len();
+
+        while pos + AR_HDR_SIZE <= buf.len() {
+            let hdr = &buf[pos..pos + AR_HDR_SIZE];
+            if &hdr[58..60] != b"`\n" {
+                eprintln!("{}: bad member header at {:#x}", path, pos);
+                break;
+            }
+            let field = |range : std::ops::Range<usize>| String::from_utf8_lossy(&hdr[range]).trim_end().to_string();
+            let raw_name = field(0..16);
+            let size : usize = field(48..58).parse().ok()?;
+
+            let mut start = pos + AR_HDR_SIZE;
+            let end = start.checked_add(size).filter(|&e| e <= buf.len())?;
+            let next = end + (end & 1);     /* members are 2-byte aligned */
+
+            let name = if raw_name == "/" || raw_name == "/SYM64/" {
+                // GNU symbol index: big-endian count, member offsets, then names
+                let word = if raw_name == "/" { 4 } else { 8 };
+                index.extend(gnu_index(&buf[start..end], word).unwrap_or_default());
+                pos = next;
+                continue;
+            } else if raw_name == "//" {
+                // GNU long name table, entries end with "/\n"
+                long_names = &buf[start..end];
+                pos = next;
+                continue;
+            } else if let Some(len) = raw_name.strip_prefix("#1/") {
+                // BSD long name, stored in front of the data and counted in its size
+                let len : usize = len.parse().ok()?;
+                let name = buf.get(start..start + len)?;
+                start += len;
+                String::from_utf8_lossy(name).trim_end_matches('\x00').to_string()
+            } else if let Some(off) = raw_name.strip_prefix('/').and_then(|o| o.parse::<usize>().ok()) {
+                let rest = long_names.get(off..).unwrap_or_default();
+                let len = rest.iter().position(|&c| c == b'\n').unwrap_or(rest.len());
+                String::from_utf8_lossy(&rest[..len]).trim_end_matches('/').to_string()
+            } else {
+                raw_name.trim_end_matches('/').to_string()
+            };
+
+            if name.starts_with("__.SYMDEF") {
+                // BSD symbol index: ranlib entries then string table
+                let word = if name.starts_with("__.SYMDEF_64") { 8 } else { 4 };
+                index.extend(bsd_index(&buf[start..end], word).unwrap_or_default());
+                pos = next;
+                continue;
+            }
+
+            members.push(Member { name, offset : pos, data : buf[start..end].to_vec() });
+            pos = next;
+        }
+
+        Some(Archive { path : path.to_string(), members, index })
+    }
+
+    pub fn member_at(&self, offset : usize) -> Option<&Member> {
+        self.members.iter().find(|m| m.offset == offset)
+    }
+
+    pub fn parser(&self, member : &Member) -> Option<Parser> {
+        // the full parser over one member object, None for non-ELF members
+        if !member.is_elf() {
+            return None;
+        }
+        let name = format!("{}({})", self.path, member.name);
+        Some(Parser::from_binbuf(BinBuf::from_bytes(&name, member.data.clone())))
+    }
+
+    pub fn symbol_index(&self) -> Vec<(String, String)> {
+        // (symbol, member) from the archive index, or from the member symbol tables without one
+        if !self.index.is_empty() {
+            return self.index.iter()
+                .map(|(sym, off)| (sym.clone(), self.member_at(*off).map(|m| m.name.clone()).unwrap_or_else(|| format!("?@{:#x}", off))))
+                .collect();
+        }
+        let mut out = vec![];
+        for member in &self.members {
+            let parser = match self.parser(member) {
+                Some(parser) => parser,
+                None => continue,
+            };
+            if let Some(symtab) = &parser.symtab {
+                for s in symtab {
+                    let global = matches!(s.sym.st_bind(), STB_GLOBAL | STB_WEAK);
+                    if global && s.sym.st_shndx != SHN_UNDEF && !s.str.is_empty() {
+                        out.push((s.str.clone(), member.name.clone()));
+                    }
+                }
+            }
+        }
+        out
+    }
+
+    pub fn show_index(&self, style : NameStyle) -> &Self {
+        let index = self.symbol_index();
+        if self.index.is_empty() {
+            println!("{}", "no archive symbol index, defined globals of the members:".yellow());
+        }
+        println!("{} '{}' contains {} symbols:", "Archive index".red(), self.path.blue(), index.len());
+        let width = self.members.iter().map(|m| m.name.len()).max().unwrap_or(0).max(6);
+        println!("{:<width$}  {}", "Member".green(), "Symbol".blue(), width = width);
+        for (sym, member) in &index {
+            println!("{:<width$}  {}", member, display_name(sym, style), width = width);
+        }
+        self
+    }
+}
+
+fn gnu_index(data : &[u8], word : usize) -> Option<Vec<(String, usize)>> {
+    let count = word_at(data, 0, word, true)?;
+    let mut names = count.checked_add(1)?.checked_mul(word)?;
+    let mut out = vec![];
+    for i in 0..count {
+        let off = word_at(data, word * (i + 1), word, true)?;
+        let name = c_str_at(data, names)?;
+        names += data[names..].iter().position(|&c| c == 0)? + 1;
+        out.push((name, off));
+    }
+    Some(out)
+}
+
+fn bsd_index(data : &[u8], word : usize) -> Option<Vec<(String, usize)>> {
+    let ranlib_size = word_at(data, 0, word, false)?;
+    let strtab = ranlib_size.checked_add(2 * word)?;
+    let mut out = vec![];
+    for i in 0..ranlib_size / (2 * word) {
+        let entry = word + i * 2 * word;
+        let strx = word_at(data, entry, word, false)?;
+        let off = word_at(data, entry + word, word, false)?;
+        out.push((c_str_at(data, strtab.checked_add(strx)?)?, off));
+    }
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    /*
+    test/libtestar.a (GNU ar) and test/libtestar_bsd.a (llvm-ar --format=bsd) hold
+    testar.o and testar_with_a_long_member_name.o, built from the .c files of the same name
+     */
+    fn check(path : &str) {
+        let archive = Archive::open(path).unwrap();
+        let names : Vec<&str> = archive.members.iter().map(|m| m.name.as_str()).collect();
+        assert_eq!(names, ["testar.o", "testar_with_a_long_member_name.o"]);
+
+        let index = archive.symbol_index();
+        let find = |sym : &str| index.iter().find(|(s, _)| s == sym).map(|(_, m)| m.as_str());
+        assert_eq!(index.len(), 5);
+        assert_eq!(find("add"), Some("testar.o"));
+        assert_eq!(find("counter"), Some("testar.o"));
+        assert_eq!(find("sum3"), Some("testar_with_a_long_member_name.o"));
+        assert_eq!(find("twice"), None);
+
+        for member in &archive.members {
+            let parser = archive.parser(member).unwrap();
+            let syms = &parser.symtab.as_ref().unwrap();
+            assert!(syms.iter().any(|s| s.str == "add"));
+        }
+    }
+
+    #[test]
+    fn test_gnu_archive() {
+        check("test/libtestar.a");
+    }
+
+    #[test]
+    fn test_bsd_archive() {
+        check("test/libtestar_bsd.a");
+    }
+
+    #[test]
+    fn test_index_from_members() {
+        // an archive built without `s` has no index, members are searched instead
+        let mut archive = Archive::open("test/libtestar.a").unwrap();
+        let mut from_index = archive.symbol_index();
+        archive.index.clear();
+        let mut from_members = archive.symbol_index();
+        from_index.sort();
+        from_members.sort();
+        assert_eq!(from_index, from_members);
+        assert!(Archive::open("test/test").is_none());
+    }
+
+    #[test]
+    fn test_index_overflow() {
+        // symbol counts and ranlib sizes near usize::MAX are rejected, not wrapped
+        let huge = [0xff; 16];
+        assert_eq!(gnu_index(&huge, 8), None);
+        assert_eq!(gnu_index(&huge, 4), None);
+        assert_eq!(bsd_index(&huge, 8), None);
+        assert_eq!(word_at(&huge, usize::MAX, 8, true), None);
+    }
+}
@@ -0,0 +1,337 @@
+use colored::Colorize;
+use std::collections::{HashMap, HashSet};
+
+use crate::parser::Parser;
+use crate::json::json_str;
+use crate::parser::dwarf::DebugInfo;
+use crate::parser::elf::elf_struct::{PF_R, PF_W, PF_X, SHF_ALLOC, SHN_ABS, SHN_UNDEF, SHT_NOBITS};
+use crate::parser::elf::elf_struct::{STT_FUNC, STT_GNU_IFUNC, STT_OBJECT, STT_TLS};
+use crate::parser::elf::segments::segtype::SegmentType;
+
+#[derive(Debug)]
+pub struct SectionSize {
+    pub name      : String,
+    pub file_size : u64,            /* bytes in the file, 0 for NOBITS */
+    pub mem_size  : u64,            /* bytes mapped at runtime, 0 unless SHF_ALLOC */
+    pub segment   : Option<usize>,  /* index of the PT_LOAD holding it */
+}
+
+#[derive(Debug)]
+pub struct SymbolSize {
+    pub name    : String,
+    pub size    : u64,
+    pub section : String,
+    pub group   : String,           /* namespace or crate the symbol belongs to */
+    pub file    : Option<String>,   /* compilation unit from DWARF */
+}
+
+#[derive(Debug)]
+pub struct SegmentSize {
+    pub index     : usize,
+    pub flags     : String,
+    pub file_size : u64,
+    pub mem_size  : u64,
+}
+
+pub struct Bloat {
+    pub file_size : u64,
+    pub segments  : Vec<SegmentSize>,   /* PT_LOAD only */
+    pub sections  : Vec<SectionSize>,   /* largest first */
+    pub symbols   : Vec<SymbolSize>,    /* largest first */
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum GroupBy {
+    Namespace,
+    File,
+}
+
+impl Parser {
+    pub fn bloat(&self) -> Bloat {
+        let loads : Vec<usize> = (0..self.segments.segs.len())
+            .filter(|&i| self.segments.segs[i].seg_type() == SegmentType::PT_LOAD)
+            .collect();
+
+        let segments = loads.iter().map(|&i| {
+            let p = &self.segments.segs[i].phdr;
+            SegmentSize {
+                index     : i,
+                flags     : seg_flags(p.p_flags),
+                file_size : p.p_filesz,
+                mem_size  : p.p_memsz,
+            }
+        }).collect();
+
+        let mut sections : Vec<SectionSize> = self.sections.secs.iter().skip(1).map(|sec| {
+            let s = &sec.shdr;
+            let alloc = s.sh_flags & SHF_ALLOC != 0;
+            let segment = match alloc {
+                true  => loads.iter().copied().find(|&i| {
+                    let p = &self.segments.segs[i].phdr;
+                    p.p_vaddr <= s.sh_addr && s.sh_addr < p.p_vaddr + p.p_memsz.max(1)
+                }),
+                false => None,
+            };
+            SectionSize {
+                name      : sec.name.trim_end_matches('\x00').to_string(),
+                file_size : if s.sh_type == SHT_NOBITS { 0 } else { s.sh_size },
+                mem_size  : if alloc { s.sh_size } else { 0 },
+                segment,
+            }
+        }).collect();
+        sections.sort_by_key(|s| std::cmp::Reverse(s.file_size.max(s.mem_size)));
+
+        let mut symbols = self.sized_symbols();
+        symbols.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.name.cmp(&b.name)));
+
+        Bloat {
+            file_size : self.binbuf.buf.len() as u64,
+            segments,
+            sections,
+            symbols,
+        }
+    }
+
+    fn sized_symbols(&self) -> Vec<SymbolSize> {
+        // defined functions and objects with a size, aliases at the same address counted once
+        let syms : Vec<_> = self.symtab_or_recovered().iter().map(|s| (&s.sym, s.shndx, s.str.as_str())).collect();
+        let dwarf = self.debug_info();
+
+        let mut seen = HashSet::new();
+        let mut out = vec![];
+        for (sym, shndx, name) in syms {
+            let typed = matches!(sym.st_type(), STT_FUNC | STT_OBJECT | STT_TLS | STT_GNU_IFUNC);
+            if !typed || sym.st_size == 0 || sym.st_shndx == SHN_UNDEF || sym.st_shndx == SHN_ABS {
+                continue;
+            }
+            if !seen.insert((sym.st_value, sym.st_size, shndx)) {
+                continue;
+            }
+            let section = self.sections.secs.get(shndx)
+                .map(|s| s.name.trim_end_matches('\x00').to_string())
+                .unwrap_or_default();
+            out.push(SymbolSize {
+                name    : self.display_name(name),
+                size    : sym.st_size,
+                section,
+                group   : namespace(name),
+                file    : dwarf.and_then(|d| unit_name(d, sym.st_value)),
+            });
+        }
+        out
+    }
+
+    pub fn show_bloat(&self, top : usize, by : GroupBy, json : bool) -> &Self {
+        let bloat = self.bloat();
+        if json {
+            println!("{}", bloat.to_json(top, by));
+            return self;
+        }
+        bloat.show(top, by);
+        self
+    }
+}
+
+fn seg_flags(p_flags : u32) -> String {
+    [(PF_R, 'R'), (PF_W, 'W'), (PF_X, 'E')].iter()
+        .map(|&(f, c)| if p_flags & f != 0 { c } else { ' ' })
+        .collect()
+}
+
+fn unit_name(dwarf : &DebugInfo, addr : u64) -> Option<String> {
+    // name of the compilation unit whose ranges cover addr (code only, data has no ranges)
+    dwarf.units.iter()
+        .find(|u| u.ranges.iter().any(|&(l, h)| l <= addr && addr < h))
+        .and_then(|u| u.name.clone())
+}
+
+pub fn namespace(name : &str) -> String {
+    // outermost namespace (C++) or crate (Rust legacy and v0) of a mangled name
+    let ident = |s : &str, v0 : bool| -> Option<String> {
+        let digits = s.bytes().take_while(|c| c.is_ascii_digit()).count();
+        let len : usize = s[..digits].parse().ok()?;
+        let mut s = &s[digits..];
+        // v0 puts a '_' separator after the length
+        if v0 {
+            s = s.strip_prefix('_').unwrap_or(s);
+        }
+        s.get(..len).map(|id| id.to_string())
+    };
+
+    if let Some(rest) = name.strip_prefix("_ZN") {
+        let rest = rest.trim_start_matches(['r', 'V', 'K']);
+        if rest.starts_with("St") {
+            return "std".to_string();
+        }
+        if let Some(id) = ident(rest, false) {
+            // Rust legacy impls are named after the self type: _$LT$core..fmt..Arguments$u20$as$u20$...
+            let Some(ty) = id.strip_prefix("_$LT$").or_else(|| id.strip_prefix("$LT$")) else {
+                return id;
+            };
+            // a bare generic self type (<T as Trait>) says nothing, use the trait instead
+            let path = match ty.split_once("$u20$as$u20$") {
+                Some((ty, tr)) if !ty.contains("..") => tr,
+                _ => ty,
+            };
+            return path.split("..").next().unwrap_or(path).to_string();
+        }
+    } else if name.starts_with("_ZSt") {
+        return "std".to_string();
+    } else if let Some(rest) = name.strip_prefix("_R") {
+        // walk down nested paths, generic args and impl paths to the crate root
+        let mut rest = rest.trim_start_matches(|c : char| c.is_ascii_digit());
+        loop {
+            if let Some(r) = rest.strip_prefix('N') {
+                rest = r.get(1..).unwrap_or("");
+            } else if let Some(r) = rest.strip_prefix('I') {
+                rest = r;
+            } else if let Some(r) = rest.strip_prefix(['M', 'X']) {
+                rest = r.strip_prefix('s').and_then(|r| r.split_once('_')).map(|(_, r)| r).unwrap_or(r);
+            } else {
+                break;
+            }
+        }
+        if let Some(mut r) = rest.strip_prefix('C') {
+            if let Some(s) = r.strip_prefix('s') {
+                r = s.split_once('_').map(|(_, r)| r).unwrap_or(r);
+            }
+            if let Some(id) = ident(r, true) {
+                return id;
+            }
+        }
+        return "(rust)".to_string();
+    } else if name.starts_with("_Z") {
+        return "(global)".to_string();
+    }
+    "(c)".to_string()
+}
+
+impl Bloat {
+    pub fn groups(&self, by : GroupBy) -> Vec<(String, u64, usize)> {
+        // (group, total size, symbol count), largest first
+        let mut map : HashMap<&str, (u64, usize)> = HashMap::new();
+        for sym in &self.symbols {
+            let key = match by {
+                GroupBy::Namespace => sym.group.as_str(),
+                GroupBy::File      => sym.file.as_deref().unwrap_or("??"),
+            };
+            let e = map.entry(key).or_default();
+            e.0 += sym.size;
+            e.1 += 1;
+        }
+        let mut groups : Vec<_> = map.into_iter().map(|(k, (size, n))| (k.to_string(), size, n)).collect();
+        groups.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        groups
+    }
+
+    fn percent(&self, size : u64) -> f64 {
+        match self.file_size {
+            0 => 0.0,
+            n => size as f64 * 100.0 / n as f64,
+        }
+    }
+
+    pub fn show(&self, top : usize, by : GroupBy) {
+        println!("{}", "Segments".red());
+        println!("{:<12}{:<6}{:>12}{:>12}{:>12}", "Segment".blue(), "Flags".yellow(), "FileSize".green(), "MemSize".cyan(), "Sections".green());
+        for seg in &self.segments {
+            let in_sections : u64 = self.sections.iter().filter(|s| s.segment == Some(seg.index)).map(|s| s.mem_size).sum();
+            println!("{:<12}{:<6}{:>12}{:>12}{:>12}", format!("LOAD[{}]", seg.index), seg.flags, seg.file_size, seg.mem_size, in_sections);
+        }
+        println!();
+
+        println!("{}", "Sections".red());
+        println!("{:>12}{:>12}{:>8}  {:<10}Name", "FileSize".green(), "MemSize".cyan(), "File%".yellow(), "Segment".blue());
+        for sec in &self.sections {
+            let seg = sec.segment.map(|i| format!("LOAD[{}]", i)).unwrap_or("-".to_string());
+            println!("{:>12}{:>12}{:>7.2}%  {:<10}{}", sec.file_size, sec.mem_size, self.percent(sec.file_size), seg, sec.name);
+        }
+        let rest = self.file_size.saturating_sub(self.sections.iter().map(|s| s.file_size).sum());
+        println!("{:>12}{:>12}{:>7.2}%  {:<10}[headers and padding]", rest, "", self.percent(rest), "-");
+        println!();
+
+        println!("{}", format!("Symbols (top {} of {})", top.min(self.symbols.len()), self.symbols.len()).red());
+        println!("{:>10}  {:<20}{:<16}Name", "Size".green(), "Section".blue(), "Group".yellow());
+        for sym in self.symbols.iter().take(top) {
+            println!("{:>10}  {:<20}{:<16}{}", sym.size, sym.section, sym.group, sym.name.cyan());
+        }
+        println!();
+
+        let total : u64 = self.symbols.iter().map(|s| s.size).sum();
+        let title = match by {
+            GroupBy::Namespace => "By namespace",
+            GroupBy::File      => "By source file",
+        };
+        println!("{}", title.red());
+        println!("{:>10}{:>8}{:>8}  Name", "Size".green(), "Syms".blue(), "%".yellow());
+        for (name, size, n) in self.groups(by).iter().take(top) {
+            let pct = if total == 0 { 0.0 } else { *size as f64 * 100.0 / total as f64 };
+            println!("{:>10}{:>8}{:>7.2}%  {}", size, n, pct, name);
+        }
+    }
+
+    pub fn to_json(&self, top : usize, by : GroupBy) -> String {
+        let segments = self.segments.iter().map(|s| {
+            format!("{{\"index\":{},\"flags\":{},\"file_size\":{},\"mem_size\":{}}}",
+                s.index, json_str(s.flags.trim()), s.file_size, s.mem_size)
+        }).collect::<Vec<String>>();
+
+        let sections = self.sections.iter().map(|s| {
+            let seg = s.segment.map(|i| i.to_string()).unwrap_or("null".to_string());
+            format!("{{\"name\":{},\"file_size\":{},\"mem_size\":{},\"segment\":{}}}",
+                json_str(&s.name), s.file_size, s.mem_size, seg)
+        }).collect::<Vec<String>>();
+
+        let symbols = self.symbols.iter().take(top).map(|s| {
+            let file = s.file.as_deref().map(json_str).unwrap_or("null".to_string());
+            format!("{{\"name\":{},\"size\":{},\"section\":{},\"group\":{},\"file\":{}}}",
+                json_str(&s.name), s.size, json_str(&s.section), json_str(&s.group), file)
+        }).collect::<Vec<String>>();
+
+        let groups = self.groups(by).iter().map(|(name, size, n)| {
+            format!("{{\"name\":{},\"size\":{},\"symbols\":{}}}", json_str(name), size, n)
+        }).collect::<Vec<String>>();
+
+        format!("{{\"file_size\":{},\"segments\":[{}],\"sections\":[{}],\"symbols\":[{}],\"groups\":[{}]}}",
+            self.file_size,
+            segments.join(","),
+            sections.join(","),
+            symbols.join(","),
+            groups.join(","))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn test_namespace() {
+        assert_eq!(namespace("_ZN3std2io5stdio6_print17h0123456789abcdefE"), "std");
+        assert_eq!(namespace("_ZNK4core3fmt9Formatter3padE"), "core");
+        assert_eq!(namespace("_ZNSt6vectorIiSaIiEE9push_backEOi"), "std");
+        assert_eq!(namespace("_ZSt4cout"), "std");
+        assert_eq!(namespace("_Z3fooi"), "(global)");
+        assert_eq!(namespace("_RNvCs1234_7mycrate3foo"), "mycrate");
+        assert_eq!(namespace("_RNvNtCs9ltgdHTiPiY_4core3ptr13drop_in_place"), "core");
+        assert_eq!(namespace("_RINvMs0_NtCsjrHSEGnQ3l9_3std9backtraceNtB6_5Cache4withE"), "std");
+        assert_eq!(namespace("_ZN51_$LT$T$u20$as$u20$alloc..string..ToString$GT$9to_string17h0123456789abcdefE"), "alloc");
+        assert_eq!(namespace("_ZN64_$LT$core..str..Utf8Error$u20$as$u20$core..fmt..Debug$GT$3fmt17h0123456789abcdefE"), "core");
+        assert_eq!(namespace("main"), "(c)");
+    }
+    #[test]
+    fn test_bloat_testdwarf() {
+        let parser = Parser::new("test/testdwarf");
+        let bloat = parser.bloat();
+
+        let text = bloat.sections.iter().find(|s| s.name == ".text").unwrap();
+        assert!(text.segment.is_some() && text.file_size == text.mem_size);
+        let bss = bloat.sections.iter().find(|s| s.name == ".bss").unwrap();
+        assert_eq!(bss.file_size, 0);
+        assert!(bloat.sections.iter().filter(|s| s.name.starts_with(".debug")).all(|s| s.mem_size == 0 && s.segment.is_none()));
+
+        let main = bloat.symbols.iter().find(|s| s.name == "main").unwrap();
+        assert_eq!(main.section, ".text");
+        assert!(main.file.as_deref().is_some_and(|f| f.ends_with("testdwarf.c")));
+        assert!(bloat.symbols.windows(2).all(|w| w[0].size >= w[1].size));
+    }
+}
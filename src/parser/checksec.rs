@@ -0,0 +1,312 @@
+use colored::Colorize;
+
+use crate::parser::Parser;
+use crate::json::json_str;
+use crate::parser::elf::elf_struct::{ET_DYN, ET_EXEC, ET_REL, PF_X};
+use crate::parser::elf::dynamic::{DT_BIND_NOW, DT_FLAGS, DT_FLAGS_1, DT_RPATH, DT_RUNPATH};
+use crate::parser::elf::dynamic::{DF_BIND_NOW, DF_1_NOW, DF_1_PIE};
+use crate::parser::elf::notes::Notes;
+use crate::parser::elf::notes::{NT_GNU_PROPERTY_TYPE_0, GNU_PROPERTY_X86_FEATURE_1_AND};
+use crate::parser::elf::notes::{GNU_PROPERTY_X86_FEATURE_1_IBT, GNU_PROPERTY_X86_FEATURE_1_SHSTK};
+use crate::parser::elf::segments::segtype::SegmentType;
+
+#[derive(Debug, PartialEq)]
+pub enum Relro {
+    No,
+    Partial,
+    Full,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Pie {
+    No,         // ET_EXEC
+    Enabled,    // ET_DYN with an interpreter or DF_1_PIE
+    Dso,        // ET_DYN shared library
+    Rel,        // ET_REL object
+}
+
+#[derive(Debug)]
+pub struct CheckSec {
+    pub relro     : Relro,
+    pub canary    : bool,
+    pub nx        : bool,
+    pub pie       : Pie,
+    pub fortified : Vec<String>,    /* __*_chk functions referenced */
+    pub rpath     : Option<String>,
+    pub runpath   : Option<String>,
+    pub ibt       : bool,
+    pub shstk     : bool,
+    pub stripped  : bool,
+}
+
+enum Level {
+    Good,
+    Warn,
+    Bad,
+}
+
+impl Parser {
+    pub fn checksec(&self) -> CheckSec {
+
+        let has_segment = |t| self.segments.get_segment(t).is_some();
+
+        /* RELRO: GNU_RELRO makes it partial, BIND_NOW makes it full */
+        let bind_now = match &self.dynamic {
+            Some(dyns) => dyns.get(DT_BIND_NOW).is_some()
+                       || dyns.has_flag(DT_FLAGS, DF_BIND_NOW)
+                       || dyns.has_flag(DT_FLAGS_1, DF_1_NOW),
+            None => false,
+        };
+        let relro = match (has_segment(SegmentType::PT_GNU_RELRO), bind_now) {
+            (false, _)    => Relro::No,
+            (true, false) => Relro::Partial,
+            (true, true)  => Relro::Full,
+        };
+
+        /* NX: stack is executable if GNU_STACK is missing or has PF_X */
+        let nx = match self.segments.get_segment(SegmentType::PT_GNU_STACK) {
+            Some(seg) => seg.phdr.p_flags & PF_X == 0,
+            None      => false,
+        };
+
+        let pie = match self.ehdr.e_type {
+            ET_EXEC => Pie::No,
+            ET_REL  => Pie::Rel,
+            ET_DYN  => {
+                let flagged = self.dynamic.as_ref().is_some_and(|d| d.has_flag(DT_FLAGS_1, DF_1_PIE));
+                if flagged || has_segment(SegmentType::PT_INTERP) {
+                    Pie::Enabled
+                } else {
+                    Pie::Dso
+                }
+            }
+            _ => Pie::No,
+        };
+
+        /* canary and fortify are inferred from referenced libc symbols */
+        let names : Vec<&str> = self.symbol_tables().flatten().map(|s| s.str.as_str()).collect();
+
+        let canary = names.iter().any(|&n| n == "__stack_chk_fail" || n == "__stack_chk_guard");
+
+        let mut fortified : Vec<String> = names.iter()
+            .filter(|n| n.starts_with("__") && n.ends_with("_chk") && **n != "__chk_fail")
+            .map(|n| n.to_string())
+            .collect();
+        fortified.sort();
+        fortified.dedup();
+
+        let dyn_str = |tag| {
+            let dyns = self.dynamic.as_ref()?;
+            let strs : Vec<String> = dyns.get_all(tag).iter().filter_map(|e| e.val_str.clone()).collect();
+            if strs.is_empty() { None } else { Some(strs.join(":")) }
+        };
+
+        let (ibt, shstk) = self.x86_features();
+
+        CheckSec {
+            relro,
+            canary,
+            nx,
+            pie,
+            fortified,
+            rpath    : dyn_str(DT_RPATH),
+            runpath  : dyn_str(DT_RUNPATH),
+            ibt,
+            shstk,
+            stripped : self.sections.get_section(".symtab").is_none(),
+        }
+    }
+
+    fn x86_features(&self) -> (bool, bool) {
+        // CET bits from GNU_PROPERTY_X86_FEATURE_1_AND in .note.gnu.property
+        let (start, size) = if let Some(sec) = self.sections.get_section(".note.gnu.property") {
+            (sec.shdr.sh_offset, sec.shdr.sh_size)
+        } else if let Some(seg) = self.segments.get_segment(SegmentType::PT_GNU_PROPERTY) {
+            (seg.phdr.p_offset, seg.phdr.p_filesz)
+        } else {
+            return (false, false);
+        };
+
+        let buf = match start.checked_add(size).and_then(|end| self.binbuf.buf.get(start as usize..end as usize)) {
+            Some(buf) => buf,
+            None      => return (false, false),
+        };
+
+        let notes = Notes::new(buf);
+        let note = match notes.find("GNU", NT_GNU_PROPERTY_TYPE_0) {
+            Some(note) => note,
+            None       => return (false, false),
+        };
+
+        for (pr_type, data) in note.gnu_properties() {
+            if pr_type == GNU_PROPERTY_X86_FEATURE_1_AND && data.len() >= 4 {
+                let bits = u32::from_le_bytes(data[..4].try_into().unwrap());
+                return (
+                    bits & GNU_PROPERTY_X86_FEATURE_1_IBT != 0,
+                    bits & GNU_PROPERTY_X86_FEATURE_1_SHSTK != 0,
+                );
+            }
+        }
+        (false, false)
+    }
+
+    pub fn show_checksec(&self, json : bool) -> &Self {
+        let cs = self.checksec();
+
+        if json {
+            println!("{}", cs.to_json());
+        } else {
+            for (name, verdict, level) in cs.rows() {
+                let verdict = match level {
+                    Level::Good => verdict.green(),
+                    Level::Warn => verdict.yellow(),
+                    Level::Bad  => verdict.red(),
+                };
+                println!("{:<16}{}", name.blue(), verdict);
+            }
+        }
+        self
+    }
+}
+
+impl CheckSec {
+    fn rows(&self) -> Vec<(&'static str, String, Level)> {
+
+        let relro = match self.relro {
+            Relro::Full    => ("Full RELRO", Level::Good),
+            Relro::Partial => ("Partial RELRO", Level::Warn),
+            Relro::No      => ("No RELRO", Level::Bad),
+        };
+        let pie = match self.pie {
+            Pie::Enabled => ("PIE enabled", Level::Good),
+            Pie::Dso => ("DSO", Level::Good),
+            Pie::Rel => ("REL", Level::Warn),
+            Pie::No  => ("No PIE", Level::Bad),
+        };
+        let path = |p : &Option<String>| match p {
+            Some(p) => (p.clone(), Level::Bad),
+            None    => ("No".to_string(), Level::Good),
+        };
+        let (rpath, rpath_level) = path(&self.rpath);
+        let (runpath, runpath_level) = path(&self.runpath);
+
+        let yes_no = |b : bool, yes : &str, no : &str| {
+            if b { (yes.to_string(), Level::Good) } else { (no.to_string(), Level::Bad) }
+        };
+        let (canary, canary_level) = yes_no(self.canary, "Canary found", "No canary found");
+        let (nx, nx_level) = yes_no(self.nx, "NX enabled", "NX disabled");
+        let (ibt, ibt_level) = yes_no(self.ibt, "Enabled", "Disabled");
+        let (shstk, shstk_level) = yes_no(self.shstk, "Enabled", "Disabled");
+
+        let fortify = if self.fortified.is_empty() {
+            ("No".to_string(), Level::Bad)
+        } else {
+            (format!("Yes ({} fortified)", self.fortified.len()), Level::Good)
+        };
+        let stripped = if self.stripped {
+            ("Yes".to_string(), Level::Warn)
+        } else {
+            ("No".to_string(), Level::Good)
+        };
+
+        vec![
+            ("RELRO",        relro.0.to_string(), relro.1),
+            ("Stack Canary", canary, canary_level),
+            ("NX",           nx, nx_level),
+            ("PIE",          pie.0.to_string(), pie.1),
+            ("FORTIFY",      fortify.0, fortify.1),
+            ("RPATH",        rpath, rpath_level),
+            ("RUNPATH",      runpath, runpath_level),
+            ("CET IBT",      ibt, ibt_level),
+            ("CET SHSTK",    shstk, shstk_level),
+            ("Stripped",     stripped.0, stripped.1),
+        ]
+    }
+
+    pub fn to_json(&self) -> String {
+        let relro = match self.relro {
+            Relro::No      => "no",
+            Relro::Partial => "partial",
+            Relro::Full    => "full",
+        };
+        let pie = match self.pie {
+            Pie::No  => "no",
+            Pie::Enabled => "pie",
+            Pie::Dso => "dso",
+            Pie::Rel => "rel",
+        };
+        let opt = |p : &Option<String>| match p {
+            Some(p) => json_str(p),
+            None    => "null".to_string(),
+        };
+        let fortified : Vec<String> = self.fortified.iter().map(|f| json_str(f)).collect();
+
+        format!(
+            "{{\"relro\":\"{}\",\"canary\":{},\"nx\":{},\"pie\":\"{}\",\"fortify\":{},\"fortified\":[{}],\
+             \"rpath\":{},\"runpath\":{},\"ibt\":{},\"shstk\":{},\"stripped\":{}}}",
+            relro,
+            self.canary,
+            self.nx,
+            pie,
+            !self.fortified.is_empty(),
+            fortified.join(","),
+            opt(&self.rpath),
+            opt(&self.runpath),
+            self.ibt,
+            self.shstk,
+            self.stripped,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::binbuf::BinBuf;
+    #[test]
+    fn test_checksec_testgot() {
+        /*
+        testgot is a non-pie executable built with IBT/SHSTK notes and partial relro
+         */
+        let parser = Parser::new("test/testgot");
+        let cs = parser.checksec();
+        assert_eq!(cs.pie, Pie::No);
+        assert_eq!(cs.relro, Relro::Partial);
+        assert!(cs.nx);
+        assert!(cs.ibt);
+        assert!(cs.shstk);
+        assert!(!cs.stripped);
+        assert!(cs.rpath.is_none() && cs.runpath.is_none());
+    }
+    #[test]
+    fn test_checksec_ls() {
+        let parser = Parser::new("/bin/ls");
+        let cs = parser.checksec();
+        assert_eq!(cs.pie, Pie::Enabled);
+        assert!(cs.canary);
+        assert!(cs.stripped);
+    }
+    #[test]
+    fn test_checksec_json() {
+        let parser = Parser::new("test/testgot");
+        let json = parser.checksec().to_json();
+        assert!(json.starts_with("{\"relro\":\"partial\""));
+        assert!(json.contains("\"stripped\":false"));
+    }
+    #[test]
+    fn test_checksec_bad_property_note() {
+        // without section headers PT_GNU_PROPERTY is read, one whose end wraps means no CET notes
+        let orig = Parser::new("test/testgot");
+        let mut buf = orig.binbuf.buf.clone();
+        buf[0x28..0x30].fill(0);
+        buf[0x3c..0x40].fill(0);
+        let stripped = Parser::from_binbuf(BinBuf::from_bytes("noshdrs", buf.clone())).checksec();
+        assert!(stripped.ibt && stripped.shstk);
+
+        let i = orig.segments.iter().position(|s| s.seg_type() == SegmentType::PT_GNU_PROPERTY).unwrap();
+        let off = orig.ehdr.e_phoff as usize + i * 56 + 0x8;
+        buf[off..off + 8].copy_from_slice(&(u64::MAX - 4).to_le_bytes());
+        let cs = Parser::from_binbuf(BinBuf::from_bytes("badnote", buf)).checksec();
+        assert!(!cs.ibt && !cs.shstk);
+    }
+}
@@ -0,0 +1,259 @@
+use colored::Colorize;
+
+use crate::binbuf::c_str_at;
+use crate::parser::Parser;
+use crate::parser::patch::parse_addr;
+use crate::parser::elf::elf_struct::{ET_CORE, EM_X86_64, EM_AARCH64, EM_RISCV};
+use crate::parser::elf::notes::{Note, Notes, FileMapping, PrPsInfo, PrStatus, SigInfo};
+use crate::parser::elf::notes::{NT_PRSTATUS, NT_PRPSINFO, NT_AUXV, NT_SIGINFO, NT_FILE};
+use crate::parser::elf::notes::{AT_PHDR, AT_PAGESZ, AT_BASE, AT_ENTRY, AT_EXECFN};
+use crate::parser::elf::segments::segtype::SegmentType;
+
+/* elf_gregset_t of each machine, in pr_reg order */
+const X86_64_REGS : [&str; 27] = [
+    "r15", "r14", "r13", "r12", "rbp", "rbx", "r11", "r10", "r9", "r8", "rax", "rcx", "rdx", "rsi", "rdi",
+    "orig_rax", "rip", "cs", "eflags", "rsp", "ss", "fs_base", "gs_base", "ds", "es", "fs", "gs",
+];
+const AARCH64_REGS : [&str; 34] = [
+    "x0", "x1", "x2", "x3", "x4", "x5", "x6", "x7", "x8", "x9", "x10", "x11", "x12", "x13", "x14", "x15",
+    "x16", "x17", "x18", "x19", "x20", "x21", "x22", "x23", "x24", "x25", "x26", "x27", "x28", "x29", "x30",
+    "sp", "pc", "pstate",
+];
+const RISCV_REGS : [&str; 32] = [
+    "pc", "ra", "sp", "gp", "tp", "t0", "t1", "t2", "s0", "s1", "a0", "a1", "a2", "a3", "a4", "a5",
+    "a6", "a7", "s2", "s3", "s4", "s5", "s6", "s7", "s8", "s9", "s10", "s11", "t3", "t4", "t5", "t6",
+];
+
+pub fn signal_name(signo : i32) -> String {
+    const NAMES : [&str; 31] = [
+        "SIGHUP", "SIGINT", "SIGQUIT", "SIGILL", "SIGTRAP", "SIGABRT", "SIGBUS", "SIGFPE", "SIGKILL", "SIGUSR1",
+        "SIGSEGV", "SIGUSR2", "SIGPIPE", "SIGALRM", "SIGTERM", "SIGSTKFLT", "SIGCHLD", "SIGCONT", "SIGSTOP", "SIGTSTP",
+        "SIGTTIN", "SIGTTOU", "SIGURG", "SIGXCPU", "SIGXFSZ", "SIGVTALRM", "SIGPROF", "SIGWINCH", "SIGIO", "SIGPWR",
+        "SIGSYS",
+    ];
+    match usize::try_from(signo).ok().and_then(|n| n.checked_sub(1)).and_then(|n| NAMES.get(n)) {
+        Some(name) => name.to_string(),
+        None       => format!("signal {}", signo),
+    }
+}
+
+impl Parser {
+    pub fn is_core(&self) -> bool {
+        self.ehdr.e_type == ET_CORE
+    }
+
+    pub fn core_notes(&self) -> Vec<Note> {
+        // every note of every PT_NOTE segment, in file order
+        let mut notes = vec![];
+        for seg in &self.segments.segs {
+            if seg.seg_type() != SegmentType::PT_NOTE {
+                continue;
+            }
+            let start = seg.phdr.p_offset as usize;
+            if let Some(buf) = self.binbuf.buf.get(start..start + seg.phdr.p_filesz as usize) {
+                notes.extend(Notes::new(buf).notes);
+            }
+        }
+        notes
+    }
+
+    pub fn threads(&self) -> Vec<PrStatus> {
+        // the kernel writes the crashing thread first
+        self.core_notes().iter().filter(|n| n.n_type == NT_PRSTATUS).filter_map(|n| n.prstatus()).collect()
+    }
+
+    pub fn psinfo(&self) -> Option<PrPsInfo> {
+        self.core_notes().iter().find(|n| n.n_type == NT_PRPSINFO)?.prpsinfo()
+    }
+
+    pub fn siginfo(&self) -> Option<SigInfo> {
+        self.core_notes().iter().find(|n| n.n_type == NT_SIGINFO)?.siginfo()
+    }
+
+    pub fn auxv(&self) -> Vec<(u64, u64)> {
+        self.core_notes().iter().find(|n| n.n_type == NT_AUXV).map(|n| n.auxv()).unwrap_or_default()
+    }
+
+    pub fn mapped_files(&self) -> Vec<FileMapping> {
+        self.core_notes().iter().find(|n| n.n_type == NT_FILE).map(|n| n.file_mappings()).unwrap_or_default()
+    }
+
+    pub fn read_memory(&self, vaddr : u64, len : usize) -> Option<&[u8]> {
+        // only what was dumped, file-backed pages are usually left out of the core
+        let start = self.segments.vaddr_to_offset(vaddr)?;
+        if len > 1 {
+            let end = self.segments.vaddr_to_offset(vaddr.checked_add(len as u64 - 1)?)?;
+            if end != start + len as u64 - 1 {
+                return None;
+            }
+        }
+        self.binbuf.buf.get(start as usize..(start as usize).checked_add(len)?)
+    }
+
+    fn read_c_str(&self, vaddr : u64) -> Option<String> {
+        let start = self.segments.vaddr_to_offset(vaddr)? as usize;
+        c_str_at(&self.binbuf.buf, start)
+    }
+
+    fn reg_names(&self, count : usize) -> Vec<String> {
+        let names : &[&str] = match self.ehdr.e_machine {
+            EM_X86_64  => &X86_64_REGS,
+            EM_AARCH64 => &AARCH64_REGS,
+            EM_RISCV   => &RISCV_REGS,
+            _          => &[],
+        };
+        if names.len() == count {
+            names.iter().map(|n| n.to_string()).collect()
+        } else {
+            (0..count).map(|i| format!("r{}", i)).collect()
+        }
+    }
+
+    pub fn show_core(&self) -> &Self {
+        if !self.is_core() {
+            eprintln!("not a core file (e_type {})", self.ehdr.e_type);
+            return self;
+        }
+
+        if let Some(ps) = self.psinfo() {
+            println!("{:<16}{} (pid {}, ppid {}, uid {}, gid {}, state {})", "Process:".red(), ps.fname.blue(), ps.pid, ps.ppid, ps.uid, ps.gid, ps.state);
+            println!("{:<16}{}", "Command line:".red(), ps.psargs);
+        }
+
+        let auxv = self.auxv();
+        let aux = |a_type| auxv.iter().find(|a| a.0 == a_type).map(|a| a.1);
+        if let Some(path) = aux(AT_EXECFN).and_then(|p| self.read_c_str(p)) {
+            println!("{:<16}{}", "Executable:".red(), path.blue());
+        }
+        for (name, a_type) in [("Entry:", AT_ENTRY), ("Program hdrs:", AT_PHDR), ("Interp base:", AT_BASE), ("Page size:", AT_PAGESZ)] {
+            if let Some(v) = aux(a_type) {
+                println!("{:<16}{:#x}", name.red(), v);
+            }
+        }
+
+        if let Some(si) = self.siginfo() {
+            let mut line = format!("{} ({}), code {}", signal_name(si.signo).yellow(), si.signo, si.code);
+            if matches!(si.signo, 4 | 7 | 8 | 11) {
+                line += &format!(", fault address {:#x}", si.addr);
+            }
+            if si.errno != 0 {
+                line += &format!(", errno {}", si.errno);
+            }
+            println!("{:<16}{}", "Signal:".red(), line);
+        }
+
+        for (i, t) in self.threads().iter().enumerate() {
+            println!();
+            println!("{} {} (pid {}), signal {}", "Thread".green(), i + 1, t.pid, signal_name(t.cursig as i32));
+            let names = self.reg_names(t.regs.len());
+            for row in names.iter().zip(&t.regs).collect::<Vec<_>>().chunks(4) {
+                let cells : Vec<String> = row.iter().map(|(n, v)| format!("{:>8} {:016x}", n.cyan(), v)).collect();
+                println!("{}", cells.join("  "));
+            }
+        }
+
+        let files = self.mapped_files();
+        if !files.is_empty() {
+            println!();
+            println!("{}", "Mapped files:".red());
+            println!("{:<18} {:<18} {:<10} {}", "Start".green(), "End".green(), "Offset".cyan(), "Path".blue());
+            for f in &files {
+                println!("{:016x}   {:016x}   {:<10x} {}", f.start, f.end, f.offset, f.path);
+            }
+        }
+        self
+    }
+
+    pub fn memory_at(&self, addr : &str, len : usize) -> Result<(u64, &[u8]), String> {
+        // what core --read dumps, every way of getting no bytes back is an error
+        let vaddr = parse_addr(addr).ok_or(format!("invalid address: {}", addr))?;
+        if len == 0 {
+            return Err(format!("nothing to read at {:#x}, the length is 0", vaddr));
+        }
+        if let Some(bytes) = self.read_memory(vaddr, len) {
+            return Ok((vaddr, bytes));
+        }
+
+        // tell apart a hole in the dump from an address that was never mapped
+        if self.segments.vaddr_to_offset(vaddr).is_some() {
+            return Err(format!("{:#x}+{:#x} runs past the dumped part of its segment", vaddr, len));
+        }
+        if let Some(f) = self.mapped_files().iter().find(|f| vaddr >= f.start && vaddr < f.end) {
+            return Err(format!("{:#x} was not dumped, it is backed by {} at offset {:#x}", vaddr, f.path, f.offset.saturating_add(vaddr - f.start)));
+        }
+        if self.segments.loadable().any(|s| vaddr >= s.phdr.p_vaddr && vaddr - s.phdr.p_vaddr < s.phdr.p_memsz) {
+            return Err(format!("{:#x} was not dumped", vaddr));
+        }
+        Err(format!("{:#x} is not in the core's LOAD segments", vaddr))
+    }
+
+    pub fn show_memory(&self, addr : &str, len : usize) -> Result<&Self, String> {
+        let (vaddr, bytes) = self.memory_at(addr, len)?;
+        for (i, row) in bytes.chunks(16).enumerate() {
+            let hex : Vec<String> = row.iter().map(|b| format!("{:02x}", b)).collect();
+            let ascii : String = row.iter().map(|&b| if b.is_ascii_graphic() || b == b' ' { b as char } else { '.' }).collect();
+            println!("{:016x}  {:<47}  {}", vaddr + i as u64 * 16, hex.join(" "), ascii);
+        }
+        Ok(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    /*
+    test/testcore.core is the dump of `./test/testcore one two`, which dereferences NULL
+    at the bottom of a short recursion
+     */
+    #[test]
+    fn test_core() {
+        let parser = Parser::new("test/testcore.core");
+        assert!(parser.is_core());
+
+        let ps = parser.psinfo().unwrap();
+        assert_eq!(ps.fname, "testcore");
+        assert_eq!(ps.psargs, "./test/testcore one two");
+
+        let si = parser.siginfo().unwrap();
+        assert_eq!(signal_name(si.signo), "SIGSEGV");
+        assert_eq!(si.addr, 0);
+
+        let threads = parser.threads();
+        assert_eq!(threads.len(), 1);
+        assert_eq!(threads[0].pid, ps.pid);
+        assert_eq!(parser.reg_names(threads[0].regs.len())[16], "rip");
+
+        // rip is inside the executable's mappings, whose pages are named by NT_FILE
+        let rip = threads[0].regs[16];
+        let files = parser.mapped_files();
+        let exe = files.iter().find(|f| rip >= f.start && rip < f.end).unwrap();
+        assert!(exe.path.ends_with("test/testcore"));
+
+        // AT_EXECFN points into the dumped stack
+        let execfn = parser.auxv().iter().find(|a| a.0 == AT_EXECFN).unwrap().1;
+        assert_eq!(parser.read_memory(execfn, 16).unwrap(), b"./test/testcore\0");
+        assert!(parser.read_memory(0, 1).is_none());
+        assert!(parser.read_memory(u64::MAX, 16).is_none());
+
+        // core --read exits 1 on any of these
+        let execfn = format!("{:#x}", execfn);
+        assert_eq!(parser.memory_at(&execfn, 4).unwrap().1, b"./te");
+        assert!(parser.memory_at("0xzz", 16).unwrap_err().starts_with("invalid address"));
+        assert!(parser.memory_at(&execfn, 0).is_err());
+        assert!(parser.memory_at("0", 16).unwrap_err().contains("not in the core's LOAD segments"));
+        assert!(parser.memory_at(&execfn, 1 << 40).unwrap_err().contains("runs past"));
+        let hole = files.iter().map(|f| f.start).find(|&a| parser.read_memory(a, 1).is_none()).unwrap();
+        assert!(parser.memory_at(&format!("{:#x}", hole), 16).unwrap_err().contains("was not dumped"));
+
+        // a crafted NT_FILE whose page offset overflows in bytes
+        let mut desc = vec![];
+        for v in [2, 0x1000, 0x1000, 0x2000, 1, 0x3000, 0x4000, u64::MAX] {
+            desc.extend(u64::to_le_bytes(v));
+        }
+        desc.extend(b"/a\0/b\0");
+        let note = Note { n_type : NT_FILE, name : "CORE".to_string(), desc };
+        let files = note.file_mappings();
+        assert_eq!(files.len(), 1);
+        assert_eq!((files[0].offset, files[0].path.as_str()), (0x1000, "/a"));
+    }
+}
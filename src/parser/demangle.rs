@@ -0,0 +1,108 @@
+pub mod itanium;
+pub mod rust;
+
+use crate::parser::Parser;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum NameStyle {
+    Mangled,
+    Demangled,
+    Both,       /* demangled name followed by the mangled one */
+}
+
+pub fn demangle(name : &str) -> Option<String> {
+    // C++ (_Z), Rust legacy (_ZN...17h<hash>E) and Rust v0 (_R), symbol versions are kept as is
+    let (sym, version) = match name.find('@') {
+        Some(i) => (&name[..i], &name[i..]),
+        None    => (name, ""),
+    };
+    let plain = if sym.starts_with("_R") {
+        rust::demangle_v0(sym)
+    } else if sym.starts_with("_ZN") {
+        rust::demangle_legacy(sym).or_else(|| itanium::demangle(sym))
+    } else if sym.starts_with("_Z") {
+        itanium::demangle(sym)
+    } else {
+        None
+    };
+    plain.map(|p| p + version)
+}
+
+pub fn display_name(name : &str, style : NameStyle) -> String {
+    // names that do not demangle are shown as they are
+    let demangled = match style {
+        NameStyle::Mangled => None,
+        _                  => demangle(name),
+    };
+    match (style, demangled) {
+        (NameStyle::Demangled, Some(d)) => d,
+        (NameStyle::Both, Some(d))      => format!("{} [{}]", d, name),
+        _                               => name.to_string(),
+    }
+}
+
+impl Parser {
+    pub fn set_name_style(&mut self, style : NameStyle) -> &mut Self {
+        self.names = style;
+        self
+    }
+
+    pub fn display_name(&self, name : &str) -> String {
+        display_name(name, self.names)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    // expected strings are GNU c++filt output, except v0 consts which follow rustc-demangle
+    #[test]
+    fn test_demangle_itanium() {
+        let cases = [
+            ("_Z1fPFviE", "f(void (*)(int))"),
+            ("_Z3maxIiET_S0_S0_", "int max<int>(int, int)"),
+            ("_ZNSt7__cxx1112basic_stringIcSt11char_traitsIcESaIcEEC1EPKcRKS3_",
+             "std::__cxx11::basic_string<char, std::char_traits<char>, std::allocator<char> >::basic_string(char const*, std::allocator<char> const&)"),
+            ("_ZNSt6vectorIN5clang14SourceLocationESaIS1_EE17_M_default_appendEm",
+             "std::vector<clang::SourceLocation, std::allocator<clang::SourceLocation> >::_M_default_append(unsigned long)"),
+            ("_ZZ4mainENKUlvE_clEv", "main::{lambda()#1}::operator()() const"),
+            ("_ZZN3foo3barIiEEvT_E1x", "foo::bar<int>(int)::x"),
+            ("_ZTVN5clang7TagDeclE", "vtable for clang::TagDecl"),
+            ("_ZThn48_N5clang9ASTReader13ReadSLocEntryEi", "non-virtual thunk to clang::ASTReader::ReadSLocEntry(int)"),
+            ("_ZNKSt5ctypeIcE8do_widenEc.cold", "std::ctype<char>::do_widen(char) const [clone .cold]"),
+            ("_ZN4llvm10make_errorINS_11StringErrorEJNS_4errcERA30_KcEEENS_5ErrorEDpOT0_",
+             "llvm::Error llvm::make_error<llvm::StringError, llvm::errc, char const (&) [30]>(llvm::errc&&, char const (&) [30])"),
+            ("_ZN4llvm12hash_combineIJPvjjEEENS_9hash_codeEDpRKT_",
+             "llvm::hash_code llvm::hash_combine<void*, unsigned int, unsigned int>(void* const&, unsigned int const&, unsigned int const&)"),
+        ];
+        for (mangled, want) in cases {
+            assert_eq!(demangle(mangled).as_deref(), Some(want), "{}", mangled);
+        }
+        assert_eq!(demangle("_Z3foov@GLIBCXX_3.4").as_deref(), Some("foo()@GLIBCXX_3.4"));
+        assert_eq!(demangle("main"), None);
+        assert_eq!(demangle("_Z"), None);
+        assert_eq!(demangle("_ZN3foo"), None);
+    }
+
+    #[test]
+    fn test_demangle_rust() {
+        assert_eq!(demangle("_ZN3std2io5stdio6_print17h0123456789abcdefE").as_deref(),
+                   Some("std::io::stdio::_print::h0123456789abcdef"));
+        assert_eq!(demangle("_ZN4core3ptr85drop_in_place$LT$std..rt..lang_start$LT$$LP$$RP$$GT$..$u7b$$u7b$closure$u7d$$u7d$$GT$17h5a3f5c0a1b2c3d4eE").as_deref(),
+                   Some("core::ptr::drop_in_place<std::rt::lang_start<()>::{{closure}}>::h5a3f5c0a1b2c3d4e"));
+        assert_eq!(demangle("_RNvCs1234_7mycrate3foo").as_deref(), Some("mycrate[3c1c0]::foo"));
+        assert_eq!(demangle("_RNCNCNvNtCsjrHSEGnQ3l9_3std2rt19lang_start_internal00").as_deref(),
+                   Some("std[e28293b1aa0f68bd]::rt::lang_start_internal::{closure#0}::{closure#0}"));
+        assert_eq!(demangle("_RNvXsa_NtCsgEmfK2I1SDS_4core5arrayAhj3_NtNtB7_3fmt5Debug3fmtCsfq2Zq5gqAK1_2v0").as_deref(),
+                   Some("<[u8; 3usize] as core[c1f1a4ba060b9bfa]::fmt::Debug>::fmt"));
+    }
+
+    #[test]
+    fn test_display_name() {
+        let name = "_Z1fPFviE";
+        assert_eq!(display_name(name, NameStyle::Mangled), name);
+        assert_eq!(display_name(name, NameStyle::Demangled), "f(void (*)(int))");
+        assert_eq!(display_name(name, NameStyle::Both), "f(void (*)(int)) [_Z1fPFviE]");
+        assert_eq!(display_name("printf@plt", NameStyle::Both), "printf@plt");
+    }
+}
@@ -0,0 +1,1090 @@
+use std::rc::Rc;
+
+// Itanium C++ ABI demangler, output follows GNU c++filt
+
+enum Node {
+    Name(String),
+    Std(&'static str, &'static str),        /* expansion and constructor name of an St/Sa/Ss/... abbreviation */
+    Qual(Rc<Node>, Rc<Node>),               /* scope::name */
+    Template(Rc<Node>, Vec<Rc<Node>>),
+    Abi(Rc<Node>, String),
+    Cv(Rc<Node>, String),                   /* " const", " _Complex", vendor qualifier, ... */
+    Ptr(Rc<Node>, &'static str),            /* "*", "&" or "&&" */
+    Fn(Rc<Sig>),
+    Array(Rc<Node>, String),
+    MemPtr(Rc<Node>, Rc<Node>),
+    Pack(Vec<Rc<Node>>),
+    Expansion(Rc<Node>),
+    Local(Rc<Node>, Rc<Node>),              /* function::entity */
+    Special(&'static str, Rc<Node>),        /* "vtable for ", "operator ", ... */
+    Encoding(Rc<Node>, Rc<Sig>),
+}
+
+struct Sig {
+    ret    : Option<Rc<Node>>,
+    params : Vec<Rc<Node>>,
+    cv     : String,
+    refq   : &'static str,
+}
+
+#[derive(PartialEq, Clone, Copy)]
+enum Kind {
+    Plain,
+    Ctor,       /* constructor, destructor or conversion operator: no return type */
+}
+
+struct NameInfo {
+    node     : Rc<Node>,
+    cv       : String,
+    refq     : &'static str,
+    template : Option<Vec<Rc<Node>>>,
+    kind     : Kind,
+}
+
+impl NameInfo {
+    fn plain(node : Rc<Node>) -> Self {
+        NameInfo { node, cv : String::new(), refq : "", template : None, kind : Kind::Plain }
+    }
+}
+
+const BUILTINS : [(u8, &str); 21] = [
+    (b'v', "void"), (b'w', "wchar_t"), (b'b', "bool"), (b'c', "char"), (b'a', "signed char"),
+    (b'h', "unsigned char"), (b's', "short"), (b't', "unsigned short"), (b'i', "int"),
+    (b'j', "unsigned int"), (b'l', "long"), (b'm', "unsigned long"), (b'x', "long long"),
+    (b'y', "unsigned long long"), (b'n', "__int128"), (b'o', "unsigned __int128"), (b'f', "float"),
+    (b'd', "double"), (b'e', "long double"), (b'g', "__float128"), (b'z', "..."),
+];
+
+const D_BUILTINS : [(u8, &str); 10] = [
+    (b'd', "decimal64"), (b'e', "decimal128"), (b'f', "decimal32"), (b'h', "half"), (b'i', "char32_t"),
+    (b's', "char16_t"), (b'u', "char8_t"), (b'a', "auto"), (b'c', "decltype(auto)"), (b'n', "decltype(nullptr)"),
+];
+
+/* code, spelling, arity */
+const OPERATORS : [(&str, &str, u8); 49] = [
+    ("nw", "new", 3), ("na", "new[]", 3), ("dl", "delete", 1), ("da", "delete[]", 1),
+    ("ps", "+", 1), ("ng", "-", 1), ("ad", "&", 1), ("de", "*", 1), ("co", "~", 1),
+    ("pl", "+", 2), ("mi", "-", 2), ("ml", "*", 2), ("dv", "/", 2), ("rm", "%", 2),
+    ("an", "&", 2), ("or", "|", 2), ("eo", "^", 2), ("aS", "=", 2), ("pL", "+=", 2),
+    ("mI", "-=", 2), ("mL", "*=", 2), ("dV", "/=", 2), ("rM", "%=", 2), ("aN", "&=", 2),
+    ("oR", "|=", 2), ("eO", "^=", 2), ("ls", "<<", 2), ("rs", ">>", 2), ("lS", "<<=", 2),
+    ("rS", ">>=", 2), ("eq", "==", 2), ("ne", "!=", 2), ("lt", "<", 2), ("gt", ">", 2),
+    ("le", "<=", 2), ("ge", ">=", 2), ("ss", "<=>", 2), ("nt", "!", 1), ("aa", "&&", 2),
+    ("oo", "||", 2), ("pp", "++", 1), ("mm", "--", 1), ("cm", ",", 2), ("pm", "->*", 2),
+    ("pt", "->", 2), ("cl", "()", 2), ("ix", "[]", 2), ("qu", "?", 3), ("dt", ".", 2),
+];
+
+struct Demangler<'a> {
+    s     : &'a [u8],
+    pos   : usize,
+    subs  : Vec<Rc<Node>>,
+    tmpl  : Vec<Rc<Node>>,  /* arguments of the template being encoded, for T_ */
+    param : Vec<(usize, usize)>,    /* substitutions that were a bare T_, by index */
+    depth : usize,
+}
+
+pub fn demangle(name : &str) -> Option<String> {
+    // GCC clones (.cold, .constprop.0, ...) come after the mangled name
+    let (mangled, suffix) = match name.find('.') {
+        Some(i) => (&name[..i], &name[i..]),
+        None    => (name, ""),
+    };
+    let rest = mangled.strip_prefix("_Z")?;
+
+    let mut d = Demangler { s : rest.as_bytes(), pos : 0, subs : vec![], tmpl : vec![], param : vec![], depth : 0 };
+    let node = d.encoding()?;
+    if d.pos != d.s.len() {
+        return None;
+    }
+
+    let mut out = render(&node);
+    let mut clones = vec![];
+    for part in suffix.split('.').skip(1) {
+        match clones.last_mut() {
+            Some(last) if part.starts_with(|c : char| c.is_ascii_digit()) => *last = format!("{}.{}", last, part),
+            _ => clones.push(format!(".{}", part)),
+        }
+    }
+    for clone in clones {
+        out.push_str(&format!(" [clone {}]", clone));
+    }
+    Some(out)
+}
+
+impl Demangler<'_> {
+    fn peek(&self) -> Option<u8> {
+        self.s.get(self.pos).copied()
+    }
+
+    fn peek_at(&self, n : usize) -> Option<u8> {
+        self.s.get(self.pos + n).copied()
+    }
+
+    fn eat(&mut self, c : u8) -> bool {
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            return true;
+        }
+        false
+    }
+
+    fn expect(&mut self, c : u8) -> Option<()> {
+        self.eat(c).then_some(())
+    }
+
+    fn number(&mut self) -> Option<i64> {
+        // [n] <decimal>
+        let neg = self.eat(b'n');
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.pos += 1;
+        }
+        let n : i64 = std::str::from_utf8(&self.s[start..self.pos]).ok()?.parse().ok()?;
+        Some(if neg { -n } else { n })
+    }
+
+    fn seq_id(&mut self) -> Option<usize> {
+        // base 36 number terminated by '_', absent means 0
+        let mut n = 0usize;
+        let mut any = false;
+        while let Some(c) = self.peek() {
+            let v = match c {
+                b'0'..=b'9' => c - b'0',
+                b'A'..=b'Z' => c - b'A' + 10,
+                b'_'        => break,
+                _           => return None,
+            };
+            n = n.checked_mul(36)?.checked_add(v as usize)?;
+            any = true;
+            self.pos += 1;
+        }
+        self.expect(b'_')?;
+        Some(if any { n + 1 } else { 0 })
+    }
+
+    fn add_sub(&mut self, node : &Rc<Node>) {
+        self.subs.push(node.clone());
+    }
+
+    fn encoding(&mut self) -> Option<Rc<Node>> {
+        self.depth += 1;
+        if self.depth > 64 {
+            return None;
+        }
+        let ret = self.encoding_inner();
+        self.depth -= 1;
+        ret
+    }
+
+    fn encoding_inner(&mut self) -> Option<Rc<Node>> {
+        match self.peek()? {
+            b'T' | b'G' => return self.special_name(),
+            _ => {}
+        }
+        let info = self.name()?;
+        if self.peek().is_none() || self.peek() == Some(b'E') {
+            return Some(info.node);
+        }
+        if let Some(args) = &info.template {
+            self.tmpl = args.clone();
+        }
+        let ret = match (&info.template, info.kind) {
+            (Some(_), Kind::Plain) => Some(self.ty()?),
+            _ => None,
+        };
+        let (params, _) = self.params()?;
+        Some(Rc::new(Node::Encoding(info.node, Rc::new(Sig { ret, params, cv : info.cv, refq : info.refq }))))
+    }
+
+    fn params(&mut self) -> Option<(Vec<Rc<Node>>, &'static str)> {
+        // types up to the end, an 'E' or a ref-qualifier
+        let mut params = vec![];
+        let mut refq = "";
+        loop {
+            match (self.peek(), self.peek_at(1)) {
+                (None, _) | (Some(b'E'), _) => break,
+                (Some(b'R'), Some(b'E')) => { self.pos += 1; refq = " &"; break; }
+                (Some(b'O'), Some(b'E')) => { self.pos += 1; refq = " &&"; break; }
+                _ => params.push(self.ty()?),
+            }
+        }
+        if params.is_empty() {
+            return None;
+        }
+        // a lone void means no parameters
+        if params.len() == 1 && matches!(&*params[0], Node::Name(n) if n == "void") {
+            params.clear();
+        }
+        Some((params, refq))
+    }
+
+    fn special_name(&mut self) -> Option<Rc<Node>> {
+        let c0 = self.peek()?;
+        let c1 = self.peek_at(1)?;
+        self.pos += 2;
+        let node = match (c0, c1) {
+            (b'T', b'V') => Node::Special("vtable for ", self.ty()?),
+            (b'T', b'T') => Node::Special("VTT for ", self.ty()?),
+            (b'T', b'I') => Node::Special("typeinfo for ", self.ty()?),
+            (b'T', b'S') => Node::Special("typeinfo name for ", self.ty()?),
+            (b'T', b'h') => {
+                self.call_offset(b'h')?;
+                Node::Special("non-virtual thunk to ", self.encoding()?)
+            }
+            (b'T', b'v') => {
+                self.call_offset(b'v')?;
+                Node::Special("virtual thunk to ", self.encoding()?)
+            }
+            (b'T', b'c') => {
+                let c = self.peek()?;
+                self.pos += 1;
+                self.call_offset(c)?;
+                let c = self.peek()?;
+                self.pos += 1;
+                self.call_offset(c)?;
+                Node::Special("covariant return thunk to ", self.encoding()?)
+            }
+            (b'T', b'C') => {
+                let derived = self.ty()?;
+                self.number()?;
+                self.expect(b'_')?;
+                let base = self.ty()?;
+                Node::Name(format!("construction vtable for {}-in-{}", render(&base), render(&derived)))
+            }
+            (b'T', b'W') => Node::Special("TLS wrapper function for ", self.name()?.node),
+            (b'T', b'H') => Node::Special("TLS init function for ", self.name()?.node),
+            (b'G', b'V') => Node::Special("guard variable for ", self.name()?.node),
+            (b'G', b'R') => {
+                let name = self.name()?.node;
+                let n = self.seq_id()?;
+                Node::Name(format!("reference temporary #{} for {}", n, render(&name)))
+            }
+            (b'G', b'T') => {
+                let kind = match self.peek()? {
+                    b'n' => "non-transaction clone for ",
+                    _    => "transaction clone for ",
+                };
+                self.pos += 1;
+                Node::Special(kind, self.encoding()?)
+            }
+            _ => return None,
+        };
+        Some(Rc::new(node))
+    }
+
+    fn call_offset(&mut self, kind : u8) -> Option<()> {
+        // h <nv-offset> _ | v <offset> _ <virtual offset> _
+        self.number()?;
+        self.expect(b'_')?;
+        if kind == b'v' {
+            self.number()?;
+            self.expect(b'_')?;
+        } else if kind != b'h' {
+            return None;
+        }
+        Some(())
+    }
+
+    fn name(&mut self) -> Option<NameInfo> {
+        match self.peek()? {
+            b'N' => self.nested_name(),
+            b'Z' => self.local_name(),
+            b'S' => {
+                let (mut node, subst) = if self.peek_at(1) == Some(b't') {
+                    self.pos += 2;
+                    let (name, _) = self.unqualified_name(None)?;
+                    (Rc::new(Node::Qual(Rc::new(Node::Name("std".to_string())), name)), false)
+                } else {
+                    (self.substitution()?, true)
+                };
+                let mut template = None;
+                if self.peek() == Some(b'I') {
+                    if !subst {
+                        self.add_sub(&node);
+                    }
+                    let args = self.template_args()?;
+                    node = Rc::new(Node::Template(node, args.clone()));
+                    template = Some(args);
+                }
+                Some(NameInfo { template, ..NameInfo::plain(node) })
+            }
+            _ => {
+                let (mut node, kind) = self.unqualified_name(None)?;
+                let mut template = None;
+                if self.peek() == Some(b'I') {
+                    self.add_sub(&node);
+                    let args = self.template_args()?;
+                    node = Rc::new(Node::Template(node, args.clone()));
+                    template = Some(args);
+                }
+                Some(NameInfo { node, cv : String::new(), refq : "", template, kind })
+            }
+        }
+    }
+
+    fn cv_qualifiers(&mut self) -> String {
+        let mut cv = String::new();
+        for (c, q) in [(b'r', " restrict"), (b'V', " volatile"), (b'K', " const")] {
+            if self.eat(c) {
+                cv.push_str(q);
+            }
+        }
+        cv
+    }
+
+    fn nested_name(&mut self) -> Option<NameInfo> {
+        self.expect(b'N')?;
+        let cv = self.cv_qualifiers();
+        let refq = if self.eat(b'R') { " &" } else if self.eat(b'O') { " &&" } else { "" };
+
+        let mut ret : Option<Rc<Node>> = None;
+        let mut template = None;
+        let mut kind = Kind::Plain;
+        loop {
+            let peek = self.peek()?;
+            match peek {
+                b'E' => {
+                    self.pos += 1;
+                    break;
+                }
+                b'M' => {
+                    // initializer scope of a lambda
+                    self.pos += 1;
+                    continue;
+                }
+                b'I' => {
+                    let args = self.template_args()?;
+                    ret = Some(Rc::new(Node::Template(ret?, args.clone())));
+                    template = Some(args);
+                }
+                b'S' => {
+                    let sub = self.substitution()?;
+                    ret = Some(qualify(ret, sub));
+                    template = None;
+                }
+                b'T' => {
+                    let param = self.template_param()?;
+                    ret = Some(qualify(ret, param));
+                    template = None;
+                }
+                b'D' if matches!(self.peek_at(1), Some(b't') | Some(b'T')) => return None,
+                _ => {
+                    let (name, k) = self.unqualified_name(ret.as_ref())?;
+                    ret = Some(qualify(ret, name));
+                    template = None;
+                    kind = k;
+                }
+            }
+            if peek != b'S' && self.peek() != Some(b'E') {
+                let node = ret.clone()?;
+                self.add_sub(&node);
+            }
+        }
+        Some(NameInfo { node : ret?, cv, refq, template, kind })
+    }
+
+    fn local_name(&mut self) -> Option<NameInfo> {
+        self.expect(b'Z')?;
+        // the enclosing function is shown without its return type
+        let mut func = self.encoding()?;
+        if let Node::Encoding(name, sig) = &*func {
+            func = Rc::new(Node::Encoding(name.clone(), Rc::new(Sig { ret : None, params : sig.params.clone(), cv : sig.cv.clone(), refq : sig.refq })));
+        }
+        self.expect(b'E')?;
+        if self.eat(b's') {
+            self.discriminator();
+            return Some(NameInfo::plain(Rc::new(Node::Local(func, Rc::new(Node::Name("string literal".to_string()))))));
+        }
+        if self.eat(b'd') {
+            let n = if self.peek() == Some(b'_') { 0 } else { self.number()? + 1 };
+            self.expect(b'_')?;
+            let entity = self.name()?;
+            let node = Rc::new(Node::Local(func, Rc::new(Node::Qual(Rc::new(Node::Name(format!("{{default arg#{}}}", n + 1))), entity.node))));
+            return Some(NameInfo { node, ..entity });
+        }
+        let entity = self.name()?;
+        self.discriminator();
+        let node = Rc::new(Node::Local(func, entity.node.clone()));
+        Some(NameInfo { node, ..entity })
+    }
+
+    fn discriminator(&mut self) {
+        // _ <digit> | __ <number> _, not printed
+        if self.peek() != Some(b'_') {
+            return;
+        }
+        let save = self.pos;
+        self.pos += 1;
+        if self.eat(b'_') {
+            if self.number().is_some() && self.eat(b'_') {
+                return;
+            }
+        } else if self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.pos += 1;
+            return;
+        }
+        self.pos = save;
+    }
+
+    fn unqualified_name(&mut self, scope : Option<&Rc<Node>>) -> Option<(Rc<Node>, Kind)> {
+        let c = self.peek()?;
+        let (mut node, kind) = match c {
+            b'0'..=b'9' => (self.source_name()?, Kind::Plain),
+            b'L' => {
+                self.pos += 1;
+                let name = self.source_name()?;
+                self.discriminator();
+                (name, Kind::Plain)
+            }
+            b'C' => {
+                self.pos += 1;
+                if self.eat(b'I') {
+                    // inheriting constructor
+                    self.pos += 1;
+                    self.ty()?;
+                } else if !matches!(self.peek()?, b'1'..=b'5') {
+                    return None;
+                } else {
+                    self.pos += 1;
+                }
+                (Rc::new(Node::Name(base_name(scope?)?)), Kind::Ctor)
+            }
+            b'D' if matches!(self.peek_at(1)?, b'0'..=b'5') => {
+                self.pos += 2;
+                (Rc::new(Node::Name(format!("~{}", base_name(scope?)?))), Kind::Ctor)
+            }
+            b'D' if self.peek_at(1) == Some(b'C') => {
+                // structured binding
+                self.pos += 2;
+                let mut names = vec![];
+                while !self.eat(b'E') {
+                    names.push(render(&*self.source_name()?));
+                }
+                (Rc::new(Node::Name(format!("[{}]", names.join(", ")))), Kind::Plain)
+            }
+            b'U' => (self.unnamed_type()?, Kind::Plain),
+            b'a'..=b'z' => self.operator_name()?,
+            _ => return None,
+        };
+        while self.peek() == Some(b'B') {
+            self.pos += 1;
+            let tag = render(&*self.source_name()?);
+            node = Rc::new(Node::Abi(node, tag));
+        }
+        Some((node, kind))
+    }
+
+    fn source_name(&mut self) -> Option<Rc<Node>> {
+        let len = self.number()?;
+        if len <= 0 {
+            return None;
+        }
+        let end = self.pos.checked_add(len as usize)?;
+        let id = std::str::from_utf8(self.s.get(self.pos..end)?).ok()?;
+        self.pos = end;
+        let name = match id.starts_with("_GLOBAL_") && matches!(id.as_bytes().get(8), Some(b'.') | Some(b'_') | Some(b'$')) && id.as_bytes().get(9) == Some(&b'N') {
+            true  => "(anonymous namespace)".to_string(),
+            false => id.to_string(),
+        };
+        Some(Rc::new(Node::Name(name)))
+    }
+
+    fn unnamed_type(&mut self) -> Option<Rc<Node>> {
+        self.expect(b'U')?;
+        match self.peek()? {
+            b't' => {
+                self.pos += 1;
+                let n = if self.peek() == Some(b'_') { 1 } else { self.number()? + 2 };
+                self.expect(b'_')?;
+                Some(Rc::new(Node::Name(format!("{{unnamed type#{}}}", n))))
+            }
+            b'l' => {
+                self.pos += 1;
+                let (params, _) = self.params()?;
+                self.expect(b'E')?;
+                let n = if self.peek() == Some(b'_') { 1 } else { self.number()? + 2 };
+                self.expect(b'_')?;
+                Some(Rc::new(Node::Name(format!("{{lambda({})#{}}}", render_params(&params), n))))
+            }
+            _ => None,
+        }
+    }
+
+    fn operator_name(&mut self) -> Option<(Rc<Node>, Kind)> {
+        let code = std::str::from_utf8(self.s.get(self.pos..self.pos + 2)?).ok()?;
+        if code == "cv" {
+            self.pos += 2;
+            let ty = self.ty()?;
+            return Some((Rc::new(Node::Special("operator ", ty)), Kind::Ctor));
+        }
+        if code == "li" {
+            self.pos += 2;
+            let suffix = render(&*self.source_name()?);
+            return Some((Rc::new(Node::Name(format!("operator\"\" {}", suffix))), Kind::Plain));
+        }
+        if code.starts_with('v') && code.as_bytes()[1].is_ascii_digit() {
+            self.pos += 2;
+            let name = render(&*self.source_name()?);
+            return Some((Rc::new(Node::Name(format!("operator {}", name))), Kind::Plain));
+        }
+        let &(_, op, _) = OPERATORS.iter().find(|o| o.0 == code)?;
+        self.pos += 2;
+        let sep = if op.starts_with(|c : char| c.is_ascii_lowercase()) { " " } else { "" };
+        Some((Rc::new(Node::Name(format!("operator{}{}", sep, op))), Kind::Plain))
+    }
+
+    fn substitution(&mut self) -> Option<Rc<Node>> {
+        self.expect(b'S')?;
+        let c = self.peek()?;
+        let std = match c {
+            b't' => Some(Node::Name("std".to_string())),
+            b'a' => Some(Node::Std("std::allocator", "allocator")),
+            b'b' => Some(Node::Std("std::basic_string", "basic_string")),
+            b's' => Some(Node::Std("std::basic_string<char, std::char_traits<char>, std::allocator<char> >", "basic_string")),
+            b'i' => Some(Node::Std("std::basic_istream<char, std::char_traits<char> >", "basic_istream")),
+            b'o' => Some(Node::Std("std::basic_ostream<char, std::char_traits<char> >", "basic_ostream")),
+            b'd' => Some(Node::Std("std::basic_iostream<char, std::char_traits<char> >", "basic_iostream")),
+            _    => None,
+        };
+        if let Some(node) = std {
+            self.pos += 1;
+            return Some(Rc::new(node));
+        }
+        let i = self.seq_id()?;
+        // c++filt resolves a substituted T_ against the template it is used in
+        if let Some(&(_, n)) = self.param.iter().find(|p| p.0 == i) {
+            return self.tmpl.get(n).cloned();
+        }
+        self.subs.get(i).cloned()
+    }
+
+    fn template_param(&mut self) -> Option<Rc<Node>> {
+        Some(self.template_param_index()?.1)
+    }
+
+    fn template_param_index(&mut self) -> Option<(usize, Rc<Node>)> {
+        self.expect(b'T')?;
+        let i = if self.eat(b'_') { 0 } else { let n = self.number()?; self.expect(b'_')?; n as usize + 1 };
+        Some((i, self.tmpl.get(i).cloned()?))
+    }
+
+    fn template_args(&mut self) -> Option<Vec<Rc<Node>>> {
+        self.expect(b'I')?;
+        let mut args = vec![];
+        while !self.eat(b'E') {
+            args.push(self.template_arg()?);
+        }
+        Some(args)
+    }
+
+    fn template_arg(&mut self) -> Option<Rc<Node>> {
+        match self.peek()? {
+            b'X' => {
+                self.pos += 1;
+                let e = self.expression()?;
+                self.expect(b'E')?;
+                Some(Rc::new(Node::Name(e)))
+            }
+            b'L' => Some(Rc::new(Node::Name(self.expr_primary()?))),
+            b'J' => {
+                self.pos += 1;
+                let mut args = vec![];
+                while !self.eat(b'E') {
+                    args.push(self.template_arg()?);
+                }
+                Some(Rc::new(Node::Pack(args)))
+            }
+            _ => self.ty(),
+        }
+    }
+
+    fn expr_primary(&mut self) -> Option<String> {
+        self.expect(b'L')?;
+        if self.peek() == Some(b'_') && self.peek_at(1) == Some(b'Z') {
+            self.pos += 2;
+            let enc = self.encoding()?;
+            self.expect(b'E')?;
+            return Some(render(&enc));
+        }
+        let ty = self.ty()?;
+        let neg = self.eat(b'n');
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c != b'E') {
+            self.pos += 1;
+        }
+        let value = format!("{}{}", if neg { "-" } else { "" }, std::str::from_utf8(&self.s[start..self.pos]).ok()?);
+        self.expect(b'E')?;
+        let ty = render(&ty);
+        Some(match ty.as_str() {
+            "bool" if value == "0" => "false".to_string(),
+            "bool" if value == "1" => "true".to_string(),
+            "int"                  => value,
+            "unsigned int"         => format!("{}u", value),
+            "long"                 => format!("{}l", value),
+            "unsigned long"        => format!("{}ul", value),
+            "long long"            => format!("{}ll", value),
+            "unsigned long long"   => format!("{}ull", value),
+            _                      => format!("({}){}", ty, value),
+        })
+    }
+
+    fn expression(&mut self) -> Option<String> {
+        Some(self.operand()?.0)
+    }
+
+    fn operand(&mut self) -> Option<(String, bool)> {
+        // the expression and whether it is a bare name, which needs no parentheses
+        self.depth += 1;
+        if self.depth > 64 {
+            return None;
+        }
+        let ret = self.expression_inner();
+        self.depth -= 1;
+        ret
+    }
+
+    fn subexpr(&mut self) -> Option<String> {
+        let (e, simple) = self.operand()?;
+        Some(if simple { e } else { format!("({})", e) })
+    }
+
+    fn expression_inner(&mut self) -> Option<(String, bool)> {
+        match (self.peek()?, self.peek_at(1)) {
+            (b'T', _) => return Some((render(&*self.template_param()?), false)),
+            (b'L', _) => return Some((self.expr_primary()?, false)),
+            (b'f', Some(b'p')) => {
+                self.pos += 2;
+                self.cv_qualifiers();
+                let n = if self.peek() == Some(b'_') { 1 } else { self.number()? + 2 };
+                self.expect(b'_')?;
+                return Some((format!("{{parm#{}}}", n), true));
+            }
+            (b's', Some(b'Z')) => {
+                self.pos += 2;
+                let arg = self.expression()?;
+                return Some((format!("sizeof...({})", arg), false));
+            }
+            (b's', Some(b't')) => {
+                self.pos += 2;
+                return Some((format!("sizeof ({})", render(&*self.ty()?)), false));
+            }
+            (b's', Some(b'z')) => {
+                self.pos += 2;
+                return Some((format!("sizeof ({})", self.expression()?), false));
+            }
+            (b'c', Some(b'v')) => {
+                self.pos += 2;
+                let ty = render(&*self.ty()?);
+                return Some((format!("({}){}", ty, self.subexpr()?), false));
+            }
+            (b'c', Some(b'l')) => {
+                self.pos += 2;
+                let f = self.subexpr()?;
+                let mut args = vec![];
+                while !self.eat(b'E') {
+                    args.push(self.expression()?);
+                }
+                return Some((format!("{}({})", f, args.join(", ")), false));
+            }
+            (b'a', Some(b'd')) if self.s[self.pos + 2..].starts_with(b"L_Z") => {
+                // the address of a non-template function is shown without its parameters
+                self.pos += 5;
+                let enc = self.encoding()?;
+                self.expect(b'E')?;
+                let e = match &*enc {
+                    Node::Encoding(name, _) if !matches!(&**name, Node::Template(..)) => render(name),
+                    _                                                                  => format!("({})", render(&enc)),
+                };
+                return Some((format!("&{}", e), false));
+            }
+            (b's', Some(b'r')) => {
+                self.pos += 2;
+                let name = self.unresolved_name()?;
+                let simple = !name.ends_with('>');
+                return Some((name, simple));
+            }
+            (b'g', Some(b's')) => {
+                self.pos += 2;
+                let (e, simple) = self.operand()?;
+                return Some((format!("::{}", e), simple));
+            }
+            (b'0'..=b'9', _) | (b'o', Some(b'n')) | (b'd', Some(b'n')) => {
+                let name = self.base_unresolved_name()?;
+                let simple = !name.ends_with('>');
+                return Some((name, simple));
+            }
+            _ => {}
+        }
+        let code = std::str::from_utf8(self.s.get(self.pos..self.pos + 2)?).ok()?;
+        let &(_, op, arity) = OPERATORS.iter().find(|o| o.0 == code)?;
+        self.pos += 2;
+        let e = match arity {
+            1 => format!("{}{}", op, self.subexpr()?),
+            2 => {
+                let a = self.subexpr()?;
+                let b = self.subexpr()?;
+                format!("{}{}{}", a, op, b)
+            }
+            _ => {
+                let a = self.subexpr()?;
+                let b = self.subexpr()?;
+                let c = self.subexpr()?;
+                format!("{}?{}:{}", a, b, c)
+            }
+        };
+        Some((e, false))
+    }
+
+    fn unresolved_name(&mut self) -> Option<String> {
+        // sr [N <type>] <simple-id>* E <base>, or sr <type> <base>
+        let mut scope = vec![];
+        if self.eat(b'N') {
+            scope.push(render(&*self.ty()?));
+            while !self.eat(b'E') {
+                scope.push(self.simple_id()?);
+            }
+        } else if self.peek()?.is_ascii_digit() {
+            while !self.eat(b'E') {
+                scope.push(self.simple_id()?);
+            }
+        } else {
+            scope.push(render(&*self.ty()?));
+        }
+        scope.push(self.base_unresolved_name()?);
+        Some(scope.join("::"))
+    }
+
+    fn simple_id(&mut self) -> Option<String> {
+        let name = self.source_name()?;
+        if self.peek() != Some(b'I') {
+            return Some(render(&name));
+        }
+        let args = self.template_args()?;
+        Some(render(&Node::Template(name, args)))
+    }
+
+    fn base_unresolved_name(&mut self) -> Option<String> {
+        if self.peek() == Some(b'o') && self.peek_at(1) == Some(b'n') {
+            self.pos += 2;
+            let op = render(&self.operator_name()?.0);
+            if self.peek() != Some(b'I') {
+                return Some(op);
+            }
+            let args = self.template_args()?;
+            return Some(render(&Node::Template(Rc::new(Node::Name(op)), args)));
+        }
+        if self.peek() == Some(b'd') && self.peek_at(1) == Some(b'n') {
+            self.pos += 2;
+            let ty = match self.peek()?.is_ascii_digit() {
+                true  => self.simple_id()?,
+                false => render(&*self.ty()?),
+            };
+            return Some(format!("~{}", ty));
+        }
+        self.simple_id()
+    }
+
+    fn function_type(&mut self) -> Option<Rc<Node>> {
+        self.expect(b'F')?;
+        self.eat(b'Y');
+        let ret = self.ty()?;
+        let (params, refq) = self.params()?;
+        self.expect(b'E')?;
+        Some(Rc::new(Node::Fn(Rc::new(Sig { ret : Some(ret), params, cv : String::new(), refq }))))
+    }
+
+    fn ty(&mut self) -> Option<Rc<Node>> {
+        self.depth += 1;
+        if self.depth > 64 {
+            return None;
+        }
+        let ret = self.ty_inner();
+        self.depth -= 1;
+        ret
+    }
+
+    fn ty_inner(&mut self) -> Option<Rc<Node>> {
+        let c = self.peek()?;
+        if let Some(&(_, name)) = BUILTINS.iter().find(|b| b.0 == c) {
+            self.pos += 1;
+            return Some(Rc::new(Node::Name(name.to_string())));
+        }
+        let node = match c {
+            b'r' | b'V' | b'K' => {
+                let cv = self.cv_qualifiers();
+                // the unqualified type of a const member function is not a candidate
+                let inner = match self.peek()? {
+                    b'F' => self.function_type()?,
+                    _    => self.ty()?,
+                };
+                Rc::new(Node::Cv(inner, cv))
+            }
+            b'P' => { self.pos += 1; Rc::new(Node::Ptr(self.ty()?, "*")) }
+            b'R' => { self.pos += 1; Rc::new(Node::Ptr(self.ty()?, "&")) }
+            b'O' => { self.pos += 1; Rc::new(Node::Ptr(self.ty()?, "&&")) }
+            b'C' => { self.pos += 1; Rc::new(Node::Cv(self.ty()?, " _Complex".to_string())) }
+            b'G' => { self.pos += 1; Rc::new(Node::Cv(self.ty()?, " _Imaginary".to_string())) }
+            b'u' => {
+                self.pos += 1;
+                self.source_name()?
+            }
+            b'U' => {
+                self.pos += 1;
+                let q = render(&*self.source_name()?);
+                Rc::new(Node::Cv(self.ty()?, format!(" {}", q)))
+            }
+            b'F' => self.function_type()?,
+            b'A' => {
+                self.pos += 1;
+                let dim = match self.peek()? {
+                    b'_'        => String::new(),
+                    b'0'..=b'9' => self.number()?.to_string(),
+                    _           => self.expression()?,
+                };
+                self.expect(b'_')?;
+                Rc::new(Node::Array(self.ty()?, dim))
+            }
+            b'M' => {
+                self.pos += 1;
+                let class = self.ty()?;
+                Rc::new(Node::MemPtr(class, self.ty()?))
+            }
+            b'T' if matches!(self.peek_at(1), Some(b's') | Some(b'u') | Some(b'e')) => {
+                self.pos += 2;
+                self.name()?.node
+            }
+            b'T' => {
+                let (i, mut param) = self.template_param_index()?;
+                self.param.push((self.subs.len(), i));
+                if self.peek() == Some(b'I') {
+                    self.add_sub(&param);
+                    let args = self.template_args()?;
+                    param = Rc::new(Node::Template(param, args));
+                }
+                param
+            }
+            b'S' => {
+                let next = self.peek_at(1)?;
+                if next.is_ascii_digit() || next == b'_' || next.is_ascii_uppercase() {
+                    let sub = self.substitution()?;
+                    if self.peek() != Some(b'I') {
+                        return Some(sub);
+                    }
+                    let args = self.template_args()?;
+                    Rc::new(Node::Template(sub, args))
+                } else {
+                    let node = self.name()?.node;
+                    if matches!(&*node, Node::Std(..)) {
+                        return Some(node);
+                    }
+                    node
+                }
+            }
+            b'D' => {
+                let next = self.peek_at(1)?;
+                if let Some(&(_, name)) = D_BUILTINS.iter().find(|b| b.0 == next) {
+                    self.pos += 2;
+                    return Some(Rc::new(Node::Name(name.to_string())));
+                }
+                match next {
+                    b't' | b'T' => {
+                        self.pos += 2;
+                        let e = self.expression()?;
+                        self.expect(b'E')?;
+                        Rc::new(Node::Name(format!("decltype ({})", e)))
+                    }
+                    b'p' => {
+                        self.pos += 2;
+                        Rc::new(Node::Expansion(self.ty()?))
+                    }
+                    b'F' => {
+                        self.pos += 2;
+                        let n = self.number()?;
+                        let x = self.eat(b'x');
+                        self.expect(b'_')?;
+                        return Some(Rc::new(Node::Name(format!("_Float{}{}", n, if x { "x" } else { "" }))));
+                    }
+                    b'v' => {
+                        self.pos += 2;
+                        let n = self.number()?;
+                        self.expect(b'_')?;
+                        Rc::new(Node::Cv(self.ty()?, format!(" __vector({})", n)))
+                    }
+                    _ => return None,
+                }
+            }
+            b'N' | b'Z' | b'0'..=b'9' => self.name()?.node,
+            _ => return None,
+        };
+        self.add_sub(&node);
+        Some(node)
+    }
+}
+
+fn qualify(scope : Option<Rc<Node>>, name : Rc<Node>) -> Rc<Node> {
+    match scope {
+        Some(scope) => Rc::new(Node::Qual(scope, name)),
+        None        => name,
+    }
+}
+
+fn base_name(node : &Node) -> Option<String> {
+    // the unqualified class name a constructor is named after
+    match node {
+        Node::Name(n)           => Some(n.clone()),
+        Node::Std(_, n)         => Some(n.to_string()),
+        Node::Qual(_, n)        => base_name(n),
+        Node::Template(n, _)    => base_name(n),
+        Node::Abi(n, _)         => base_name(n),
+        Node::Local(_, n)       => base_name(n),
+        _                       => None,
+    }
+}
+
+fn render(node : &Node) -> String {
+    let (l, r) = parts(node);
+    l + &r
+}
+
+fn render_params(params : &[Rc<Node>]) -> String {
+    // packs expand in place
+    let mut out = vec![];
+    for p in params {
+        match &**p {
+            Node::Pack(items)      => out.extend(items.iter().map(|i| render(i))),
+            Node::Expansion(inner) => match find_pack(inner) {
+                Some(pack) => match &*pack {
+                    Node::Pack(items) => out.extend(items.iter().map(|i| render(&subst(inner, &pack, i)))),
+                    _                 => unreachable!(),
+                },
+                None => out.push(render(inner) + "..."),
+            },
+            _ => out.push(render(p)),
+        }
+    }
+    out.retain(|a| !a.is_empty());
+    out.join(", ")
+}
+
+fn find_pack(node : &Rc<Node>) -> Option<Rc<Node>> {
+    // the argument pack an expansion pattern is repeated over
+    match &**node {
+        Node::Pack(_)              => Some(node.clone()),
+        Node::Ptr(n, _)            => find_pack(n),
+        Node::Cv(n, _)             => find_pack(n),
+        Node::Template(_, args)    => args.iter().find_map(find_pack),
+        _                          => None,
+    }
+}
+
+fn subst(node : &Rc<Node>, pack : &Rc<Node>, item : &Rc<Node>) -> Rc<Node> {
+    // the pattern with one element of the pack in place of the pack
+    if Rc::ptr_eq(node, pack) {
+        return item.clone();
+    }
+    match &**node {
+        Node::Ptr(n, p)         => Rc::new(Node::Ptr(subst(n, pack, item), p)),
+        Node::Cv(n, q)          => Rc::new(Node::Cv(subst(n, pack, item), q.clone())),
+        Node::Template(n, args) => Rc::new(Node::Template(n.clone(), args.iter().map(|a| subst(a, pack, item)).collect())),
+        _                       => node.clone(),
+    }
+}
+
+fn is_fn(node : &Node) -> bool {
+    match node {
+        Node::Fn(_)     => true,
+        Node::Cv(n, _)  => is_fn(n),
+        _               => false,
+    }
+}
+
+fn parts(node : &Node) -> (String, String) {
+    // a declarator splits around the name: "void (*" + ")(int)"
+    match node {
+        Node::Name(n)       => (n.clone(), String::new()),
+        Node::Std(n, _)     => (n.to_string(), String::new()),
+        Node::Qual(a, b)    => (format!("{}::{}", render(a), render(b)), String::new()),
+        Node::Local(a, b)   => (format!("{}::{}", render(a), render(b)), String::new()),
+        Node::Abi(n, tag)   => (format!("{}[abi:{}]", render(n), tag), String::new()),
+        Node::Special(p, n) => (format!("{}{}", p, render(n)), String::new()),
+        Node::Pack(items)   => (render_params(items), String::new()),
+        Node::Expansion(n)  => (render_params(&[Rc::new(Node::Expansion(n.clone()))]), String::new()),
+        Node::Template(n, args) => {
+            let name = render(n);
+            let open = if name.ends_with('<') { " <" } else { "<" };
+            // an empty trailing pack leaves c++filt's last character at the ", " it took back
+            let trailing = args.len() > 1 && render_params(&args[args.len() - 1..]).is_empty();
+            let args = render_params(args);
+            let close = if args.ends_with('>') && !trailing { " >" } else { ">" };
+            (format!("{}{}{}{}", name, open, args, close), String::new())
+        }
+        Node::Cv(n, q) => {
+            // a qualifier already present is not repeated
+            if let Node::Cv(_, inner) = &**n {
+                if inner == q {
+                    return parts(n);
+                }
+            }
+            // qualifiers on an array belong to its elements
+            if let Node::Array(e, dim) = &**n {
+                return parts(&Node::Array(Rc::new(Node::Cv(e.clone(), q.clone())), dim.clone()));
+            }
+            let (l, r) = parts(n);
+            match is_fn(n) {
+                true  => (l, r + q),
+                false => (l + q, r),
+            }
+        }
+        Node::Ptr(n, p) => {
+            // references to references collapse, && only when both are
+            if let Node::Ptr(m, q) = &**n {
+                if p.starts_with('&') && q.starts_with('&') {
+                    let p = if *p == "&&" && *q == "&&" { "&&" } else { "&" };
+                    return parts(&Node::Ptr(m.clone(), p));
+                }
+            }
+            if let Node::Cv(a, q) = &**n {
+                if let Node::Array(e, dim) = &**a {
+                    let array = Node::Array(Rc::new(Node::Cv(e.clone(), q.clone())), dim.clone());
+                    return parts(&Node::Ptr(Rc::new(array), p));
+                }
+            }
+            let (l, r) = parts(n);
+            match &**n {
+                _ if is_fn(n)     => (format!("{}({}", l, p), format!("){}", r)),
+                Node::Array(..)   => (format!("{}({}", l, p), format!(") {}", r)),
+                _                 => (l + p, r),
+            }
+        }
+        Node::MemPtr(class, n) => {
+            let class = render(class);
+            let (l, r) = parts(n);
+            match is_fn(n) {
+                true  => (format!("{}({}::*", l, class), format!("){}", r)),
+                false => (format!("{} {}::*", l, class), r),
+            }
+        }
+        Node::Array(n, dim) => {
+            let (l, r) = parts(n);
+            match &**n {
+                Node::Array(..)         => (l, format!("[{}]{}", dim, r)),
+                _ if !r.is_empty()      => (format!("{} [{}]", l, dim), r),
+                _                       => (l + " ", format!("[{}]", dim)),
+            }
+        }
+        Node::Fn(sig) => {
+            let (rl, rr) = sig.ret.as_deref().map(parts).unwrap_or_default();
+            let l = if rr.is_empty() { rl + " " } else { rl };
+            (l, format!("({}){}{}{}", render_params(&sig.params), sig.cv, sig.refq, rr))
+        }
+        Node::Encoding(name, sig) => {
+            let name = render(name);
+            let tail = format!("({}){}{}", render_params(&sig.params), sig.cv, sig.refq);
+            match sig.ret.as_deref().map(parts) {
+                Some((rl, rr)) if rr.is_empty() => (format!("{} {}{}", rl, name, tail), String::new()),
+                Some((rl, rr))                  => (format!("{}{}{}", rl, name, tail), rr),
+                None                            => (name + &tail, String::new()),
+            }
+        }
+    }
+}
@@ -0,0 +1,579 @@
+use std::fmt::Write;
+
+// Rust symbol demangling, legacy (_ZN...17h<hash>E) and v0 (_R...), output follows rustc-demangle
+
+pub fn demangle_legacy(name : &str) -> Option<String> {
+    let rest = name.strip_prefix("_ZN")?;
+    let mut s = rest.as_bytes();
+    let mut parts = vec![];
+    loop {
+        if s.first() == Some(&b'E') {
+            s = &s[1..];
+            break;
+        }
+        let digits = s.iter().take_while(|c| c.is_ascii_digit()).count();
+        let len : usize = std::str::from_utf8(&s[..digits]).ok()?.parse().ok()?;
+        let id = std::str::from_utf8(s.get(digits..digits + len)?).ok()?;
+        parts.push(id);
+        s = &s[digits + len..];
+    }
+    // legacy names always end with the hash, anything after the E is an llvm suffix
+    let hash = parts.last()?;
+    let is_hash = hash.len() == 17 && hash.starts_with('h') && hash[1..].bytes().all(|c| c.is_ascii_hexdigit());
+    if !is_hash || !(s.is_empty() || s.starts_with(b".")) {
+        return None;
+    }
+
+    let mut out = String::new();
+    for (i, part) in parts.iter().enumerate() {
+        if i > 0 {
+            out.push_str("::");
+        }
+        out.push_str(&unescape_legacy(part)?);
+    }
+    Some(out)
+}
+
+fn unescape_legacy(part : &str) -> Option<String> {
+    // $LT$ style escapes and .. for ::
+    let mut rest = if part.starts_with("_$") { &part[1..] } else { part };
+    let mut out = String::new();
+    while let Some(c) = rest.chars().next() {
+        if c == '$' {
+            let end = rest[1..].find('$')? + 1;
+            let esc = &rest[1..end];
+            let ch = match esc {
+                "SP" => '@',
+                "BP" => '*',
+                "RF" => '&',
+                "LT" => '<',
+                "GT" => '>',
+                "LP" => '(',
+                "RP" => ')',
+                "C"  => ',',
+                _ => char::from_u32(u32::from_str_radix(esc.strip_prefix('u')?, 16).ok()?)?,
+            };
+            out.push(ch);
+            rest = &rest[end + 1..];
+        } else if let Some(r) = rest.strip_prefix("..") {
+            out.push_str("::");
+            rest = r;
+        } else {
+            out.push(c);
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+    Some(out)
+}
+
+pub fn demangle_v0(name : &str) -> Option<String> {
+    let rest = name.strip_prefix("_R")?;
+    // vendor suffixes such as .llvm.1234 are dropped
+    let sym = match rest.find('.') {
+        Some(i) => &rest[..i],
+        None    => rest,
+    };
+    if !sym.bytes().all(|c| c.is_ascii_alphanumeric() || c == b'_') {
+        return None;
+    }
+    let mut p = V0 { s : sym.as_bytes(), pos : 0, out : String::new(), depth : 0, bound : 0 };
+    // optional encoding version
+    if p.peek().is_some_and(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    p.path(true)?;
+    // an instantiating crate may follow, it is not printed
+    if p.pos < p.s.len() {
+        let mut skip = V0 { s : p.s, pos : p.pos, out : String::new(), depth : 0, bound : 0 };
+        skip.path(false)?;
+        if skip.pos != p.s.len() {
+            return None;
+        }
+    }
+    Some(p.out)
+}
+
+struct V0<'a> {
+    s     : &'a [u8],
+    pos   : usize,
+    out   : String,
+    depth : usize,
+    bound : usize,      /* lifetimes bound by enclosing for<...> binders */
+}
+
+const BASIC : [(u8, &str); 21] = [
+    (b'a', "i8"), (b'b', "bool"), (b'c', "char"), (b'd', "f64"), (b'e', "str"), (b'f', "f32"),
+    (b'h', "u8"), (b'i', "isize"), (b'j', "usize"), (b'l', "i32"), (b'm', "u32"), (b'n', "i128"),
+    (b'o', "u128"), (b's', "i16"), (b't', "u16"), (b'u', "()"), (b'v', "..."), (b'x', "i64"),
+    (b'y', "u64"), (b'z', "!"), (b'p', "_"),
+];
+
+impl<'a> V0<'a> {
+    fn peek(&self) -> Option<u8> {
+        self.s.get(self.pos).copied()
+    }
+
+    fn next(&mut self) -> Option<u8> {
+        let c = self.peek()?;
+        self.pos += 1;
+        Some(c)
+    }
+
+    fn eat(&mut self, c : u8) -> bool {
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            return true;
+        }
+        false
+    }
+
+    fn base62(&mut self) -> Option<u64> {
+        // "_" is 0, otherwise the digits plus one
+        if self.eat(b'_') {
+            return Some(0);
+        }
+        let mut n : u64 = 0;
+        loop {
+            let c = self.next()?;
+            let v = match c {
+                b'0'..=b'9' => c - b'0',
+                b'a'..=b'z' => c - b'a' + 10,
+                b'A'..=b'Z' => c - b'A' + 36,
+                b'_'        => return n.checked_add(1),
+                _           => return None,
+            };
+            n = n.checked_mul(62)?.checked_add(v as u64)?;
+        }
+    }
+
+    fn opt_base62(&mut self, tag : u8) -> Option<u64> {
+        // <tag> <base-62-number> adds one, absent is 0
+        if !self.eat(tag) {
+            return Some(0);
+        }
+        self.base62()?.checked_add(1)
+    }
+
+    fn decimal(&mut self) -> Option<usize> {
+        // a leading zero is the whole number
+        let start = self.pos;
+        if self.eat(b'0') {
+            return Some(0);
+        }
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.pos += 1;
+        }
+        if start == self.pos {
+            return None;
+        }
+        std::str::from_utf8(&self.s[start..self.pos]).ok()?.parse().ok()
+    }
+
+    fn ident(&mut self) -> Option<String> {
+        // ["u"] <decimal> ["_"] <bytes>
+        let puny = self.eat(b'u');
+        let len = self.decimal()?;
+        self.eat(b'_');
+        let end = self.pos.checked_add(len)?;
+        let raw = std::str::from_utf8(self.s.get(self.pos..end)?).ok()?;
+        self.pos = end;
+        match puny {
+            true  => punycode(raw),
+            false => Some(raw.to_string()),
+        }
+    }
+
+    fn backref(&mut self) -> Option<V0<'a>> {
+        // B <base-62-number>, a position before the current one
+        let start = self.pos - 1;
+        let target = self.base62()? as usize;
+        if target >= start {
+            return None;
+        }
+        Some(V0 { s : self.s, pos : target, out : String::new(), depth : self.depth + 1, bound : self.bound })
+    }
+
+    fn enter(&mut self) -> Option<()> {
+        self.depth += 1;
+        (self.depth < 128).then_some(())
+    }
+
+    fn path(&mut self, in_value : bool) -> Option<()> {
+        self.enter()?;
+        match self.next()? {
+            b'C' => {
+                let dis = self.opt_base62(b's')?;
+                let name = self.ident()?;
+                self.out.push_str(&name);
+                if dis != 0 {
+                    let _ = write!(self.out, "[{:x}]", dis);
+                }
+            }
+            b'N' => {
+                let ns = self.next()?;
+                if !ns.is_ascii_alphabetic() {
+                    return None;
+                }
+                self.path(in_value)?;
+                let dis = self.opt_base62(b's')?;
+                let name = self.ident()?;
+                if ns.is_ascii_uppercase() {
+                    self.out.push_str("::{");
+                    match ns {
+                        b'C' => self.out.push_str("closure"),
+                        b'S' => self.out.push_str("shim"),
+                        c    => self.out.push(c as char),
+                    }
+                    if !name.is_empty() {
+                        self.out.push(':');
+                        self.out.push_str(&name);
+                    }
+                    let _ = write!(self.out, "#{}}}", dis);
+                } else if !name.is_empty() {
+                    self.out.push_str("::");
+                    self.out.push_str(&name);
+                }
+            }
+            b'M' | b'X' | b'Y' => {
+                let tag = self.s[self.pos - 1];
+                if tag != b'Y' {
+                    self.opt_base62(b's')?;
+                    self.skip_path()?;
+                }
+                self.out.push('<');
+                self.ty()?;
+                if tag != b'M' {
+                    self.out.push_str(" as ");
+                    self.path(false)?;
+                }
+                self.out.push('>');
+            }
+            b'I' => {
+                self.path(in_value)?;
+                if in_value {
+                    self.out.push_str("::");
+                }
+                self.out.push('<');
+                let mut first = true;
+                while !self.eat(b'E') {
+                    if !first {
+                        self.out.push_str(", ");
+                    }
+                    first = false;
+                    self.generic_arg()?;
+                }
+                self.out.push('>');
+            }
+            b'B' => {
+                let mut sub = self.backref()?;
+                sub.path(in_value)?;
+                self.out.push_str(&sub.out);
+            }
+            _ => return None,
+        }
+        self.depth -= 1;
+        Some(())
+    }
+
+    fn skip_path(&mut self) -> Option<()> {
+        // impl paths are parsed but not printed
+        let len = self.out.len();
+        self.path(false)?;
+        self.out.truncate(len);
+        Some(())
+    }
+
+    fn generic_arg(&mut self) -> Option<()> {
+        if self.eat(b'L') {
+            let lt = self.base62()?;
+            return self.lifetime(lt);
+        }
+        if self.eat(b'K') {
+            return self.constant();
+        }
+        self.ty()
+    }
+
+    fn lifetime(&mut self, lt : u64) -> Option<()> {
+        if lt == 0 {
+            self.out.push_str("'_");
+            return Some(());
+        }
+        let depth = (self.bound as u64).checked_sub(lt)?;
+        match depth {
+            d if d < 26 => { let _ = write!(self.out, "'{}", (b'a' + d as u8) as char); }
+            d           => { let _ = write!(self.out, "'_{}", d); }
+        }
+        Some(())
+    }
+
+    fn binder(&mut self) -> Option<usize> {
+        // G <base-62-number> opens n + 1 lifetimes, printed as for<'a, 'b>
+        if !self.eat(b'G') {
+            return Some(0);
+        }
+        let n = self.base62()? as usize + 1;
+        self.out.push_str("for<");
+        for i in 0..n {
+            if i > 0 {
+                self.out.push_str(", ");
+            }
+            self.bound += 1;
+            self.lifetime(1)?;
+        }
+        self.out.push_str("> ");
+        Some(n)
+    }
+
+    fn ty(&mut self) -> Option<()> {
+        self.enter()?;
+        let c = self.next()?;
+        if let Some(&(_, name)) = BASIC.iter().find(|b| b.0 == c) {
+            self.out.push_str(name);
+            self.depth -= 1;
+            return Some(());
+        }
+        match c {
+            b'R' | b'Q' => {
+                self.out.push('&');
+                if self.eat(b'L') {
+                    let lt = self.base62()?;
+                    if lt != 0 {
+                        self.lifetime(lt)?;
+                        self.out.push(' ');
+                    }
+                }
+                if c == b'Q' {
+                    self.out.push_str("mut ");
+                }
+                self.ty()?;
+            }
+            b'P' => { self.out.push_str("*const "); self.ty()?; }
+            b'O' => { self.out.push_str("*mut "); self.ty()?; }
+            b'A' => {
+                self.out.push('[');
+                self.ty()?;
+                self.out.push_str("; ");
+                self.constant()?;
+                self.out.push(']');
+            }
+            b'S' => {
+                self.out.push('[');
+                self.ty()?;
+                self.out.push(']');
+            }
+            b'T' => {
+                self.out.push('(');
+                let mut n = 0;
+                while !self.eat(b'E') {
+                    if n > 0 {
+                        self.out.push_str(", ");
+                    }
+                    self.ty()?;
+                    n += 1;
+                }
+                if n == 1 {
+                    self.out.push(',');
+                }
+                self.out.push(')');
+            }
+            b'F' => {
+                let bound = self.binder()?;
+                if self.eat(b'U') {
+                    self.out.push_str("unsafe ");
+                }
+                if self.eat(b'K') {
+                    let abi = match self.eat(b'C') {
+                        true  => "C".to_string(),
+                        false => self.ident()?.replace('_', "-"),
+                    };
+                    let _ = write!(self.out, "extern \"{}\" ", abi);
+                }
+                self.out.push_str("fn(");
+                let mut first = true;
+                while !self.eat(b'E') {
+                    if !first {
+                        self.out.push_str(", ");
+                    }
+                    first = false;
+                    self.ty()?;
+                }
+                self.out.push(')');
+                if self.eat(b'u') {
+                    // unit return is omitted
+                } else {
+                    self.out.push_str(" -> ");
+                    self.ty()?;
+                }
+                self.bound -= bound;
+            }
+            b'D' => {
+                self.out.push_str("dyn ");
+                let bound = self.binder()?;
+                let mut first = true;
+                while !self.eat(b'E') {
+                    if !first {
+                        self.out.push_str(" + ");
+                    }
+                    first = false;
+                    self.dyn_trait()?;
+                }
+                self.bound -= bound;
+                if !self.eat(b'L') {
+                    return None;
+                }
+                let lt = self.base62()?;
+                if lt != 0 {
+                    self.out.push_str(" + ");
+                    self.lifetime(lt)?;
+                }
+            }
+            b'B' => {
+                let mut sub = self.backref()?;
+                sub.ty()?;
+                self.out.push_str(&sub.out);
+            }
+            _ => {
+                self.pos -= 1;
+                self.path(false)?;
+            }
+        }
+        self.depth -= 1;
+        Some(())
+    }
+
+    fn dyn_trait(&mut self) -> Option<()> {
+        // a trait path whose generic list is left open for associated type bindings
+        let open = self.path_maybe_open()?;
+        let mut open = open;
+        while self.eat(b'p') {
+            self.out.push_str(if open { ", " } else { "<" });
+            open = true;
+            let name = self.ident()?;
+            self.out.push_str(&name);
+            self.out.push_str(" = ");
+            self.ty()?;
+        }
+        if open {
+            self.out.push('>');
+        }
+        Some(())
+    }
+
+    fn path_maybe_open(&mut self) -> Option<bool> {
+        // like path(false) but leaves a trailing generic list unclosed, true if so
+        if self.peek() == Some(b'I') {
+            self.pos += 1;
+            self.path(false)?;
+            self.out.push('<');
+            let mut first = true;
+            while !self.eat(b'E') {
+                if !first {
+                    self.out.push_str(", ");
+                }
+                first = false;
+                self.generic_arg()?;
+            }
+            return Some(true);
+        }
+        if self.peek() == Some(b'B') {
+            self.pos += 1;
+            let mut sub = self.backref()?;
+            let open = sub.path_maybe_open()?;
+            self.out.push_str(&sub.out);
+            return Some(open);
+        }
+        self.path(false)?;
+        Some(false)
+    }
+
+    fn constant(&mut self) -> Option<()> {
+        self.enter()?;
+        match self.next()? {
+            b'p' => self.out.push('_'),
+            b'B' => {
+                let mut sub = self.backref()?;
+                sub.constant()?;
+                self.out.push_str(&sub.out);
+            }
+            c => {
+                let neg = self.eat(b'n');
+                let start = self.pos;
+                while self.peek().is_some_and(|c| c.is_ascii_hexdigit()) {
+                    self.pos += 1;
+                }
+                let hex = std::str::from_utf8(&self.s[start..self.pos]).ok()?;
+                if !self.eat(b'_') {
+                    return None;
+                }
+                let v = match hex {
+                    "" => 0,
+                    h  => u128::from_str_radix(h, 16).ok()?,
+                };
+                match c {
+                    b'b' => self.out.push_str(match v { 0 => "false", 1 => "true", _ => return None }),
+                    b'c' => {
+                        let ch = char::from_u32(u32::try_from(v).ok()?)?;
+                        let _ = write!(self.out, "{:?}", ch);
+                    }
+                    b'a' | b'h' | b'i' | b'j' | b'l' | b'm' | b'n' | b'o' | b's' | b't' | b'x' | b'y' => {
+                        let ty = BASIC.iter().find(|b| b.0 == c)?.1;
+                        let _ = write!(self.out, "{}{}{}", if neg { "-" } else { "" }, v, ty);
+                    }
+                    _ => return None,
+                }
+            }
+        }
+        self.depth -= 1;
+        Some(())
+    }
+}
+
+fn punycode(raw : &str) -> Option<String> {
+    // RFC 3492 decoding, '_' replaces '-' as the delimiter in Rust symbols
+    let (basic, encoded) = match raw.rfind('_') {
+        Some(i) => (&raw[..i], &raw[i + 1..]),
+        None    => ("", raw),
+    };
+    let mut out : Vec<char> = basic.chars().collect();
+    let (base, tmin, tmax, skew, damp) = (36u32, 1u32, 26u32, 38u32, 700u32);
+    let (mut n, mut i, mut bias) = (128u32, 0u32, 72u32);
+    let mut digits = encoded.bytes().peekable();
+
+    while digits.peek().is_some() {
+        let old_i = i;
+        let mut w = 1u32;
+        let mut k = base;
+        loop {
+            let c = digits.next()?;
+            let d = match c {
+                b'a'..=b'z' => c - b'a',
+                b'0'..=b'9' => c - b'0' + 26,
+                _           => return None,
+            } as u32;
+            i = i.checked_add(d.checked_mul(w)?)?;
+            let t = if k <= bias { tmin } else if k >= bias + tmax { tmax } else { k - bias };
+            if d < t {
+                break;
+            }
+            w = w.checked_mul(base - t)?;
+            k += base;
+        }
+        let len = out.len() as u32 + 1;
+        // adapt the bias
+        let mut delta = if old_i == 0 { (i - old_i) / damp } else { (i - old_i) / 2 };
+        delta += delta / len;
+        let mut k = 0;
+        while delta > ((base - tmin) * tmax) / 2 {
+            delta /= base - tmin;
+            k += base;
+        }
+        bias = k + (base - tmin + 1) * delta / (delta + skew);
+
+        n = n.checked_add(i / len)?;
+        i %= len;
+        out.insert(i as usize, char::from_u32(n)?);
+        i += 1;
+    }
+    Some(out.into_iter().collect())
+}
@@ -0,0 +1,333 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::mem;
+use std::path::Path;
+use colored::Colorize;
+
+use crate::binbuf::{BinBuf, c_str};
+use crate::parser::Parser;
+use crate::parser::ldcache::LdCache;
+use crate::parser::linkcheck::check_links;
+use crate::parser::elf::elf_struct::{Elf64Ehdr, EM_X86_64, EM_AARCH64, EM_RISCV};
+use crate::parser::elf::dynamic::{DT_NEEDED, DT_SONAME, DT_RPATH, DT_RUNPATH, DT_FLAGS_1, DF_1_NODEFLIB};
+use crate::parser::elf::segments::segtype::SegmentType;
+
+pub const LD_SO_CACHE : &str = "/etc/ld.so.cache";
+
+#[derive(Debug)]
+pub struct DepRow {
+    // one line of the tree, a library already shown once is not expanded again
+    pub depth : usize,
+    pub name  : String,
+    pub path  : Option<String>,     /* None if not found */
+    pub seen  : bool,
+}
+
+pub struct DepReport {
+    pub interp     : Option<String>,
+    pub rows       : Vec<DepRow>,
+    pub unresolved : Vec<(String, String)>,    /* (object, symbol), missing versions included */
+}
+
+impl DepReport {
+    pub fn missing(&self) -> impl Iterator<Item = &str> {
+        self.rows.iter().filter(|r| r.path.is_none() && !r.seen).map(|r| r.name.as_str())
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.missing().next().is_none() && self.unresolved.is_empty()
+    }
+
+    pub fn show(&self, root : &str) {
+        if let Some(interp) = &self.interp {
+            println!("{} {}", "interpreter".yellow(), interp);
+        }
+        println!("{}", root.red());
+        for row in &self.rows {
+            let indent = "    ".repeat(row.depth);
+            match (&row.path, row.seen) {
+                (Some(path), false) => println!("{}{} => {}", indent, row.name.blue(), path),
+                (Some(path), true)  => println!("{}{} => {} {}", indent, row.name.blue(), path, "(already listed)".yellow()),
+                (None, _)           => println!("{}{} => {}", indent, row.name.blue(), "not found".red()),
+            }
+        }
+        if self.unresolved.is_empty() {
+            return;
+        }
+        println!("{} {}", "unresolved symbols:".red(), self.unresolved.len());
+        for (object, sym) in &self.unresolved {
+            println!("    {:<40} {}", sym, object);
+        }
+    }
+}
+
+type Chain = Vec<(Vec<String>, bool)>;
+
+struct Resolver<'a> {
+    cache   : Option<&'a LdCache>,
+    machine : u16,
+    mapped  : HashSet<String>,                              /* paths of every object loaded so far */
+    names   : HashMap<String, String>,                      /* DT_NEEDED name or soname -> path it was mapped from */
+    needs   : HashMap<String, Vec<(String, Option<String>)>>, /* path -> its DT_NEEDED and where each was found */
+}
+
+impl Parser {
+    pub fn interp(&self) -> Option<String> {
+        let seg = self.segments.get_segment(SegmentType::PT_INTERP)?;
+        let start = seg.phdr.p_offset as usize;
+        self.binbuf.buf.get(start..start.checked_add(seg.phdr.p_filesz as usize)?).map(c_str)
+    }
+
+    pub fn dyn_strs(&self, tag : i64) -> Vec<String> {
+        // string values of a tag like DT_NEEDED, DT_SONAME or DT_RUNPATH
+        self.dynamic.as_ref().map(|d| d.get_all(tag).iter().filter_map(|e| e.val_str.clone()).collect()).unwrap_or_default()
+    }
+
+    pub fn needed(&self) -> Vec<String> {
+        self.dyn_strs(DT_NEEDED)
+    }
+
+    pub fn resolve_deps(&self, cache : Option<&LdCache>) -> DepReport {
+        // what ld.so would map for this file, found by reading headers only, nothing is executed
+        let (rows, loaded) = self.map_deps(cache);
+
+        let mut objects : Vec<(&str, &Parser)> = vec![(self.binbuf.name(), self)];
+        objects.extend(loaded.iter().map(|(path, parser)| (path.as_str(), parser)));
+        // missing versions stop ld.so as much as missing symbols do
+        let unresolved = check_links(&objects).findings.iter()
+            .filter(|f| f.is_error())
+            .map(|f| (f.object().to_string(), f.symbol()))
+            .collect();
+
+        DepReport { interp : self.interp(), rows, unresolved }
+    }
+
+    pub fn map_deps(&self, cache : Option<&LdCache>) -> (Vec<DepRow>, Vec<(String, Parser)>) {
+        // the dependency tree and every library it maps, in load order
+        let path = self.binbuf.name();
+        let origin = std::fs::canonicalize(path).ok()
+            .and_then(|p| p.parent().map(|d| d.to_string_lossy().into_owned()))
+            .unwrap_or_else(|| ".".to_string());
+
+        let mut resolver = Resolver { cache, machine : self.ehdr.e_machine, mapped : HashSet::new(), names : HashMap::new(), needs : HashMap::new() };
+        // the interpreter is mapped first, DT_NEEDED entries of ld-linux name that copy
+        if let Some(interp) = self.interp().filter(|p| is_loadable(p, self.ehdr.e_machine)) {
+            if let Some(name) = Path::new(&interp).file_name() {
+                resolver.names.insert(name.to_string_lossy().into_owned(), interp.clone());
+            }
+        }
+
+        // breadth first like ld.so, a library may rely on a sibling its loader mapped earlier
+        let mut loaded : Vec<(String, Parser)> = vec![];
+        let (chain, new) = resolver.visit(path, self, &origin, &[]);
+        let mut queue : VecDeque<_> = new.into_iter().map(|n| (n, chain.clone())).collect();
+        while let Some(((path, parser), loaders)) = queue.pop_front() {
+            let origin = Path::new(&path).parent().map(|d| d.to_string_lossy().into_owned()).unwrap_or_default();
+            let (chain, new) = resolver.visit(&path, &parser, &origin, &loaders);
+            queue.extend(new.into_iter().map(|n| (n, chain.clone())));
+            loaded.push((path, parser));
+        }
+
+        let mut rows = vec![];
+        let mut shown = HashSet::new();
+        resolver.rows(path, 0, &mut shown, &mut rows);
+        (rows, loaded)
+    }
+}
+
+impl<'a> Resolver<'a> {
+    fn visit(&mut self, path : &str, obj : &Parser, origin : &str, loaders : &[(Vec<String>, bool)]) -> (Chain, Vec<(String, Parser)>) {
+        // find the DT_NEEDED of obj, returns its loader chain and the objects mapped for the first time
+        // loaders: (DT_RPATH dirs, has DT_RUNPATH) of every object on the way from the executable
+        let expand = |dirs : Vec<String>| -> Vec<String> {
+            dirs.iter().flat_map(|d| d.split(':')).filter(|d| !d.is_empty()).map(|d| expand_dst(d, origin, self.machine)).collect()
+        };
+        let runpath = expand(obj.dyn_strs(DT_RUNPATH));
+        let mut chain = vec![(expand(obj.dyn_strs(DT_RPATH)), !runpath.is_empty())];
+        chain.extend(loaders.iter().cloned());
+        let nodeflib = obj.dynamic.as_ref().is_some_and(|d| d.has_flag(DT_FLAGS_1, DF_1_NODEFLIB));
+
+        let mut needs = vec![];
+        let mut new = vec![];
+        for name in obj.needed() {
+            // ld.so reuses an object already mapped under that name before searching
+            let found = match self.names.get(&name) {
+                Some(p) => Some(p.clone()),
+                None    => self.search(&name, &chain, &runpath, nodeflib),
+            };
+            needs.push((name.clone(), found.clone()));
+
+            let found = match found {
+                Some(p) if !self.mapped.contains(&p) => p,
+                _ => continue,
+            };
+            if let Some(parser) = load(&found, self.machine) {
+                self.mapped.insert(found.clone());
+                self.names.insert(name, found.clone());
+                if let Some(soname) = parser.dyn_strs(DT_SONAME).pop() {
+                    self.names.insert(soname, found.clone());
+                }
+                new.push((found, parser));
+            }
+        }
+        self.needs.insert(path.to_string(), needs);
+        (chain, new)
+    }
+
+    fn rows(&self, path : &str, depth : usize, shown : &mut HashSet<String>, rows : &mut Vec<DepRow>) {
+        // the tree depth first, every library expanded under its first appearance only
+        for (name, found) in self.needs.get(path).into_iter().flatten() {
+            let seen = found.as_ref().is_some_and(|p| !shown.insert(p.clone()));
+            rows.push(DepRow { depth, name : name.clone(), path : found.clone(), seen });
+            if let (Some(p), false) = (found, seen) {
+                self.rows(p, depth + 1, shown, rows);
+            }
+        }
+    }
+
+    fn search(&self, name : &str, chain : &[(Vec<String>, bool)], runpath : &[String], nodeflib : bool) -> Option<String> {
+        // ld.so order: DT_RPATH up the loader chain unless DT_RUNPATH is set, DT_RUNPATH, ld.so.cache, default dirs
+        if name.contains('/') {
+            return Some(name.to_string()).filter(|p| is_loadable(p, self.machine));
+        }
+        let try_dirs = |dirs : &[String]| dirs.iter().map(|d| format!("{}/{}", d.trim_end_matches('/'), name)).find(|p| is_loadable(p, self.machine));
+
+        if !chain[0].1 {
+            // a loader with DT_RUNPATH contributes no DT_RPATH
+            for (rpath, _) in chain.iter().filter(|(_, has_runpath)| !has_runpath) {
+                if let Some(p) = try_dirs(rpath) {
+                    return Some(p);
+                }
+            }
+        }
+        if let Some(p) = try_dirs(runpath) {
+            return Some(p);
+        }
+        if nodeflib {
+            return None;
+        }
+        if let Some(p) = self.cache.and_then(|c| c.lookup(name, self.machine)).filter(|p| is_loadable(p, self.machine)) {
+            return Some(p.to_string());
+        }
+        try_dirs(&default_dirs(self.machine))
+    }
+}
+
+fn expand_dst(dir : &str, origin : &str, machine : u16) -> String {
+    // dynamic string tokens of DT_RPATH/DT_RUNPATH
+    dir.replace("${ORIGIN}", origin).replace("$ORIGIN", origin)
+       .replace("${LIB}", &lib_dir(machine)).replace("$LIB", &lib_dir(machine))
+       .replace("${PLATFORM}", platform(machine)).replace("$PLATFORM", platform(machine))
+}
+
+fn platform(machine : u16) -> &'static str {
+    match machine {
+        EM_X86_64  => "x86_64",
+        EM_AARCH64 => "aarch64",
+        EM_RISCV   => "riscv64",
+        _          => "",
+    }
+}
+
+fn default_dirs(machine : u16) -> Vec<String> {
+    // glibc's built-in search path, with the Debian multiarch directories first
+    let triplet = format!("{}-linux-gnu", platform(machine));
+    vec![format!("/lib/{}", triplet), format!("/usr/lib/{}", triplet),
+         "/lib64".to_string(), "/usr/lib64".to_string(), "/lib".to_string(), "/usr/lib".to_string()]
+}
+
+fn lib_dir(machine : u16) -> String {
+    // $LIB is the system library directory below / or /usr: lib/x86_64-linux-gnu on Debian, lib64 on Fedora
+    let dirs = default_dirs(machine);
+    let dir = dirs.iter().find(|d| Path::new(d).is_dir()).unwrap_or(&dirs[0]);
+    dir.trim_start_matches("/usr").trim_start_matches('/').to_string()
+}
+
+fn is_loadable(path : &str, machine : u16) -> bool {
+    // ld.so skips files of another class or machine and keeps searching, a short file is no library either
+    let mut ehdr = [0u8; mem::size_of::<Elf64Ehdr>()];
+    match std::fs::File::open(path) {
+        Ok(mut f) => std::io::Read::read_exact(&mut f, &mut ehdr).is_ok()
+            && Parser::check_elf(&ehdr).is_ok()
+            && u16::from_le_bytes([ehdr[18], ehdr[19]]) == machine,
+        Err(_) => false,
+    }
+}
+
+fn load(path : &str, machine : u16) -> Option<Parser> {
+    if !is_loadable(path, machine) {
+        return None;
+    }
+    let buf = std::fs::read(path).ok()?;
+    Some(Parser::from_binbuf(BinBuf::from_bytes(path, buf)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn copy_fixtures(dir : &str, names : &[&str]) -> String {
+        // a private directory with some of the test/testlink* files, $ORIGIN finds only those
+        let dir = std::env::temp_dir().join(format!("exparser_deps_{}_{}", dir, std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        for name in names {
+            std::fs::copy(format!("test/{}", name), dir.join(name)).unwrap();
+        }
+        dir.to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn test_resolve_deps() {
+        // testlink -> libtestbase.so.1 -> libtestleaf.so, all through DT_RUNPATH $ORIGIN
+        let parser = Parser::new("test/testlink");
+        let report = parser.resolve_deps(None);
+        assert_eq!(report.interp.as_deref(), Some("/lib64/ld-linux-x86-64.so.2"));
+        let rows : Vec<(usize, &str)> = report.rows.iter().map(|r| (r.depth, r.name.as_str())).collect();
+        assert_eq!(rows, [(0, "libtestbase.so.1"), (1, "libtestleaf.so")]);
+        assert!(report.rows[1].path.as_ref().unwrap().ends_with("test/libtestleaf.so"));
+        assert!(report.is_complete(), "{:?} {:?}", report.rows, report.unresolved);
+    }
+    #[test]
+    fn test_missing_deps() {
+        // without the leaf library, base_fn's leaf_fn cannot bind either
+        let dir = copy_fixtures("missing", &["testlink", "libtestbase.so.1"]);
+        let report = Parser::new(&format!("{}/testlink", dir)).resolve_deps(None);
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(report.missing().collect::<Vec<_>>(), ["libtestleaf.so"]);
+        assert_eq!(report.unresolved.len(), 1);
+        assert!(report.unresolved[0].0.ends_with("/libtestbase.so.1") && report.unresolved[0].1 == "leaf_fn");
+        assert!(!report.is_complete());
+    }
+    #[test]
+    fn test_missing_version() {
+        // every library is found, but libtestbase.so.1 does not define the version base_new asks for
+        let dir = copy_fixtures("version", &["libtestbase.so.1", "libtestleaf.so"]);
+        let mut buf = std::fs::read("test/testlink").unwrap();
+        let pos = buf.windows(12).position(|w| w == b"TESTBASE_2.0").unwrap();
+        buf[pos..pos + 12].copy_from_slice(b"TESTBASE_2.9");
+        std::fs::write(format!("{}/testlink", dir), buf).unwrap();
+
+        let report = Parser::new(&format!("{}/testlink", dir)).resolve_deps(None);
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(report.missing().count(), 0);
+        assert_eq!(report.unresolved.len(), 1, "{:?}", report.unresolved);
+        assert!(report.unresolved[0].0.ends_with("/testlink") && report.unresolved[0].1.starts_with("base_new"));
+        assert!(!report.is_complete());
+    }
+    #[test]
+    fn test_truncated_dep() {
+        // an ELF ident with nothing behind it is skipped like a library of another machine
+        let dir = copy_fixtures("truncated", &["testlink", "libtestbase.so.1"]);
+        let leaf = std::fs::read("test/libtestleaf.so").unwrap();
+        std::fs::write(format!("{}/libtestleaf.so", dir), &leaf[..32]).unwrap();
+        let report = Parser::new(&format!("{}/testlink", dir)).resolve_deps(None);
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(report.missing().collect::<Vec<_>>(), ["libtestleaf.so"]);
+    }
+    #[test]
+    fn test_expand_dst() {
+        assert_eq!(expand_dst("$ORIGIN/../lib", "/opt/app/bin", EM_X86_64), "/opt/app/bin/../lib");
+        let lib = lib_dir(EM_X86_64);
+        assert!(default_dirs(EM_X86_64).iter().any(|d| d.ends_with(&format!("/{}", lib))));
+        assert_eq!(expand_dst("${ORIGIN}/$LIB/$PLATFORM", "/opt", EM_X86_64), format!("/opt/{}/x86_64", lib));
+    }
+}
@@ -0,0 +1,357 @@
+use colored::Colorize;
+use std::collections::{BTreeMap, HashMap};
+
+use crate::parser::Parser;
+use crate::json::json_str;
+use crate::parser::elf::elf_struct::{Elf64Sym, PF_R, PF_W, PF_X, SHN_UNDEF, SHT_NOBITS, STT_FILE, STT_SECTION};
+use crate::parser::elf::dynamic::*;
+use crate::parser::symbols::{get_sym_bind_str, get_sym_type_str};
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Kind {
+    Added,
+    Removed,
+    Changed,
+}
+
+#[derive(Debug)]
+pub struct Change {
+    pub kind   : Kind,
+    pub scope  : &'static str,  /* header, segment, section, dynamic, dynsym or symbol */
+    pub name   : String,
+    pub detail : String,        /* "old -> new" for each changed field */
+    pub delta  : i64,           /* size growth in bytes from a to b */
+}
+
+pub struct ElfDiff {
+    pub changes   : Vec<Change>,
+    pub file_size : (u64, u64),
+}
+
+struct Item {
+    // one comparable entry: its size and the fields that must stay equal
+    size   : u64,
+    fields : Vec<(&'static str, String)>,
+}
+
+/* tags whose d_val is a size or count, the rest are addresses that move between builds */
+const DYN_VALUE_TAGS : [i64; 16] = [
+    DT_PLTRELSZ, DT_RELASZ, DT_RELAENT, DT_STRSZ, DT_SYMENT, DT_RELSZ, DT_RELENT, DT_PLTREL,
+    DT_INIT_ARRAYSZ, DT_FINI_ARRAYSZ, DT_FLAGS, DT_RELACOUNT, DT_RELCOUNT, DT_FLAGS_1,
+    DT_VERDEFNUM, DT_VERNEEDNUM,
+];
+
+impl Parser {
+    pub fn diff(&self, other : &Parser) -> ElfDiff {
+        let mut changes = vec![];
+
+        self.diff_header(other, &mut changes);
+        compare(&mut changes, "segment", self.segment_items(), other.segment_items());
+        compare(&mut changes, "section", self.section_items(), other.section_items());
+        compare(&mut changes, "dynamic", self.dynamic_items(), other.dynamic_items());
+        compare(&mut changes, "dynsym", sym_items(self.dynsym.iter().map(|s| (&s.sym, s.str.as_str()))),
+                                        sym_items(other.dynsym.iter().map(|s| (&s.sym, s.str.as_str()))));
+
+        // recovered sub_* names depend on addresses, only real .symtab are worth comparing
+        if let (Some(a), Some(b)) = (&self.symtab, &other.symtab) {
+            compare(&mut changes, "symbol", sym_items(a.iter().map(|s| (&s.sym, s.str.as_str()))),
+                                            sym_items(b.iter().map(|s| (&s.sym, s.str.as_str()))));
+        }
+
+        // symbols are matched by mangled name, shown in the chosen style
+        for c in changes.iter_mut().filter(|c| c.scope == "dynsym" || c.scope == "symbol") {
+            c.name = match c.name.split_once('#') {
+                Some((name, n)) => format!("{}#{}", self.display_name(name), n),
+                None            => self.display_name(&c.name),
+            };
+        }
+
+        ElfDiff {
+            changes,
+            file_size : (self.binbuf.buf.len() as u64, other.binbuf.buf.len() as u64),
+        }
+    }
+
+    fn diff_header(&self, other : &Parser, changes : &mut Vec<Change>) {
+        let (a, b) = (&self.ehdr, &other.ehdr);
+        let ident = |i : &[u8]| i.iter().map(|b| format!("{:02x}", b)).collect::<String>();
+        let stripped = |p : &Parser| if p.symtab.is_some() { "no" } else { "yes" };
+
+        let fields = [
+            ("e_ident",    ident(&a.e_ident),             ident(&b.e_ident)),
+            ("e_type",     a.e_type.to_string(),          b.e_type.to_string()),
+            ("e_machine",  a.e_machine.to_string(),       b.e_machine.to_string()),
+            ("e_version",  a.e_version.to_string(),       b.e_version.to_string()),
+            ("e_entry",    format!("{:#x}", a.e_entry),   format!("{:#x}", b.e_entry)),
+            ("e_phoff",    format!("{:#x}", a.e_phoff),   format!("{:#x}", b.e_phoff)),
+            ("e_shoff",    format!("{:#x}", a.e_shoff),   format!("{:#x}", b.e_shoff)),
+            ("e_flags",    format!("{:#x}", a.e_flags),   format!("{:#x}", b.e_flags)),
+            ("e_phnum",    a.e_phnum.to_string(),         b.e_phnum.to_string()),
+            ("e_shnum",    a.e_shnum.to_string(),         b.e_shnum.to_string()),
+            ("e_shstrndx", a.e_shstrndx.to_string(),      b.e_shstrndx.to_string()),
+            ("stripped",   stripped(self).to_string(),    stripped(other).to_string()),
+        ];
+
+        for (name, old, new) in fields {
+            if old != new {
+                changes.push(Change {
+                    kind   : Kind::Changed,
+                    scope  : "header",
+                    name   : name.to_string(),
+                    detail : format!("{} -> {}", old, new),
+                    delta  : 0,
+                });
+            }
+        }
+    }
+
+    fn segment_items(&self) -> BTreeMap<String, Item> {
+        // segments have no name, key them by type and position among the same type
+        let mut items = Keyed::default();
+        for seg in &self.segments.segs {
+            let p = &seg.phdr;
+            let flags = [(PF_R, 'R'), (PF_W, 'W'), (PF_X, 'E')].iter()
+                .map(|&(f, c)| if p.p_flags & f != 0 { c } else { ' ' })
+                .collect::<String>();
+            items.insert(&seg.name, Item {
+                size   : p.p_memsz,
+                fields : vec![
+                    ("flags",  flags),
+                    ("vaddr",  format!("{:#x}", p.p_vaddr)),
+                    ("filesz", format!("{:#x}", p.p_filesz)),
+                    ("memsz",  format!("{:#x}", p.p_memsz)),
+                ],
+            });
+        }
+        items.map
+    }
+
+    fn section_items(&self) -> BTreeMap<String, Item> {
+        let mut items = Keyed::default();
+        for sec in self.sections.secs.iter().skip(1) {
+            let s = &sec.shdr;
+            let hash = match s.sh_type {
+                SHT_NOBITS => "-".to_string(),
+                _ => {
+                    let start = s.sh_offset as usize;
+                    let data = self.binbuf.buf.get(start..start + s.sh_size as usize).unwrap_or(&[]);
+                    format!("{:016x}", fnv1a(data))
+                }
+            };
+            items.insert(sec.name.trim_end_matches('\x00'), Item {
+                size   : s.sh_size,
+                fields : vec![
+                    ("type",  s.sh_type.to_string()),
+                    ("flags", format!("{:#x}", s.sh_flags)),
+                    ("size",  format!("{:#x}", s.sh_size)),
+                    ("hash",  hash),
+                ],
+            });
+        }
+        items.map
+    }
+
+    fn dynamic_items(&self) -> BTreeMap<String, Item> {
+        let mut items = Keyed::default();
+        let entries = match &self.dynamic {
+            Some(dyns) => &dyns.entries[..],
+            None       => &[],
+        };
+        for e in entries {
+            let tag = e.entry.d_tag;
+            if tag == DT_NULL {
+                continue;
+            }
+            // string entries are keyed by value so that reordering DT_NEEDED is not a change
+            let (key, fields) = match &e.val_str {
+                Some(s) => (format!("{} {}", e.name, s), vec![]),
+                None if DYN_VALUE_TAGS.contains(&tag) => (e.name.clone(), vec![("value", format!("{:#x}", e.entry.d_val))]),
+                None => (e.name.clone(), vec![]),
+            };
+            items.insert(&key, Item { size : 0, fields });
+        }
+        items.map
+    }
+}
+
+fn sym_items<'a>(syms : impl Iterator<Item = (&'a Elf64Sym, &'a str)>) -> BTreeMap<String, Item> {
+    let mut items = Keyed::default();
+    for (sym, name) in syms {
+        if name.is_empty() || sym.st_type() == STT_SECTION || sym.st_type() == STT_FILE {
+            continue;
+        }
+        items.insert(name, Item {
+            size   : sym.st_size,
+            fields : vec![
+                ("type",    get_sym_type_str(sym.st_type())),
+                ("bind",    get_sym_bind_str(sym.st_bind())),
+                ("defined", (sym.st_shndx != SHN_UNDEF).to_string()),
+                ("size",    format!("{:#x}", sym.st_size)),
+            ],
+        });
+    }
+    items.map
+}
+
+#[derive(Default)]
+struct Keyed {
+    // map of items, repeated keys (LOAD, static functions, ...) get a #n suffix
+    map  : BTreeMap<String, Item>,
+    seen : HashMap<String, usize>,
+}
+
+impl Keyed {
+    fn insert(&mut self, key : &str, item : Item) {
+        let n = self.seen.entry(key.to_string()).or_insert(0);
+        let key = match *n {
+            0 => key.to_string(),
+            i => format!("{}#{}", key, i),
+        };
+        *n += 1;
+        self.map.insert(key, item);
+    }
+}
+
+fn compare(changes : &mut Vec<Change>, scope : &'static str, a : BTreeMap<String, Item>, mut b : BTreeMap<String, Item>) {
+    for (name, old) in a {
+        let Some(new) = b.remove(&name) else {
+            changes.push(Change {
+                kind   : Kind::Removed,
+                detail : describe(&old),
+                delta  : -(old.size as i64),
+                scope,
+                name,
+            });
+            continue;
+        };
+        let detail = old.fields.iter().zip(&new.fields)
+            .filter(|(o, n)| o.1 != n.1)
+            .map(|(o, n)| format!("{} {} -> {}", o.0, o.1, n.1))
+            .collect::<Vec<String>>();
+        if !detail.is_empty() {
+            changes.push(Change {
+                kind   : Kind::Changed,
+                detail : detail.join(", "),
+                delta  : new.size as i64 - old.size as i64,
+                scope,
+                name,
+            });
+        }
+    }
+    for (name, new) in b {
+        changes.push(Change {
+            kind   : Kind::Added,
+            detail : describe(&new),
+            delta  : new.size as i64,
+            scope,
+            name,
+        });
+    }
+}
+
+fn describe(item : &Item) -> String {
+    // fields of an added/removed item, the hash says nothing on its own
+    item.fields.iter()
+        .filter(|f| f.0 != "hash")
+        .map(|f| format!("{} {}", f.0, f.1))
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+fn fnv1a(data : &[u8]) -> u64 {
+    // 64-bit FNV-1a, enough to tell whether section content changed
+    data.iter().fold(0xcbf29ce484222325, |h, &b| (h ^ b as u64).wrapping_mul(0x100000001b3))
+}
+
+impl ElfDiff {
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    pub fn count(&self, kind : Kind) -> usize {
+        self.changes.iter().filter(|c| c.kind == kind).count()
+    }
+
+    pub fn size_delta(&self) -> i64 {
+        self.file_size.1 as i64 - self.file_size.0 as i64
+    }
+
+    pub fn show(&self) -> &Self {
+        for c in &self.changes {
+            let mark = match c.kind {
+                Kind::Added   => "+".green(),
+                Kind::Removed => "-".red(),
+                Kind::Changed => "~".yellow(),
+            };
+            let delta = match c.delta {
+                0 => String::new(),
+                d => format!("{:+}", d),
+            };
+            let line = format!("{} {:<8} {:<32} {:>8}  {}", mark, c.scope.blue(), c.name, delta, c.detail);
+            println!("{}", line.trim_end());
+        }
+        println!("{} added, {} removed, {} changed, file size {:#x} -> {:#x} ({:+})",
+            self.count(Kind::Added),
+            self.count(Kind::Removed),
+            self.count(Kind::Changed),
+            self.file_size.0,
+            self.file_size.1,
+            self.size_delta());
+        self
+    }
+
+    pub fn to_json(&self) -> String {
+        let changes = self.changes.iter().map(|c| {
+            let kind = match c.kind {
+                Kind::Added   => "added",
+                Kind::Removed => "removed",
+                Kind::Changed => "changed",
+            };
+            format!("{{\"kind\":\"{}\",\"scope\":\"{}\",\"name\":{},\"detail\":{},\"delta\":{}}}",
+                kind, c.scope, json_str(&c.name), json_str(&c.detail), c.delta)
+        }).collect::<Vec<String>>();
+
+        format!("{{\"identical\":{},\"size_delta\":{},\"changes\":[{}]}}",
+            self.is_empty(),
+            self.size_delta(),
+            changes.join(","))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn test_diff_self() {
+        let parser = Parser::new("test/testgot");
+        let diff = parser.diff(&Parser::new("test/testgot"));
+        assert!(diff.is_empty());
+        assert_eq!(diff.size_delta(), 0);
+        assert!(diff.to_json().starts_with("{\"identical\":true,\"size_delta\":0"));
+    }
+    #[test]
+    fn test_diff_not_elf() {
+        // diff exits 2 on these instead of comparing garbage
+        assert!(Parser::open("test/test.c").err().unwrap().contains("too small for an ELF header"));
+        assert!(Parser::open("test/testmod.c").err().unwrap().contains("not an ELF file"));
+        assert!(Parser::open("test/nosuchfile").is_err());
+        assert!(Parser::open("test/testgot").unwrap().diff(&Parser::open("test/testgot").unwrap()).is_empty());
+    }
+    #[test]
+    fn test_diff_stripped() {
+        /*
+        testgot_stripped is testgot run through strip: .symtab/.strtab are gone, the rest is equal
+         */
+        let a = Parser::new("test/testgot");
+        let b = Parser::new("test/testgot_stripped");
+        let diff = a.diff(&b);
+        let find = |scope, name| diff.changes.iter().find(|c| c.scope == scope && c.name == name);
+
+        assert_eq!(find("section", ".symtab").map(|c| c.kind), Some(Kind::Removed));
+        assert_eq!(find("section", ".strtab").map(|c| c.kind), Some(Kind::Removed));
+        assert!(find("header", "stripped").is_some_and(|c| c.detail == "no -> yes"));
+        assert!(find("section", ".text").is_none());
+        assert!(!diff.changes.iter().any(|c| c.scope == "dynsym" || c.scope == "dynamic"));
+        assert!(diff.size_delta() < 0);
+    }
+}
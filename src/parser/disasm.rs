@@ -0,0 +1,353 @@
+pub mod x86_64;
+pub mod aarch64;
+pub mod riscv;
+
+use colored::Colorize;
+
+use crate::parser::Parser;
+use crate::parser::demangle::demangle;
+use crate::parser::elf::elf_struct::{EM_X86_64, EM_AARCH64, EM_RISCV, SHN_UNDEF, SHT_NOBITS};
+
+#[derive(Debug, Clone)]
+pub struct Insn {
+    pub addr     : u64,
+    pub len      : usize,
+    pub mnemonic : String,
+    pub operands : String,
+    pub target   : Option<u64>,     /* destination of a direct call/jmp */
+    pub mem_ref  : Option<u64>,     /* pc-relative memory address, e.g. a GOT slot */
+}
+
+/* register -> page loaded by adrp/auipc, resolves the low half of such a pair */
+pub type Pages = [Option<u64>; 32];
+
+impl Insn {
+    fn bad(machine : u16, buf : &[u8], addr : u64) -> Self {
+        // undecodable bytes, skipped by the smallest instruction size
+        let (len, mnemonic) = match machine {
+            EM_AARCH64 => (4, "<unknown>"),
+            EM_RISCV => (riscv::insn_len(buf), "<unknown>"),
+            _ => (1, "(bad)"),
+        };
+        Insn {
+            addr,
+            len      : len.min(buf.len()),
+            mnemonic : mnemonic.to_string(),
+            operands : String::new(),
+            target   : None,
+            mem_ref  : None,
+        }
+    }
+}
+
+pub fn decode(machine : u16, buf : &[u8], addr : u64, pages : &mut Pages) -> Option<Insn> {
+    // one instruction of the given architecture, None if undecodable
+    match machine {
+        EM_X86_64 => x86_64::decode(buf, addr),
+        EM_AARCH64 => aarch64::decode(buf, addr, pages),
+        EM_RISCV => riscv::decode(buf, addr, pages),
+        _ => None,
+    }
+}
+
+pub fn is_supported(machine : u16) -> bool {
+    matches!(machine, EM_X86_64 | EM_AARCH64 | EM_RISCV)
+}
+
+pub fn sweep(machine : u16, buf : &[u8], start : u64) -> Vec<Insn> {
+    // linear sweep over buf loaded at start, an undecodable byte becomes (bad)
+    let mut insns = vec![];
+    let mut off = 0;
+    let mut pages = [None; 32];
+
+    while off < buf.len() {
+        let addr = start + off as u64;
+        let insn = decode(machine, &buf[off..], addr, &mut pages).unwrap_or_else(|| Insn::bad(machine, &buf[off..], addr));
+        off += insn.len;
+        insns.push(insn);
+    }
+    insns
+}
+
+impl Parser {
+    pub fn code_bytes(&self, start : u64, end : u64, shndx : Option<usize>) -> Result<&[u8], String> {
+        // a section of a relocatable object is addressed by offset, anything else through the PT_LOAD it is in
+        let not_backed = || format!("{:#x}..{:#x}: range not file-backed / not mapped", start, end);
+        let (base, offset, size) = match shndx {
+            Some(i) => {
+                let sec = self.sections.secs.get(i).filter(|s| s.shdr.sh_type != SHT_NOBITS).ok_or_else(not_backed)?;
+                (sec.shdr.sh_addr, sec.shdr.sh_offset, sec.shdr.sh_size)
+            }
+            None => {
+                let seg = self.segments.loadable()
+                    .find(|s| start >= s.phdr.p_vaddr && start - s.phdr.p_vaddr < s.phdr.p_filesz)
+                    .ok_or_else(not_backed)?;
+                (seg.phdr.p_vaddr, seg.phdr.p_offset, seg.phdr.p_filesz)
+            }
+        };
+        if start < base || end < start || end - base > size {
+            return Err(not_backed());
+        }
+        let off = offset.checked_add(start - base).ok_or_else(not_backed)?;
+        let len = end - start;
+        off.checked_add(len)
+            .and_then(|e| self.binbuf.buf.get(off as usize..e as usize))
+            .ok_or_else(not_backed)
+    }
+
+    pub fn symbol_range(&self, name : &str) -> Option<(u64, u64)> {
+        // [start, end) of a symbol, unsized ones run to the next symbol
+        let index = match self.symbol_shndx(name) {
+            Some(i) => self.section_symbol_index(i),
+            None => self.symbol_index(),
+        };
+        // either form of a mangled name is accepted
+        let sym = index.iter().find(|s| s.name == name || demangle(&s.name).as_deref() == Some(name))?;
+        if sym.size() != 0 {
+            return Some((sym.start, sym.end));
+        }
+        Some((sym.start, index.next(sym.start)?.start))
+    }
+
+    pub fn section_range(&self, name : &str) -> Option<(u64, u64)> {
+        let sec = self.sections.get_section(name)?;
+        Some((sec.shdr.sh_addr, sec.shdr.sh_addr + sec.shdr.sh_size))
+    }
+
+    pub fn symbol_shndx(&self, name : &str) -> Option<usize> {
+        // the section a symbol of a relocatable object is an offset into, None for anything linked
+        if !self.is_relocatable() {
+            return None;
+        }
+        self.symbol_tables()
+            .flat_map(|t| t.iter())
+            .find(|s| s.sym.st_shndx != SHN_UNDEF && (s.str == name || demangle(&s.str).as_deref() == Some(name)))
+            .map(|s| s.sym.st_shndx as usize)
+    }
+
+    pub fn section_shndx(&self, name : &str) -> Option<usize> {
+        self.sections.index_of(name).filter(|_| self.is_relocatable())
+    }
+
+    pub fn show_disasm(&self, start : u64, end : u64, shndx : Option<usize>) -> Result<&Self, String> {
+
+        if !is_supported(self.ehdr.e_machine) {
+            return Err(format!("disassembly of e_machine {} is not supported", self.ehdr.e_machine));
+        }
+        let code = self.code_bytes(start, end, shndx)?;
+
+        let index = match shndx {
+            Some(i) => self.section_symbol_index(i),
+            None => self.symbol_index(),
+        };
+        let got = self.got_names();
+
+        let label = |addr : u64| -> Option<String> {
+            // enclosing symbol, else the nearest one before addr, as name or name+off
+            let sym = index.containing(addr).or_else(|| index.prev(addr))?;
+            let name = self.display_name(&sym.name);
+            if sym.start == addr {
+                Some(name)
+            } else {
+                Some(format!("{}+{:#x}", name, addr - sym.start))
+            }
+        };
+
+        for insn in sweep(self.ehdr.e_machine, code, start) {
+
+            if let Some(sym) = index.at(insn.addr) {
+                println!();
+                println!("{:016x} <{}>:", insn.addr, self.display_name(&sym.name).green());
+            }
+
+            let off = (insn.addr - start) as usize;
+            let bytes = code[off..off + insn.len]
+                .iter()
+                .map(|b| format!("{:02x}", b))
+                .collect::<Vec<_>>()
+                .join(" ");
+
+            let mut line = format!("{:>8x}:  {:<24} {:<7} {}",
+                insn.addr,
+                bytes,
+                insn.mnemonic.yellow(),
+                insn.operands);
+
+            if let Some(target) = insn.target {
+                if let Some(l) = label(target) {
+                    line += &format!(" <{}>", l.blue());
+                }
+            } else if let Some(r) = insn.mem_ref {
+                let name = got.get(&r).map(|n| format!("{}@got", self.display_name(n))).or_else(|| label(r));
+                match name {
+                    Some(name) => line += &format!("        # {:#x} <{}>", r, name.blue()),
+                    None => line += &format!("        # {:#x}", r),
+                }
+            }
+            println!("{}", line);
+        }
+        Ok(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn disasm(parser : &Parser, start : u64, end : u64) -> Vec<Insn> {
+        sweep(parser.ehdr.e_machine, parser.code_bytes(start, end, None).unwrap(), start)
+    }
+
+    fn asm(bytes : &[u8]) -> String {
+        let insn = x86_64::decode(bytes, 0x1000).unwrap();
+        assert_eq!(insn.len, bytes.len());
+        format!("{} {}", insn.mnemonic, insn.operands).trim_end().to_string()
+    }
+
+    #[test]
+    fn test_decode_x86_64() {
+        assert_eq!(asm(&[0xf3, 0x0f, 0x1e, 0xfa]), "endbr64");
+        assert_eq!(asm(&[0x48, 0x89, 0xe5]), "mov rbp,rsp");
+        assert_eq!(asm(&[0x48, 0x83, 0xec, 0x10]), "sub rsp,0x10");
+        assert_eq!(asm(&[0x48, 0x83, 0xe4, 0xf0]), "and rsp,0xfffffffffffffff0");
+        assert_eq!(asm(&[0x89, 0x7d, 0xfc]), "mov DWORD PTR [rbp-0x4],edi");
+        assert_eq!(asm(&[0x64, 0x48, 0x8b, 0x04, 0x25, 0x28, 0x00, 0x00, 0x00]), "mov rax,QWORD PTR fs:0x28");
+        assert_eq!(asm(&[0x0f, 0x1f, 0x84, 0x00, 0x00, 0x00, 0x00, 0x00]), "nop DWORD PTR [rax+rax*1+0x0]");
+        assert_eq!(asm(&[0x42, 0x8b, 0x04, 0xa5, 0x10, 0x20, 0x40, 0x00]), "mov eax,DWORD PTR [r12*4+0x402010]");
+        assert_eq!(asm(&[0x40, 0x88, 0xf7]), "mov dil,sil");
+        assert_eq!(asm(&[0x48, 0xb8, 1, 2, 3, 4, 5, 6, 7, 8]), "movabs rax,0x807060504030201");
+        assert_eq!(asm(&[0xf3, 0x48, 0xab]), "rep stos QWORD PTR es:[rdi],rax");
+        assert_eq!(asm(&[0xf2, 0x0f, 0x10, 0x45, 0xf8]), "movsd xmm0,QWORD PTR [rbp-0x8]");
+        assert_eq!(asm(&[0x66, 0x0f, 0xef, 0xc0]), "pxor xmm0,xmm0");
+        assert_eq!(asm(&[0xc5, 0xf9, 0xef, 0xc0]), "vpxor xmm0,xmm0,xmm0");
+        assert_eq!(asm(&[0xc5, 0xfe, 0x6f, 0x06]), "vmovdqu ymm0,YMMWORD PTR [rsi]");
+        assert_eq!(asm(&[0xc4, 0xe2, 0x7d, 0x78, 0xc0]), "vpbroadcastb ymm0,xmm0");
+        assert_eq!(asm(&[0xdb, 0x6d, 0x10]), "fld TBYTE PTR [rbp+0x10]");
+        assert_eq!(asm(&[0xf2, 0x0f, 0xc2, 0xc1, 0x01]), "cmpltsd xmm0,xmm1");
+
+        // EVEX with opmask, disp8*N and broadcast, VEX opmask and FMA4
+        assert_eq!(asm(&[0x62, 0xf1, 0x7d, 0x4d, 0xef, 0x46, 0x02]), "vpxord zmm0{k5},zmm0,ZMMWORD PTR [rsi+0x80]");
+        assert_eq!(asm(&[0x62, 0xf1, 0xfd, 0x58, 0xef, 0x46, 0x02]), "vpxorq zmm0,zmm0,QWORD BCST [rsi+0x10]");
+        assert_eq!(asm(&[0x62, 0xe1, 0x7d, 0xc9, 0x6f, 0xc8]), "vmovdqa32 zmm17{k1}{z},zmm0");
+        assert_eq!(asm(&[0x62, 0xf3, 0x7d, 0x48, 0x3e, 0xc1, 0x01]), "vpcmpltub k0,zmm0,zmm1");
+        assert_eq!(asm(&[0xc5, 0xfb, 0x93, 0xc0]), "kmovd eax,k0");
+        assert_eq!(asm(&[0xc4, 0xe3, 0xf9, 0x6b, 0xc2, 0x10]), "vfmaddsd xmm0,xmm0,xmm1,xmm2");
+
+        let call = x86_64::decode(&[0xe8, 0xfb, 0xff, 0xff, 0xff], 0x1000).unwrap();
+        assert_eq!(call.target, Some(0x1000));
+        let lea = x86_64::decode(&[0x48, 0x8d, 0x35, 0xba, 0x0e, 0x00, 0x00], 0x401143).unwrap();
+        assert_eq!(lea.operands, "rsi,[rip+0xeba]");
+        assert_eq!(lea.mem_ref, Some(0x402004));
+    }
+
+    #[test]
+    fn test_disasm_main() {
+        let parser = Parser::new("test/testgot");
+        let (start, end) = parser.symbol_range("main").unwrap();
+        assert_eq!((start, end), (0x401136, 0x40115b));
+
+        let insns = disasm(&parser, start, end);
+        let text : Vec<String> = insns.iter().map(|i| format!("{} {}", i.mnemonic, i.operands).trim_end().to_string()).collect();
+        assert_eq!(text, [
+            "endbr64",
+            "push rbp",
+            "mov rbp,rsp",
+            "mov edx,0x10",
+            "lea rsi,[rip+0xeba]",
+            "mov edi,0x1",
+            "call 0x401040",
+            "mov eax,0x0",
+            "pop rbp",
+            "ret",
+        ]);
+
+        // the call lands on the PLT stub, which jumps through the GOT slot of write
+        let stub = parser.symbol_range("write@plt").unwrap();
+        assert_eq!(insns[6].target, Some(stub.0));
+        let jmp = &disasm(&parser, stub.0, stub.1)[1];
+        assert_eq!(jmp.mnemonic, "bnd jmp");
+        assert_eq!(parser.got_names().get(&jmp.mem_ref.unwrap()).unwrap(), "write");
+    }
+
+    #[test]
+    fn test_disasm_unmapped() {
+        // relocatable code is read from its section, unmapped or bss ranges are errors
+        let rel = Parser::new("test/testrel.o");
+        let shndx = rel.section_shndx(".text");
+        let (start, end) = rel.section_range(".text").unwrap();
+        let code = rel.code_bytes(start, end, shndx).unwrap();
+        assert_eq!(sweep(rel.ehdr.e_machine, code, start)[0].mnemonic, "push");
+        assert!(rel.show_disasm(start, end, shndx).is_ok());
+        assert_eq!(rel.symbol_range("_Z2ili"), Some((0, 0x1d)));
+        assert!(rel.code_bytes(start, end, None).is_err());
+
+        let parser = Parser::new("test/test");
+        assert!(parser.code_bytes(0x10, 0x20, None).unwrap_err().contains("not mapped"));
+        assert!(parser.show_disasm(0x10, 0x20, None).is_err());
+        let got = Parser::new("test/testgot");
+        assert!(got.code_bytes(0x404028, 0x404038, None).is_err());
+        assert_eq!(disasm(&got, 0x401136, 0x40113a).len(), 1);
+    }
+
+    fn asm_at(machine : u16, bytes : &[u8], addr : u64) -> String {
+        let insn = decode(machine, bytes, addr, &mut [None; 32]).unwrap();
+        assert_eq!(insn.len, bytes.len());
+        format!("{} {}", insn.mnemonic, insn.operands).trim_end().to_string()
+    }
+
+    #[test]
+    fn test_decode_aarch64() {
+        let a64 = |bytes : &[u8]| asm_at(EM_AARCH64, bytes, 0x1000);
+        assert_eq!(a64(&[0xfd, 0x7b, 0xbf, 0xa9]), "stp x29, x30, [sp, #-16]!");
+        assert_eq!(a64(&[0x80, 0x46, 0x82, 0xd2]), "mov x0, #4660");
+        assert_eq!(a64(&[0x11, 0x06, 0x40, 0xf9]), "ldr x17, [x16, #8]");
+        assert_eq!(a64(&[0x20, 0xc8, 0x62, 0x38]), "ldrb w0, [x1, w2, sxtw]");
+        assert_eq!(a64(&[0xe0, 0x17, 0x9f, 0x1a]), "cset w0, eq");
+        assert_eq!(a64(&[0x20, 0x2c, 0x44, 0xd3]), "ubfx x0, x1, #4, #8");
+        assert_eq!(a64(&[0x00, 0x10, 0x6e, 0x1e]), "fmov d0, #1.00000000");
+        assert_eq!(a64(&[0x00, 0xa0, 0xdf, 0x4c]), "ld1 { v0.16b, v1.16b }, [x0], #32");
+        assert_eq!(a64(&[0x40, 0xd0, 0x3b, 0xd5]), "mrs x0, TPIDR_EL0");
+        assert_eq!(a64(&[0x57, 0x01, 0xe3, 0xf8]), "ldaddal x3, x23, [x10]");
+        assert_eq!(a64(&[0x00, 0x00, 0x00, 0x94]), "bl 0x1000");
+
+        // adrp + ldr resolve to the loaded address
+        let mut pages = [None; 32];
+        aarch64::decode(&[0x10, 0x01, 0x00, 0x90], 0x210350, &mut pages).unwrap();
+        let ldr = aarch64::decode(&[0x11, 0x26, 0x42, 0xf9], 0x210354, &mut pages).unwrap();
+        assert_eq!(ldr.mem_ref, Some(0x230448));
+    }
+
+    #[test]
+    fn test_decode_riscv() {
+        let rv = |bytes : &[u8]| asm_at(EM_RISCV, bytes, 0x1000);
+        assert_eq!(rv(&[0x13, 0x05, 0x40, 0x06]), "li a0, 100");
+        assert_eq!(rv(&[0xb7, 0x25, 0x01, 0x00]), "lui a1, 18");
+        assert_eq!(rv(&[0x03, 0x3e, 0x0e, 0x01]), "ld t3, 16(t3)");
+        assert_eq!(rv(&[0x1b, 0x85, 0x05, 0x00]), "sext.w a0, a1");
+        assert_eq!(rv(&[0x53, 0x15, 0x05, 0xc2]), "fcvt.w.d a0, fa0, rtz");
+        assert_eq!(rv(&[0x2f, 0x25, 0xb6, 0x04]), "amoadd.w.aq a0, a1, (a2)");
+        // compressed forms print as what they expand to
+        assert_eq!(rv(&[0x11, 0x05]), "addi a0, a0, 4");
+        assert_eq!(rv(&[0x82, 0x80]), "ret");
+        assert_eq!(rv(&[0x00, 0x00]), "unimp");
+    }
+
+    #[test]
+    fn test_disasm_plt_aarch64_riscv() {
+        // main calls write through the PLT, whose stub loads the GOT slot of write
+        for path in ["test/testplt_aarch64", "test/testplt_riscv64"] {
+            let parser = Parser::new(path);
+            let (start, end) = parser.symbol_range("main").unwrap();
+            let stub = parser.symbol_range("write@plt").unwrap();
+            let insns = disasm(&parser, start, end);
+            assert!(insns.iter().any(|i| i.target == Some(stub.0)), "{}", path);
+
+            let got = parser.got_names();
+            let load = disasm(&parser, stub.0, stub.1)
+                .into_iter()
+                .find(|i| i.mnemonic == "ldr" || i.mnemonic == "ld")
+                .unwrap();
+            assert_eq!(got.get(&load.mem_ref.unwrap()).unwrap(), "write", "{}", path);
+        }
+    }
+}
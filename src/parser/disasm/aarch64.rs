@@ -0,0 +1,1583 @@
+use crate::parser::disasm::{Insn, Pages};
+
+/*
+ A64 decoder, syntax and aliases as printed by `llvm-objdump`
+
+ covers the base integer instruction set, loads/stores including LSE atomics,
+ scalar floating point and the common Advanced SIMD groups. SVE and other
+ extensions come out as <unknown>
+ */
+
+const COND   : [&str; 16] = ["eq", "ne", "hs", "lo", "mi", "pl", "vs", "vc",
+                             "hi", "ls", "ge", "lt", "gt", "le", "al", "nv"];
+const SHIFT  : [&str; 4] = ["lsl", "lsr", "asr", "ror"];
+const EXTEND : [&str; 8] = ["uxtb", "uxth", "uxtw", "uxtx", "sxtb", "sxth", "sxtw", "sxtx"];
+const ARR    : [&str; 8] = ["8b", "16b", "4h", "8h", "2s", "4s", "1d", "2d"];
+const BARRIER : [&str; 16] = ["#0", "oshld", "oshst", "osh", "#4", "nshld", "nshst", "nsh",
+                              "#8", "ishld", "ishst", "ish", "#12", "ld", "st", "sy"];
+
+/* (op0, op1, CRn, CRm, op2) */
+type SysRegId = (u32, u32, u32, u32, u32);
+
+/* system registers user space code touches */
+const SYSREGS : [(SysRegId, &str); 20] = [
+    ((3, 3, 4, 2, 0), "NZCV"),
+    ((3, 3, 4, 2, 1), "DAIF"),
+    ((3, 3, 4, 4, 0), "FPCR"),
+    ((3, 3, 4, 4, 1), "FPSR"),
+    ((3, 3, 13, 0, 2), "TPIDR_EL0"),
+    ((3, 3, 13, 0, 3), "TPIDRRO_EL0"),
+    ((3, 0, 13, 0, 4), "TPIDR_EL1"),
+    ((3, 3, 0, 0, 1), "CTR_EL0"),
+    ((3, 3, 0, 0, 7), "DCZID_EL0"),
+    ((3, 3, 14, 0, 0), "CNTFRQ_EL0"),
+    ((3, 3, 14, 0, 1), "CNTPCT_EL0"),
+    ((3, 3, 14, 0, 2), "CNTVCT_EL0"),
+    ((3, 0, 0, 0, 0), "MIDR_EL1"),
+    ((3, 0, 0, 0, 5), "MPIDR_EL1"),
+    ((3, 0, 0, 0, 6), "REVIDR_EL1"),
+    ((3, 0, 0, 4, 0), "ID_AA64PFR0_EL1"),
+    ((3, 0, 0, 6, 0), "ID_AA64ISAR0_EL1"),
+    ((3, 0, 0, 6, 1), "ID_AA64ISAR1_EL1"),
+    ((3, 0, 4, 2, 2), "CurrentEL"),
+    ((3, 3, 2, 4, 0), "RNDR"),
+];
+
+fn bits(w : u32, hi : u32, lo : u32) -> u32 {
+    (w >> lo) & ((1 << (hi - lo + 1)) - 1)
+}
+
+fn bit(w : u32, n : u32) -> bool {
+    w & (1 << n) != 0
+}
+
+fn sext(v : u32, width : u32) -> i64 {
+    let shift = 64 - width;
+    ((v as u64) << shift) as i64 >> shift
+}
+
+fn reg(n : u32, sf : bool) -> String {
+    // general purpose register, 31 is the zero register
+    match (n, sf) {
+        (31, true) => "xzr".to_string(),
+        (31, false) => "wzr".to_string(),
+        (_, true) => format!("x{}", n),
+        _ => format!("w{}", n),
+    }
+}
+
+fn reg_sp(n : u32, sf : bool) -> String {
+    // general purpose register, 31 is the stack pointer
+    match (n, sf) {
+        (31, true) => "sp".to_string(),
+        (31, false) => "wsp".to_string(),
+        _ => reg(n, sf),
+    }
+}
+
+fn imm(v : i64) -> String {
+    format!("#{}", v)
+}
+
+fn decode_bitmask(n : u32, imms : u32, immr : u32, sf : bool) -> Option<u64> {
+    // DecodeBitMasks of the logical immediate instructions
+    let combined = (n << 6) | (!imms & 0x3f);
+    if combined == 0 {
+        return None;
+    }
+    let len = 31 - combined.leading_zeros();
+    if len < 1 || (!sf && n == 1) {
+        return None;
+    }
+    let size = 1u32 << len;
+    let levels = size - 1;
+    let s = imms & levels;
+    let r = immr & levels;
+    if s == levels {
+        return None;
+    }
+    let welem = (1u64 << (s + 1)) - 1;
+    let mask = if size == 64 { u64::MAX } else { (1u64 << size) - 1 };
+    let elem = ((welem >> r) | (welem << ((size - r) % size))) & mask;
+    let mut v = 0u64;
+    let mut i = 0;
+    while i < 64 {
+        v |= elem << i;
+        i += size;
+    }
+    Some(if sf { v } else { v & 0xffff_ffff })
+}
+
+fn move_wide_preferred(sf : bool, n : u32, imms : u32, immr : u32) -> bool {
+    // whether movz/movn rather than orr is the natural mov of a logical immediate
+    let width = if sf { 64 } else { 32 };
+    if (sf && n != 1) || (!sf && (n != 0 || imms & 0x20 != 0)) {
+        return false;
+    }
+    if imms < 16 {
+        return (16 - immr % 16) % 16 <= 15 - imms;
+    }
+    if imms >= width - 15 {
+        return immr % 16 <= imms - (width - 15);
+    }
+    false
+}
+
+fn fp_imm(imm8 : u32) -> f64 {
+    // VFPExpandImm
+    let sign = if imm8 & 0x80 != 0 { -1.0 } else { 1.0 };
+    let exp = ((imm8 >> 4) & 7) as i32;
+    let exp = if exp & 4 != 0 { exp - 8 } else { exp } + 1;
+    let frac = 16.0 + (imm8 & 0xf) as f64;
+    sign * frac / 16.0 * 2f64.powi(exp)
+}
+
+struct Decoded {
+    mnemonic : String,
+    operands : Vec<String>,
+    target   : Option<u64>,
+    mem_ref  : Option<u64>,
+    writes   : Option<u32>,     /* general purpose register written */
+}
+
+fn insn(mnemonic : &str, operands : &[String]) -> Option<Decoded> {
+    Some(Decoded {
+        mnemonic : mnemonic.to_string(),
+        operands : operands.to_vec(),
+        target   : None,
+        mem_ref  : None,
+        writes   : None,
+    })
+}
+
+fn dp_imm(w : u32, addr : u64, pages : &mut Pages) -> Option<Decoded> {
+    let sf = bit(w, 31);
+    let rd = bits(w, 4, 0);
+    let rn = bits(w, 9, 5);
+
+    let mut d = match bits(w, 25, 23) {
+        0 | 1 => {
+            let off = sext((bits(w, 23, 5) << 2) | bits(w, 30, 29), 21);
+            let (mn, target) = if sf {
+                ("adrp", (addr & !0xfff).wrapping_add((off << 12) as u64))
+            } else {
+                ("adr", addr.wrapping_add(off as u64))
+            };
+            let d = insn(mn, &[reg(rd, true), format!("{:#x}", target)]);
+            pages[rd as usize] = if sf { Some(target) } else { None };
+            return d.map(|mut d| { d.mem_ref = Some(target); d });
+        }
+        2 => {
+            let (op, s) = (bit(w, 30), bit(w, 29));
+            let imm12 = bits(w, 21, 10) as i64;
+            let shifted = bit(w, 22);
+            let mut ops = match (op, s) {
+                (false, false) if !shifted && imm12 == 0 && (rd == 31 || rn == 31) => {
+                    return insn("mov", &[reg_sp(rd, sf), reg_sp(rn, sf)]).map(|mut d| { d.writes = Some(rd); d });
+                }
+                (_, true) if rd == 31 => vec![reg_sp(rn, sf), imm(imm12)],
+                (_, true) => vec![reg(rd, sf), reg_sp(rn, sf), imm(imm12)],
+                _ => vec![reg_sp(rd, sf), reg_sp(rn, sf), imm(imm12)],
+            };
+            if shifted {
+                ops.push("lsl #12".to_string());
+            }
+            let mn = match (op, s, rd) {
+                (false, true, 31) => "cmn",
+                (true, true, 31) => "cmp",
+                (false, false, _) => "add",
+                (false, true, _) => "adds",
+                (true, false, _) => "sub",
+                (true, true, _) => "subs",
+            };
+            let mut d = insn(mn, &ops)?;
+            // add completing an adrp is an address
+            if mn == "add" && !shifted {
+                d.mem_ref = pages[rn as usize].map(|p| p.wrapping_add(imm12 as u64));
+            }
+            d
+        }
+        4 => {
+            let n = bits(w, 22, 22);
+            let v = decode_bitmask(n, bits(w, 15, 10), bits(w, 21, 16), sf)?;
+            let hex = format!("#{:#x}", v);
+            match bits(w, 30, 29) {
+                0 => insn("and", &[reg_sp(rd, sf), reg(rn, sf), hex])?,
+                1 if rn == 31 && !move_wide_preferred(sf, n, bits(w, 15, 10), bits(w, 21, 16)) => {
+                    let v = if sf { v as i64 } else { v as u32 as i32 as i64 };
+                    insn("mov", &[reg_sp(rd, sf), imm(v)])?
+                }
+                1 => insn("orr", &[reg_sp(rd, sf), reg(rn, sf), hex])?,
+                2 => insn("eor", &[reg_sp(rd, sf), reg(rn, sf), hex])?,
+                _ if rd == 31 => insn("tst", &[reg(rn, sf), hex])?,
+                _ => insn("ands", &[reg(rd, sf), reg(rn, sf), hex])?,
+            }
+        }
+        5 => {
+            let hw = bits(w, 22, 21);
+            let imm16 = bits(w, 20, 5) as u64;
+            if !sf && hw >= 2 {
+                return None;
+            }
+            let shift = hw * 16;
+            let lsl = format!("lsl #{}", shift);
+            let as_signed = |v : u64| if sf { v as i64 } else { v as u32 as i32 as i64 };
+            match bits(w, 30, 29) {
+                0 if !(imm16 == 0 && hw != 0) && (sf || imm16 != 0xffff) => insn("mov", &[reg(rd, sf), imm(as_signed(!(imm16 << shift)))])?,
+                2 if !(imm16 == 0 && hw != 0) => insn("mov", &[reg(rd, sf), imm(as_signed(imm16 << shift))])?,
+                1 => return None,
+                op => {
+                    let mn = ["movn", "", "movz", "movk"][op as usize];
+                    if hw == 0 {
+                        insn(mn, &[reg(rd, sf), imm(imm16 as i64)])?
+                    } else {
+                        insn(mn, &[reg(rd, sf), imm(imm16 as i64), lsl])?
+                    }
+                }
+            }
+        }
+        6 => {
+            let (immr, imms) = (bits(w, 21, 16), bits(w, 15, 10));
+            let size = if sf { 64 } else { 32 };
+            if bits(w, 22, 22) != sf as u32 || (!sf && (immr >= 32 || imms >= 32)) {
+                return None;
+            }
+            let (rd_s, rn_s) = (reg(rd, sf), reg(rn, sf));
+            let bfx_preferred = |uns : bool| -> bool {
+                if imms < immr || imms == size - 1 {
+                    return false;
+                }
+                if immr == 0 {
+                    if !sf && (imms == 7 || imms == 15) {
+                        return false;
+                    }
+                    if sf && !uns && (imms == 7 || imms == 15 || imms == 31) {
+                        return false;
+                    }
+                }
+                true
+            };
+            let i = |v : u32| imm(v as i64);
+            match bits(w, 30, 29) {
+                0 => {
+                    if imms == size - 1 {
+                        insn("asr", &[rd_s, rn_s, i(immr)])?
+                    } else if imms < immr {
+                        insn("sbfiz", &[rd_s, rn_s, i(size - immr), i(imms + 1)])?
+                    } else if bfx_preferred(false) {
+                        insn("sbfx", &[rd_s, rn_s, i(immr), i(imms - immr + 1)])?
+                    } else if immr == 0 && (imms == 7 || imms == 15 || imms == 31) {
+                        let mn = match imms { 7 => "sxtb", 15 => "sxth", _ => "sxtw" };
+                        insn(mn, &[rd_s, reg(rn, false)])?
+                    } else {
+                        insn("sbfm", &[rd_s, rn_s, i(immr), i(imms)])?
+                    }
+                }
+                1 => {
+                    if rn == 31 && imms < immr {
+                        insn("bfc", &[rd_s, i(size - immr), i(imms + 1)])?
+                    } else if rn == 31 && immr == 0 {
+                        insn("bfc", &[rd_s, i(0), i(imms + 1)])?
+                    } else if imms < immr {
+                        insn("bfi", &[rd_s, rn_s, i(size - immr), i(imms + 1)])?
+                    } else {
+                        insn("bfxil", &[rd_s, rn_s, i(immr), i(imms - immr + 1)])?
+                    }
+                }
+                2 => {
+                    if imms != size - 1 && imms + 1 == immr {
+                        insn("lsl", &[rd_s, rn_s, i(size - 1 - imms)])?
+                    } else if imms == size - 1 {
+                        insn("lsr", &[rd_s, rn_s, i(immr)])?
+                    } else if imms < immr {
+                        insn("ubfiz", &[rd_s, rn_s, i(size - immr), i(imms + 1)])?
+                    } else if bfx_preferred(true) {
+                        insn("ubfx", &[rd_s, rn_s, i(immr), i(imms - immr + 1)])?
+                    } else if immr == 0 && (imms == 7 || imms == 15) {
+                        insn(if imms == 7 { "uxtb" } else { "uxth" }, &[rd_s, reg(rn, false)])?
+                    } else {
+                        insn("ubfm", &[rd_s, rn_s, i(immr), i(imms)])?
+                    }
+                }
+                _ => return None,
+            }
+        }
+        7 => {
+            let (rm, imms) = (bits(w, 20, 16), bits(w, 15, 10));
+            if bits(w, 30, 29) != 0 || bit(w, 21) || bits(w, 22, 22) != sf as u32 || (!sf && imms >= 32) {
+                return None;
+            }
+            if rn == rm {
+                insn("ror", &[reg(rd, sf), reg(rn, sf), imm(imms as i64)])?
+            } else {
+                insn("extr", &[reg(rd, sf), reg(rn, sf), reg(rm, sf), imm(imms as i64)])?
+            }
+        }
+        _ => return None,
+    };
+    d.writes = Some(rd);
+    Some(d)
+}
+
+fn sysreg(op0 : u32, op1 : u32, crn : u32, crm : u32, op2 : u32) -> String {
+    SYSREGS
+        .iter()
+        .find(|(k, _)| *k == (op0, op1, crn, crm, op2))
+        .map(|(_, name)| name.to_string())
+        .unwrap_or_else(|| format!("S{}_{}_C{}_C{}_{}", op0, op1, crn, crm, op2))
+}
+
+fn system(w : u32) -> Option<Decoded> {
+    let l = bit(w, 21);
+    let op0 = bits(w, 20, 19);
+    let op1 = bits(w, 18, 16);
+    let crn = bits(w, 15, 12);
+    let crm = bits(w, 11, 8);
+    let op2 = bits(w, 7, 5);
+    let rt = bits(w, 4, 0);
+
+    match (l, op0, op1, crn) {
+        (false, 0, 3, 2) if rt == 31 => {
+            let hint = (crm << 3) | op2;
+            let name = match hint {
+                0 => "nop",
+                1 => "yield",
+                2 => "wfe",
+                3 => "wfi",
+                4 => "sev",
+                5 => "sevl",
+                7 => "xpaclri",
+                8 => "pacia1716",
+                10 => "pacib1716",
+                12 => "autia1716",
+                14 => "autib1716",
+                0x14 => "csdb",
+                0x18 => "paciaz",
+                0x19 => "paciasp",
+                0x1a => "pacibz",
+                0x1b => "pacibsp",
+                0x1c => "autiaz",
+                0x1d => "autiasp",
+                0x1e => "autibz",
+                0x1f => "autibsp",
+                0x20 => "bti",
+                0x22 => return insn("bti", &["c".to_string()]),
+                0x24 => return insn("bti", &["j".to_string()]),
+                0x26 => return insn("bti", &["jc".to_string()]),
+                _ => return insn("hint", &[imm(hint as i64)]),
+            };
+            insn(name, &[])
+        }
+        (false, 0, 3, 3) if rt == 31 => match op2 {
+            2 if crm == 15 => insn("clrex", &[]),
+            2 => insn("clrex", &[imm(crm as i64)]),
+            4 if crm == 0 => insn("ssbb", &[]),
+            4 if crm == 4 => insn("pssbb", &[]),
+            4 | 5 => insn(if op2 == 4 { "dsb" } else { "dmb" }, &[BARRIER[crm as usize].to_string()]),
+            6 if crm == 15 => insn("isb", &[]),
+            6 => insn("isb", &[imm(crm as i64)]),
+            7 if crm == 0 => insn("sb", &[]),
+            _ => None,
+        },
+        (false, 1, _, 7) => {
+            // cache maintenance
+            let name = match (op1, crm, op2) {
+                (3, 4, 1) => ("dc", "zva"),
+                (3, 10, 1) => ("dc", "cvac"),
+                (3, 11, 1) => ("dc", "cvau"),
+                (3, 12, 1) => ("dc", "cvap"),
+                (3, 14, 1) => ("dc", "civac"),
+                (0, 6, 1) => ("dc", "ivac"),
+                (3, 5, 1) => ("ic", "ivau"),
+                (0, 5, 0) if rt == 31 => return insn("ic", &["iallu".to_string()]),
+                (0, 1, 0) if rt == 31 => return insn("ic", &["ialluis".to_string()]),
+                _ => return sys(w),
+            };
+            insn(name.0, &[name.1.to_string(), reg(rt, true)])
+        }
+        (false, 1, _, _) => sys(w),
+        (_, 2 | 3, _, _) => {
+            let name = sysreg(op0, op1, crn, crm, op2);
+            if l {
+                insn("mrs", &[reg(rt, true), name]).map(|mut d| { d.writes = Some(rt); d })
+            } else {
+                insn("msr", &[name, reg(rt, true)])
+            }
+        }
+        (false, 0, _, 4) if rt == 31 => {
+            let name = match (op1, op2) {
+                (0, 5) => "SPSel",
+                (3, 6) => "DAIFSet",
+                (3, 7) => "DAIFClr",
+                (0, 3) => "UAO",
+                (0, 4) => "PAN",
+                (3, 2) => "DIT",
+                (3, 1) => "SSBS",
+                (3, 4) => "TCO",
+                _ => return None,
+            };
+            insn("msr", &[name.to_string(), imm(crm as i64)])
+        }
+        _ => None,
+    }
+}
+
+fn sys(w : u32) -> Option<Decoded> {
+    let rt = bits(w, 4, 0);
+    let mut ops = vec![
+        imm(bits(w, 18, 16) as i64),
+        format!("c{}", bits(w, 15, 12)),
+        format!("c{}", bits(w, 11, 8)),
+        imm(bits(w, 7, 5) as i64),
+    ];
+    if rt != 31 {
+        ops.push(reg(rt, true));
+    }
+    insn("sys", &ops)
+}
+
+fn branch_sys(w : u32, addr : u64, pages : &mut Pages) -> Option<Decoded> {
+    let pc = |off : i64| addr.wrapping_add(off as u64);
+    let branch = |mn : &str, mut ops : Vec<String>, target : u64| -> Option<Decoded> {
+        ops.push(format!("{:#x}", target));
+        insn(mn, &ops).map(|mut d| { d.target = Some(target); d })
+    };
+
+    if bits(w, 30, 26) == 0b00101 {
+        let target = pc(sext(bits(w, 25, 0), 26) << 2);
+        if !bit(w, 31) {
+            pages.fill(None);
+        }
+        return branch(if bit(w, 31) { "bl" } else { "b" }, vec![], target);
+    }
+    if bits(w, 31, 24) == 0b0101_0100 && !bit(w, 4) {
+        let target = pc(sext(bits(w, 23, 5), 19) << 2);
+        return branch(&format!("b.{}", COND[bits(w, 3, 0) as usize]), vec![], target);
+    }
+    if bits(w, 30, 25) == 0b011010 {
+        let target = pc(sext(bits(w, 23, 5), 19) << 2);
+        let mn = if bit(w, 24) { "cbnz" } else { "cbz" };
+        return branch(mn, vec![reg(bits(w, 4, 0), bit(w, 31))], target);
+    }
+    if bits(w, 30, 25) == 0b011011 {
+        let target = pc(sext(bits(w, 18, 5), 14) << 2);
+        let mn = if bit(w, 24) { "tbnz" } else { "tbz" };
+        let b = (bits(w, 31, 31) << 5) | bits(w, 23, 19);
+        return branch(mn, vec![reg(bits(w, 4, 0), bit(w, 31)), imm(b as i64)], target);
+    }
+    if bits(w, 31, 24) == 0b1101_0100 {
+        let imm16 = match bits(w, 20, 5) {
+            0 => "#0".to_string(),
+            v => format!("#{:#x}", v),
+        };
+        let mn = match (bits(w, 23, 21), bits(w, 4, 0)) {
+            (0, 1) => "svc",
+            (0, 2) => "hvc",
+            (0, 3) => "smc",
+            (1, 0) => "brk",
+            (2, 0) => "hlt",
+            (5, 1) => "dcps1",
+            (5, 2) => "dcps2",
+            (5, 3) => "dcps3",
+            _ => return None,
+        };
+        return insn(mn, &[imm16]);
+    }
+    if bits(w, 31, 22) == 0b11_0101_0100 {
+        return system(w);
+    }
+    if bits(w, 31, 25) == 0b1101011 {
+        let rn = bits(w, 9, 5);
+        let d = match (bits(w, 24, 21), bits(w, 20, 16), bits(w, 15, 10), rn, bits(w, 4, 0)) {
+            (0, 31, 0, _, 0) => insn("br", &[reg(rn, true)]),
+            (1, 31, 0, _, 0) => insn("blr", &[reg(rn, true)]),
+            (2, 31, 0, 30, 0) => insn("ret", &[]),
+            (2, 31, 0, _, 0) => insn("ret", &[reg(rn, true)]),
+            (4, 31, 0, 31, 0) => insn("eret", &[]),
+            (5, 31, 0, 31, 0) => insn("drps", &[]),
+            (2, 31, 2, 31, 31) => insn("retaa", &[]),
+            (2, 31, 3, 31, 31) => insn("retab", &[]),
+            (0, 31, 2, _, 31) => insn("braaz", &[reg(rn, true)]),
+            (0, 31, 3, _, 31) => insn("brabz", &[reg(rn, true)]),
+            (1, 31, 2, _, 31) => insn("blraaz", &[reg(rn, true)]),
+            (1, 31, 3, _, 31) => insn("blrabz", &[reg(rn, true)]),
+            _ => None,
+        };
+        if d.as_ref().is_some_and(|d| d.mnemonic != "blr" && d.mnemonic != "blraaz" && d.mnemonic != "blrabz") {
+            pages.fill(None);
+        }
+        return d;
+    }
+    None
+}
+
+fn mem_off(rn : u32, off : i64) -> String {
+    // [base, #off] with a zero offset left out
+    if off == 0 {
+        format!("[{}]", reg_sp(rn, true))
+    } else {
+        format!("[{}, #{}]", reg_sp(rn, true), off)
+    }
+}
+
+fn fpreg(size : u32, n : u32) -> String {
+    // b/h/s/d/q register by log2 of the byte size
+    format!("{}{}", ["b", "h", "s", "d", "q"][size as usize], n)
+}
+
+fn load_store(w : u32, addr : u64, pages : &Pages) -> Option<Decoded> {
+    let rt = bits(w, 4, 0);
+    let rn = bits(w, 9, 5);
+    let size = bits(w, 31, 30);
+    let v = bit(w, 26);
+
+    // exclusive and ordered
+    if bits(w, 29, 24) == 0b001000 && !v {
+        let (o2, l, o1, o0) = (bit(w, 23), bit(w, 22), bit(w, 21), bit(w, 15));
+        let rs = bits(w, 20, 16);
+        let rt2 = bits(w, 14, 10);
+        let sf = size == 3;
+        let suffix = ["b", "h", "", ""][size as usize];
+        let base = format!("[{}]", reg_sp(rn, true));
+        let d = match (o2, o1) {
+            (false, false) => {
+                let mn = match (l, o0) { (false, false) => "stxr", (false, true) => "stlxr", (true, false) => "ldxr", _ => "ldaxr" };
+                let mn = format!("{}{}", mn, suffix);
+                if l {
+                    insn(&mn, &[reg(rt, sf), base])?
+                } else {
+                    insn(&mn, &[reg(rs, false), reg(rt, sf), base])?
+                }
+            }
+            (false, true) if size >= 2 => {
+                let mn = match (l, o0) { (false, false) => "stxp", (false, true) => "stlxp", (true, false) => "ldxp", _ => "ldaxp" };
+                if l {
+                    insn(mn, &[reg(rt, sf), reg(rt2, sf), base])?
+                } else {
+                    insn(mn, &[reg(rs, false), reg(rt, sf), reg(rt2, sf), base])?
+                }
+            }
+            (false, true) => {
+                // casp, register pairs
+                let sf = size == 1;
+                let mn = format!("casp{}{}", if l { "a" } else { "" }, if o0 { "l" } else { "" });
+                if rs & 1 != 0 || rt & 1 != 0 || rt2 != 31 {
+                    return None;
+                }
+                insn(&mn, &[reg(rs, sf), reg(rs + 1, sf), reg(rt, sf), reg(rt + 1, sf), base])?
+            }
+            (true, false) => {
+                let mn = match (l, o0) { (false, false) => "stllr", (false, true) => "stlr", (true, false) => "ldlar", _ => "ldar" };
+                insn(&format!("{}{}", mn, suffix), &[reg(rt, sf), base])?
+            }
+            (true, true) if rt2 == 31 => {
+                let mn = format!("cas{}{}{}", if l { "a" } else { "" }, if o0 { "l" } else { "" }, suffix);
+                insn(&mn, &[reg(rs, sf), reg(rt, sf), base])?
+            }
+            _ => return None,
+        };
+        return Some(Decoded { writes : if l { Some(rt) } else { None }, ..d });
+    }
+
+    // load literal
+    if bits(w, 29, 27) == 0b011 && !bit(w, 24) {
+        let target = addr.wrapping_add((sext(bits(w, 23, 5), 19) << 2) as u64);
+        let t = format!("{:#x}", target);
+        let opc = size;
+        let mut d = match (v, opc) {
+            (false, 0) => insn("ldr", &[reg(rt, false), t])?,
+            (false, 1) => insn("ldr", &[reg(rt, true), t])?,
+            (false, 2) => insn("ldrsw", &[reg(rt, true), t])?,
+            (false, _) => insn("prfm", &[prfop(rt), t])?,
+            (true, 3) => return None,
+            (true, _) => insn("ldr", &[fpreg(opc + 2, rt), t])?,
+        };
+        d.mem_ref = Some(target);
+        if !v && opc != 3 {
+            d.writes = Some(rt);
+        }
+        return Some(d);
+    }
+
+    // pairs
+    if bits(w, 29, 27) == 0b101 {
+        let kind = bits(w, 24, 23);
+        let l = bit(w, 22);
+        let rt2 = bits(w, 14, 10);
+        let (scale, r, r2, mn) = match (v, size, l) {
+            (false, 0, _) => (2, reg(rt, false), reg(rt2, false), if l { "ldp" } else { "stp" }),
+            (false, 1, true) if kind != 0 => (2, reg(rt, true), reg(rt2, true), "ldpsw"),
+            (false, 2, _) => (3, reg(rt, true), reg(rt2, true), if l { "ldp" } else { "stp" }),
+            (true, 0..=2, _) => (size + 2, fpreg(size + 2, rt), fpreg(size + 2, rt2), if l { "ldp" } else { "stp" }),
+            _ => return None,
+        };
+        let mn = if kind == 0 { if l { "ldnp" } else { "stnp" } } else { mn };
+        let off = sext(bits(w, 21, 15), 7) << scale;
+        let base = reg_sp(rn, true);
+        let m = match kind {
+            1 => vec![format!("[{}]", base), imm(off)],
+            3 => vec![format!("[{}, #{}]!", base, off)],
+            _ => vec![mem_off(rn, off)],
+        };
+        let mut ops = vec![r, r2];
+        ops.extend(m);
+        let mut d = insn(mn, &ops)?;
+        if l && !v {
+            d.writes = Some(rt);
+        }
+        return Some(d);
+    }
+
+    // single register
+    if bits(w, 29, 27) == 0b111 {
+        if !bit(w, 24) && bit(w, 21) && bits(w, 11, 10) == 0 && !v {
+            return atomic(w);
+        }
+        let opc = bits(w, 23, 22);
+        let (mn, r, scale, load) = if v {
+            let (lg, load) = match (size, opc) {
+                (0, 0) => (0, false),
+                (0, 1) => (0, true),
+                (0, 2) => (4, false),
+                (0, 3) => (4, true),
+                (_, 0) => (size, false),
+                (_, 1) => (size, true),
+                _ => return None,
+            };
+            (if load { "ldr" } else { "str" }, fpreg(lg, rt), lg, load)
+        } else {
+            let sfx = ["b", "h", "", ""][size as usize];
+            match (size, opc) {
+                (_, 0) => (["strb", "strh", "str", "str"][size as usize], reg(rt, size == 3), size, false),
+                (_, 1) => (["ldrb", "ldrh", "ldr", "ldr"][size as usize], reg(rt, size == 3), size, true),
+                (0 | 1, _) => (if sfx == "b" { "ldrsb" } else { "ldrsh" }, reg(rt, opc == 2), size, true),
+                (2, 2) => ("ldrsw", reg(rt, true), size, true),
+                (3, 2) => ("prfm", prfop(rt), size, false),
+                _ => return None,
+            }
+        };
+
+        let base = reg_sp(rn, true);
+        let mut d = if bit(w, 24) {
+            let off = (bits(w, 21, 10) as i64) << scale;
+            let mut d = insn(mn, &[r, mem_off(rn, off)])?;
+            // ldr completing an adrp reads the GOT
+            d.mem_ref = pages[rn as usize].map(|p| p.wrapping_add(off as u64));
+            d
+        } else if !bit(w, 21) {
+            let off = sext(bits(w, 20, 12), 9);
+            match bits(w, 11, 10) {
+                0 => {
+                    let mn = match mn {
+                        "prfm" => "prfum".to_string(),
+                        _ => format!("{}u{}", &mn[..2], &mn[2..]),
+                    };
+                    insn(&mn, &[r, mem_off(rn, off)])?
+                }
+                1 if mn != "prfm" => insn(mn, &[r, format!("[{}]", base), imm(off)])?,
+                2 if !v && mn != "prfm" => insn(&format!("{}tr{}", &mn[..2], &mn[3..]), &[r, mem_off(rn, off)])?,
+                3 if mn != "prfm" => insn(mn, &[r, format!("[{}, #{}]!", base, off)])?,
+                _ => return None,
+            }
+        } else if bits(w, 11, 10) == 2 {
+            let rm = bits(w, 20, 16);
+            let option = bits(w, 15, 13);
+            if option & 2 == 0 {
+                return None;
+            }
+            let index = reg(rm, option & 1 != 0);
+            let amount = if bit(w, 12) { scale } else { 0 };
+            let m = match (option, bit(w, 12)) {
+                (3, false) => format!("[{}, {}]", base, index),
+                (3, true) => format!("[{}, {}, lsl #{}]", base, index, amount),
+                (_, false) => format!("[{}, {}, {}]", base, index, EXTEND[option as usize]),
+                (_, true) => format!("[{}, {}, {} #{}]", base, index, EXTEND[option as usize], amount),
+            };
+            insn(mn, &[r, m])?
+        } else {
+            return None;
+        };
+        if load && !v {
+            d.writes = Some(rt);
+        }
+        return Some(d);
+    }
+
+    // release/acquire with unscaled offset
+    if bits(w, 29, 24) == 0b011001 && !bit(w, 21) && bits(w, 11, 10) == 0 {
+        let opc = bits(w, 23, 22);
+        let sfx = ["b", "h", "", ""][size as usize];
+        let (mn, sf) = match (size, opc) {
+            (_, 0) => (format!("stlur{}", sfx), size == 3),
+            (_, 1) => (format!("ldapur{}", sfx), size == 3),
+            (0 | 1, _) => (format!("ldapurs{}", sfx), opc == 2),
+            (2, 2) => ("ldapursw".to_string(), true),
+            _ => return None,
+        };
+        let mut d = insn(&mn, &[reg(rt, sf), mem_off(rn, sext(bits(w, 20, 12), 9))])?;
+        if opc != 0 {
+            d.writes = Some(rt);
+        }
+        return Some(d);
+    }
+
+    // vector single structure, a lane or replicated to all lanes
+    if !bit(w, 31) && bits(w, 29, 24) == 0b001101 {
+        let post = bit(w, 23);
+        let q = bits(w, 30, 30);
+        let l = bit(w, 22);
+        let opcode = bits(w, 15, 13);
+        let s = bits(w, 12, 12);
+        let sz = bits(w, 11, 10);
+        let nregs = (((opcode & 1) << 1) | bits(w, 21, 21)) + 1;
+        if !post && bits(w, 20, 16) != 0 {
+            return None;
+        }
+        let (t, index, lg) = match opcode >> 1 {
+            0 => ("b", (q << 3) | (s << 2) | sz, 0),
+            1 if sz & 1 == 0 => ("h", (q << 2) | (s << 1) | (sz >> 1), 1),
+            2 if sz == 0 => ("s", (q << 1) | s, 2),
+            2 if sz == 1 && s == 0 => ("d", q, 3),
+            3 if l && s == 0 => ("", 0, sz),
+            _ => return None,
+        };
+        let list = if t.is_empty() {
+            let arr = ARR[(sz * 2 + q) as usize];
+            (0..nregs).map(|i| format!("v{}.{}", (rt + i) % 32, arr)).collect::<Vec<_>>().join(", ")
+        } else {
+            (0..nregs).map(|i| format!("v{}.{}", (rt + i) % 32, t)).collect::<Vec<_>>().join(", ")
+        };
+        let mn = format!("{}{}{}", if l { "ld" } else { "st" }, nregs, if t.is_empty() { "r" } else { "" });
+        let list = if t.is_empty() { format!("{{ {} }}", list) } else { format!("{{ {} }}[{}]", list, index) };
+        let mut ops = vec![list, format!("[{}]", reg_sp(rn, true))];
+        if post {
+            let rm = bits(w, 20, 16);
+            ops.push(if rm == 31 { imm((nregs << lg) as i64) } else { reg(rm, true) });
+        }
+        return insn(&mn, &ops);
+    }
+
+    // vector multiple structures
+    if !bit(w, 31) && bits(w, 29, 24) == 0b001100 && !bit(w, 21) && v {
+        let post = bit(w, 23);
+        let q = bit(w, 30) as u32;
+        let (mn, nregs) = match bits(w, 15, 12) {
+            0b0000 => ("4", 4),
+            0b0010 => ("1", 4),
+            0b0100 => ("3", 3),
+            0b0110 => ("1", 3),
+            0b0111 => ("1", 1),
+            0b1000 => ("2", 2),
+            0b1010 => ("1", 2),
+            _ => return None,
+        };
+        let sz = bits(w, 11, 10);
+        if sz == 3 && q == 0 && mn != "1" {
+            return None;
+        }
+        if !post && bits(w, 20, 16) != 0 {
+            return None;
+        }
+        let arr = ARR[(sz * 2 + q) as usize];
+        let list = (0..nregs).map(|i| format!("v{}.{}", (rt + i) % 32, arr)).collect::<Vec<_>>().join(", ");
+        let mn = format!("{}{}", if bit(w, 22) { "ld" } else { "st" }, mn);
+        let mut ops = vec![format!("{{ {} }}", list), format!("[{}]", reg_sp(rn, true))];
+        if post {
+            let rm = bits(w, 20, 16);
+            ops.push(if rm == 31 { imm((nregs * (8 << q)) as i64) } else { reg(rm, true) });
+        }
+        return insn(&mn, &ops);
+    }
+    None
+}
+
+fn prfop(rt : u32) -> String {
+    // prefetch operation, e.g. pldl1keep
+    let kind = ["pld", "pli", "pst"];
+    match (rt >> 3, (rt >> 1) & 3) {
+        (k, t) if k < 3 && t < 3 => format!("{}l{}{}", kind[k as usize], t + 1, if rt & 1 == 0 { "keep" } else { "strm" }),
+        _ => imm(rt as i64),
+    }
+}
+
+fn atomic(w : u32) -> Option<Decoded> {
+    // LSE atomic memory operations
+    let size = bits(w, 31, 30);
+    let (a, r) = (bit(w, 23), bit(w, 22));
+    let rs = bits(w, 20, 16);
+    let rn = bits(w, 9, 5);
+    let rt = bits(w, 4, 0);
+    let sf = size == 3;
+    let suffix = ["b", "h", "", ""][size as usize];
+    let order = format!("{}{}", if a { "a" } else { "" }, if r { "l" } else { "" });
+    let base = format!("[{}]", reg_sp(rn, true));
+
+    let op = match (bit(w, 15), bits(w, 14, 12)) {
+        (false, o) => ["add", "clr", "eor", "set", "smax", "smin", "umax", "umin"][o as usize],
+        (true, 0) => "swp",
+        (true, 4) if a && !r && rs == 31 => return insn(&format!("ldapr{}", suffix), &[reg(rt, sf), base]),
+        _ => return None,
+    };
+    if op == "swp" {
+        return insn(&format!("swp{}{}", order, suffix), &[reg(rs, sf), reg(rt, sf), base]);
+    }
+    if rt == 31 && !a {
+        // result discarded
+        return insn(&format!("st{}{}{}", op, if r { "l" } else { "" }, suffix), &[reg(rs, sf), base]);
+    }
+    let mut d = insn(&format!("ld{}{}{}", op, order, suffix), &[reg(rs, sf), reg(rt, sf), base])?;
+    d.writes = Some(rt);
+    Some(d)
+}
+
+fn shifted(ops : &mut Vec<String>, shift : u32, amount : u32) {
+    // optional trailing shift of a register operand
+    if amount != 0 || shift != 0 {
+        ops.push(format!("{} #{}", SHIFT[shift as usize], amount));
+    }
+}
+
+fn dp_reg(w : u32) -> Option<Decoded> {
+    let sf = bit(w, 31);
+    let rd = bits(w, 4, 0);
+    let rn = bits(w, 9, 5);
+    let rm = bits(w, 20, 16);
+    let (r, rsp) = (|n| reg(n, sf), |n| reg_sp(n, sf));
+
+    let mut d = if !bit(w, 28) && !bit(w, 24) {
+        // logical, shifted register
+        let (shift, amount) = (bits(w, 23, 22), bits(w, 15, 10));
+        if !sf && amount >= 32 {
+            return None;
+        }
+        let opc = (bits(w, 30, 29) << 1) | bits(w, 21, 21);
+        let mn = ["and", "bic", "orr", "orn", "eor", "eon", "ands", "bics"][opc as usize];
+        let mut ops = match (mn, rn, rd) {
+            ("orr", 31, _) if shift == 0 && amount == 0 => return insn("mov", &[r(rd), r(rm)]).map(|mut d| { d.writes = Some(rd); d }),
+            ("orn", 31, _) => vec![r(rd), r(rm)],
+            ("ands", _, 31) => vec![r(rn), r(rm)],
+            _ => vec![r(rd), r(rn), r(rm)],
+        };
+        shifted(&mut ops, shift, amount);
+        let mn = match (mn, rn, rd) {
+            ("orn", 31, _) => "mvn",
+            ("ands", _, 31) => "tst",
+            _ => mn,
+        };
+        insn(mn, &ops)?
+    } else if !bit(w, 28) && !bit(w, 21) {
+        // add/sub, shifted register
+        let (shift, amount) = (bits(w, 23, 22), bits(w, 15, 10));
+        if shift == 3 || (!sf && amount >= 32) {
+            return None;
+        }
+        let (op, s) = (bit(w, 30), bit(w, 29));
+        let (mn, mut ops) = match (op, s, rd, rn) {
+            (false, true, 31, _) => ("cmn", vec![r(rn), r(rm)]),
+            (true, true, 31, _) => ("cmp", vec![r(rn), r(rm)]),
+            (true, false, _, 31) => ("neg", vec![r(rd), r(rm)]),
+            (true, true, _, 31) => ("negs", vec![r(rd), r(rm)]),
+            _ => ([["add", "adds"], ["sub", "subs"]][op as usize][s as usize], vec![r(rd), r(rn), r(rm)]),
+        };
+        shifted(&mut ops, shift, amount);
+        insn(mn, &ops)?
+    } else if !bit(w, 28) {
+        // add/sub, extended register
+        let (op, s) = (bit(w, 30), bit(w, 29));
+        let option = bits(w, 15, 13);
+        let amount = bits(w, 12, 10);
+        if bits(w, 23, 22) != 0 || amount > 4 {
+            return None;
+        }
+        let rm_s = reg(rm, sf && option & 3 == 3);
+        let lsl_form = if sf { 3 } else { 2 };
+        let sp_involved = rn == 31 || (!s && rd == 31);
+        let ext = if sp_involved && option == lsl_form {
+            if amount == 0 { None } else { Some(format!("lsl #{}", amount)) }
+        } else if amount == 0 {
+            Some(EXTEND[option as usize].to_string())
+        } else {
+            Some(format!("{} #{}", EXTEND[option as usize], amount))
+        };
+        let (mn, mut ops) = match (op, s, rd) {
+            (false, true, 31) => ("cmn", vec![rsp(rn), rm_s]),
+            (true, true, 31) => ("cmp", vec![rsp(rn), rm_s]),
+            (_, true, _) => (if op { "subs" } else { "adds" }, vec![r(rd), rsp(rn), rm_s]),
+            _ => (if op { "sub" } else { "add" }, vec![rsp(rd), rsp(rn), rm_s]),
+        };
+        ops.extend(ext);
+        insn(mn, &ops)?
+    } else if bit(w, 24) {
+        // three source
+        let ra = bits(w, 14, 10);
+        let o0 = bit(w, 15);
+        if bits(w, 30, 29) != 0 {
+            return None;
+        }
+        let (wide, name) = match (bits(w, 23, 21), o0) {
+            (0, false) => (false, if ra == 31 { "mul" } else { "madd" }),
+            (0, true) => (false, if ra == 31 { "mneg" } else { "msub" }),
+            (1, false) if sf => (true, if ra == 31 { "smull" } else { "smaddl" }),
+            (1, true) if sf => (true, if ra == 31 { "smnegl" } else { "smsubl" }),
+            (5, false) if sf => (true, if ra == 31 { "umull" } else { "umaddl" }),
+            (5, true) if sf => (true, if ra == 31 { "umnegl" } else { "umsubl" }),
+            (2, false) if sf => return insn("smulh", &[r(rd), r(rn), r(rm)]).map(|mut d| { d.writes = Some(rd); d }),
+            (6, false) if sf => return insn("umulh", &[r(rd), r(rn), r(rm)]).map(|mut d| { d.writes = Some(rd); d }),
+            _ => return None,
+        };
+        let src = |n| if wide { reg(n, false) } else { r(n) };
+        let mut ops = vec![r(rd), src(rn), src(rm)];
+        if ra != 31 {
+            ops.push(r(ra));
+        }
+        insn(name, &ops)?
+    } else {
+        match bits(w, 24, 21) {
+            0 => {
+                if bits(w, 15, 10) != 0 {
+                    return None;
+                }
+                let (op, s) = (bit(w, 30), bit(w, 29));
+                let mn = [["adc", "adcs"], ["sbc", "sbcs"]][op as usize][s as usize];
+                if op && rn == 31 {
+                    insn(if s { "ngcs" } else { "ngc" }, &[r(rd), r(rm)])?
+                } else {
+                    insn(mn, &[r(rd), r(rn), r(rm)])?
+                }
+            }
+            2 => {
+                if !bit(w, 29) || bit(w, 10) || bit(w, 4) {
+                    return None;
+                }
+                let mn = if bit(w, 30) { "ccmp" } else { "ccmn" };
+                let second = if bit(w, 11) { imm(rm as i64) } else { r(rm) };
+                let d = insn(mn, &[r(rn), second, imm(bits(w, 3, 0) as i64), COND[bits(w, 15, 12) as usize].to_string()]);
+                return d;
+            }
+            4 => {
+                let cond = bits(w, 15, 12);
+                let inv = COND[(cond ^ 1) as usize].to_string();
+                let c = COND[cond as usize].to_string();
+                if bit(w, 29) || bits(w, 11, 11) != 0 {
+                    return None;
+                }
+                let no_al = cond >> 1 != 7;
+                match (bit(w, 30), bit(w, 10)) {
+                    (false, false) => insn("csel", &[r(rd), r(rn), r(rm), c])?,
+                    (false, true) if rn == 31 && rm == 31 && no_al => insn("cset", &[r(rd), inv])?,
+                    (false, true) if rn == rm && rn != 31 && no_al => insn("cinc", &[r(rd), r(rn), inv])?,
+                    (false, true) => insn("csinc", &[r(rd), r(rn), r(rm), c])?,
+                    (true, false) if rn == 31 && rm == 31 && no_al => insn("csetm", &[r(rd), inv])?,
+                    (true, false) if rn == rm && rn != 31 && no_al => insn("cinv", &[r(rd), r(rn), inv])?,
+                    (true, false) => insn("csinv", &[r(rd), r(rn), r(rm), c])?,
+                    (true, true) if rn == rm && no_al => insn("cneg", &[r(rd), r(rn), inv])?,
+                    (true, true) => insn("csneg", &[r(rd), r(rn), r(rm), c])?,
+                }
+            }
+            6 if bit(w, 30) => {
+                if bit(w, 29) || rm != 0 {
+                    return None;
+                }
+                let mn = match (bits(w, 15, 10), sf) {
+                    (0, _) => "rbit",
+                    (1, _) => "rev16",
+                    (2, true) => "rev32",
+                    (2, false) => "rev",
+                    (3, true) => "rev",
+                    (4, _) => "clz",
+                    (5, _) => "cls",
+                    _ => return None,
+                };
+                insn(mn, &[r(rd), r(rn)])?
+            }
+            6 => {
+                if bit(w, 29) {
+                    return None;
+                }
+                let opcode = bits(w, 15, 10);
+                match opcode {
+                    2 | 3 | 8..=11 => {
+                        let mn = match opcode { 2 => "udiv", 3 => "sdiv", 8 => "lsl", 9 => "lsr", 10 => "asr", _ => "ror" };
+                        insn(mn, &[r(rd), r(rn), r(rm)])?
+                    }
+                    16..=23 => {
+                        let sz = opcode & 3;
+                        if (sz == 3) != sf {
+                            return None;
+                        }
+                        let mn = format!("crc32{}{}", if opcode & 4 != 0 { "c" } else { "" }, ["b", "h", "w", "x"][sz as usize]);
+                        insn(&mn, &[reg(rd, false), reg(rn, false), reg(rm, sz == 3)])?
+                    }
+                    _ => return None,
+                }
+            }
+            _ => return None,
+        }
+    };
+    d.writes = Some(rd);
+    Some(d)
+}
+
+fn fp_type(ftype : u32) -> Option<u32> {
+    // log2 size of a scalar fp type field
+    match ftype {
+        0 => Some(2),
+        1 => Some(3),
+        3 => Some(1),
+        _ => None,
+    }
+}
+
+fn scalar_fp(w : u32) -> Option<Decoded> {
+    let rd = bits(w, 4, 0);
+    let rn = bits(w, 9, 5);
+    let rm = bits(w, 20, 16);
+    let ftype = bits(w, 23, 22);
+    let sf = bit(w, 31);
+    let f = |n, lg| fpreg(lg, n);
+
+    if bits(w, 30, 29) != 0 {
+        return None;
+    }
+    if bits(w, 28, 24) == 0b11111 {
+        let lg = fp_type(ftype)?;
+        if sf {
+            return None;
+        }
+        let mn = ["fmadd", "fmsub", "fnmadd", "fnmsub"][((bits(w, 21, 21) << 1) | bits(w, 15, 15)) as usize];
+        return insn(mn, &[f(rd, lg), f(rn, lg), f(rm, lg), f(bits(w, 14, 10), lg)]);
+    }
+
+    if !bit(w, 21) {
+        // fixed-point conversion
+        let lg = fp_type(ftype)?;
+        let scale = bits(w, 15, 10);
+        if !sf && scale < 32 {
+            return None;
+        }
+        let fbits = imm(64 - scale as i64);
+        return match (bits(w, 20, 19), bits(w, 18, 16)) {
+            (0, 2) => insn("scvtf", &[f(rd, lg), reg(rn, sf), fbits]),
+            (0, 3) => insn("ucvtf", &[f(rd, lg), reg(rn, sf), fbits]),
+            (3, 0) => insn("fcvtzs", &[reg(rd, sf), f(rn, lg), fbits]),
+            (3, 1) => insn("fcvtzu", &[reg(rd, sf), f(rn, lg), fbits]),
+            _ => None,
+        };
+    }
+    if sf && bits(w, 15, 10) != 0 {
+        return None;
+    }
+
+    match bits(w, 11, 10) {
+        0 if bits(w, 15, 10) == 0 => {
+            // conversion between fp and integer
+            let (rmode, opcode) = (bits(w, 20, 19), bits(w, 18, 16));
+            if ftype == 2 {
+                // fmov to/from the top half of a 128-bit register
+                return match (sf, rmode, opcode) {
+                    (true, 1, 6) => insn("fmov", &[reg(rd, true), format!("v{}.d[1]", rn)]),
+                    (true, 1, 7) => insn("fmov", &[format!("v{}.d[1]", rd), reg(rn, true)]),
+                    _ => None,
+                };
+            }
+            let lg = fp_type(ftype)?;
+            match (rmode, opcode) {
+                (_, 0 | 1) => {
+                    let mn = format!("fcvt{}{}", ["n", "p", "m", "z"][rmode as usize], if opcode == 1 { "u" } else { "s" });
+                    insn(&mn, &[reg(rd, sf), f(rn, lg)])
+                }
+                (0, 2) => insn("scvtf", &[f(rd, lg), reg(rn, sf)]),
+                (0, 3) => insn("ucvtf", &[f(rd, lg), reg(rn, sf)]),
+                (0, 4) => insn("fcvtas", &[reg(rd, sf), f(rn, lg)]),
+                (0, 5) => insn("fcvtau", &[reg(rd, sf), f(rn, lg)]),
+                (0, 6) if sf == (lg == 3) || lg == 1 => insn("fmov", &[reg(rd, sf), f(rn, lg)]),
+                (0, 7) if sf == (lg == 3) || lg == 1 => insn("fmov", &[f(rd, lg), reg(rn, sf)]),
+                _ => None,
+            }
+        }
+        0 if bits(w, 14, 10) == 0b10000 => {
+            let lg = fp_type(ftype)?;
+            let opcode = bits(w, 20, 15);
+            match opcode {
+                0 => insn("fmov", &[f(rd, lg), f(rn, lg)]),
+                1 => insn("fabs", &[f(rd, lg), f(rn, lg)]),
+                2 => insn("fneg", &[f(rd, lg), f(rn, lg)]),
+                3 => insn("fsqrt", &[f(rd, lg), f(rn, lg)]),
+                4 | 5 | 7 if opcode & 3 != ftype => insn("fcvt", &[f(rd, [2, 3, 0, 1][(opcode & 3) as usize]), f(rn, lg)]),
+                8..=12 | 14 | 15 => {
+                    let mn = ["frintn", "frintp", "frintm", "frintz", "frinta", "", "frintx", "frinti"][(opcode - 8) as usize];
+                    insn(mn, &[f(rd, lg), f(rn, lg)])
+                }
+                _ => None,
+            }
+        }
+        0 if bits(w, 13, 10) == 0b1000 && bits(w, 15, 14) == 0 => {
+            let lg = fp_type(ftype)?;
+            let mn = if bit(w, 4) { "fcmpe" } else { "fcmp" };
+            match bits(w, 3, 0) {
+                0 => insn(mn, &[f(rn, lg), f(rm, lg)]),
+                8 if rm == 0 => insn(mn, &[f(rn, lg), "#0.0".to_string()]),
+                _ => None,
+            }
+        }
+        0 if bits(w, 12, 10) == 0b100 && bits(w, 9, 5) == 0 => {
+            let lg = fp_type(ftype)?;
+            insn("fmov", &[f(rd, lg), format!("#{:.8}", fp_imm(bits(w, 20, 13)))])
+        }
+        1 => {
+            let lg = fp_type(ftype)?;
+            let mn = if bit(w, 4) { "fccmpe" } else { "fccmp" };
+            insn(mn, &[f(rn, lg), f(rm, lg), imm(bits(w, 3, 0) as i64), COND[bits(w, 15, 12) as usize].to_string()])
+        }
+        2 => {
+            let lg = fp_type(ftype)?;
+            let mn = ["fmul", "fdiv", "fadd", "fsub", "fmax", "fmin", "fmaxnm", "fminnm", "fnmul"].get(bits(w, 15, 12) as usize)?;
+            insn(mn, &[f(rd, lg), f(rn, lg), f(rm, lg)])
+        }
+        3 => {
+            let lg = fp_type(ftype)?;
+            insn("fcsel", &[f(rd, lg), f(rn, lg), f(rm, lg), COND[bits(w, 15, 12) as usize].to_string()])
+        }
+        _ => None,
+    }
+}
+
+fn simd(w : u32) -> Option<Decoded> {
+    // Advanced SIMD vector groups
+    let rd = bits(w, 4, 0);
+    let rn = bits(w, 9, 5);
+    let rm = bits(w, 20, 16);
+    let q = bits(w, 30, 30);
+    let u = bit(w, 29);
+    let size = bits(w, 23, 22);
+    let v = |n : u32, arr : &str| format!("v{}.{}", n, arr);
+    let arr = ARR[(size * 2 + q) as usize];
+
+    if bit(w, 31) {
+        return None;
+    }
+
+    // copy: dup, ins, umov, smov
+    if bits(w, 28, 21) == 0b01110000 && bit(w, 10) && !bit(w, 15) {
+        let imm5 = bits(w, 20, 16);
+        let imm4 = bits(w, 14, 11);
+        let lg = imm5.trailing_zeros();
+        if lg > 3 {
+            return None;
+        }
+        let t = ["b", "h", "s", "d"][lg as usize];
+        let index = imm5 >> (lg + 1);
+        let elem = |n : u32, i : u32| format!("v{}.{}[{}]", n, t, i);
+        return match (u, imm4) {
+            (false, 0) if !(lg == 3 && q == 0) => insn("dup", &[v(rd, ARR[(lg * 2 + q) as usize]), elem(rn, index)]),
+            (false, 1) if !(lg == 3 && q == 0) => insn("dup", &[v(rd, ARR[(lg * 2 + q) as usize]), reg(rn, lg == 3)]),
+            (false, 3) if q == 1 => insn("mov", &[elem(rd, index), reg(rn, lg == 3)]),
+            (false, 5) if lg < 2 || (lg == 2 && q == 1) => insn("smov", &[reg(rd, q == 1), elem(rn, index)]),
+            (false, 7) if (lg == 3) == (q == 1) => {
+                let mn = if lg >= 2 { "mov" } else { "umov" };
+                insn(mn, &[reg(rd, q == 1), elem(rn, index)])
+            }
+            (true, _) if q == 1 => insn("mov", &[elem(rd, index), elem(rn, imm4 >> lg)]),
+            _ => None,
+        };
+    }
+
+    // modified immediate
+    if bits(w, 28, 19) == 0b0111100000 && bit(w, 10) && !bit(w, 11) {
+        let abc = bits(w, 18, 16);
+        let imm8 = (abc << 5) | bits(w, 9, 5);
+        let cmode = bits(w, 15, 12);
+        let op = u;
+        return match (cmode, op) {
+            (0b1110, false) => insn("movi", &[v(rd, ARR[q as usize]), imm(imm8 as i64)]),
+            (0b1110, true) => {
+                let mut x = 0u64;
+                for i in 0..8 {
+                    if imm8 & (1 << i) != 0 {
+                        x |= 0xff << (i * 8);
+                    }
+                }
+                let dst = if q == 1 { v(rd, "2d") } else { format!("d{}", rd) };
+                let x = if x == 0 { "#0000000000000000".to_string() } else { format!("#{:#016x}", x) };
+                insn("movi", &[dst, x])
+            }
+            (0b1111, false) => insn("fmov", &[v(rd, ARR[(4 + q) as usize]), format!("#{:.8}", fp_imm(imm8))]),
+            (0b1111, true) if q == 1 => insn("fmov", &[v(rd, "2d"), format!("#{:.8}", fp_imm(imm8))]),
+            (0b1111, true) => None,
+            _ => {
+                let (wide, shift, msl) = match cmode {
+                    0..=7 => (true, (cmode >> 1) * 8, false),
+                    8..=11 => (false, ((cmode >> 1) & 1) * 8, false),
+                    _ => (true, ((cmode & 1) + 1) * 8, true),
+                };
+                let arr = if wide { ARR[(4 + q) as usize] } else { ARR[(2 + q) as usize] };
+                let orr_bic = !msl && cmode & 1 == 1;
+                let mn = match (orr_bic, op) {
+                    (false, false) => "movi",
+                    (false, true) => "mvni",
+                    (true, false) => "orr",
+                    (true, true) => "bic",
+                };
+                let mut ops = vec![v(rd, arr), imm(imm8 as i64)];
+                if msl {
+                    ops.push(format!("msl #{}", shift));
+                } else if shift != 0 {
+                    ops.push(format!("lsl #{}", shift));
+                }
+                insn(mn, &ops)
+            }
+        };
+    }
+
+    // shift by immediate
+    if bits(w, 28, 23) == 0b011110 && bit(w, 10) && bits(w, 22, 19) != 0 {
+        let immh = bits(w, 22, 19);
+        let immhb = bits(w, 22, 16);
+        let lg = 31 - immh.leading_zeros();
+        let esize = 8 << lg;
+        if lg == 3 && q == 0 {
+            return None;
+        }
+        let full = ARR[(lg * 2 + q) as usize];
+        let right = imm((2 * esize - immhb) as i64);
+        let left = imm((immhb - esize) as i64);
+        let two = if q == 1 { "2" } else { "" };
+        return match (bits(w, 15, 11), u) {
+            (0b00000, _) => insn(if u { "ushr" } else { "sshr" }, &[v(rd, full), v(rn, full), right]),
+            (0b00010, _) => insn(if u { "usra" } else { "ssra" }, &[v(rd, full), v(rn, full), right]),
+            (0b00100, _) => insn(if u { "urshr" } else { "srshr" }, &[v(rd, full), v(rn, full), right]),
+            (0b01010, false) => insn("shl", &[v(rd, full), v(rn, full), left]),
+            (0b01010, true) => insn("sli", &[v(rd, full), v(rn, full), left]),
+            (0b01000, true) => insn("sri", &[v(rd, full), v(rn, full), right]),
+            (0b10000, _) | (0b10001, _) | (0b10010, _) | (0b10011, _) if lg < 3 => {
+                let name = match (bits(w, 15, 11), u) {
+                    (0b10000, false) => "shrn",
+                    (0b10001, false) => "rshrn",
+                    (0b10000, true) => "sqshrun",
+                    (0b10001, true) => "sqrshrun",
+                    (0b10010, false) => "sqshrn",
+                    (0b10011, false) => "sqrshrn",
+                    (0b10010, true) => "uqshrn",
+                    _ => "uqrshrn",
+                };
+                insn(&format!("{}{}", name, two), &[v(rd, full), v(rn, ARR[(lg * 2 + 3) as usize]), right])
+            }
+            (0b10100, _) if lg < 3 => {
+                let wide = ARR[(lg * 2 + 3) as usize];
+                if immhb == esize {
+                    insn(&format!("{}{}", if u { "uxtl" } else { "sxtl" }, two), &[v(rd, wide), v(rn, full)])
+                } else {
+                    insn(&format!("{}{}", if u { "ushll" } else { "sshll" }, two), &[v(rd, wide), v(rn, full), left])
+                }
+            }
+            _ => None,
+        };
+    }
+
+    if bits(w, 28, 24) != 0b01110 {
+        // ext, tbl/tbx and permutes live in the 0x0e/0x2e space checked below
+        return None;
+    }
+
+    // extract
+    if u && bits(w, 23, 21) == 0 && !bit(w, 15) && !bit(w, 10) {
+        let a = ARR[q as usize];
+        let index = bits(w, 14, 11);
+        if q == 0 && index >= 8 {
+            return None;
+        }
+        return insn("ext", &[v(rd, a), v(rn, a), v(rm, a), imm(index as i64)]);
+    }
+
+    // table lookup
+    if !u && bits(w, 23, 21) == 0 && !bit(w, 15) && bits(w, 11, 10) == 0 {
+        let a = ARR[q as usize];
+        let n = bits(w, 14, 13) + 1;
+        let list = (0..n).map(|i| v((rn + i) % 32, "16b")).collect::<Vec<_>>().join(", ");
+        let mn = if bit(w, 12) { "tbx" } else { "tbl" };
+        return insn(mn, &[v(rd, a), format!("{{ {} }}", list), v(rm, a)]);
+    }
+
+    // permute
+    if !u && !bit(w, 21) && !bit(w, 15) && bits(w, 11, 10) == 2 {
+        if size == 3 && q == 0 {
+            return None;
+        }
+        let mn = ["", "uzp1", "trn1", "zip1", "", "uzp2", "trn2", "zip2"][bits(w, 14, 12) as usize];
+        if mn.is_empty() {
+            return None;
+        }
+        return insn(mn, &[v(rd, arr), v(rn, arr), v(rm, arr)]);
+    }
+
+    if !bit(w, 21) {
+        return None;
+    }
+
+    // three same
+    if bit(w, 10) {
+        let opcode = bits(w, 15, 11);
+        if opcode == 0b00011 {
+            let a = ARR[q as usize];
+            let mn = match (u, size) {
+                (false, 0) => "and",
+                (false, 1) => "bic",
+                (false, 2) if rn == rm => return insn("mov", &[v(rd, a), v(rn, a)]),
+                (false, 2) => "orr",
+                (false, _) => "orn",
+                (true, 0) => "eor",
+                (true, 1) => "bsl",
+                (true, 2) => "bit",
+                (true, _) => "bif",
+            };
+            return insn(mn, &[v(rd, a), v(rn, a), v(rm, a)]);
+        }
+        if opcode >= 0b11000 {
+            // floating point, size<1> selects the second table and size<0> the precision
+            let sz = size & 1;
+            if sz == 1 && q == 0 {
+                return None;
+            }
+            let a = ARR[(4 + sz * 2 + q) as usize];
+            let mn = match (u, size >> 1, opcode & 7) {
+                (false, 0, 0) => "fmaxnm",
+                (false, 0, 1) => "fmla",
+                (false, 0, 2) => "fadd",
+                (false, 0, 3) => "fmulx",
+                (false, 0, 4) => "fcmeq",
+                (false, 0, 6) => "fmax",
+                (false, 0, 7) => "frecps",
+                (false, 1, 0) => "fminnm",
+                (false, 1, 1) => "fmls",
+                (false, 1, 2) => "fsub",
+                (false, 1, 6) => "fmin",
+                (false, 1, 7) => "frsqrts",
+                (true, 0, 0) => "fmaxnmp",
+                (true, 0, 2) => "faddp",
+                (true, 0, 3) => "fmul",
+                (true, 0, 4) => "fcmge",
+                (true, 0, 5) => "facge",
+                (true, 0, 6) => "fmaxp",
+                (true, 0, 7) => "fdiv",
+                (true, 1, 0) => "fminnmp",
+                (true, 1, 2) => "fabd",
+                (true, 1, 4) => "fcmgt",
+                (true, 1, 5) => "facgt",
+                (true, 1, 6) => "fminp",
+                _ => return None,
+            };
+            return insn(mn, &[v(rd, a), v(rn, a), v(rm, a)]);
+        }
+        if size == 3 && q == 0 {
+            return None;
+        }
+        let names = if u {
+            ["uhadd", "uqadd", "urhadd", "", "uhsub", "uqsub", "cmhi", "cmhs",
+             "ushl", "uqshl", "urshl", "uqrshl", "umax", "umin", "uabd", "uaba",
+             "sub", "cmeq", "mls", "pmul", "umaxp", "uminp", "sqrdmulh", ""]
+        } else {
+            ["shadd", "sqadd", "srhadd", "", "shsub", "sqsub", "cmgt", "cmge",
+             "sshl", "sqshl", "srshl", "sqrshl", "smax", "smin", "sabd", "saba",
+             "add", "cmtst", "mla", "mul", "smaxp", "sminp", "sqdmulh", "addp"]
+        };
+        let mn = names[opcode as usize];
+        let bad_size = match mn {
+            "" => true,
+            "mla" | "mls" | "mul" | "smax" | "smin" | "umax" | "umin" | "sabd" | "uabd" | "saba" | "uaba"
+            | "shadd" | "uhadd" | "srhadd" | "urhadd" | "shsub" | "uhsub" | "smaxp" | "sminp" | "umaxp" | "uminp" => size == 3,
+            "pmul" => size != 0,
+            "sqdmulh" | "sqrdmulh" => size == 0 || size == 3,
+            _ => false,
+        };
+        if bad_size {
+            return None;
+        }
+        return insn(mn, &[v(rd, arr), v(rn, arr), v(rm, arr)]);
+    }
+
+    // two register misc and across lanes
+    if bits(w, 11, 10) == 2 && bits(w, 20, 17) == 0b0000 {
+        let opcode = bits(w, 16, 12);
+        let half = ARR[(size * 2) as usize];
+        let two = if q == 1 { "2" } else { "" };
+        let wide = |s : u32| ARR.get((s * 2 + 2 + q) as usize).copied();
+        let (mn, ops) = match (u, opcode) {
+            (false, 0) if size < 3 => ("rev64", vec![v(rd, arr), v(rn, arr)]),
+            (false, 1) if size == 0 => ("rev16", vec![v(rd, arr), v(rn, arr)]),
+            (true, 0) if size < 2 => ("rev32", vec![v(rd, arr), v(rn, arr)]),
+            (_, 2) if size < 3 => (if u { "uaddlp" } else { "saddlp" }, vec![v(rd, wide(size)?), v(rn, arr)]),
+            (_, 6) if size < 3 => (if u { "uadalp" } else { "sadalp" }, vec![v(rd, wide(size)?), v(rn, arr)]),
+            (false, 4) if size < 3 => ("cls", vec![v(rd, arr), v(rn, arr)]),
+            (true, 4) if size < 3 => ("clz", vec![v(rd, arr), v(rn, arr)]),
+            (false, 5) if size == 0 => ("cnt", vec![v(rd, arr), v(rn, arr)]),
+            (true, 5) if size == 0 => ("mvn", vec![v(rd, arr), v(rn, arr)]),
+            (true, 5) if size == 1 => ("rbit", vec![v(rd, ARR[q as usize]), v(rn, ARR[q as usize])]),
+            (_, 8) if !(size == 3 && q == 0) => (if u { "cmge" } else { "cmgt" }, vec![v(rd, arr), v(rn, arr), "#0".to_string()]),
+            (_, 9) if !(size == 3 && q == 0) => (if u { "cmle" } else { "cmeq" }, vec![v(rd, arr), v(rn, arr), "#0".to_string()]),
+            (false, 10) if !(size == 3 && q == 0) => ("cmlt", vec![v(rd, arr), v(rn, arr), "#0".to_string()]),
+            (_, 11) if !(size == 3 && q == 0) => (if u { "neg" } else { "abs" }, vec![v(rd, arr), v(rn, arr)]),
+            (false, 18) if size < 3 => ("xtn", vec![v(rd, ARR[(size * 2 + q) as usize]), v(rn, ARR[(size * 2 + 3) as usize])]),
+            (true, 18) if size < 3 => ("sqxtun", vec![v(rd, ARR[(size * 2 + q) as usize]), v(rn, ARR[(size * 2 + 3) as usize])]),
+            (_, 20) if size < 3 => (if u { "uqxtn" } else { "sqxtn" }, vec![v(rd, ARR[(size * 2 + q) as usize]), v(rn, ARR[(size * 2 + 3) as usize])]),
+            _ => return None,
+        };
+        let _ = half;
+        let narrow = matches!(mn, "xtn" | "sqxtun" | "uqxtn" | "sqxtn");
+        return insn(&format!("{}{}", mn, if narrow { two } else { "" }), &ops);
+    }
+    if bits(w, 11, 10) == 2 && bits(w, 20, 17) == 0b1000 {
+        let opcode = bits(w, 16, 12);
+        if size == 3 || (size == 2 && q == 0) {
+            return None;
+        }
+        let scalar = |lg : u32| fpreg(lg, rd);
+        return match (u, opcode) {
+            (_, 3) => insn(if u { "uaddlv" } else { "saddlv" }, &[scalar(size + 1), v(rn, arr)]),
+            (_, 10) => insn(if u { "umaxv" } else { "smaxv" }, &[scalar(size), v(rn, arr)]),
+            (_, 26) => insn(if u { "uminv" } else { "sminv" }, &[scalar(size), v(rn, arr)]),
+            (false, 27) => insn("addv", &[scalar(size), v(rn, arr)]),
+            _ => None,
+        };
+    }
+
+    // three different
+    if bits(w, 11, 10) == 0 {
+        let opcode = bits(w, 15, 12);
+        let two = if q == 1 { "2" } else { "" };
+        if size == 3 && opcode != 14 {
+            return None;
+        }
+        let wide = ARR.get((size * 2 + 3) as usize).copied().unwrap_or("1q");
+        let name = match (u, opcode) {
+            (_, 0) => if u { "uaddl" } else { "saddl" },
+            (_, 1) => if u { "uaddw" } else { "saddw" },
+            (_, 2) => if u { "usubl" } else { "ssubl" },
+            (_, 3) => if u { "usubw" } else { "ssubw" },
+            (_, 8) => if u { "umlal" } else { "smlal" },
+            (_, 10) => if u { "umlsl" } else { "smlsl" },
+            (_, 12) => if u { "umull" } else { "smull" },
+            (false, 14) if size == 0 || size == 3 => "pmull",
+            (_, 5) => if u { "uabal" } else { "sabal" },
+            (_, 7) => if u { "uabdl" } else { "sabdl" },
+            (false, 4) => "addhn",
+            (true, 4) => "raddhn",
+            (false, 6) => "subhn",
+            (true, 6) => "rsubhn",
+            _ => return None,
+        };
+        let mn = format!("{}{}", name, two);
+        return match opcode {
+            1 | 3 => insn(&mn, &[v(rd, wide), v(rn, wide), v(rm, arr)]),
+            4 | 6 => insn(&mn, &[v(rd, arr), v(rn, wide), v(rm, wide)]),
+            _ => insn(&mn, &[v(rd, wide), v(rn, arr), v(rm, arr)]),
+        };
+    }
+    None
+}
+
+fn by_element(w : u32) -> Option<Decoded> {
+    // vector by indexed element
+    let rd = bits(w, 4, 0);
+    let rn = bits(w, 9, 5);
+    let q = bits(w, 30, 30);
+    let u = bit(w, 29);
+    let size = bits(w, 23, 22);
+    let (h, l, m) = (bits(w, 11, 11), bits(w, 21, 21), bits(w, 20, 20));
+    let opcode = bits(w, 15, 12);
+    let two = if q == 1 { "2" } else { "" };
+    let v = |n : u32, arr : &str| format!("v{}.{}", n, arr);
+
+    if bit(w, 31) || bits(w, 28, 24) != 0b01111 || bit(w, 10) {
+        return None;
+    }
+
+    let fp = matches!((u, opcode), (false, 1) | (false, 5) | (false, 9) | (true, 9));
+    if fp {
+        let mn = match opcode { 1 => "fmla", 5 => "fmls", _ if u => "fmulx", _ => "fmul" };
+        let (t, arr, index, rm) = match size {
+            0 => ("h", ARR[(2 + q) as usize], (h << 2) | (l << 1) | m, bits(w, 19, 16)),
+            2 => ("s", ARR[(4 + q) as usize], (h << 1) | l, bits(w, 20, 16)),
+            3 if l == 0 && q == 1 => ("d", "2d", h, bits(w, 20, 16)),
+            _ => return None,
+        };
+        return insn(mn, &[v(rd, arr), v(rn, arr), format!("v{}.{}[{}]", rm, t, index)]);
+    }
+
+    let (t, index, rm) = match size {
+        1 => ("h", (h << 2) | (l << 1) | m, bits(w, 19, 16)),
+        2 => ("s", (h << 1) | l, bits(w, 20, 16)),
+        _ => return None,
+    };
+    let elem = format!("v{}.{}[{}]", rm, t, index);
+    let arr = ARR[(size * 2 + q) as usize];
+    let wide = ARR[(size * 2 + 3) as usize];
+    let (long, name) = match (u, opcode) {
+        (false, 8) => (false, "mul"),
+        (true, 0) => (false, "mla"),
+        (true, 4) => (false, "mls"),
+        (false, 12) => (false, "sqdmulh"),
+        (false, 13) => (false, "sqrdmulh"),
+        (true, 13) => (false, "sqrdmlah"),
+        (true, 15) => (false, "sqrdmlsh"),
+        (_, 2) => (true, if u { "umlal" } else { "smlal" }),
+        (_, 6) => (true, if u { "umlsl" } else { "smlsl" }),
+        (_, 10) => (true, if u { "umull" } else { "smull" }),
+        (false, 3) => (true, "sqdmlal"),
+        (false, 7) => (true, "sqdmlsl"),
+        (false, 11) => (true, "sqdmull"),
+        _ => return None,
+    };
+    if long {
+        insn(&format!("{}{}", name, two), &[v(rd, wide), v(rn, arr), elem])
+    } else {
+        insn(name, &[v(rd, arr), v(rn, arr), elem])
+    }
+}
+
+fn crypto(w : u32) -> Option<Decoded> {
+    // AES
+    if w & 0xffff_cc00 != 0x4e28_4800 {
+        return None;
+    }
+    let mn = ["aese", "aesd", "aesmc", "aesimc"][bits(w, 13, 12) as usize];
+    insn(mn, &[format!("v{}.16b", bits(w, 4, 0)), format!("v{}.16b", bits(w, 9, 5))])
+}
+
+pub fn decode(buf : &[u8], addr : u64, pages : &mut Pages) -> Option<Insn> {
+    let w = u32::from_le_bytes(buf.get(..4)?.try_into().unwrap());
+
+    let d = match bits(w, 28, 25) {
+        0b1000 | 0b1001 => dp_imm(w, addr, pages),
+        0b1010 | 0b1011 => branch_sys(w, addr, pages),
+        0b0100 | 0b0110 | 0b1100 | 0b1110 => load_store(w, addr, pages),
+        0b0101 | 0b1101 => dp_reg(w),
+        0b0111 | 0b1111 => {
+            if bits(w, 28, 24) == 0b11110 || bits(w, 28, 24) == 0b11111 {
+                scalar_fp(w)
+            } else {
+                crypto(w).or_else(|| simd(w)).or_else(|| by_element(w))
+            }
+        }
+        _ => None,
+    };
+    let d = match d {
+        Some(d) => d,
+        None if w == 0 => insn("udf", &["#0".to_string()])?,
+        None => return None,
+    };
+
+    // a register overwritten by anything but adrp no longer holds a page
+    if let Some(r) = d.writes {
+        if d.mnemonic != "adrp" && r != 31 {
+            pages[r as usize] = None;
+        }
+    }
+    Some(Insn {
+        addr,
+        len      : 4,
+        mnemonic : d.mnemonic,
+        operands : d.operands.join(", "),
+        target   : d.target,
+        mem_ref  : d.mem_ref,
+    })
+}
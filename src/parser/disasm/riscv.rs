@@ -0,0 +1,589 @@
+use crate::parser::disasm::{Insn, Pages};
+
+/*
+ RV64GC decoder, syntax and aliases as printed by `llvm-objdump`
+
+ compressed instructions are expanded to their 32-bit equivalent first and
+ printed like that one, e.g. c.addi a0, 4 reads as addi a0, a0, 4
+ */
+
+const XREG : [&str; 32] = ["zero", "ra", "sp", "gp", "tp", "t0", "t1", "t2",
+                           "s0", "s1", "a0", "a1", "a2", "a3", "a4", "a5",
+                           "a6", "a7", "s2", "s3", "s4", "s5", "s6", "s7",
+                           "s8", "s9", "s10", "s11", "t3", "t4", "t5", "t6"];
+const FREG : [&str; 32] = ["ft0", "ft1", "ft2", "ft3", "ft4", "ft5", "ft6", "ft7",
+                           "fs0", "fs1", "fa0", "fa1", "fa2", "fa3", "fa4", "fa5",
+                           "fa6", "fa7", "fs2", "fs3", "fs4", "fs5", "fs6", "fs7",
+                           "fs8", "fs9", "fs10", "fs11", "ft8", "ft9", "ft10", "ft11"];
+const RM   : [&str; 8] = ["rne", "rtz", "rdn", "rup", "rmm", "", "", "dyn"];
+
+fn bits(w : u32, hi : u32, lo : u32) -> u32 {
+    (w >> lo) & ((1 << (hi - lo + 1)) - 1)
+}
+
+fn sext(v : u32, width : u32) -> i64 {
+    let shift = 64 - width;
+    ((v as u64) << shift) as i64 >> shift
+}
+
+/* 32-bit encodings used to expand compressed instructions */
+fn enc_i(op : u32, f3 : u32, rd : u32, rs1 : u32, imm : i64) -> u32 {
+    ((imm as u32 & 0xfff) << 20) | (rs1 << 15) | (f3 << 12) | (rd << 7) | op
+}
+
+fn enc_s(op : u32, f3 : u32, rs1 : u32, rs2 : u32, imm : i64) -> u32 {
+    let imm = imm as u32;
+    (bits(imm, 11, 5) << 25) | (rs2 << 20) | (rs1 << 15) | (f3 << 12) | (bits(imm, 4, 0) << 7) | op
+}
+
+fn enc_r(op : u32, f3 : u32, f7 : u32, rd : u32, rs1 : u32, rs2 : u32) -> u32 {
+    (f7 << 25) | (rs2 << 20) | (rs1 << 15) | (f3 << 12) | (rd << 7) | op
+}
+
+fn enc_b(f3 : u32, rs1 : u32, rs2 : u32, imm : i64) -> u32 {
+    let imm = imm as u32;
+    (bits(imm, 12, 12) << 31) | (bits(imm, 10, 5) << 25) | (rs2 << 20) | (rs1 << 15)
+        | (f3 << 12) | (bits(imm, 4, 1) << 8) | (bits(imm, 11, 11) << 7) | 0x63
+}
+
+fn enc_j(rd : u32, imm : i64) -> u32 {
+    let imm = imm as u32;
+    (bits(imm, 20, 20) << 31) | (bits(imm, 10, 1) << 21) | (bits(imm, 11, 11) << 20)
+        | (bits(imm, 19, 12) << 12) | (rd << 7) | 0x6f
+}
+
+fn expand(c : u32) -> Option<u32> {
+    // RVC instruction as the 32-bit instruction it stands for
+    let f3 = bits(c, 15, 13);
+    let rd = bits(c, 11, 7);
+    let rs2 = bits(c, 6, 2);
+    let rdp = 8 + bits(c, 4, 2);      /* rd'/rs2' */
+    let rs1p = 8 + bits(c, 9, 7);     /* rs1' */
+    let imm6 = sext((bits(c, 12, 12) << 5) | bits(c, 6, 2), 6);
+    let uimm_d = (bits(c, 12, 10) << 3) | (bits(c, 6, 5) << 6);                     /* ld/sd/fld/fsd */
+    let uimm_w = (bits(c, 12, 10) << 3) | (bits(c, 6, 6) << 2) | (bits(c, 5, 5) << 6); /* lw/sw */
+
+    let w = match (c & 3, f3) {
+        (0, 0) => {
+            let imm = (bits(c, 12, 11) << 4) | (bits(c, 10, 7) << 6) | (bits(c, 6, 6) << 2) | (bits(c, 5, 5) << 3);
+            if imm == 0 {
+                return None;
+            }
+            enc_i(0x13, 0, rdp, 2, imm as i64)
+        }
+        (0, 1) => enc_i(0x07, 3, rdp, rs1p, uimm_d as i64),
+        (0, 2) => enc_i(0x03, 2, rdp, rs1p, uimm_w as i64),
+        (0, 3) => enc_i(0x03, 3, rdp, rs1p, uimm_d as i64),
+        (0, 5) => enc_s(0x27, 3, rs1p, rdp, uimm_d as i64),
+        (0, 6) => enc_s(0x23, 2, rs1p, rdp, uimm_w as i64),
+        (0, 7) => enc_s(0x23, 3, rs1p, rdp, uimm_d as i64),
+        (1, 0) => enc_i(0x13, 0, rd, rd, imm6),
+        (1, 1) if rd != 0 => enc_i(0x1b, 0, rd, rd, imm6),
+        (1, 2) => enc_i(0x13, 0, rd, 0, imm6),
+        (1, 3) if rd == 2 => {
+            let imm = (bits(c, 12, 12) << 9) | (bits(c, 6, 6) << 4) | (bits(c, 5, 5) << 6)
+                | (bits(c, 4, 3) << 7) | (bits(c, 2, 2) << 5);
+            if imm == 0 {
+                return None;
+            }
+            enc_i(0x13, 0, 2, 2, sext(imm, 10))
+        }
+        (1, 3) => {
+            ((imm6 as u32 & 0xfffff) << 12) | (rd << 7) | 0x37
+        }
+        (1, 4) => {
+            let rd = rs1p;
+            let shamt = (bits(c, 12, 12) << 5) | bits(c, 6, 2);
+            match (bits(c, 11, 10), bits(c, 12, 12), bits(c, 6, 5)) {
+                (0, _, _) => enc_i(0x13, 5, rd, rd, shamt as i64),
+                (1, _, _) => enc_i(0x13, 5, rd, rd, (shamt | 0x400) as i64),
+                (2, _, _) => enc_i(0x13, 7, rd, rd, imm6),
+                (_, 0, 0) => enc_r(0x33, 0, 0x20, rd, rd, rdp),
+                (_, 0, 1) => enc_r(0x33, 4, 0, rd, rd, rdp),
+                (_, 0, 2) => enc_r(0x33, 6, 0, rd, rd, rdp),
+                (_, 0, _) => enc_r(0x33, 7, 0, rd, rd, rdp),
+                (_, _, 0) => enc_r(0x3b, 0, 0x20, rd, rd, rdp),
+                (_, _, 1) => enc_r(0x3b, 0, 0, rd, rd, rdp),
+                _ => return None,
+            }
+        }
+        (1, 5) => {
+            let imm = (bits(c, 12, 12) << 11) | (bits(c, 11, 11) << 4) | (bits(c, 10, 9) << 8)
+                | (bits(c, 8, 8) << 10) | (bits(c, 7, 7) << 6) | (bits(c, 6, 6) << 7)
+                | (bits(c, 5, 3) << 1) | (bits(c, 2, 2) << 5);
+            enc_j(0, sext(imm, 12))
+        }
+        (1, 6) | (1, 7) => {
+            let imm = (bits(c, 12, 12) << 8) | (bits(c, 11, 10) << 3) | (bits(c, 6, 5) << 6)
+                | (bits(c, 4, 3) << 1) | (bits(c, 2, 2) << 5);
+            enc_b(f3 - 6, rs1p, 0, sext(imm, 9))
+        }
+        (2, 0) => enc_i(0x13, 1, rd, rd, ((bits(c, 12, 12) << 5) | rs2) as i64),
+        (2, 2) | (2, 3) if rd == 0 => return None,
+        (2, 1) | (2, 3) => {
+            let imm = (bits(c, 12, 12) << 5) | (bits(c, 6, 5) << 3) | (bits(c, 4, 2) << 6);
+            enc_i(if f3 == 1 { 0x07 } else { 0x03 }, 3, rd, 2, imm as i64)
+        }
+        (2, 2) => {
+            let imm = (bits(c, 12, 12) << 5) | (bits(c, 6, 4) << 2) | (bits(c, 3, 2) << 6);
+            enc_i(0x03, 2, rd, 2, imm as i64)
+        }
+        (2, 4) => match (bits(c, 12, 12), rd, rs2) {
+            (0, 0, 0) => return None,
+            (0, _, 0) => enc_i(0x67, 0, 0, rd, 0),
+            (0, _, _) => enc_i(0x13, 0, rd, rs2, 0),
+            (_, 0, 0) => 0x00100073,
+            (_, _, 0) => enc_i(0x67, 0, 1, rd, 0),
+            _ => enc_r(0x33, 0, 0, rd, rd, rs2),
+        },
+        (2, 5) | (2, 7) => {
+            let imm = (bits(c, 12, 10) << 3) | (bits(c, 9, 7) << 6);
+            enc_s(if f3 == 5 { 0x27 } else { 0x23 }, 3, 2, rs2, imm as i64)
+        }
+        (2, 6) => {
+            let imm = (bits(c, 12, 9) << 2) | (bits(c, 8, 7) << 6);
+            enc_s(0x23, 2, 2, rs2, imm as i64)
+        }
+        _ => return None,
+    };
+    Some(w)
+}
+
+fn csr_name(csr : u32) -> String {
+    match csr {
+        0x001 => "fflags".to_string(),
+        0x002 => "frm".to_string(),
+        0x003 => "fcsr".to_string(),
+        0xc00 => "cycle".to_string(),
+        0xc01 => "time".to_string(),
+        0xc02 => "instret".to_string(),
+        0x100 => "sstatus".to_string(),
+        0x105 => "stvec".to_string(),
+        0x140 => "sscratch".to_string(),
+        0x141 => "sepc".to_string(),
+        0x142 => "scause".to_string(),
+        0x143 => "stval".to_string(),
+        0x144 => "sip".to_string(),
+        0x104 => "sie".to_string(),
+        0x180 => "satp".to_string(),
+        0x300 => "mstatus".to_string(),
+        0x305 => "mtvec".to_string(),
+        0x340 => "mscratch".to_string(),
+        0x341 => "mepc".to_string(),
+        0x342 => "mcause".to_string(),
+        0x343 => "mtval".to_string(),
+        0xf14 => "mhartid".to_string(),
+        _ => csr.to_string(),
+    }
+}
+
+struct Decoded {
+    mnemonic : String,
+    operands : Vec<String>,
+    target   : Option<u64>,
+    mem_ref  : Option<u64>,
+}
+
+fn insn(mnemonic : &str, operands : &[String]) -> Option<Decoded> {
+    Some(Decoded { mnemonic : mnemonic.to_string(), operands : operands.to_vec(), target : None, mem_ref : None })
+}
+
+fn hint(c : u32) -> Option<Decoded> {
+    // RVC hints have no 32-bit equivalent worth showing, keep the c. form
+    let rd = bits(c, 11, 7);
+    let rs2 = bits(c, 6, 2);
+    let imm6 = sext((bits(c, 12, 12) << 5) | rs2, 6);
+    let shamt = (bits(c, 12, 12) << 5) | rs2;
+    let x = |r : u32| XREG[r as usize].to_string();
+    match (c & 3, bits(c, 15, 13)) {
+        (1, 0) if rd == 0 && imm6 != 0 => insn("c.nop", &[imm6.to_string()]),
+        (1, 2) if rd == 0 => insn("c.li", &[x(0), imm6.to_string()]),
+        (1, 3) if rd == 0 => insn("c.lui", &[x(0), imm6.to_string()]),
+        (1, 4) if bits(c, 11, 11) == 0 && shamt == 0 => {
+            let mn = if bits(c, 10, 10) == 0 { "c.srli64" } else { "c.srai64" };
+            insn(mn, &[x(8 + bits(c, 9, 7))])
+        }
+        (2, 0) if shamt == 0 => insn("c.slli64", &[x(rd)]),
+        (2, 0) if rd == 0 => insn("c.slli", &[x(0), shamt.to_string()]),
+        (2, 4) if rd == 0 && rs2 != 0 => insn(if bits(c, 12, 12) == 0 { "c.mv" } else { "c.add" }, &[x(0), x(rs2)]),
+        _ => None,
+    }
+}
+
+fn decode32(w : u32, addr : u64, pages : &mut Pages) -> Option<Decoded> {
+    let op = w & 0x7f;
+    let rd = bits(w, 11, 7) as usize;
+    let rs1 = bits(w, 19, 15) as usize;
+    let rs2 = bits(w, 24, 20) as usize;
+    let f3 = bits(w, 14, 12);
+    let f7 = bits(w, 31, 25);
+    let imm_i = sext(bits(w, 31, 20), 12);
+    let imm_s = sext((f7 << 5) | rd as u32, 12);
+    let x = |r : usize| XREG[r].to_string();
+    let f = |r : usize| FREG[r].to_string();
+    let mem = |imm : i64, r : usize| format!("{}({})", imm, XREG[r]);
+    let pc_off = |imm : i64| addr.wrapping_add(imm as u64);
+
+    // the low half of an auipc pair: base register + 12-bit offset
+    let lo = |pages : &Pages, r : usize, imm : i64| pages[r].map(|p| p.wrapping_add(imm as u64));
+
+    let mut d = match op {
+        0x37 => insn("lui", &[x(rd), bits(w, 31, 12).to_string()])?,
+        0x17 => {
+            let d = insn("auipc", &[x(rd), bits(w, 31, 12).to_string()]);
+            pages[rd] = Some(pc_off(sext(bits(w, 31, 12) << 12, 32)));
+            return d;
+        }
+        0x6f => {
+            let imm = (bits(w, 31, 31) << 20) | (bits(w, 30, 21) << 1) | (bits(w, 20, 20) << 11) | (bits(w, 19, 12) << 12);
+            let target = pc_off(sext(imm, 21));
+            let t = format!("{:#x}", target);
+            let mut d = match rd {
+                0 => insn("j", &[t])?,
+                1 => insn("jal", &[t])?,
+                _ => insn("jal", &[x(rd), t])?,
+            };
+            d.target = Some(target);
+            d
+        }
+        0x67 if f3 == 0 => {
+            let mut d = match (rd, rs1, imm_i) {
+                (0, 1, 0) => insn("ret", &[])?,
+                (0, _, 0) => insn("jr", &[x(rs1)])?,
+                (1, _, 0) => insn("jalr", &[x(rs1)])?,
+                (_, _, 0) => insn("jalr", &[x(rd), x(rs1)])?,
+                (0, _, _) => insn("jr", &[mem(imm_i, rs1)])?,
+                (1, _, _) => insn("jalr", &[mem(imm_i, rs1)])?,
+                _ => insn("jalr", &[x(rd), mem(imm_i, rs1)])?,
+            };
+            // auipc + jalr is a call or tail call
+            d.target = lo(pages, rs1, imm_i);
+            d
+        }
+        0x63 => {
+            let imm = (bits(w, 31, 31) << 12) | (bits(w, 30, 25) << 5) | (bits(w, 11, 8) << 1) | (bits(w, 7, 7) << 11);
+            let target = pc_off(sext(imm, 13));
+            let t = format!("{:#x}", target);
+            let name = ["beq", "bne", "", "", "blt", "bge", "bltu", "bgeu"][f3 as usize];
+            let mut d = match (name, rs1, rs2) {
+                ("", _, _) => return None,
+                ("beq" | "bne", _, 0) => insn(&format!("{}z", name), &[x(rs1), t])?,
+                ("blt", _, 0) => insn("bltz", &[x(rs1), t])?,
+                ("blt", 0, _) => insn("bgtz", &[x(rs2), t])?,
+                ("bge", 0, _) => insn("blez", &[x(rs2), t])?,
+                ("bge", _, 0) => insn("bgez", &[x(rs1), t])?,
+                _ => insn(name, &[x(rs1), x(rs2), t])?,
+            };
+            d.target = Some(target);
+            d
+        }
+        0x03 => {
+            let name = ["lb", "lh", "lw", "ld", "lbu", "lhu", "lwu", ""][f3 as usize];
+            if name.is_empty() {
+                return None;
+            }
+            let mut d = insn(name, &[x(rd), mem(imm_i, rs1)])?;
+            d.mem_ref = lo(pages, rs1, imm_i);
+            d
+        }
+        0x07 if f3 == 2 || f3 == 3 => {
+            let mut d = insn(if f3 == 2 { "flw" } else { "fld" }, &[f(rd), mem(imm_i, rs1)])?;
+            d.mem_ref = lo(pages, rs1, imm_i);
+            d
+        }
+        0x23 if f3 < 4 => {
+            let mut d = insn(["sb", "sh", "sw", "sd"][f3 as usize], &[x(rs2), mem(imm_s, rs1)])?;
+            d.mem_ref = lo(pages, rs1, imm_s);
+            d
+        }
+        0x27 if f3 == 2 || f3 == 3 => {
+            let mut d = insn(if f3 == 2 { "fsw" } else { "fsd" }, &[f(rs2), mem(imm_s, rs1)])?;
+            d.mem_ref = lo(pages, rs1, imm_s);
+            d
+        }
+        0x13 => {
+            let shamt = bits(w, 25, 20).to_string();
+            let mut d = match (f3, bits(w, 31, 26)) {
+                (0, _) if rd == 0 && rs1 == 0 && imm_i == 0 => insn("nop", &[])?,
+                (0, _) if rs1 == 0 => insn("li", &[x(rd), imm_i.to_string()])?,
+                (0, _) if imm_i == 0 => insn("mv", &[x(rd), x(rs1)])?,
+                (0, _) => insn("addi", &[x(rd), x(rs1), imm_i.to_string()])?,
+                (1, 0) => insn("slli", &[x(rd), x(rs1), shamt])?,
+                (2, _) => insn("slti", &[x(rd), x(rs1), imm_i.to_string()])?,
+                (3, _) if imm_i == 1 => insn("seqz", &[x(rd), x(rs1)])?,
+                (3, _) => insn("sltiu", &[x(rd), x(rs1), imm_i.to_string()])?,
+                (4, _) if imm_i == -1 => insn("not", &[x(rd), x(rs1)])?,
+                (4, _) => insn("xori", &[x(rd), x(rs1), imm_i.to_string()])?,
+                (5, 0) => insn("srli", &[x(rd), x(rs1), shamt])?,
+                (5, 0x10) => insn("srai", &[x(rd), x(rs1), shamt])?,
+                (6, _) => insn("ori", &[x(rd), x(rs1), imm_i.to_string()])?,
+                (7, _) => insn("andi", &[x(rd), x(rs1), imm_i.to_string()])?,
+                _ => return None,
+            };
+            // addi completing an auipc is an address
+            if f3 == 0 && rs1 != 0 {
+                d.mem_ref = lo(pages, rs1, imm_i);
+            }
+            d
+        }
+        0x1b => {
+            let shamt = rs2.to_string();
+            match (f3, f7) {
+                (0, _) if imm_i == 0 => insn("sext.w", &[x(rd), x(rs1)])?,
+                (0, _) => insn("addiw", &[x(rd), x(rs1), imm_i.to_string()])?,
+                (1, 0) => insn("slliw", &[x(rd), x(rs1), shamt])?,
+                (5, 0) => insn("srliw", &[x(rd), x(rs1), shamt])?,
+                (5, 0x20) => insn("sraiw", &[x(rd), x(rs1), shamt])?,
+                _ => return None,
+            }
+        }
+        0x33 => {
+            let name = match (f7, f3) {
+                (0, _) => ["add", "sll", "slt", "sltu", "xor", "srl", "or", "and"][f3 as usize],
+                (0x20, 0) => "sub",
+                (0x20, 5) => "sra",
+                (1, _) => ["mul", "mulh", "mulhsu", "mulhu", "div", "divu", "rem", "remu"][f3 as usize],
+                _ => return None,
+            };
+            match (name, rs1, rs2) {
+                ("sub", 0, _) => insn("neg", &[x(rd), x(rs2)])?,
+                ("sltu", 0, _) => insn("snez", &[x(rd), x(rs2)])?,
+                ("slt", _, 0) => insn("sltz", &[x(rd), x(rs1)])?,
+                ("slt", 0, _) => insn("sgtz", &[x(rd), x(rs2)])?,
+                _ => insn(name, &[x(rd), x(rs1), x(rs2)])?,
+            }
+        }
+        0x3b => {
+            let name = match (f7, f3) {
+                (0, 0) => "addw",
+                (0, 1) => "sllw",
+                (0, 5) => "srlw",
+                (0x20, 0) => "subw",
+                (0x20, 5) => "sraw",
+                (1, 0) => "mulw",
+                (1, 4) => "divw",
+                (1, 5) => "divuw",
+                (1, 6) => "remw",
+                (1, 7) => "remuw",
+                _ => return None,
+            };
+            if name == "subw" && rs1 == 0 {
+                insn("negw", &[x(rd), x(rs2)])?
+            } else {
+                insn(name, &[x(rd), x(rs1), x(rs2)])?
+            }
+        }
+        0x0f => match f3 {
+            0 if w == 0x8330000f => insn("fence.tso", &[])?,
+            0 if bits(w, 31, 28) == 0 && rd == 0 && rs1 == 0 => {
+                let set = |v : u32| -> String {
+                    "iorw".chars().enumerate().filter(|(i, _)| v & (8 >> i) != 0).map(|(_, c)| c).collect()
+                };
+                let (pred, succ) = (bits(w, 27, 24), bits(w, 23, 20));
+                if pred == 0xf && succ == 0xf {
+                    insn("fence", &[])?
+                } else if pred == 0 || succ == 0 {
+                    return None;
+                } else {
+                    insn("fence", &[set(pred), set(succ)])?
+                }
+            }
+            1 if w == 0x0000100f => insn("fence.i", &[])?,
+            _ => return None,
+        },
+        0x73 => {
+            let csr = bits(w, 31, 20);
+            match (f3, w) {
+                (0, 0x00000073) => insn("ecall", &[])?,
+                (0, 0x00100073) => insn("ebreak", &[])?,
+                (0, 0x10200073) => insn("sret", &[])?,
+                (0, 0x30200073) => insn("mret", &[])?,
+                (0, 0x10500073) => insn("wfi", &[])?,
+                (0, _) if f7 == 0x09 && rd == 0 => match (rs1, rs2) {
+                    (0, 0) => insn("sfence.vma", &[])?,
+                    (_, 0) => insn("sfence.vma", &[x(rs1)])?,
+                    _ => insn("sfence.vma", &[x(rs1), x(rs2)])?,
+                },
+                (0, _) | (4, _) => return None,
+                _ => csr_insn(f3, csr, rd, rs1)?,
+            }
+        }
+        0x2f if f3 == 2 || f3 == 3 => {
+            let sz = if f3 == 2 { "w" } else { "d" };
+            let order = ["", ".rl", ".aq", ".aqrl"][bits(w, 26, 25) as usize];
+            let addr = format!("({})", XREG[rs1]);
+            let name = match bits(w, 31, 27) {
+                0x00 => "amoadd",
+                0x01 => "amoswap",
+                0x02 if rs2 == 0 => "lr",
+                0x03 => "sc",
+                0x04 => "amoxor",
+                0x08 => "amoor",
+                0x0c => "amoand",
+                0x10 => "amomin",
+                0x14 => "amomax",
+                0x18 => "amominu",
+                0x1c => "amomaxu",
+                _ => return None,
+            };
+            if name == "lr" {
+                insn(&format!("lr.{}{}", sz, order), &[x(rd), addr])?
+            } else {
+                insn(&format!("{}.{}{}", name, sz, order), &[x(rd), x(rs2), addr])?
+            }
+        }
+        0x43 | 0x47 | 0x4b | 0x4f => {
+            let sz = match bits(w, 26, 25) { 0 => "s", 1 => "d", _ => return None };
+            let name = ["fmadd", "fmsub", "fnmsub", "fnmadd"][((op >> 2) & 3) as usize];
+            let mut ops = vec![f(rd), f(rs1), f(rs2), f(bits(w, 31, 27) as usize)];
+            rounding(&mut ops, f3)?;
+            insn(&format!("{}.{}", name, sz), &ops)?
+        }
+        0x53 => op_fp(rd, rs1, rs2, f3, f7)?,
+        _ => return None,
+    };
+
+    // an instruction writing rd ends whatever page it held
+    if rd != 0 && !matches!(op, 0x23 | 0x27 | 0x63) {
+        pages[rd] = None;
+    }
+    if d.mnemonic == "j" || d.mnemonic == "ret" || d.mnemonic == "jr" {
+        pages.fill(None);
+    }
+    d.operands.retain(|o| !o.is_empty());
+    Some(d)
+}
+
+fn rounding(ops : &mut Vec<String>, rm : u32) -> Option<()> {
+    // dynamic rounding is the default and not shown
+    match RM[rm as usize] {
+        "" => None,
+        "dyn" => Some(()),
+        s => {
+            ops.push(s.to_string());
+            Some(())
+        }
+    }
+}
+
+fn csr_insn(f3 : u32, csr : u32, rd : usize, rs1 : usize) -> Option<Decoded> {
+    let x = |r : usize| XREG[r].to_string();
+    let name = csr_name(csr);
+    let uimm = rs1.to_string();
+
+    // floating point status and counters have their own aliases
+    let special = match (f3, csr, rd, rs1) {
+        (2, 0x001, _, 0) => Some(("frflags", vec![x(rd)])),
+        (2, 0x002, _, 0) => Some(("frrm", vec![x(rd)])),
+        (2, 0x003, _, 0) => Some(("frcsr", vec![x(rd)])),
+        (2, 0xc00, _, 0) => Some(("rdcycle", vec![x(rd)])),
+        (2, 0xc01, _, 0) => Some(("rdtime", vec![x(rd)])),
+        (2, 0xc02, _, 0) => Some(("rdinstret", vec![x(rd)])),
+        (1, 0x001, 0, _) => Some(("fsflags", vec![x(rs1)])),
+        (1, 0x002, 0, _) => Some(("fsrm", vec![x(rs1)])),
+        (1, 0x003, 0, _) => Some(("fscsr", vec![x(rs1)])),
+        (1, 0x001, _, _) => Some(("fsflags", vec![x(rd), x(rs1)])),
+        (1, 0x002, _, _) => Some(("fsrm", vec![x(rd), x(rs1)])),
+        (1, 0x003, _, _) => Some(("fscsr", vec![x(rd), x(rs1)])),
+        (5, 0x001, 0, _) => Some(("fsflagsi", vec![uimm.clone()])),
+        (5, 0x002, 0, _) => Some(("fsrmi", vec![uimm.clone()])),
+        (5, 0x001, _, _) => Some(("fsflagsi", vec![x(rd), uimm.clone()])),
+        (5, 0x002, _, _) => Some(("fsrmi", vec![x(rd), uimm.clone()])),
+        _ => None,
+    };
+    if let Some((mn, ops)) = special {
+        return insn(mn, &ops);
+    }
+
+    match (f3, rd, rs1) {
+        (2, _, 0) => insn("csrr", &[x(rd), name]),
+        (1, 0, _) => insn("csrw", &[name, x(rs1)]),
+        (2, 0, _) => insn("csrs", &[name, x(rs1)]),
+        (3, 0, _) => insn("csrc", &[name, x(rs1)]),
+        (5, 0, _) => insn("csrwi", &[name, uimm]),
+        (6, 0, _) => insn("csrsi", &[name, uimm]),
+        (7, 0, _) => insn("csrci", &[name, uimm]),
+        (1, _, _) => insn("csrrw", &[x(rd), name, x(rs1)]),
+        (2, _, _) => insn("csrrs", &[x(rd), name, x(rs1)]),
+        (3, _, _) => insn("csrrc", &[x(rd), name, x(rs1)]),
+        (5, _, _) => insn("csrrwi", &[x(rd), name, uimm]),
+        (6, _, _) => insn("csrrsi", &[x(rd), name, uimm]),
+        _ => insn("csrrci", &[x(rd), name, uimm]),
+    }
+}
+
+fn op_fp(rd : usize, rs1 : usize, rs2 : usize, f3 : u32, f7 : u32) -> Option<Decoded> {
+    let x = |r : usize| XREG[r].to_string();
+    let f = |r : usize| FREG[r].to_string();
+    let sz = match f7 & 3 { 0 => "s", 1 => "d", _ => return None };
+    let int = ["w", "wu", "l", "lu"];
+
+    let with_rm = |name : String, mut ops : Vec<String>| -> Option<Decoded> {
+        rounding(&mut ops, f3)?;
+        insn(&name, &ops)
+    };
+
+    match f7 >> 2 {
+        0x00 => with_rm(format!("fadd.{}", sz), vec![f(rd), f(rs1), f(rs2)]),
+        0x01 => with_rm(format!("fsub.{}", sz), vec![f(rd), f(rs1), f(rs2)]),
+        0x02 => with_rm(format!("fmul.{}", sz), vec![f(rd), f(rs1), f(rs2)]),
+        0x03 => with_rm(format!("fdiv.{}", sz), vec![f(rd), f(rs1), f(rs2)]),
+        0x0b if rs2 == 0 => with_rm(format!("fsqrt.{}", sz), vec![f(rd), f(rs1)]),
+        0x04 => match (f3, rs1 == rs2) {
+            (0, true) => insn(&format!("fmv.{}", sz), &[f(rd), f(rs1)]),
+            (1, true) => insn(&format!("fneg.{}", sz), &[f(rd), f(rs1)]),
+            (2, true) => insn(&format!("fabs.{}", sz), &[f(rd), f(rs1)]),
+            (0..=2, _) => insn(&format!("{}.{}", ["fsgnj", "fsgnjn", "fsgnjx"][f3 as usize], sz), &[f(rd), f(rs1), f(rs2)]),
+            _ => None,
+        },
+        0x05 if f3 < 2 => insn(&format!("{}.{}", ["fmin", "fmax"][f3 as usize], sz), &[f(rd), f(rs1), f(rs2)]),
+        0x08 => match (sz, rs2) {
+            ("s", 1) => with_rm("fcvt.s.d".to_string(), vec![f(rd), f(rs1)]),
+            ("d", 0) if !RM[f3 as usize].is_empty() => insn("fcvt.d.s", &[f(rd), f(rs1)]),
+            _ => None,
+        },
+        0x14 if f3 < 3 => insn(&format!("{}.{}", ["fle", "flt", "feq"][f3 as usize], sz), &[x(rd), f(rs1), f(rs2)]),
+        0x18 if rs2 < 4 => with_rm(format!("fcvt.{}.{}", int[rs2], sz), vec![x(rd), f(rs1)]),
+        // int to double is exact, no rounding mode shown
+        0x1a if rs2 < 2 && sz == "d" && !RM[f3 as usize].is_empty() => insn(&format!("fcvt.d.{}", int[rs2]), &[f(rd), x(rs1)]),
+        0x1a if rs2 < 4 => with_rm(format!("fcvt.{}.{}", sz, int[rs2]), vec![f(rd), x(rs1)]),
+        0x1c if rs2 == 0 && f3 == 0 => insn(&format!("fmv.x.{}", if sz == "s" { "w" } else { "d" }), &[x(rd), f(rs1)]),
+        0x1c if rs2 == 0 && f3 == 1 => insn(&format!("fclass.{}", sz), &[x(rd), f(rs1)]),
+        0x1e if rs2 == 0 && f3 == 0 => insn(&format!("fmv.{}.x", if sz == "s" { "w" } else { "d" }), &[f(rd), x(rs1)]),
+        _ => None,
+    }
+}
+
+pub fn insn_len(buf : &[u8]) -> usize {
+    // 2 for compressed, 4 otherwise
+    match buf.first() {
+        Some(b) if b & 3 != 3 => 2,
+        _ => 4,
+    }
+}
+
+pub fn decode(buf : &[u8], addr : u64, pages : &mut Pages) -> Option<Insn> {
+    let len = insn_len(buf);
+    let b = buf.get(..len)?;
+    let d = if b == [0, 0] {
+        // all-zero halfword is the defined illegal instruction
+        insn("unimp", &[])?
+    } else if len == 2 {
+        let c = u16::from_le_bytes([b[0], b[1]]) as u32;
+        if let Some(d) = hint(c) {
+            d
+        } else {
+            decode32(expand(c)?, addr, pages)?
+        }
+    } else {
+        decode32(u32::from_le_bytes(b.try_into().unwrap()), addr, pages)?
+    };
+    Some(Insn {
+        addr,
+        len,
+        mnemonic : d.mnemonic,
+        operands : d.operands.join(", "),
+        target   : d.target,
+        mem_ref  : d.mem_ref,
+    })
+}
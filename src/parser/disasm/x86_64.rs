@@ -0,0 +1,1500 @@
+use crate::parser::disasm::Insn;
+
+/*
+ table driven x86-64 decoder, Intel syntax as printed by `objdump -M intel`
+
+ an entry is "mnemonic operands" with operands in the notation of the Intel SDM opcode maps:
+    E/G   modrm r/m and reg as general purpose register or memory
+    M/R   modrm r/m that must be memory/register
+    V/W/U/H/L  xmm/ymm from reg, r/m, r/m (register only), VEX.vvvv, imm8[7:4]
+    P/Q/N mmx from reg, r/m, r/m (register only)
+    B     general purpose register from VEX.vvvv
+    I/J   immediate and relative branch target, sIb is sign-extended imm8
+    Z     register in low 3 bits of opcode
+    O     moffs, X/Y string source/destination
+ followed by a size: b w d q, v (operand size), z (16/32), y (32/64 by REX.W), x (xmm/ymm by VEX.L),
+ dq (xmm), qq (ymm), ss/sd (scalar float), t (tbyte), p (far pointer)
+
+ "a|b|c|d" selects by mandatory prefix (none|66|F3|F2), "mem~reg" by modrm.mod,
+ "#grp" dispatches on modrm.reg and "x/y" or "x/y/z" selects mnemonic by REX.W or operand size
+ */
+
+const REG64 : [&str; 16] = ["rax", "rcx", "rdx", "rbx", "rsp", "rbp", "rsi", "rdi",
+                            "r8", "r9", "r10", "r11", "r12", "r13", "r14", "r15"];
+const REG32 : [&str; 16] = ["eax", "ecx", "edx", "ebx", "esp", "ebp", "esi", "edi",
+                            "r8d", "r9d", "r10d", "r11d", "r12d", "r13d", "r14d", "r15d"];
+const REG16 : [&str; 16] = ["ax", "cx", "dx", "bx", "sp", "bp", "si", "di",
+                            "r8w", "r9w", "r10w", "r11w", "r12w", "r13w", "r14w", "r15w"];
+const REG8  : [&str; 16] = ["al", "cl", "dl", "bl", "spl", "bpl", "sil", "dil",
+                            "r8b", "r9b", "r10b", "r11b", "r12b", "r13b", "r14b", "r15b"];
+const REG8_LEGACY : [&str; 8] = ["al", "cl", "dl", "bl", "ah", "ch", "dh", "bh"];
+const SEGS  : [&str; 8] = ["es", "cs", "ss", "ds", "fs", "gs", "?", "?"];
+const CMP_PRED : [&str; 32] = ["eq", "lt", "le", "unord", "neq", "nlt", "nle", "ord",
+                               "eq_uq", "nge", "ngt", "false", "neq_oq", "ge", "gt", "true",
+                               "eq_os", "lt_oq", "le_oq", "unord_s", "neq_us", "nlt_uq", "nle_uq", "ord_s",
+                               "eq_us", "nge_uq", "ngt_uq", "false_os", "neq_os", "ge_oq", "gt_oq", "true_us"];
+
+fn one_byte(op : u8) -> &'static str {
+    match op {
+        0x00 => "add Eb,Gb", 0x01 => "add Ev,Gv", 0x02 => "add Gb,Eb", 0x03 => "add Gv,Ev", 0x04 => "add AL,Ib", 0x05 => "add rAX,Iz",
+        0x08 => "or Eb,Gb",  0x09 => "or Ev,Gv",  0x0a => "or Gb,Eb",  0x0b => "or Gv,Ev",  0x0c => "or AL,Ib",  0x0d => "or rAX,Iz",
+        0x10 => "adc Eb,Gb", 0x11 => "adc Ev,Gv", 0x12 => "adc Gb,Eb", 0x13 => "adc Gv,Ev", 0x14 => "adc AL,Ib", 0x15 => "adc rAX,Iz",
+        0x18 => "sbb Eb,Gb", 0x19 => "sbb Ev,Gv", 0x1a => "sbb Gb,Eb", 0x1b => "sbb Gv,Ev", 0x1c => "sbb AL,Ib", 0x1d => "sbb rAX,Iz",
+        0x20 => "and Eb,Gb", 0x21 => "and Ev,Gv", 0x22 => "and Gb,Eb", 0x23 => "and Gv,Ev", 0x24 => "and AL,Ib", 0x25 => "and rAX,Iz",
+        0x28 => "sub Eb,Gb", 0x29 => "sub Ev,Gv", 0x2a => "sub Gb,Eb", 0x2b => "sub Gv,Ev", 0x2c => "sub AL,Ib", 0x2d => "sub rAX,Iz",
+        0x30 => "xor Eb,Gb", 0x31 => "xor Ev,Gv", 0x32 => "xor Gb,Eb", 0x33 => "xor Gv,Ev", 0x34 => "xor AL,Ib", 0x35 => "xor rAX,Iz",
+        0x38 => "cmp Eb,Gb", 0x39 => "cmp Ev,Gv", 0x3a => "cmp Gb,Eb", 0x3b => "cmp Gv,Ev", 0x3c => "cmp AL,Ib", 0x3d => "cmp rAX,Iz",
+        0x50..=0x57 => "push Zq",
+        0x58..=0x5f => "pop Zq",
+        0x63 => "movsxd Gv,Ed",
+        0x68 => "push Iz",
+        0x69 => "imul Gv,Ev,Iz",
+        0x6a => "push sIb",
+        0x6b => "imul Gv,Ev,sIb",
+        0x6c => "ins Yb,DX",
+        0x6d => "ins Yz,DX",
+        0x6e => "outs DX,Xb",
+        0x6f => "outs DX,Xz",
+        0x70 => "jo Jb",  0x71 => "jno Jb", 0x72 => "jb Jb",  0x73 => "jae Jb",
+        0x74 => "je Jb",  0x75 => "jne Jb", 0x76 => "jbe Jb", 0x77 => "ja Jb",
+        0x78 => "js Jb",  0x79 => "jns Jb", 0x7a => "jp Jb",  0x7b => "jnp Jb",
+        0x7c => "jl Jb",  0x7d => "jge Jb", 0x7e => "jle Jb", 0x7f => "jg Jb",
+        0x80 => "#grp1 Eb,Ib",
+        0x81 => "#grp1 Ev,Iz",
+        0x83 => "#grp1 Ev,sIb",
+        0x84 => "test Eb,Gb",
+        0x85 => "test Ev,Gv",
+        0x86 => "xchg Eb,Gb",
+        0x87 => "xchg Ev,Gv",
+        0x88 => "mov Eb,Gb",
+        0x89 => "mov Ev,Gv",
+        0x8a => "mov Gb,Eb",
+        0x8b => "mov Gv,Ev",
+        0x8c => "mov Ev,Sw",
+        0x8d => "lea Gv,M",
+        0x8e => "mov Sw,Ew",
+        0x8f => "#grp1a",
+        0x90 => "nop",
+        0x91..=0x97 => "xchg Zv,rAX",
+        0x98 => "cbw/cwde/cdqe",
+        0x99 => "cwd/cdq/cqo",
+        0x9b => "fwait",
+        0x9c => "pushf",
+        0x9d => "popf",
+        0x9e => "sahf",
+        0x9f => "lahf",
+        0xa0 => "movabs AL,Ob",
+        0xa1 => "movabs rAX,Ov",
+        0xa2 => "movabs Ob,AL",
+        0xa3 => "movabs Ov,rAX",
+        0xa4 => "movs Yb,Xb",
+        0xa5 => "movs Yv,Xv",
+        0xa6 => "cmps Xb,Yb",
+        0xa7 => "cmps Xv,Yv",
+        0xa8 => "test AL,Ib",
+        0xa9 => "test rAX,Iz",
+        0xaa => "stos Yb,AL",
+        0xab => "stos Yv,rAX",
+        0xac => "lods AL,Xb",
+        0xad => "lods rAX,Xv",
+        0xae => "scas AL,Yb",
+        0xaf => "scas rAX,Yv",
+        0xb0..=0xb7 => "mov Zb,Ib",
+        0xb8..=0xbf => "mov Zv,Iv",
+        0xc0 => "#grp2 Eb,Ib",
+        0xc1 => "#grp2 Ev,Ib",
+        0xc2 => "ret Iw",
+        0xc3 => "ret",
+        0xc6 => "#grp11b",
+        0xc7 => "#grp11v",
+        0xc8 => "enter Iw,Ib",
+        0xc9 => "leave",
+        0xca => "retf Iw",
+        0xcb => "retf",
+        0xcc => "int3",
+        0xcd => "int Ib",
+        0xcf => "iret/iretd/iretq",
+        0xd0 => "#grp2 Eb,1",
+        0xd1 => "#grp2 Ev,1",
+        0xd2 => "#grp2 Eb,CL",
+        0xd3 => "#grp2 Ev,CL",
+        0xd7 => "xlat",
+        0xe0 => "loopne Jb",
+        0xe1 => "loope Jb",
+        0xe2 => "loop Jb",
+        0xe3 => "jrcxz Jb",
+        0xe4 => "in AL,Ib",
+        0xe5 => "in eAX,Ib",
+        0xe6 => "out Ib,AL",
+        0xe7 => "out Ib,eAX",
+        0xe8 => "call Jz",
+        0xe9 => "jmp Jz",
+        0xeb => "jmp Jb",
+        0xec => "in AL,DX",
+        0xed => "in eAX,DX",
+        0xee => "out DX,AL",
+        0xef => "out DX,eAX",
+        0xf1 => "int1",
+        0xf4 => "hlt",
+        0xf5 => "cmc",
+        0xf6 => "#grp3b",
+        0xf7 => "#grp3v",
+        0xf8 => "clc",
+        0xf9 => "stc",
+        0xfa => "cli",
+        0xfb => "sti",
+        0xfc => "cld",
+        0xfd => "std",
+        0xfe => "#grp4",
+        0xff => "#grp5",
+        _ => "",
+    }
+}
+
+fn two_byte(op : u8) -> &'static str {
+    match op {
+        0x00 => "#grp6",
+        0x01 => "#grp7",
+        0x02 => "lar Gv,Ew",
+        0x03 => "lsl Gv,Ew",
+        0x05 => "syscall",
+        0x06 => "clts",
+        0x07 => "sysret",
+        0x08 => "invd",
+        0x09 => "wbinvd",
+        0x0b => "ud2",
+        0x0d => "#grpp",
+        0x10 => "movups Vx,Wx|movupd Vx,Wx|movss Vdq,Hdq,Wss|movsd Vdq,Hdq,Wsd",
+        0x11 => "movups Wx,Vx|movupd Wx,Vx|movss Wss,Hdq,Vdq|movsd Wsd,Hdq,Vdq",
+        0x12 => "movlps Vdq,Hdq,Mq~movhlps Vdq,Hdq,Udq|movlpd Vdq,Hdq,Mq|movsldup Vx,Wx|movddup Vx,Wq",
+        0x13 => "movlps Mq,Vdq|movlpd Mq,Vdq||",
+        0x14 => "unpcklps Vx,Hx,Wx|unpcklpd Vx,Hx,Wx||",
+        0x15 => "unpckhps Vx,Hx,Wx|unpckhpd Vx,Hx,Wx||",
+        0x16 => "movhps Vdq,Hdq,Mq~movlhps Vdq,Hdq,Udq|movhpd Vdq,Hdq,Mq|movshdup Vx,Wx|",
+        0x17 => "movhps Mq,Vdq|movhpd Mq,Vdq||",
+        0x18 => "#grp16",
+        0x19..=0x1f => "nop Ev",
+        0x28 => "movaps Vx,Wx|movapd Vx,Wx||",
+        0x29 => "movaps Wx,Vx|movapd Wx,Vx||",
+        0x2a => "cvtpi2ps Vdq,Qq|cvtpi2pd Vdq,Qq|cvtsi2ss Vdq,Hdq,Ey|cvtsi2sd Vdq,Hdq,Ey",
+        0x2b => "movntps Mx,Vx|movntpd Mx,Vx||",
+        0x2c => "cvttps2pi Pq,Wq|cvttpd2pi Pq,Wdq|cvttss2si Gy,Wss|cvttsd2si Gy,Wsd",
+        0x2d => "cvtps2pi Pq,Wq|cvtpd2pi Pq,Wdq|cvtss2si Gy,Wss|cvtsd2si Gy,Wsd",
+        0x2e => "ucomiss Vdq,Wss|ucomisd Vdq,Wsd||",
+        0x2f => "comiss Vdq,Wss|comisd Vdq,Wsd||",
+        0x30 => "wrmsr",
+        0x31 => "rdtsc",
+        0x32 => "rdmsr",
+        0x33 => "rdpmc",
+        0x34 => "sysenter",
+        0x35 => "sysexit",
+        0x40 => "cmovo Gv,Ev",  0x41 => "cmovno Gv,Ev", 0x42 => "cmovb Gv,Ev",  0x43 => "cmovae Gv,Ev",
+        0x44 => "cmove Gv,Ev",  0x45 => "cmovne Gv,Ev", 0x46 => "cmovbe Gv,Ev", 0x47 => "cmova Gv,Ev",
+        0x48 => "cmovs Gv,Ev",  0x49 => "cmovns Gv,Ev", 0x4a => "cmovp Gv,Ev",  0x4b => "cmovnp Gv,Ev",
+        0x4c => "cmovl Gv,Ev",  0x4d => "cmovge Gv,Ev", 0x4e => "cmovle Gv,Ev", 0x4f => "cmovg Gv,Ev",
+        0x50 => "movmskps Gd,Ux|movmskpd Gd,Ux||",
+        0x51 => "sqrtps Vx,Wx|sqrtpd Vx,Wx|sqrtss Vdq,Hdq,Wss|sqrtsd Vdq,Hdq,Wsd",
+        0x52 => "rsqrtps Vx,Wx||rsqrtss Vdq,Hdq,Wss|",
+        0x53 => "rcpps Vx,Wx||rcpss Vdq,Hdq,Wss|",
+        0x54 => "andps Vx,Hx,Wx|andpd Vx,Hx,Wx||",
+        0x55 => "andnps Vx,Hx,Wx|andnpd Vx,Hx,Wx||",
+        0x56 => "orps Vx,Hx,Wx|orpd Vx,Hx,Wx||",
+        0x57 => "xorps Vx,Hx,Wx|xorpd Vx,Hx,Wx||",
+        0x58 => "addps Vx,Hx,Wx|addpd Vx,Hx,Wx|addss Vdq,Hdq,Wss|addsd Vdq,Hdq,Wsd",
+        0x59 => "mulps Vx,Hx,Wx|mulpd Vx,Hx,Wx|mulss Vdq,Hdq,Wss|mulsd Vdq,Hdq,Wsd",
+        0x5a => "cvtps2pd Vx,Wq|cvtpd2ps Vdq,Wx|cvtss2sd Vdq,Hdq,Wss|cvtsd2ss Vdq,Hdq,Wsd",
+        0x5b => "cvtdq2ps Vx,Wx|cvtps2dq Vx,Wx|cvttps2dq Vx,Wx|",
+        0x5c => "subps Vx,Hx,Wx|subpd Vx,Hx,Wx|subss Vdq,Hdq,Wss|subsd Vdq,Hdq,Wsd",
+        0x5d => "minps Vx,Hx,Wx|minpd Vx,Hx,Wx|minss Vdq,Hdq,Wss|minsd Vdq,Hdq,Wsd",
+        0x5e => "divps Vx,Hx,Wx|divpd Vx,Hx,Wx|divss Vdq,Hdq,Wss|divsd Vdq,Hdq,Wsd",
+        0x5f => "maxps Vx,Hx,Wx|maxpd Vx,Hx,Wx|maxss Vdq,Hdq,Wss|maxsd Vdq,Hdq,Wsd",
+        0x60 => "punpcklbw Pq,Qd|punpcklbw Vx,Hx,Wx||",
+        0x61 => "punpcklwd Pq,Qd|punpcklwd Vx,Hx,Wx||",
+        0x62 => "punpckldq Pq,Qd|punpckldq Vx,Hx,Wx||",
+        0x63 => "packsswb Pq,Qq|packsswb Vx,Hx,Wx||",
+        0x64 => "pcmpgtb Pq,Qq|pcmpgtb Vx,Hx,Wx||",
+        0x65 => "pcmpgtw Pq,Qq|pcmpgtw Vx,Hx,Wx||",
+        0x66 => "pcmpgtd Pq,Qq|pcmpgtd Vx,Hx,Wx||",
+        0x67 => "packuswb Pq,Qq|packuswb Vx,Hx,Wx||",
+        0x68 => "punpckhbw Pq,Qq|punpckhbw Vx,Hx,Wx||",
+        0x69 => "punpckhwd Pq,Qq|punpckhwd Vx,Hx,Wx||",
+        0x6a => "punpckhdq Pq,Qq|punpckhdq Vx,Hx,Wx||",
+        0x6b => "packssdw Pq,Qq|packssdw Vx,Hx,Wx||",
+        0x6c => "|punpcklqdq Vx,Hx,Wx||",
+        0x6d => "|punpckhqdq Vx,Hx,Wx||",
+        0x6e => "movd/movq Pq,Ey|movd/movq Vdq,Ey||",
+        0x6f => "movq Pq,Qq|movdqa Vx,Wx|movdqu Vx,Wx|",
+        0x70 => "pshufw Pq,Qq,Ib|pshufd Vx,Wx,Ib|pshufhw Vx,Wx,Ib|pshuflw Vx,Wx,Ib",
+        0x71 => "#grp12",
+        0x72 => "#grp13",
+        0x73 => "#grp14",
+        0x74 => "pcmpeqb Pq,Qq|pcmpeqb Vx,Hx,Wx||",
+        0x75 => "pcmpeqw Pq,Qq|pcmpeqw Vx,Hx,Wx||",
+        0x76 => "pcmpeqd Pq,Qq|pcmpeqd Vx,Hx,Wx||",
+        0x77 => "emms",
+        0x7c => "|haddpd Vx,Hx,Wx||haddps Vx,Hx,Wx",
+        0x7d => "|hsubpd Vx,Hx,Wx||hsubps Vx,Hx,Wx",
+        0x7e => "movd/movq Ey,Pq|movd/movq Ey,Vdq|movq Vdq,Wq|",
+        0x7f => "movq Qq,Pq|movdqa Wx,Vx|movdqu Wx,Vx|",
+        0x80 => "jo Jz",  0x81 => "jno Jz", 0x82 => "jb Jz",  0x83 => "jae Jz",
+        0x84 => "je Jz",  0x85 => "jne Jz", 0x86 => "jbe Jz", 0x87 => "ja Jz",
+        0x88 => "js Jz",  0x89 => "jns Jz", 0x8a => "jp Jz",  0x8b => "jnp Jz",
+        0x8c => "jl Jz",  0x8d => "jge Jz", 0x8e => "jle Jz", 0x8f => "jg Jz",
+        0x90 => "seto Eb", 0x91 => "setno Eb", 0x92 => "setb Eb",  0x93 => "setae Eb",
+        0x94 => "sete Eb", 0x95 => "setne Eb", 0x96 => "setbe Eb", 0x97 => "seta Eb",
+        0x98 => "sets Eb", 0x99 => "setns Eb", 0x9a => "setp Eb",  0x9b => "setnp Eb",
+        0x9c => "setl Eb", 0x9d => "setge Eb", 0x9e => "setle Eb", 0x9f => "setg Eb",
+        0xa0 => "push fs",
+        0xa1 => "pop fs",
+        0xa2 => "cpuid",
+        0xa3 => "bt Ev,Gv",
+        0xa4 => "shld Ev,Gv,Ib",
+        0xa5 => "shld Ev,Gv,CL",
+        0xa8 => "push gs",
+        0xa9 => "pop gs",
+        0xab => "bts Ev,Gv",
+        0xac => "shrd Ev,Gv,Ib",
+        0xad => "shrd Ev,Gv,CL",
+        0xae => "#grp15",
+        0xaf => "imul Gv,Ev",
+        0xb0 => "cmpxchg Eb,Gb",
+        0xb1 => "cmpxchg Ev,Gv",
+        0xb2 => "lss Gv,Mp",
+        0xb3 => "btr Ev,Gv",
+        0xb4 => "lfs Gv,Mp",
+        0xb5 => "lgs Gv,Mp",
+        0xb6 => "movzx Gv,Eb",
+        0xb7 => "movzx Gv,Ew",
+        0xb8 => "||popcnt Gv,Ev|",
+        0xb9 => "ud1 Gv,Ev",
+        0xba => "#grp8 Ev,Ib",
+        0xbb => "btc Ev,Gv",
+        0xbc => "bsf Gv,Ev||tzcnt Gv,Ev|",
+        0xbd => "bsr Gv,Ev||lzcnt Gv,Ev|",
+        0xbe => "movsx Gv,Eb",
+        0xbf => "movsx Gv,Ew",
+        0xc0 => "xadd Eb,Gb",
+        0xc1 => "xadd Ev,Gv",
+        0xc2 => "cmpps Vx,Hx,Wx,Ib|cmppd Vx,Hx,Wx,Ib|cmpss Vdq,Hdq,Wss,Ib|cmpsd Vdq,Hdq,Wsd,Ib",
+        0xc3 => "movnti My,Gy",
+        0xc4 => "pinsrw Pq,Mw,Ib~pinsrw Pq,Rd,Ib|pinsrw Vdq,Hdq,Mw,Ib~pinsrw Vdq,Hdq,Rd,Ib||",
+        0xc5 => "pextrw Gd,Nq,Ib|pextrw Gd,Udq,Ib||",
+        0xc6 => "shufps Vx,Hx,Wx,Ib|shufpd Vx,Hx,Wx,Ib||",
+        0xc7 => "#grp9",
+        0xc8..=0xcf => "bswap Zv",
+        0xd0 => "|addsubpd Vx,Hx,Wx||addsubps Vx,Hx,Wx",
+        0xd1 => "psrlw Pq,Qq|psrlw Vx,Hx,Wdq||",
+        0xd2 => "psrld Pq,Qq|psrld Vx,Hx,Wdq||",
+        0xd3 => "psrlq Pq,Qq|psrlq Vx,Hx,Wdq||",
+        0xd4 => "paddq Pq,Qq|paddq Vx,Hx,Wx||",
+        0xd5 => "pmullw Pq,Qq|pmullw Vx,Hx,Wx||",
+        0xd6 => "|movq Wq,Vdq|movq2dq Vdq,Nq|movdq2q Pq,Udq",
+        0xd7 => "pmovmskb Gd,Nq|pmovmskb Gd,Ux||",
+        0xd8 => "psubusb Pq,Qq|psubusb Vx,Hx,Wx||",
+        0xd9 => "psubusw Pq,Qq|psubusw Vx,Hx,Wx||",
+        0xda => "pminub Pq,Qq|pminub Vx,Hx,Wx||",
+        0xdb => "pand Pq,Qq|pand Vx,Hx,Wx||",
+        0xdc => "paddusb Pq,Qq|paddusb Vx,Hx,Wx||",
+        0xdd => "paddusw Pq,Qq|paddusw Vx,Hx,Wx||",
+        0xde => "pmaxub Pq,Qq|pmaxub Vx,Hx,Wx||",
+        0xdf => "pandn Pq,Qq|pandn Vx,Hx,Wx||",
+        0xe0 => "pavgb Pq,Qq|pavgb Vx,Hx,Wx||",
+        0xe1 => "psraw Pq,Qq|psraw Vx,Hx,Wdq||",
+        0xe2 => "psrad Pq,Qq|psrad Vx,Hx,Wdq||",
+        0xe3 => "pavgw Pq,Qq|pavgw Vx,Hx,Wx||",
+        0xe4 => "pmulhuw Pq,Qq|pmulhuw Vx,Hx,Wx||",
+        0xe5 => "pmulhw Pq,Qq|pmulhw Vx,Hx,Wx||",
+        0xe6 => "|cvttpd2dq Vdq,Wx|cvtdq2pd Vx,Wq|cvtpd2dq Vdq,Wx",
+        0xe7 => "movntq Mq,Pq|movntdq Mx,Vx||",
+        0xe8 => "psubsb Pq,Qq|psubsb Vx,Hx,Wx||",
+        0xe9 => "psubsw Pq,Qq|psubsw Vx,Hx,Wx||",
+        0xea => "pminsw Pq,Qq|pminsw Vx,Hx,Wx||",
+        0xeb => "por Pq,Qq|por Vx,Hx,Wx||",
+        0xec => "paddsb Pq,Qq|paddsb Vx,Hx,Wx||",
+        0xed => "paddsw Pq,Qq|paddsw Vx,Hx,Wx||",
+        0xee => "pmaxsw Pq,Qq|pmaxsw Vx,Hx,Wx||",
+        0xef => "pxor Pq,Qq|pxor Vx,Hx,Wx||",
+        0xf0 => "|||lddqu Vx,Mx",
+        0xf1 => "psllw Pq,Qq|psllw Vx,Hx,Wdq||",
+        0xf2 => "pslld Pq,Qq|pslld Vx,Hx,Wdq||",
+        0xf3 => "psllq Pq,Qq|psllq Vx,Hx,Wdq||",
+        0xf4 => "pmuludq Pq,Qq|pmuludq Vx,Hx,Wx||",
+        0xf5 => "pmaddwd Pq,Qq|pmaddwd Vx,Hx,Wx||",
+        0xf6 => "psadbw Pq,Qq|psadbw Vx,Hx,Wx||",
+        0xf7 => "maskmovq Pq,Nq|maskmovdqu Vdq,Udq||",
+        0xf8 => "psubb Pq,Qq|psubb Vx,Hx,Wx||",
+        0xf9 => "psubw Pq,Qq|psubw Vx,Hx,Wx||",
+        0xfa => "psubd Pq,Qq|psubd Vx,Hx,Wx||",
+        0xfb => "psubq Pq,Qq|psubq Vx,Hx,Wx||",
+        0xfc => "paddb Pq,Qq|paddb Vx,Hx,Wx||",
+        0xfd => "paddw Pq,Qq|paddw Vx,Hx,Wx||",
+        0xfe => "paddd Pq,Qq|paddd Vx,Hx,Wx||",
+        0xff => "ud0 Gv,Ev",
+        _ => "",
+    }
+}
+
+fn three_byte_38(op : u8) -> &'static str {
+    match op {
+        0x00 => "pshufb Pq,Qq|pshufb Vx,Hx,Wx||",
+        0x01 => "phaddw Pq,Qq|phaddw Vx,Hx,Wx||",
+        0x02 => "phaddd Pq,Qq|phaddd Vx,Hx,Wx||",
+        0x03 => "phaddsw Pq,Qq|phaddsw Vx,Hx,Wx||",
+        0x04 => "pmaddubsw Pq,Qq|pmaddubsw Vx,Hx,Wx||",
+        0x05 => "phsubw Pq,Qq|phsubw Vx,Hx,Wx||",
+        0x06 => "phsubd Pq,Qq|phsubd Vx,Hx,Wx||",
+        0x07 => "phsubsw Pq,Qq|phsubsw Vx,Hx,Wx||",
+        0x08 => "psignb Pq,Qq|psignb Vx,Hx,Wx||",
+        0x09 => "psignw Pq,Qq|psignw Vx,Hx,Wx||",
+        0x0a => "psignd Pq,Qq|psignd Vx,Hx,Wx||",
+        0x0b => "pmulhrsw Pq,Qq|pmulhrsw Vx,Hx,Wx||",
+        0x10 => "|pblendvb Vdq,Wdq,XMM0||",
+        0x14 => "|blendvps Vdq,Wdq,XMM0||",
+        0x15 => "|blendvpd Vdq,Wdq,XMM0||",
+        0x17 => "|ptest Vx,Wx||",
+        0x1c => "pabsb Pq,Qq|pabsb Vx,Wx||",
+        0x1d => "pabsw Pq,Qq|pabsw Vx,Wx||",
+        0x1e => "pabsd Pq,Qq|pabsd Vx,Wx||",
+        0x20 => "|pmovsxbw Vx,Wq||",
+        0x21 => "|pmovsxbd Vx,Wd||",
+        0x22 => "|pmovsxbq Vx,Ww||",
+        0x23 => "|pmovsxwd Vx,Wq||",
+        0x24 => "|pmovsxwq Vx,Wd||",
+        0x25 => "|pmovsxdq Vx,Wq||",
+        0x28 => "|pmuldq Vx,Hx,Wx||",
+        0x29 => "|pcmpeqq Vx,Hx,Wx||",
+        0x2a => "|movntdqa Vx,Mx||",
+        0x2b => "|packusdw Vx,Hx,Wx||",
+        0x30 => "|pmovzxbw Vx,Wq||",
+        0x31 => "|pmovzxbd Vx,Wd||",
+        0x32 => "|pmovzxbq Vx,Ww||",
+        0x33 => "|pmovzxwd Vx,Wq||",
+        0x34 => "|pmovzxwq Vx,Wd||",
+        0x35 => "|pmovzxdq Vx,Wq||",
+        0x37 => "|pcmpgtq Vx,Hx,Wx||",
+        0x38 => "|pminsb Vx,Hx,Wx||",
+        0x39 => "|pminsd Vx,Hx,Wx||",
+        0x3a => "|pminuw Vx,Hx,Wx||",
+        0x3b => "|pminud Vx,Hx,Wx||",
+        0x3c => "|pmaxsb Vx,Hx,Wx||",
+        0x3d => "|pmaxsd Vx,Hx,Wx||",
+        0x3e => "|pmaxuw Vx,Hx,Wx||",
+        0x3f => "|pmaxud Vx,Hx,Wx||",
+        0x40 => "|pmulld Vx,Hx,Wx||",
+        0x41 => "|phminposuw Vdq,Wdq||",
+        0xc8 => "sha1nexte Vdq,Wdq|||",
+        0xc9 => "sha1msg1 Vdq,Wdq|||",
+        0xca => "sha1msg2 Vdq,Wdq|||",
+        0xcb => "sha256rnds2 Vdq,Wdq,XMM0|||",
+        0xcc => "sha256msg1 Vdq,Wdq|||",
+        0xcd => "sha256msg2 Vdq,Wdq|||",
+        0xcf => "|gf2p8mulb Vx,Hx,Wx||",
+        0xdb => "|aesimc Vdq,Wdq||",
+        0xdc => "|aesenc Vx,Hx,Wx||",
+        0xdd => "|aesenclast Vx,Hx,Wx||",
+        0xde => "|aesdec Vx,Hx,Wx||",
+        0xdf => "|aesdeclast Vx,Hx,Wx||",
+        0xf0 => "movbe Gv,Mv|movbe Gw,Mw||crc32 Gy,Eb",
+        0xf1 => "movbe Mv,Gv|movbe Mw,Gw||crc32 Gy,Ev",
+        0xf6 => "|adcx Gy,Ey|adox Gy,Ey|",
+        _ => "",
+    }
+}
+
+fn three_byte_3a(op : u8) -> &'static str {
+    match op {
+        0x08 => "|roundps Vx,Wx,Ib||",
+        0x09 => "|roundpd Vx,Wx,Ib||",
+        0x0a => "|roundss Vdq,Hdq,Wss,Ib||",
+        0x0b => "|roundsd Vdq,Hdq,Wsd,Ib||",
+        0x0c => "|blendps Vx,Hx,Wx,Ib||",
+        0x0d => "|blendpd Vx,Hx,Wx,Ib||",
+        0x0e => "|pblendw Vx,Hx,Wx,Ib||",
+        0x0f => "palignr Pq,Qq,Ib|palignr Vx,Hx,Wx,Ib||",
+        0x14 => "|pextrb Mb,Vdq,Ib~pextrb Rd,Vdq,Ib||",
+        0x15 => "|pextrw Mw,Vdq,Ib~pextrw Rd,Vdq,Ib||",
+        0x16 => "|pextrd/pextrq Ey,Vdq,Ib||",
+        0x17 => "|extractps Ed,Vdq,Ib||",
+        0x20 => "|pinsrb Vdq,Hdq,Mb,Ib~pinsrb Vdq,Hdq,Rd,Ib||",
+        0x21 => "|insertps Vdq,Hdq,Wd,Ib||",
+        0x22 => "|pinsrd/pinsrq Vdq,Hdq,Ey,Ib||",
+        0x40 => "|dpps Vx,Hx,Wx,Ib||",
+        0x41 => "|dppd Vdq,Hdq,Wdq,Ib||",
+        0x42 => "|mpsadbw Vx,Hx,Wx,Ib||",
+        0x44 => "|pclmulqdq Vdq,Hdq,Wdq,Ib||",
+        0x60 => "|pcmpestrm Vdq,Wdq,Ib||",
+        0x61 => "|pcmpestri Vdq,Wdq,Ib||",
+        0x62 => "|pcmpistrm Vdq,Wdq,Ib||",
+        0x63 => "|pcmpistri Vdq,Wdq,Ib||",
+        0xcc => "sha1rnds4 Vdq,Wdq,Ib|||",
+        0xce => "|gf2p8affineqb Vx,Hx,Wx,Ib||",
+        0xcf => "|gf2p8affineinvqb Vx,Hx,Wx,Ib||",
+        0xdf => "|aeskeygenassist Vdq,Wdq,Ib||",
+        _ => "",
+    }
+}
+
+fn vex_only(map : u8, op : u8) -> &'static str {
+    // instructions without a legacy SSE form, mnemonics are complete
+    match (map, op) {
+        (2, 0x0c) => "|vpermilps Vx,Hx,Wx||",
+        (2, 0x0d) => "|vpermilpd Vx,Hx,Wx||",
+        (2, 0x0e) => "|vtestps Vx,Wx||",
+        (2, 0x0f) => "|vtestpd Vx,Wx||",
+        (2, 0x13) => "|vcvtph2ps Vx,Wq||",
+        (2, 0x16) => "|vpermps Vqq,Hqq,Wqq||",
+        (2, 0x18) => "|vbroadcastss Vx,Wd||",
+        (2, 0x19) => "|vbroadcastsd Vqq,Wq||",
+        (2, 0x1a) => "|vbroadcastf128 Vqq,Mdq||",
+        (2, 0x2c) => "|vmaskmovps Vx,Hx,Mx||",
+        (2, 0x2d) => "|vmaskmovpd Vx,Hx,Mx||",
+        (2, 0x2e) => "|vmaskmovps Mx,Hx,Vx||",
+        (2, 0x2f) => "|vmaskmovpd Mx,Hx,Vx||",
+        (2, 0x36) => "|vpermd Vqq,Hqq,Wqq||",
+        (2, 0x45) => "|vpsrlvd/vpsrlvq Vx,Hx,Wx||",
+        (2, 0x46) => "|vpsravd Vx,Hx,Wx||",
+        (2, 0x47) => "|vpsllvd/vpsllvq Vx,Hx,Wx||",
+        (2, 0x58) => "|vpbroadcastd Vx,Wd||",
+        (2, 0x59) => "|vpbroadcastq Vx,Wq||",
+        (2, 0x5a) => "|vbroadcasti128 Vqq,Mdq||",
+        (2, 0x78) => "|vpbroadcastb Vx,Wb||",
+        (2, 0x79) => "|vpbroadcastw Vx,Ww||",
+        (2, 0x8c) => "|vpmaskmovd/vpmaskmovq Vx,Hx,Mx||",
+        (2, 0x8e) => "|vpmaskmovd/vpmaskmovq Mx,Hx,Vx||",
+        (2, 0xf2) => "andn Gy,By,Ey|||",
+        (2, 0xf3) => "#grp17|||",
+        (2, 0xf5) => "bzhi Gy,Ey,By||pext Gy,By,Ey|pdep Gy,By,Ey",
+        (2, 0xf6) => "|||mulx Gy,By,Ey",
+        (2, 0xf7) => "bextr Gy,Ey,By|shlx Gy,Ey,By|sarx Gy,Ey,By|shrx Gy,Ey,By",
+        (3, 0x00) => "|vpermq Vqq,Wqq,Ib||",
+        (3, 0x01) => "|vpermpd Vqq,Wqq,Ib||",
+        (3, 0x02) => "|vpblendd Vx,Hx,Wx,Ib||",
+        (3, 0x04) => "|vpermilps Vx,Wx,Ib||",
+        (3, 0x05) => "|vpermilpd Vx,Wx,Ib||",
+        (3, 0x06) => "|vperm2f128 Vqq,Hqq,Wqq,Ib||",
+        (3, 0x18) => "|vinsertf128 Vqq,Hqq,Wdq,Ib||",
+        (3, 0x19) => "|vextractf128 Wdq,Vqq,Ib||",
+        (3, 0x1d) => "|vcvtps2ph Wq,Vx,Ib||",
+        (3, 0x38) => "|vinserti128 Vqq,Hqq,Wdq,Ib||",
+        (3, 0x39) => "|vextracti128 Wdq,Vqq,Ib||",
+        (3, 0x46) => "|vperm2i128 Vqq,Hqq,Wqq,Ib||",
+        (3, 0x4a) => "|vblendvps Vx,Hx,Wx,Lx||",
+        (3, 0x4b) => "|vblendvpd Vx,Hx,Wx,Lx||",
+        (3, 0x4c) => "|vpblendvb Vx,Hx,Wx,Lx||",
+        (3, 0xf0) => "|||rorx Gy,Ey,Ib",
+        _ => "",
+    }
+}
+
+fn evex_only(map : u8, op : u8) -> &'static str {
+    // EVEX encodings that differ from their VEX counterparts or have none
+    match (map, op) {
+        (1, 0x64) => "|vpcmpgtb Kk,Hx,Wx||",
+        (1, 0x65) => "|vpcmpgtw Kk,Hx,Wx||",
+        (1, 0x66) => "|vpcmpgtd Kk,Hx,Wx||",
+        (1, 0x6f) => "|vmovdqa32/vmovdqa64 Vx,Wx|vmovdqu32/vmovdqu64 Vx,Wx|vmovdqu8/vmovdqu16 Vx,Wx",
+        (1, 0x74) => "|vpcmpeqb Kk,Hx,Wx||",
+        (1, 0x75) => "|vpcmpeqw Kk,Hx,Wx||",
+        (1, 0x76) => "|vpcmpeqd Kk,Hx,Wx||",
+        (1, 0x7f) => "|vmovdqa32/vmovdqa64 Wx,Vx|vmovdqu32/vmovdqu64 Wx,Vx|vmovdqu8/vmovdqu16 Wx,Vx",
+        (1, 0xdb) => "|vpandd/vpandq Vx,Hx,Wx||",
+        (1, 0xdf) => "|vpandnd/vpandnq Vx,Hx,Wx||",
+        (1, 0xeb) => "|vpord/vporq Vx,Hx,Wx||",
+        (1, 0xef) => "|vpxord/vpxorq Vx,Hx,Wx||",
+        (2, 0x26) => "|vptestmb/vptestmw Kk,Hx,Wx|vptestnmb/vptestnmw Kk,Hx,Wx|",
+        (2, 0x27) => "|vptestmd/vptestmq Kk,Hx,Wx|vptestnmd/vptestnmq Kk,Hx,Wx|",
+        (2, 0x28) => "|vpmuldq Vx,Hx,Wx|vpmovm2b/vpmovm2w Vx,Uk|",
+        (2, 0x29) => "|vpcmpeqq Kk,Hx,Wx|vpmovb2m/vpmovw2m Kk,Ux|",
+        (2, 0x37) => "|vpcmpgtq Kk,Hx,Wx||",
+        (2, 0x38) => "|vpminsb Vx,Hx,Wx|vpmovm2d/vpmovm2q Vx,Uk|",
+        (2, 0x39) => "|vpminsd/vpminsq Vx,Hx,Wx|vpmovd2m/vpmovq2m Kk,Ux|",
+        (2, 0x3b) => "|vpminud/vpminuq Vx,Hx,Wx||",
+        (2, 0x3d) => "|vpmaxsd/vpmaxsq Vx,Hx,Wx||",
+        (2, 0x3f) => "|vpmaxud/vpmaxuq Vx,Hx,Wx||",
+        (2, 0x62) => "|vpexpandb/vpexpandw Vx,Wx||",
+        (2, 0x63) => "|vpcompressb/vpcompressw Wx,Vx||",
+        (2, 0x64) => "|vpblendmd/vpblendmq Vx,Hx,Wx||",
+        (2, 0x66) => "|vpblendmb/vpblendmw Vx,Hx,Wx||",
+        (2, 0x75) => "|vpermi2b/vpermi2w Vx,Hx,Wx||",
+        (2, 0x76) => "|vpermi2d/vpermi2q Vx,Hx,Wx||",
+        (2, 0x77) => "|vpermi2ps/vpermi2pd Vx,Hx,Wx||",
+        (2, 0x7a) => "|vpbroadcastb Vx,Rd||",
+        (2, 0x7b) => "|vpbroadcastw Vx,Rd||",
+        (2, 0x7c) => "|vpbroadcastd/vpbroadcastq Vx,Ry||",
+        (2, 0x7d) => "|vpermt2b/vpermt2w Vx,Hx,Wx||",
+        (2, 0x7e) => "|vpermt2d/vpermt2q Vx,Hx,Wx||",
+        (2, 0x7f) => "|vpermt2ps/vpermt2pd Vx,Hx,Wx||",
+        (2, 0x88) => "|vexpandps/vexpandpd Vx,Wx||",
+        (2, 0x89) => "|vpexpandd/vpexpandq Vx,Wx||",
+        (2, 0x8a) => "|vcompressps/vcompresspd Wx,Vx||",
+        (2, 0x8b) => "|vpcompressd/vpcompressq Wx,Vx||",
+        (2, 0x8d) => "|vpermb/vpermw Vx,Hx,Wx||",
+        (3, 0x1e) => "|vpcmpud/vpcmpuq Kk,Hx,Wx,Ib||",
+        (3, 0x1f) => "|vpcmpd/vpcmpq Kk,Hx,Wx,Ib||",
+        (3, 0x25) => "|vpternlogd/vpternlogq Vx,Hx,Wx,Ib||",
+        (3, 0x3e) => "|vpcmpub/vpcmpuw Kk,Hx,Wx,Ib||",
+        (3, 0x3f) => "|vpcmpb/vpcmpw Kk,Hx,Wx,Ib||",
+        _ => "",
+    }
+}
+
+fn group(name : &str, reg : u8, md : u8) -> &'static str {
+    let reg = reg as usize;
+    match name {
+        "grp1"  => ["add", "or", "adc", "sbb", "and", "sub", "xor", "cmp"][reg],
+        "grp1a" => ["pop Eq", "", "", "", "", "", "", ""][reg],
+        "grp2"  => ["rol", "ror", "rcl", "rcr", "shl", "shr", "shl", "sar"][reg],
+        "grp3b" => ["test Eb,Ib", "test Eb,Ib", "not Eb", "neg Eb", "mul Eb", "imul Eb", "div Eb", "idiv Eb"][reg],
+        "grp3v" => ["test Ev,Iz", "test Ev,Iz", "not Ev", "neg Ev", "mul Ev", "imul Ev", "div Ev", "idiv Ev"][reg],
+        "grp4"  => ["inc Eb", "dec Eb", "", "", "", "", "", ""][reg],
+        "grp5"  => ["inc Ev", "dec Ev", "call Eq", "call Mp", "jmp Eq", "jmp Mp", "push Eq", ""][reg],
+        "grp6"  => ["sldt Ew", "str Ew", "lldt Ew", "ltr Ew", "verr Ew", "verw Ew", "", ""][reg],
+        "grp7"  => ["sgdt M", "sidt M", "lgdt M", "lidt M", "smsw Ew", "", "lmsw Ew", "invlpg Mb"][reg],
+        "grp8"  => ["", "", "", "", "bt", "bts", "btr", "btc"][reg],
+        "grp9"  if md != 3 => ["", "cmpxchg8b/cmpxchg16b Mq", "", "xrstors M", "xsavec M", "xsaves M", "vmptrld Mq", "vmptrst Mq"][reg],
+        "grp9"  => ["", "", "", "", "", "", "rdrand Rv", "rdseed Rv||rdpid Rq|"][reg],
+        "grp11b" => ["mov Eb,Ib", "", "", "", "", "", "", if md == 3 { "xabort Ib" } else { "" }][reg],
+        "grp11v" => ["mov Ev,Iz", "", "", "", "", "", "", if md == 3 { "xbegin Jz" } else { "" }][reg],
+        "grp12" => ["", "", "psrlw Nq,Ib|psrlw Hx,Ux,Ib||", "", "psraw Nq,Ib|psraw Hx,Ux,Ib||", "", "psllw Nq,Ib|psllw Hx,Ux,Ib||", ""][reg],
+        "grp13" => ["", "", "psrld Nq,Ib|psrld Hx,Ux,Ib||", "", "psrad Nq,Ib|psrad Hx,Ux,Ib||", "", "pslld Nq,Ib|pslld Hx,Ux,Ib||", ""][reg],
+        "grp14" => ["", "", "psrlq Nq,Ib|psrlq Hx,Ux,Ib||", "|psrldq Hx,Ux,Ib||", "", "", "psllq Nq,Ib|psllq Hx,Ux,Ib||", "|pslldq Hx,Ux,Ib||"][reg],
+        "grp15" if md != 3 => ["fxsave M|||", "fxrstor M|||", "ldmxcsr Md|||", "stmxcsr Md|||", "xsave M|||", "xrstor M|||",
+                               "xsaveopt M|clwb Mb||", "clflush Mb|clflushopt Mb||"][reg],
+        "grp15" => ["||rdfsbase Ry|", "||rdgsbase Ry|", "||wrfsbase Ry|", "||wrgsbase Ry|", "", "lfence||incsspd/incsspq Ry|", "mfence|||", "sfence|||"][reg],
+        "grp16" => ["prefetchnta Mb", "prefetcht0 Mb", "prefetcht1 Mb", "prefetcht2 Mb", "nop Ev", "nop Ev", "nop Ev", "nop Ev"][reg],
+        "grp17" => ["", "blsr By,Ey", "blsmsk By,Ey", "blsi By,Ey", "", "", "", ""][reg],
+        "grpp"  => ["prefetch Mb", "prefetchw Mb", "prefetchwt1 Mb", "prefetch Mb", "prefetch Mb", "prefetch Mb", "prefetch Mb", "prefetch Mb"][reg],
+        _ => "",
+    }
+}
+
+fn x87(op : u8, modrm : u8) -> (&'static str, &'static str) {
+    // (mnemonic, operands) of escape opcodes d8-df
+    let reg = ((modrm >> 3) & 7) as usize;
+    let rm = modrm & 7;
+    if modrm < 0xc0 {
+        let (names, size) = match op {
+            0xd8 => (["fadd", "fmul", "fcom", "fcomp", "fsub", "fsubr", "fdiv", "fdivr"], "Md"),
+            0xd9 => (["fld", "", "fst", "fstp", "fldenv", "fldcw", "fnstenv", "fnstcw"], "Md"),
+            0xda => (["fiadd", "fimul", "ficom", "ficomp", "fisub", "fisubr", "fidiv", "fidivr"], "Md"),
+            0xdb => (["fild", "fisttp", "fist", "fistp", "", "fld", "", "fstp"], "Md"),
+            0xdc => (["fadd", "fmul", "fcom", "fcomp", "fsub", "fsubr", "fdiv", "fdivr"], "Mq"),
+            0xdd => (["fld", "fisttp", "fst", "fstp", "frstor", "", "fnsave", "fnstsw"], "Mq"),
+            0xde => (["fiadd", "fimul", "ficom", "ficomp", "fisub", "fisubr", "fidiv", "fidivr"], "Mw"),
+            _    => (["fild", "fisttp", "fist", "fistp", "fbld", "fild", "fbstp", "fistp"], "Mw"),
+        };
+        let size = match (op, reg) {
+            (0xd9, 4) | (0xd9, 6) | (0xdd, 4) | (0xdd, 6) => "M",
+            (0xd9, 5) | (0xd9, 7) | (0xdd, 7) => "Mw",
+            (0xdb, 5) | (0xdb, 7) | (0xdf, 4) | (0xdf, 6) => "Mt",
+            (0xdf, 5) | (0xdf, 7) => "Mq",
+            _ => size,
+        };
+        return (names[reg], size);
+    }
+    match (op, reg) {
+        (0xd8, _) => (["fadd", "fmul", "fcom", "fcomp", "fsub", "fsubr", "fdiv", "fdivr"][reg],
+                      if reg == 2 || reg == 3 { "STi" } else { "ST,STi" }),
+        (0xd9, 0) => ("fld", "STi"),
+        (0xd9, 1) => ("fxch", "STi"),
+        (0xd9, 2) if rm == 0 => ("fnop", ""),
+        (0xd9, 4) => (["fchs", "fabs", "", "", "ftst", "fxam", "", ""][rm as usize], ""),
+        (0xd9, 5) => (["fld1", "fldl2t", "fldl2e", "fldpi", "fldlg2", "fldln2", "fldz", ""][rm as usize], ""),
+        (0xd9, 6) => (["f2xm1", "fyl2x", "fptan", "fpatan", "fxtract", "fprem1", "fdecstp", "fincstp"][rm as usize], ""),
+        (0xd9, 7) => (["fprem", "fyl2xp1", "fsqrt", "fsincos", "frndint", "fscale", "fsin", "fcos"][rm as usize], ""),
+        (0xda, 0..=3) => (["fcmovb", "fcmove", "fcmovbe", "fcmovu"][reg], "ST,STi"),
+        (0xda, 5) if rm == 1 => ("fucompp", ""),
+        (0xdb, 0..=3) => (["fcmovnb", "fcmovne", "fcmovnbe", "fcmovnu"][reg], "ST,STi"),
+        (0xdb, 4) if rm == 2 => ("fnclex", ""),
+        (0xdb, 4) if rm == 3 => ("fninit", ""),
+        (0xdb, 5) => ("fucomi", "ST,STi"),
+        (0xdb, 6) => ("fcomi", "ST,STi"),
+        // the reversed sub/div pairs follow the historical AT&T assembler, as objdump does
+        (0xdc, 0) | (0xdc, 1) | (0xdc, 4..=7) => (["fadd", "fmul", "", "", "fsubr", "fsub", "fdivr", "fdiv"][reg], "STi,ST"),
+        (0xdd, 0) => ("ffree", "STi"),
+        (0xdd, 2) => ("fst", "STi"),
+        (0xdd, 3) => ("fstp", "STi"),
+        (0xdd, 4) => ("fucom", "STi"),
+        (0xdd, 5) => ("fucomp", "STi"),
+        (0xde, 3) if rm == 1 => ("fcompp", ""),
+        (0xde, 0) | (0xde, 1) | (0xde, 4..=7) => (["faddp", "fmulp", "", "", "fsubrp", "fsubp", "fdivrp", "fdivp"][reg], "STi,ST"),
+        (0xdf, 0) => ("ffreep", "STi"),
+        (0xdf, 4) if rm == 0 => ("fnstsw", "AX"),
+        (0xdf, 5) => ("fucomip", "ST,STi"),
+        (0xdf, 6) => ("fcomip", "ST,STi"),
+        _ => ("", ""),
+    }
+}
+
+struct Vex {
+    map  : u8,
+    w    : bool,
+    l    : u8,      /* vector length, 0: 128, 1: 256, 2: 512 bits */
+    vvvv : u8,      /* with EVEX.V' */
+    pp   : u8,
+    evex : Option<Evex>,
+}
+
+struct Evex {
+    z    : bool,    /* zeroing masking */
+    bcst : bool,    /* embedded broadcast (or rounding for register operands) */
+    aaa  : u8,      /* opmask register */
+}
+
+struct ModRm {
+    md    : u8,
+    reg   : u8,     /* with REX.R and EVEX.R' */
+    rm    : u8,     /* with REX.B, only meaningful for register operands */
+    rm_hi : u8,     /* EVEX.X, selects xmm16-31 for vector register operands */
+    base  : Option<&'static str>,
+    index : Option<(&'static str, u8)>,
+    disp  : Option<i64>,
+}
+
+struct Decoder<'a> {
+    buf      : &'a [u8],
+    pos      : usize,
+    addr     : u64,
+    opsize   : bool,    /* 0x66 not consumed as mandatory prefix */
+    data16   : usize,   /* redundant 0x66 prefixes */
+    adsize   : bool,
+    rep      : Option<u8>,
+    lock     : bool,
+    seg      : Option<usize>,
+    rex      : u8,
+    vex      : Option<Vex>,
+    op       : u8,
+    modrm    : Option<ModRm>,
+    rip_disp : Option<i64>,
+    target   : Option<u64>,
+    imm8     : Option<u8>,
+    ksize    : usize,   /* operand size of opmask instructions */
+}
+
+impl<'a> Decoder<'a> {
+    fn byte(&mut self) -> Option<u8> {
+        let b = *self.buf.get(self.pos)?;
+        self.pos += 1;
+        Some(b)
+    }
+
+    fn imm(&mut self, size : usize) -> Option<i64> {
+        // sign-extended little-endian immediate
+        let b = self.buf.get(self.pos..self.pos + size)?;
+        self.pos += size;
+        Some(match size {
+            1 => b[0] as i8 as i64,
+            2 => i16::from_le_bytes(b.try_into().unwrap()) as i64,
+            4 => i32::from_le_bytes(b.try_into().unwrap()) as i64,
+            _ => i64::from_le_bytes(b.try_into().unwrap()),
+        })
+    }
+
+    fn rex_w(&self) -> bool {
+        self.rex & 8 != 0 || self.vex.as_ref().is_some_and(|v| v.w)
+    }
+
+    fn evex(&self) -> Option<&Evex> {
+        self.vex.as_ref().and_then(|v| v.evex.as_ref())
+    }
+
+    fn osize(&self) -> usize {
+        if self.rex & 8 != 0 {
+            8
+        } else if self.opsize {
+            2
+        } else {
+            4
+        }
+    }
+
+    fn size(&self, code : &str) -> usize {
+        match code {
+            "b"  => 1,
+            "w"  => 2,
+            "d" | "ss" => 4,
+            "q" | "sd" => 8,
+            "t"  => 10,
+            "p"  => 6,
+            "dq" => 16,
+            "qq" => 32,
+            "k"  => self.ksize,
+            "v"  => self.osize(),
+            "z"  => self.osize().min(4),
+            "y"  => if self.rex_w() { 8 } else { 4 },
+            "x"  => 16 << self.vex.as_ref().map_or(0, |v| v.l),
+            _    => 0,
+        }
+    }
+
+    fn gpr(&self, n : u8, size : usize) -> String {
+        let n = n as usize & 15;
+        match size {
+            1 if self.rex == 0 && self.vex.is_none() => REG8_LEGACY[n & 7],
+            1 => REG8[n],
+            2 => REG16[n],
+            4 => REG32[n],
+            _ => REG64[n],
+        }.to_string()
+    }
+
+    fn vreg(&self, n : u8, code : &str) -> String {
+        // xmm/ymm/zmm by operand size, k for opmask operands
+        match (code, self.size(code)) {
+            ("k", _) => format!("k{}", n & 7),
+            (_, 64)  => format!("zmm{}", n & 31),
+            (_, 32)  => format!("ymm{}", n & 31),
+            _        => format!("xmm{}", n & 31),
+        }
+    }
+
+    fn parse_modrm(&mut self) -> Option<()> {
+        if self.modrm.is_some() {
+            return Some(());
+        }
+        let b = self.byte()?;
+        let md = b >> 6;
+        let mut reg = ((b >> 3) & 7) | if self.rex & 4 != 0 { 8 } else { 0 };
+        let rm = b & 7;
+        let rex_b = if self.rex & 1 != 0 { 8 } else { 0 };
+        let rex_x = if self.rex & 2 != 0 { 8 } else { 0 };
+        let mut rm_hi = 0;
+        if self.evex().is_some() {
+            // EVEX.R' was stashed in bit 4 of rex, EVEX.X doubles as the high bit of vector r/m
+            reg |= self.rex & 0x10;
+            rm_hi = rex_x << 1;
+        }
+
+        let mut modrm = ModRm { md, reg, rm : rm | rex_b, rm_hi, base : None, index : None, disp : None };
+        if md != 3 {
+            let regs = if self.adsize { &REG32 } else { &REG64 };
+
+            if rm == 4 {
+                let sib = self.byte()?;
+                let idx = ((sib >> 3) & 7) | rex_x;
+                let b = sib & 7;
+                if idx != 4 {
+                    modrm.index = Some((regs[idx as usize], 1 << (sib >> 6)));
+                }
+                if b == 5 && md == 0 {
+                    modrm.disp = Some(self.imm(4)?);
+                } else {
+                    modrm.base = Some(regs[(b | rex_b) as usize]);
+                }
+            } else if rm == 5 && md == 0 {
+                let d = self.imm(4)?;
+                self.rip_disp = Some(d);
+                modrm.base = Some(if self.adsize { "eip" } else { "rip" });
+                modrm.disp = Some(d);
+            } else {
+                modrm.base = Some(regs[(rm | rex_b) as usize]);
+            }
+            match md {
+                1 => modrm.disp = Some(self.imm(1)?),
+                2 => modrm.disp = Some(self.imm(4)?),
+                _ => {}
+            }
+        }
+
+        self.modrm = Some(modrm);
+        Some(())
+    }
+
+    fn mem_addr(&self, scale : i64) -> String {
+        // address without size, e.g. "[rbp-0x8]", scale is the EVEX disp8*N factor
+        let m = self.modrm.as_ref().unwrap();
+        // es/cs/ss/ds are ignored in 64-bit mode and shown as plain prefixes instead
+        let seg = self.seg.filter(|&s| s >= 4).map(|s| format!("{}:", SEGS[s])).unwrap_or_default();
+        let disp = m.disp.map(|d| if m.md == 1 { d * scale } else { d });
+
+        if let (None, None, Some(d)) = (m.base, m.index, disp) {
+            // absolute address
+            let seg = if seg.is_empty() { "ds:".to_string() } else { seg };
+            return format!("{}{:#x}", seg, d as u32);
+        }
+
+        let mut parts = vec![];
+        parts.extend(m.base.map(|b| b.to_string()));
+        parts.extend(m.index.map(|(i, scale)| format!("{}*{}", i, scale)));
+        let mut s = parts.join("+");
+        if let Some(d) = disp {
+            if self.rip_disp.is_some() {
+                s += &format!("+{:#x}", d as u64);
+            } else if d < 0 {
+                s += &format!("-{:#x}", -d);
+            } else {
+                s += &format!("+{:#x}", d);
+            }
+        }
+        format!("{}[{}]", seg, s)
+    }
+
+    fn mem_operand(&self, size : usize) -> String {
+        let ptr = |size| match size {
+            1  => "BYTE PTR ",
+            2  => "WORD PTR ",
+            4  => "DWORD PTR ",
+            6  => "FWORD PTR ",
+            8  => "QWORD PTR ",
+            10 => "TBYTE PTR ",
+            16 => "XMMWORD PTR ",
+            32 => "YMMWORD PTR ",
+            64 => "ZMMWORD PTR ",
+            _  => "",
+        };
+        match self.evex() {
+            Some(e) if e.bcst && size >= 16 => {
+                // one element broadcast to the whole vector
+                let (elem, name) = if self.rex_w() { (8, "QWORD") } else { (4, "DWORD") };
+                format!("{} BCST {}", name, self.mem_addr(elem))
+            }
+            Some(_) => format!("{}{}", ptr(size), self.mem_addr(size.max(1) as i64)),
+            None => format!("{}{}", ptr(size), self.mem_addr(1)),
+        }
+    }
+
+    fn is_mem(&self) -> bool {
+        self.modrm.as_ref().is_some_and(|m| m.md != 3)
+    }
+
+    fn operand(&mut self, spec : &str) -> Option<Option<String>> {
+        // render one operand, Some(None) for operands hidden in this encoding (H without VEX)
+        let fixed = match spec {
+            "AL"   => Some("al".to_string()),
+            "CL"   => Some("cl".to_string()),
+            "DX"   => Some("dx".to_string()),
+            "AX"   => Some("ax".to_string()),
+            "1"    => Some("1".to_string()),
+            "XMM0" => Some("xmm0".to_string()),
+            "ST"   => Some("st".to_string()),
+            "STi"  => Some(format!("st({})", self.modrm.as_ref()?.rm & 7)),
+            "rAX"  => Some(self.gpr(0, self.osize())),
+            "eAX"  => Some(self.gpr(0, self.osize().min(4))),
+            _ => None,
+        };
+        if fixed.is_some() {
+            return Some(fixed);
+        }
+
+        if let Some(code) = spec.strip_prefix("sI") {
+            // sign-extended imm8, shown in operand size
+            let v = self.imm(self.size(code))?;
+            return Some(Some(self.hex(v, self.osize())));
+        }
+
+        let (kind, code) = spec.split_at(1);
+        let s = match kind {
+            "E" => {
+                let size = self.size(code);
+                let m = self.modrm.as_ref()?;
+                if m.md == 3 { self.gpr(m.rm, size) } else { self.mem_operand(size) }
+            }
+            "G" => self.gpr(self.modrm.as_ref()?.reg, self.size(code)),
+            "M" => {
+                if !self.is_mem() {
+                    return None;
+                }
+                self.mem_operand(self.size(code))
+            }
+            "R" => {
+                let m = self.modrm.as_ref()?;
+                if m.md != 3 {
+                    return None;
+                }
+                self.gpr(m.rm, self.size(code))
+            }
+            "V" => self.vreg(self.modrm.as_ref()?.reg, code),
+            "W" => {
+                let m = self.modrm.as_ref()?;
+                if m.md == 3 { self.vreg(m.rm | m.rm_hi, code) } else { self.mem_operand(self.size(code)) }
+            }
+            "U" => {
+                let m = self.modrm.as_ref()?;
+                if m.md != 3 {
+                    return None;
+                }
+                self.vreg(m.rm | m.rm_hi, code)
+            }
+            "K" => format!("k{}", self.modrm.as_ref()?.reg & 7),
+            "H" => match &self.vex {
+                Some(v) => self.vreg(v.vvvv, code),
+                None => return Some(None),
+            },
+            "B" => self.gpr(self.vex.as_ref()?.vvvv, self.size(code)),
+            "L" => {
+                let b = self.imm(1)? as u8;
+                self.vreg(b >> 4, code)
+            }
+            "P" => format!("mm{}", self.modrm.as_ref()?.reg & 7),
+            "Q" => {
+                let m = self.modrm.as_ref()?;
+                if m.md == 3 { format!("mm{}", m.rm & 7) } else { self.mem_operand(self.size(code)) }
+            }
+            "N" => {
+                let m = self.modrm.as_ref()?;
+                if m.md != 3 {
+                    return None;
+                }
+                format!("mm{}", m.rm & 7)
+            }
+            "S" => SEGS[(self.modrm.as_ref()?.reg & 7) as usize].to_string(),
+            "Z" => {
+                let n = (self.op & 7) | if self.rex & 1 != 0 { 8 } else { 0 };
+                let size = match code { "q" => if self.opsize { 2 } else { 8 }, _ => self.size(code) };
+                self.gpr(n, size)
+            }
+            "I" => {
+                let (size, shown) = match code {
+                    "v" => (self.osize(), self.osize()),
+                    "z" => (self.osize().min(4), self.osize()),
+                    _   => (self.size(code), self.size(code)),
+                };
+                let v = self.imm(size)?;
+                if size == 1 {
+                    self.imm8 = Some(v as u8);
+                }
+                self.hex(v, shown)
+            }
+            "J" => {
+                let rel = self.imm(self.size(code))?;
+                let target = self.addr.wrapping_add(self.pos as u64).wrapping_add(rel as u64);
+                self.target = Some(target);
+                format!("{:#x}", target)
+            }
+            "O" => {
+                let a = self.imm(8)? as u64;
+                let size = if code == "b" { 1 } else { self.osize() };
+                let seg = SEGS[self.seg.unwrap_or(3)];
+                let ptr = ["", "BYTE", "WORD", "", "DWORD", "", "", "", "QWORD"][size];
+                format!("{} PTR {}:{:#x}", ptr, seg, a)
+            }
+            "X" | "Y" => {
+                let size = if code == "b" { 1 } else if code == "z" { self.osize().min(4) } else { self.osize() };
+                let ptr = ["", "BYTE", "WORD", "", "DWORD", "", "", "", "QWORD"][size];
+                let (seg, reg) = if kind == "X" { (SEGS[self.seg.unwrap_or(3)], "rsi") } else { ("es", "rdi") };
+                format!("{} PTR {}:[{}]", ptr, seg, reg)
+            }
+            _ => return None,
+        };
+        Some(Some(s))
+    }
+
+    fn hex(&self, v : i64, size : usize) -> String {
+        // immediate as unsigned hex of the operand size
+        match size {
+            1 => format!("{:#x}", v as u8),
+            2 => format!("{:#x}", v as u16),
+            4 => format!("{:#x}", v as u32),
+            _ => format!("{:#x}", v as u64),
+        }
+    }
+
+    fn select(&mut self, spec : &'static str) -> &'static str {
+        // alternatives by mandatory prefix, consuming the prefix that selected one
+        let alts : Vec<&'static str> = spec.split('|').collect();
+        if alts.len() != 4 {
+            return spec;
+        }
+        let pp = match &self.vex {
+            Some(v) => v.pp as usize,
+            None => match self.rep {
+                Some(0xf3) if !alts[2].is_empty() => 2,
+                Some(0xf2) if !alts[3].is_empty() => 3,
+                _ if self.opsize && !alts[1].is_empty() => 1,
+                _ => 0,
+            },
+        };
+        match pp {
+            1 => self.opsize = false,
+            2 | 3 => self.rep = None,
+            _ => {}
+        }
+        alts[pp]
+    }
+
+    fn decode(&mut self) -> Option<Insn> {
+
+        /* legacy prefixes and REX */
+        loop {
+            let b = *self.buf.get(self.pos)?;
+            match b {
+                0x66 => {
+                    if self.opsize {
+                        self.data16 += 1;
+                    }
+                    self.opsize = true;
+                }
+                0x67 => self.adsize = true,
+                0xf2 | 0xf3 => self.rep = Some(b),
+                0xf0 => self.lock = true,
+                0x26 => self.seg = Some(0),
+                0x2e => self.seg = Some(1),
+                0x36 => self.seg = Some(2),
+                0x3e => self.seg = Some(3),
+                0x64 => self.seg = Some(4),
+                0x65 => self.seg = Some(5),
+                _ => break,
+            }
+            self.pos += 1;
+            if self.pos >= 14 {
+                return None;
+            }
+        }
+        let b = self.byte()?;
+        let op = if (0x40..=0x4f).contains(&b) {
+            self.rex = b;
+            self.byte()?
+        } else {
+            b
+        };
+        self.op = op;
+
+        let mut vex_table = false;
+        let mut spec = match op {
+            0x0f => {
+                let op2 = self.byte()?;
+                self.op = op2;
+                match op2 {
+                    0x38 => { self.op = self.byte()?; three_byte_38(self.op) }
+                    0x3a => { self.op = self.byte()?; three_byte_3a(self.op) }
+                    _ => {
+                        if let Some(insn) = self.two_byte_special(op2) {
+                            return insn;
+                        }
+                        two_byte(op2)
+                    }
+                }
+            }
+            0xc4 | 0xc5 | 0x62 => {
+                // VEX/EVEX, REX bits come inverted from the prefix
+                let b1 = self.byte()?;
+                let (map, w, b2) = if op == 0xc5 {
+                    self.rex = if b1 & 0x80 == 0 { 0x44 } else { 0x40 };
+                    (1, false, b1)
+                } else {
+                    let b2 = self.byte()?;
+                    self.rex = 0x40 | ((!b1 >> 5) & 7);
+                    (b1 & if op == 0x62 { 7 } else { 0x1f }, b2 & 0x80 != 0, b2)
+                };
+                let mut vex = Vex {
+                    map,
+                    w,
+                    l    : (b2 >> 2) & 1,
+                    vvvv : (!b2 >> 3) & 0xf,
+                    pp   : b2 & 3,
+                    evex : None,
+                };
+                if op == 0x62 {
+                    // R' rides along in bit 4 of rex, V' extends vvvv
+                    if b2 & 4 == 0 {
+                        return None;
+                    }
+                    let b3 = self.byte()?;
+                    if b1 & 0x10 == 0 {
+                        self.rex |= 0x10;
+                    }
+                    if b3 & 8 == 0 {
+                        vex.vvvv |= 0x10;
+                    }
+                    vex.l = (b3 >> 5) & 3;
+                    vex.evex = Some(Evex { z : b3 & 0x80 != 0, bcst : b3 & 0x10 != 0, aaa : b3 & 7 });
+                }
+                self.vex = Some(vex);
+                self.op = self.byte()?;
+                let op = self.op;
+                let only = if self.evex().is_some() { evex_only(map, op) } else { "" };
+                let only = if only.is_empty() { vex_only(map, op) } else { only };
+                if !only.is_empty() {
+                    vex_table = true;
+                    only
+                } else {
+                    match map {
+                        1 => {
+                            if op == 0x77 && self.evex().is_none() {
+                                let l = self.vex.as_ref().unwrap().l;
+                                return self.finish(if l != 0 { "vzeroall" } else { "vzeroupper" }, true);
+                            }
+                            if (0x41..=0x4b).contains(&op) || (0x90..=0x9f).contains(&op) {
+                                return self.kmask(op);
+                            }
+                            // only the SSE rows and their shift/mxcsr groups
+                            match two_byte(op) {
+                                spec if spec.contains('|') || matches!(op, 0x71..=0x73 | 0xae) => spec,
+                                _ => return None,
+                            }
+                        }
+                        2 => match op {
+                            0x96..=0x9f | 0xa6..=0xaf | 0xb6..=0xbf => return self.fma(op),
+                            _ => three_byte_38(op),
+                        },
+                        3 => match op {
+                            0x30..=0x33 => return self.kmask(op),
+                            0x5c..=0x5f | 0x68..=0x7f => return self.fma4(op),
+                            _ => three_byte_3a(op),
+                        },
+                        _ => return None,
+                    }
+                }
+            }
+            0x9b => {
+                // fwait before a no-wait control instruction reads as its waiting form
+                let next = self.buf.get(self.pos..self.pos + 2).map(|b| (b[0], b[1]));
+                if let Some((esc @ 0xd8..=0xdf, modrm)) = next {
+                    if let Some(name) = x87(esc, modrm).0.strip_prefix("fn") {
+                        self.op = esc;
+                        self.pos += 1;
+                        self.parse_modrm()?;
+                        return self.emit(format!("f{}", name), x87(esc, modrm).1);
+                    }
+                }
+                one_byte(op)
+            }
+            0xd8..=0xdf => {
+                let modrm = *self.buf.get(self.pos)?;
+                self.parse_modrm()?;
+                let (mn, ops) = x87(op, modrm);
+                if mn.is_empty() {
+                    return None;
+                }
+                return self.emit(mn.to_string(), ops);
+            }
+            _ => one_byte(op),
+        };
+        if vex_table {
+            spec = self.select(spec);
+        }
+        self.finish(spec, vex_table)
+    }
+
+    fn two_byte_special(&mut self, op : u8) -> Option<Option<Insn>> {
+        // 0f opcodes whose meaning depends on the whole modrm byte
+        let next = *self.buf.get(self.pos)?;
+        let name = match (op, next, self.rep) {
+            (0x01, 0xc8, _) => "monitor",
+            (0x01, 0xc9, _) => "mwait",
+            (0x01, 0xca, _) => "clac",
+            (0x01, 0xcb, _) => "stac",
+            (0x01, 0xd0, _) => "xgetbv",
+            (0x01, 0xd1, _) => "xsetbv",
+            (0x01, 0xd5, _) => "xend",
+            (0x01, 0xd6, _) => "xtest",
+            (0x01, 0xee, _) => "rdpkru",
+            (0x01, 0xef, _) => "wrpkru",
+            (0x01, 0xf8, _) => "swapgs",
+            (0x01, 0xf9, _) => "rdtscp",
+            (0x1e, 0xc8..=0xcf, Some(0xf3)) => {
+                // rdsspd/rdsspq r
+                self.pos += 1;
+                self.rep = None;
+                let reg = self.gpr((next & 7) | if self.rex & 1 != 0 { 8 } else { 0 }, if self.rex_w() { 8 } else { 4 });
+                let name = if self.rex_w() { "rdsspq" } else { "rdsspd" };
+                return Some(self.emit(name.to_string(), "").map(|mut i| { i.operands = reg; i }));
+            }
+            (0x1e, 0xfa, Some(0xf3)) => "endbr64",
+            (0x1e, 0xfb, Some(0xf3)) => "endbr32",
+            _ => return None,
+        };
+        self.pos += 1;
+        self.rep = None;
+        Some(self.emit(name.to_string(), ""))
+    }
+
+    fn fma(&mut self, op : u8) -> Option<Insn> {
+        // VEX 0f38 96-bf: vfmadd132ps and friends
+        let v = self.vex.as_ref()?;
+        if v.pp != 1 {
+            return None;
+        }
+        let order = match op >> 4 { 9 => "132", 0xa => "213", _ => "231" };
+        let (name, scalar) = match op & 0xf {
+            0x6 => ("fmaddsub", false),
+            0x7 => ("fmsubadd", false),
+            0x8 => ("fmadd", false),
+            0x9 => ("fmadd", true),
+            0xa => ("fmsub", false),
+            0xb => ("fmsub", true),
+            0xc => ("fnmadd", false),
+            0xd => ("fnmadd", true),
+            0xe => ("fnmsub", false),
+            _   => ("fnmsub", true),
+        };
+        let suffix = match (scalar, v.w) {
+            (false, false) => "ps",
+            (false, true)  => "pd",
+            (true, false)  => "ss",
+            (true, true)   => "sd",
+        };
+        let ops = match (scalar, v.w) {
+            (false, _)    => "Vx,Hx,Wx",
+            (true, false) => "Vdq,Hdq,Wss",
+            (true, true)  => "Vdq,Hdq,Wsd",
+        };
+        self.parse_modrm()?;
+        self.emit(format!("v{}{}{}", name, order, suffix), ops)
+    }
+
+    fn kmask(&mut self, op : u8) -> Option<Insn> {
+        // VEX opmask instructions, the suffix comes from pp and W
+        let v = self.vex.as_ref()?;
+        if v.evex.is_some() {
+            return None;
+        }
+        let (map, pp, w) = (v.map, v.pp, v.w);
+        let (suffix, size) = match (map, op, pp, w) {
+            (3, 0x30 | 0x32, 1, false) => ("b", 1),
+            (3, 0x30 | 0x32, 1, true)  => ("w", 2),
+            (3, 0x31 | 0x33, 1, false) => ("d", 4),
+            (3, 0x31 | 0x33, 1, true)  => ("q", 8),
+            (1, 0x92 | 0x93, 3, false) => ("d", 4),
+            (1, 0x92 | 0x93, 3, true)  => ("q", 8),
+            (1, 0x4b, 1, false) => ("bw", 2),
+            (1, 0x4b, 0, false) => ("wd", 4),
+            (1, 0x4b, 0, true)  => ("dq", 8),
+            (1, 0x4b, _, _)     => return None,
+            (1, _, 0, false)    => ("w", 2),
+            (1, _, 0, true)     => ("q", 8),
+            (1, _, 1, false)    => ("b", 1),
+            (1, _, 1, true)     => ("d", 4),
+            _ => return None,
+        };
+        self.ksize = size;
+        let (name, ops) = match (map, op) {
+            (1, 0x41) => ("kand", "Vk,Hk,Uk"),
+            (1, 0x42) => ("kandn", "Vk,Hk,Uk"),
+            (1, 0x44) => ("knot", "Vk,Uk"),
+            (1, 0x45) => ("kor", "Vk,Hk,Uk"),
+            (1, 0x46) => ("kxnor", "Vk,Hk,Uk"),
+            (1, 0x47) => ("kxor", "Vk,Hk,Uk"),
+            (1, 0x4a) => ("kadd", "Vk,Hk,Uk"),
+            (1, 0x4b) => ("kunpck", "Vk,Hk,Uk"),
+            (1, 0x90) => ("kmov", "Vk,Wk"),
+            (1, 0x91) => ("kmov", "Mk,Vk"),
+            (1, 0x92) => ("kmov", "Vk,Ry"),
+            (1, 0x93) => ("kmov", "Gy,Uk"),
+            (1, 0x98) => ("kortest", "Vk,Uk"),
+            (1, 0x99) => ("ktest", "Vk,Uk"),
+            (3, 0x30 | 0x31) => ("kshiftr", "Vk,Uk,Ib"),
+            (3, 0x32 | 0x33) => ("kshiftl", "Vk,Uk,Ib"),
+            _ => return None,
+        };
+        self.emit(format!("{}{}", name, suffix), ops)
+    }
+
+    fn fma4(&mut self, op : u8) -> Option<Insn> {
+        // AMD four operand FMA, VEX.W swaps the register and memory sources
+        let v = self.vex.as_ref()?;
+        if v.pp != 1 || v.evex.is_some() {
+            return None;
+        }
+        let name = match op & 0xfc {
+            0x5c => ["fmaddsubps", "fmaddsubpd", "fmsubaddps", "fmsubaddpd"],
+            0x68 => ["fmaddps", "fmaddpd", "fmaddss", "fmaddsd"],
+            0x6c => ["fmsubps", "fmsubpd", "fmsubss", "fmsubsd"],
+            0x78 => ["fnmaddps", "fnmaddpd", "fnmaddss", "fnmaddsd"],
+            0x7c => ["fnmsubps", "fnmsubpd", "fnmsubss", "fnmsubsd"],
+            _ => return None,
+        }[(op & 3) as usize];
+        let src = match name.get(name.len() - 2..) {
+            Some("ss") => "Wss",
+            Some("sd") => "Wsd",
+            _ => "Wx",
+        };
+        let ops = if v.w { format!("Vx,Hx,Lx,{}", src) } else { format!("Vx,Hx,{},Lx", src) };
+        self.parse_modrm()?;
+        self.emit(format!("v{}", name), &ops)
+    }
+
+    fn finish(&mut self, spec : &'static str, vex_table : bool) -> Option<Insn> {
+        // resolve prefix alternatives, groups and mem/reg forms, then render
+        let mut spec = spec;
+        let mut inherited = "";
+        loop {
+            if spec.is_empty() {
+                return None;
+            }
+            if spec.contains('|') {
+                spec = self.select(spec);
+                continue;
+            }
+            if let Some(grp) = spec.strip_prefix('#') {
+                let (name, ops) = grp.split_once(' ').unwrap_or((grp, ""));
+                self.parse_modrm()?;
+                let m = self.modrm.as_ref().unwrap();
+                spec = group(name, m.reg & 7, m.md);
+                if !ops.is_empty() {
+                    inherited = ops;
+                }
+                continue;
+            }
+            if let Some((mem, reg)) = spec.split_once('~') {
+                self.parse_modrm()?;
+                spec = if self.is_mem() { mem } else { reg };
+                continue;
+            }
+            break;
+        }
+
+        let (mn, ops) = spec.split_once(' ').unwrap_or((spec, inherited));
+
+        // pick mnemonic by REX.W (x/y) or operand size (x/y/z)
+        let names : Vec<&str> = mn.split('/').collect();
+        let mn = match names.len() {
+            2 => names[self.rex_w() as usize],
+            3 => names[match self.osize() { 2 => 0, 4 => 1, _ => 2 }],
+            _ => mn,
+        };
+
+        let mut mn = mn.to_string();
+        if self.vex.is_some() && !vex_table {
+            // MMX and general purpose forms of the legacy tables have no VEX encoding
+            let gp_only = ["movbe", "crc32", "adcx", "adox"].contains(&mn.as_str()) || mn.starts_with("sha");
+            if gp_only || ops.split(',').any(|o| matches!(o.chars().next(), Some('P' | 'Q' | 'N'))) {
+                return None;
+            }
+            mn = format!("v{}", mn);
+        }
+        self.emit(mn, ops)
+    }
+
+    fn emit(&mut self, mut mn : String, ops : &str) -> Option<Insn> {
+
+        let needs_modrm = ops.split(',').any(|o| {
+            matches!(o.chars().next(), Some('E' | 'G' | 'M' | 'R' | 'V' | 'W' | 'U' | 'K' | 'P' | 'Q' | 'N' | 'S'))
+                || o == "STi"
+        });
+        if needs_modrm {
+            self.parse_modrm()?;
+        }
+
+        // vmovss/vmovsd loads and stores have no vvvv operand
+        let ops = if (mn == "vmovss" || mn == "vmovsd") && self.is_mem() {
+            ops.replace(",Hdq", "")
+        } else {
+            ops.to_string()
+        };
+
+        // push immediate defaults to 64-bit operand size
+        if mn == "push" && !self.opsize {
+            self.rex |= 8;
+        }
+
+        let mut rendered = vec![];
+        for spec in ops.split(',').filter(|s| !s.is_empty()) {
+            if let Some(s) = self.operand(spec)? {
+                rendered.push(s);
+            }
+        }
+
+        // special cases by opcode
+        let legacy = self.vex.is_none();
+        match (legacy, mn.as_str()) {
+            (true, "nop") if self.op == 0x90 && self.rex & 1 != 0 => {
+                mn = "xchg".to_string();
+                rendered = vec![self.gpr(8, self.osize()), self.gpr(0, self.osize())];
+            }
+            (true, "nop") if self.op == 0x90 && self.rep == Some(0xf3) => {
+                mn = "pause".to_string();
+                self.rep = None;
+            }
+            (true, "nop") if self.op == 0x90 && self.opsize => {
+                mn = "xchg".to_string();
+                rendered = vec!["ax".to_string(), "ax".to_string()];
+            }
+            (true, "mov") if self.op >= 0xb8 && self.op <= 0xbf && self.rex & 8 != 0 => {
+                mn = "movabs".to_string();
+            }
+            _ => {}
+        }
+        let base = mn.strip_prefix('v').unwrap_or(&mn);
+        let is_cmp = base.len() == 5 && ["cmpps", "cmppd", "cmpss", "cmpsd"].contains(&base);
+        if is_cmp {
+            // comparison predicate folded into the mnemonic
+            if let Some(imm) = self.imm8 {
+                let max = if legacy { 8 } else { 32 };
+                if (imm as usize) < max {
+                    let at = mn.len() - 2;
+                    mn.insert_str(at, CMP_PRED[imm as usize]);
+                    rendered.pop();
+                }
+            }
+        }
+
+        if let (Some(rest), Some(imm), Some(_)) = (mn.strip_prefix("vpcmp"), self.imm8, self.evex()) {
+            // EVEX integer compares fold all but the always false/true predicates
+            let pred = ["eq", "lt", "le", "", "neq", "nlt", "nle", ""][imm as usize & 7];
+            if !pred.is_empty() && imm < 8 && !rest.starts_with("eq") && !rest.starts_with("gt") {
+                mn = format!("vpcmp{}{}", pred, rest);
+                rendered.pop();
+            }
+        }
+        if let Some(e) = self.evex() {
+            // opmask and zeroing decorate the destination
+            if let Some(dst) = rendered.first_mut() {
+                if e.aaa != 0 {
+                    *dst += &format!("{{k{}}}", e.aaa);
+                }
+                if e.z {
+                    *dst += "{z}";
+                }
+            }
+        }
+
+        let is_string = matches!(mn.as_str(), "movs" | "cmps" | "stos" | "lods" | "scas" | "ins" | "outs");
+        let is_branch = mn == "call" || mn == "ret" || mn.starts_with('j');
+
+        // 0x66 is also redundant when REX.W sets the operand size
+        let data16 = self.data16 + (self.opsize && self.rex & 8 != 0) as usize;
+        let mut prefix = "data16 ".repeat(data16);
+        if self.lock {
+            prefix += "lock ";
+        }
+        match self.seg {
+            Some(3) if (mn == "jmp" || mn == "call") && self.target.is_none() => prefix += "notrack ",
+            Some(s) if s < 4 && !is_string => prefix += &format!("{} ", SEGS[s]),
+            _ => {}
+        }
+        match self.rep {
+            Some(0xf3) if is_string && (mn == "cmps" || mn == "scas") => prefix += "repz ",
+            Some(0xf3) if is_string => prefix += "rep ",
+            Some(0xf2) if is_branch => prefix += "bnd ",
+            Some(0xf2) => prefix += "repnz ",
+            Some(_) => prefix += "repz ",
+            None => {}
+        }
+
+        let mem_ref = self.rip_disp.map(|d| self.addr.wrapping_add(self.pos as u64).wrapping_add(d as u64));
+
+        Some(Insn {
+            addr     : self.addr,
+            len      : self.pos,
+            mnemonic : format!("{}{}", prefix, mn),
+            operands : rendered.join(","),
+            target   : self.target,
+            mem_ref,
+        })
+    }
+}
+
+pub fn decode(buf : &[u8], addr : u64) -> Option<Insn> {
+    // decode one instruction at the start of buf, None if invalid or truncated
+    let buf = &buf[..buf.len().min(15)];
+    Decoder {
+        buf,
+        pos      : 0,
+        addr,
+        opsize   : false,
+        data16   : 0,
+        adsize   : false,
+        rep      : None,
+        lock     : false,
+        seg      : None,
+        rex      : 0,
+        vex      : None,
+        op       : 0,
+        modrm    : None,
+        rip_disp : None,
+        target   : None,
+        imm8     : None,
+        ksize    : 0,
+    }.decode()
+}
@@ -0,0 +1,444 @@
+pub mod reader;
+pub mod abbrev;
+pub mod info;
+pub mod line;
+pub mod expr;
+
+use std::collections::HashMap;
+use colored::Colorize;
+
+use crate::parser::Parser;
+use crate::parser::demangle::NameStyle;
+use self::info::{AttrValue, RawUnit};
+use self::info::{DW_TAG_compile_unit, DW_TAG_partial_unit, DW_TAG_skeleton_unit, DW_TAG_subprogram};
+use self::info::{DW_AT_name, DW_AT_linkage_name, DW_AT_MIPS_linkage_name, DW_AT_producer, DW_AT_comp_dir};
+use self::info::{DW_AT_language, DW_AT_stmt_list, DW_AT_decl_file, DW_AT_decl_line};
+use self::info::{DW_AT_specification, DW_AT_abstract_origin, DW_AT_declaration};
+use self::line::LineProgram;
+
+/*
+.debug_info      --> DIE tree of every unit, attributes are encoded as described in .debug_abbrev
+.debug_abbrev    --> abbreviation tables
+.debug_str       --> strings referenced by DW_FORM_strp / strx
+.debug_line_str  --> strings referenced by DW_FORM_line_strp (DWARF 5)
+.debug_line      --> line number programs, one per unit (DW_AT_stmt_list)
+ */
+#[derive(Default)]
+pub struct DwarfSections<'a> {
+    // missing sections are empty slices
+    pub info        : &'a [u8],
+    pub abbrev      : &'a [u8],
+    pub str         : &'a [u8],
+    pub line_str    : &'a [u8],
+    pub str_offsets : &'a [u8],
+    pub addr        : &'a [u8],
+    pub ranges      : &'a [u8],
+    pub rnglists    : &'a [u8],
+    pub line        : &'a [u8],
+}
+
+#[derive(Debug)]
+pub struct Function {
+    pub name         : String,
+    pub linkage_name : Option<String>,
+    pub ranges       : Vec<(u64, u64)>,
+    pub decl_file    : Option<String>,
+    pub decl_line    : Option<u64>,
+}
+
+#[derive(Debug)]
+pub struct CompUnit {
+    pub offset    : u64,
+    pub version   : u16,
+    pub name      : Option<String>,
+    pub producer  : Option<String>,
+    pub comp_dir  : Option<String>,
+    pub language  : Option<u64>,
+    pub ranges    : Vec<(u64, u64)>,
+    pub functions : Vec<Function>,
+    pub lines     : Option<LineProgram>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct SourceLoc {
+    pub file     : String,
+    pub line     : u64,
+    pub column   : u64,
+    pub function : Option<String>,
+}
+
+// offset of subprogram DIE -> (name, linkage name, specification/origin)
+type SubprogramNames = HashMap<u64, (Option<String>, Option<String>, Option<u64>)>;
+
+struct AddrRanges<T> {
+    // [start, end) -> T sorted by start, the same walk back as SymbolIndex::containing
+    ranges  : Vec<(u64, u64, T)>,
+    max_end : Vec<u64>,
+}
+
+impl<T> AddrRanges<T> {
+    fn new(mut ranges : Vec<(u64, u64, T)>) -> Self {
+        // stable, so equal starts keep the order they were pushed in
+        ranges.sort_by_key(|r| r.0);
+        let mut end = 0;
+        let max_end = ranges.iter().map(|r| { end = end.max(r.1); end }).collect();
+        AddrRanges { ranges, max_end }
+    }
+
+    fn covering(&self, addr : u64) -> impl Iterator<Item = &T> {
+        // nearest start first
+        let i = self.ranges.partition_point(|r| r.0 <= addr);
+        (0..i).rev()
+            .take_while(move |&j| self.max_end[j] > addr)
+            .map(|j| &self.ranges[j])
+            .filter(move |r| addr < r.1)
+            .map(|r| &r.2)
+    }
+}
+
+pub struct DebugInfo {
+    pub units : Vec<CompUnit>,
+    lines     : AddrRanges<(usize, usize)>,     /* row ranges of every sequence -> (unit, row) */
+    functions : AddrRanges<(usize, usize)>,     /* function ranges -> (unit, function) */
+}
+
+impl DebugInfo {
+    pub fn new(secs : &DwarfSections) -> Self {
+
+        let raw_units = info::parse_units(secs);
+
+        // names of every subprogram, so out-of-line definitions and inlined copies
+        // can be named through DW_AT_specification / DW_AT_abstract_origin
+        let mut names = SubprogramNames::new();
+        for unit in &raw_units {
+            for die in unit.dies.iter().filter(|d| d.tag == DW_TAG_subprogram) {
+                let name = die.attr(DW_AT_name).and_then(|v| unit.string(v, secs));
+                let linkage = die.attr(DW_AT_linkage_name)
+                    .or_else(|| die.attr(DW_AT_MIPS_linkage_name))
+                    .and_then(|v| unit.string(v, secs));
+                let origin = match die.attr(DW_AT_specification).or_else(|| die.attr(DW_AT_abstract_origin)) {
+                    Some(AttrValue::Ref(r)) => Some(*r),
+                    _ => None,
+                };
+                names.insert(die.offset, (name, linkage, origin));
+            }
+        }
+
+        let units : Vec<CompUnit> = raw_units.iter()
+            .filter_map(|unit| Self::comp_unit(unit, secs, &names))
+            .collect();
+
+        // lookups are binary searches over every line row and function range
+        let mut lines = vec![];
+        let mut functions = vec![];
+        for (u, unit) in units.iter().enumerate() {
+            if let Some(program) = &unit.lines {
+                for (r, w) in program.rows.windows(2).enumerate() {
+                    if !w[0].end_sequence && w[0].addr < w[1].addr {
+                        lines.push((w[0].addr, w[1].addr, (u, r)));
+                    }
+                }
+            }
+            for (f, func) in unit.functions.iter().enumerate() {
+                functions.extend(func.ranges.iter().filter(|(l, h)| l < h).map(|&(l, h)| (l, h, (u, f))));
+            }
+        }
+
+        DebugInfo { units, lines : AddrRanges::new(lines), functions : AddrRanges::new(functions) }
+    }
+
+    fn comp_unit(
+        unit  : &RawUnit,
+        secs  : &DwarfSections,
+        names : &SubprogramNames
+    ) -> Option<CompUnit> {
+
+        let die = unit.dies.first()?;
+        if die.tag != DW_TAG_compile_unit && die.tag != DW_TAG_partial_unit && die.tag != DW_TAG_skeleton_unit {
+            return None;
+        }
+
+        let string = |at| die.attr(at).and_then(|v| unit.string(v, secs));
+        let name = string(DW_AT_name);
+        let comp_dir = string(DW_AT_comp_dir);
+
+        let lines = die.attr(DW_AT_stmt_list)
+            .and_then(|v| unit.udata(v))
+            .and_then(|off| LineProgram::new(
+                secs,
+                off,
+                comp_dir.as_deref().unwrap_or(""),
+                name.as_deref().unwrap_or(""),
+            ));
+
+        let mut functions = vec![];
+        for sub in unit.dies.iter().filter(|d| d.tag == DW_TAG_subprogram) {
+
+            if matches!(sub.attr(DW_AT_declaration), Some(AttrValue::Flag(true))) {
+                continue;
+            }
+            let ranges = unit.ranges(sub, secs);
+            if ranges.is_empty() {
+                continue;
+            }
+
+            // follow specification/origin until both names are known
+            let (mut fname, mut linkage, mut origin) = names.get(&sub.offset).cloned().unwrap_or((None, None, None));
+            let mut hops = 0;
+            while let (Some(next), true) = (origin, fname.is_none() || linkage.is_none()) {
+                let (n, l, o) = match names.get(&next) {
+                    Some(entry) => entry.clone(),
+                    None        => break,
+                };
+                fname = fname.or(n);
+                linkage = linkage.or(l);
+                origin = o;
+                hops += 1;
+                if hops > 8 {
+                    break;
+                }
+            }
+
+            let decl_file = sub.attr(DW_AT_decl_file)
+                .and_then(|v| unit.udata(v))
+                .and_then(|idx| lines.as_ref().and_then(|l| l.file_name(idx)))
+                .map(|f| f.to_string());
+
+            functions.push(Function {
+                name      : fname.or_else(|| linkage.clone()).unwrap_or_else(|| "<anonymous>".to_string()),
+                linkage_name : linkage,
+                ranges,
+                decl_file,
+                decl_line : sub.attr(DW_AT_decl_line).and_then(|v| unit.udata(v)),
+            });
+        }
+        functions.sort_by_key(|f| f.ranges[0].0);
+
+        Some(CompUnit {
+            offset   : unit.header.offset,
+            version  : unit.header.version,
+            name,
+            producer : string(DW_AT_producer),
+            comp_dir,
+            language : die.attr(DW_AT_language).and_then(|v| unit.udata(v)),
+            ranges   : unit.ranges(die, secs),
+            functions,
+            lines,
+        })
+    }
+
+    pub fn find_function(&self, addr : u64) -> Option<&Function> {
+        // innermost (smallest) function covering addr
+        self.functions.covering(addr)
+            .map(|&(u, f)| &self.units[u].functions[f])
+            .min_by_key(|f| f.ranges.iter().map(|(l, h)| h - l).sum::<u64>())
+    }
+
+    pub fn find_location(&self, addr : u64) -> Option<SourceLoc> {
+        // map an address to file:line through the line table of the unit covering it,
+        // a unit with ranges only answers for them, leftovers of discarded code overlap at low addresses
+        let &(u, r) = self.lines.covering(addr).find(|&&(u, _)| {
+            let ranges = &self.units[u].ranges;
+            ranges.is_empty() || ranges.iter().any(|&(l, h)| l <= addr && addr < h)
+        })?;
+        let lines = self.units[u].lines.as_ref()?;
+        let row = &lines.rows[r];
+        Some(SourceLoc {
+            file     : lines.file_name(row.file).unwrap_or("??").to_string(),
+            line     : row.line,
+            column   : row.column,
+            function : self.find_function(addr).map(|f| f.name.clone()),
+        })
+    }
+}
+
+pub fn get_lang_str(lang : u64) -> &'static str {
+    // DW_LANG_*
+    match lang {
+        0x01 => "C89",
+        0x02 => "C",
+        0x04 => "C++",
+        0x07 => "Fortran77",
+        0x0c => "C99",
+        0x16 => "Go",
+        0x19 => "C++03",
+        0x1a => "C++11",
+        0x1c => "Rust",
+        0x1d => "C11",
+        0x21 => "C++14",
+        0x8001 => "Mips Assembler",
+        _    => "unknown",
+    }
+}
+
+impl Parser {
+    fn dwarf_sections(&self) -> DwarfSections<'_> {
+        let get = |name| self.section_data(name).unwrap_or(&[]);
+        DwarfSections {
+            info        : get(".debug_info"),
+            abbrev      : get(".debug_abbrev"),
+            str         : get(".debug_str"),
+            line_str    : get(".debug_line_str"),
+            str_offsets : get(".debug_str_offsets"),
+            addr        : get(".debug_addr"),
+            ranges      : get(".debug_ranges"),
+            rnglists    : get(".debug_rnglists"),
+            line        : get(".debug_line"),
+        }
+    }
+
+    pub fn debug_info(&self) -> Option<&DebugInfo> {
+        // None if the binary carries no .debug_info, parsed once on first use
+        self.dwarf.get_or_init(|| {
+            let secs = self.dwarf_sections();
+            (!secs.info.is_empty()).then(|| DebugInfo::new(&secs))
+        }).as_ref()
+    }
+
+    pub fn source_for_vaddr(&self, vaddr : u64) -> Option<SourceLoc> {
+        // what --addr2line prints, the function named in the current style
+        let dwarf = self.debug_info()?;
+        let mut loc = dwarf.find_location(vaddr)?;
+        if self.names != NameStyle::Mangled {
+            loc.function = dwarf.find_function(vaddr).map(|f| self.function_name(f));
+        }
+        Some(loc)
+    }
+
+    pub fn show_debug_info(&self) -> &Self {
+
+        let dwarf = match self.debug_info() {
+            Some(dwarf) => dwarf,
+            None => {
+                println!("no DWARF debug info");
+                return self;
+            }
+        };
+
+        for unit in &dwarf.units {
+            let name = unit.name.as_deref().unwrap_or("??");
+            println!("{} {} {}", format!("CU 0x{:x}", unit.offset).red(), name.blue(), format!("(DWARF {})", unit.version).yellow());
+            if let Some(dir) = &unit.comp_dir {
+                println!("    {:<10} {}", "comp_dir", dir);
+            }
+            if let Some(producer) = &unit.producer {
+                println!("    {:<10} {}", "producer", producer);
+            }
+            if let Some(lang) = unit.language {
+                println!("    {:<10} {}", "language", get_lang_str(lang));
+            }
+            let ranges : Vec<String> = unit.ranges.iter().map(|(l, h)| format!("0x{:x}-0x{:x}", l, h)).collect();
+            if !ranges.is_empty() {
+                println!("    {:<10} {}", "ranges", ranges.join(" "));
+            }
+
+            for func in &unit.functions {
+                let (low, high) = func.ranges[0];
+                let decl = match (&func.decl_file, func.decl_line) {
+                    (Some(file), Some(line)) => format!("{}:{}", file, line),
+                    (Some(file), None)       => file.clone(),
+                    _                        => String::new(),
+                };
+                println!("    {:<36} {:<24} {}", format!("0x{:016x}-0x{:016x}", low, high).green(), self.function_name(func).cyan(), decl);
+            }
+            println!("-----------------------------------------------");
+        }
+        self
+    }
+
+    fn function_name(&self, func : &Function) -> String {
+        // DW_AT_name is unqualified, demangling the linkage name gives the full C++/Rust path
+        match &func.linkage_name {
+            Some(linkage) if self.names != NameStyle::Mangled => self.display_name(linkage),
+            _                                                  => func.name.clone(),
+        }
+    }
+
+    pub fn show_source_for_vaddr(&self, vaddr : u64) -> &Self {
+        // addr2line -f style output
+        match self.source_for_vaddr(vaddr) {
+            Some(loc) => {
+                println!("{}", loc.function.as_deref().unwrap_or("??").cyan());
+                println!("{}:{}", loc.file, loc.line);
+            }
+            None => {
+                println!("??");
+                println!("??:0");
+            }
+        }
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    /*
+    test/testdwarf and test/testdwarf4 are test/testdwarf.c built with -g -O0 (DWARF 5) and -gdwarf-4 -O0
+
+    int add(int a, int b)       // line 1
+    static int square(int x)    // line 6
+    int main()                  // line 11
+     */
+    fn check(path : &str, version : u16) {
+        let parser = Parser::new(path);
+        let dwarf = parser.debug_info().unwrap();
+        assert_eq!(dwarf.units.len(), 1);
+
+        let unit = &dwarf.units[0];
+        assert_eq!(unit.version, version);
+        assert_eq!(unit.name.as_deref(), Some("testdwarf.c"));
+        assert!(unit.producer.as_ref().unwrap().starts_with("GNU C"));
+
+        let names : Vec<&str> = unit.functions.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, vec!["add", "square", "main"]);
+
+        let square = &unit.functions[1];
+        assert_eq!(square.decl_line, Some(6));
+        assert!(square.decl_file.as_ref().unwrap().ends_with("testdwarf.c"));
+
+        // the first instruction of each function belongs to the line of its declaration
+        for (func, line) in unit.functions.iter().zip([2, 7, 12]) {
+            let loc = dwarf.find_location(func.ranges[0].0).unwrap();
+            assert_eq!(loc.function.as_deref(), Some(func.name.as_str()));
+            assert!(loc.file.ends_with("testdwarf.c"));
+            assert_eq!(loc.line, line);
+        }
+    }
+    #[test]
+    fn test_dwarf5() {
+        check("test/testdwarf", 5);
+    }
+    #[test]
+    fn test_dwarf4() {
+        check("test/testdwarf4", 4);
+    }
+    #[test]
+    fn test_lookup_matches_scan() {
+        // the binary searches agree with walking every unit, row and function
+        let parser = Parser::new("test/testdwarf");
+        let dwarf = parser.debug_info().unwrap();
+        assert!(std::ptr::eq(dwarf, parser.debug_info().unwrap()));
+
+        let (start, end) = parser.section_range(".text").unwrap();
+        for addr in start - 0x10..end + 0x10 {
+            let function = dwarf.units.iter()
+                .flat_map(|u| u.functions.iter())
+                .filter(|f| f.ranges.iter().any(|&(l, h)| l <= addr && addr < h))
+                .min_by_key(|f| f.ranges.iter().map(|(l, h)| h - l).sum::<u64>());
+            assert_eq!(dwarf.find_function(addr).map(|f| &f.name), function.map(|f| &f.name), "{:#x}", addr);
+
+            let row = dwarf.units.iter().filter_map(|u| u.lines.as_ref()).find_map(|l| {
+                l.rows.windows(2).find(|w| !w[0].end_sequence && w[0].addr <= addr && addr < w[1].addr).map(|w| (l, &w[0]))
+            });
+            let scanned = row.map(|(l, r)| (l.file_name(r.file).unwrap().to_string(), r.line, r.column));
+            let found = dwarf.find_location(addr).map(|loc| (loc.file, loc.line, loc.column));
+            assert_eq!(found, scanned, "{:#x}", addr);
+        }
+    }
+    #[test]
+    fn test_no_dwarf() {
+        let parser = Parser::new("test/testgot");
+        assert!(parser.debug_info().is_none());
+    }
+}
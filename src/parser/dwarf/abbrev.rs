@@ -0,0 +1,59 @@
+use std::collections::HashMap;
+
+use crate::parser::dwarf::reader::Reader;
+use crate::parser::dwarf::info::DW_FORM_implicit_const;
+
+#[derive(Debug)]
+pub struct AttrSpec {
+    pub at             : u64,
+    pub form           : u64,
+    pub implicit_const : i64,   /* only for DW_FORM_implicit_const */
+}
+
+#[derive(Debug)]
+pub struct Abbrev {
+    pub tag          : u64,
+    pub has_children : bool,
+    pub attrs        : Vec<AttrSpec>,
+}
+
+pub struct Abbrevs {
+    // one abbreviation table of .debug_abbrev, keyed by abbrev code
+    pub table : HashMap<u64, Abbrev>,
+}
+
+impl Abbrevs {
+    pub fn new(debug_abbrev : &[u8], offset : u64) -> Option<Self> {
+
+        let mut r = Reader::new(debug_abbrev, offset as usize);
+        let mut table = HashMap::new();
+
+        loop {
+            let code = r.uleb()?;
+            if code == 0 {
+                break;
+            }
+            let tag = r.uleb()?;
+            let has_children = r.u8()? != 0;
+
+            let mut attrs = vec![];
+            loop {
+                let at = r.uleb()?;
+                let form = r.uleb()?;
+                if at == 0 && form == 0 {
+                    break;
+                }
+                let implicit_const = if form == DW_FORM_implicit_const { r.sleb()? } else { 0 };
+                attrs.push(AttrSpec { at, form, implicit_const });
+            }
+
+            table.insert(code, Abbrev { tag, has_children, attrs });
+        }
+
+        Some(Abbrevs { table })
+    }
+
+    pub fn get(&self, code : u64) -> Option<&Abbrev> {
+        self.table.get(&code)
+    }
+}
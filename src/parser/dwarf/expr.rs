@@ -0,0 +1,120 @@
+use crate::parser::dwarf::reader::Reader;
+use crate::parser::elf::elf_struct::{EM_X86_64, EM_AARCH64, EM_RISCV};
+
+const X86_64_REGS : [&str; 17] = [
+    "rax", "rdx", "rcx", "rbx", "rsi", "rdi", "rbp", "rsp",
+    "r8", "r9", "r10", "r11", "r12", "r13", "r14", "r15", "rip",
+];
+
+const RISCV_REGS : [&str; 32] = [
+    "zero", "ra", "sp", "gp", "tp", "t0", "t1", "t2",
+    "s0", "s1", "a0", "a1", "a2", "a3", "a4", "a5",
+    "a6", "a7", "s2", "s3", "s4", "s5", "s6", "s7",
+    "s8", "s9", "s10", "s11", "t3", "t4", "t5", "t6",
+];
+
+pub fn dwarf_reg_name(machine : u16, reg : u64) -> String {
+    // readelf style "r7 (rsp)", register numbering follows each psABI
+    let name = match machine {
+        EM_X86_64  => X86_64_REGS.get(reg as usize).map(|r| r.to_string()),
+        EM_AARCH64 => match reg {
+            0..=30 => Some(format!("x{}", reg)),
+            31     => Some("sp".to_string()),
+            64..=95 => Some(format!("v{}", reg - 64)),
+            _      => None,
+        },
+        EM_RISCV   => RISCV_REGS.get(reg as usize).map(|r| r.to_string()),
+        _ => None,
+    };
+    match name {
+        Some(name) => format!("r{} ({})", reg, name),
+        None       => format!("r{}", reg),
+    }
+}
+
+pub fn describe(ops : &[u8], machine : u16) -> String {
+    // decode a DWARF expression into "DW_OP_a; DW_OP_b: 1; ..."
+    let mut r = Reader::new(ops, 0);
+    let mut out = vec![];
+
+    while !r.is_empty() {
+        let op = match r.u8() {
+            Some(op) => op,
+            None     => break,
+        };
+        let s = match op {
+            0x03 => r.u64().map(|v| format!("DW_OP_addr: {:x}", v)),
+            0x06 => Some("DW_OP_deref".to_string()),
+            0x08 => r.u8().map(|v| format!("DW_OP_const1u: {}", v)),
+            0x09 => r.i8().map(|v| format!("DW_OP_const1s: {}", v)),
+            0x0a => r.u16().map(|v| format!("DW_OP_const2u: {}", v)),
+            0x0b => r.u16().map(|v| format!("DW_OP_const2s: {}", v as i16)),
+            0x0c => r.u32().map(|v| format!("DW_OP_const4u: {}", v)),
+            0x0d => r.u32().map(|v| format!("DW_OP_const4s: {}", v as i32)),
+            0x0e => r.u64().map(|v| format!("DW_OP_const8u: {}", v)),
+            0x0f => r.u64().map(|v| format!("DW_OP_const8s: {}", v as i64)),
+            0x10 => r.uleb().map(|v| format!("DW_OP_constu: {}", v)),
+            0x11 => r.sleb().map(|v| format!("DW_OP_consts: {}", v)),
+            0x12 => Some("DW_OP_dup".to_string()),
+            0x13 => Some("DW_OP_drop".to_string()),
+            0x14 => Some("DW_OP_over".to_string()),
+            0x15 => r.u8().map(|v| format!("DW_OP_pick: {}", v)),
+            0x16 => Some("DW_OP_swap".to_string()),
+            0x17 => Some("DW_OP_rot".to_string()),
+            0x19 => Some("DW_OP_abs".to_string()),
+            0x1a => Some("DW_OP_and".to_string()),
+            0x1b => Some("DW_OP_div".to_string()),
+            0x1c => Some("DW_OP_minus".to_string()),
+            0x1d => Some("DW_OP_mod".to_string()),
+            0x1e => Some("DW_OP_mul".to_string()),
+            0x1f => Some("DW_OP_neg".to_string()),
+            0x20 => Some("DW_OP_not".to_string()),
+            0x21 => Some("DW_OP_or".to_string()),
+            0x22 => Some("DW_OP_plus".to_string()),
+            0x23 => r.uleb().map(|v| format!("DW_OP_plus_uconst: {}", v)),
+            0x24 => Some("DW_OP_shl".to_string()),
+            0x25 => Some("DW_OP_shr".to_string()),
+            0x26 => Some("DW_OP_shra".to_string()),
+            0x27 => Some("DW_OP_xor".to_string()),
+            0x28 => r.u16().map(|v| format!("DW_OP_bra: {}", v as i16)),
+            0x29 => Some("DW_OP_eq".to_string()),
+            0x2a => Some("DW_OP_ge".to_string()),
+            0x2b => Some("DW_OP_gt".to_string()),
+            0x2c => Some("DW_OP_le".to_string()),
+            0x2d => Some("DW_OP_lt".to_string()),
+            0x2e => Some("DW_OP_ne".to_string()),
+            0x2f => r.u16().map(|v| format!("DW_OP_skip: {}", v as i16)),
+            0x30..=0x4f => Some(format!("DW_OP_lit{}", op - 0x30)),
+            0x50..=0x6f => Some(format!("DW_OP_reg{} ({})", op - 0x50, reg_short(machine, (op - 0x50) as u64))),
+            0x70..=0x8f => r.sleb().map(|v| {
+                let reg = (op - 0x70) as u64;
+                format!("DW_OP_breg{} ({}): {}", reg, reg_short(machine, reg), v)
+            }),
+            0x90 => r.uleb().map(|reg| format!("DW_OP_regx: {}", dwarf_reg_name(machine, reg))),
+            0x92 => r.uleb().and_then(|reg| r.sleb().map(|v| format!("DW_OP_bregx: {}: {}", dwarf_reg_name(machine, reg), v))),
+            0x94 => r.u8().map(|v| format!("DW_OP_deref_size: {}", v)),
+            0x96 => Some("DW_OP_nop".to_string()),
+            0x9c => Some("DW_OP_call_frame_cfa".to_string()),
+            0x9f => Some("DW_OP_stack_value".to_string()),
+            _    => None,
+        };
+        match s {
+            Some(s) => out.push(s),
+            None => {
+                // unknown operand layout, the rest can't be decoded
+                out.push(format!("DW_OP_<0x{:x}> ...", op));
+                break;
+            }
+        }
+    }
+    out.join("; ")
+}
+
+fn reg_short(machine : u16, reg : u64) -> String {
+    // "rsp" out of "r7 (rsp)"
+    let full = dwarf_reg_name(machine, reg);
+    match full.find('(') {
+        Some(i) => full[i + 1..full.len() - 1].to_string(),
+        None    => full,
+    }
+}
@@ -0,0 +1,471 @@
+#![allow(non_upper_case_globals)]
+
+use crate::parser::dwarf::DwarfSections;
+use crate::parser::dwarf::abbrev::Abbrevs;
+use crate::parser::dwarf::reader::{Reader, str_at};
+
+/* unit types (DWARF 5) */
+pub const DW_UT_compile       : u8 = 0x01;
+pub const DW_UT_type          : u8 = 0x02;
+pub const DW_UT_skeleton      : u8 = 0x04;
+pub const DW_UT_split_compile : u8 = 0x05;
+pub const DW_UT_split_type    : u8 = 0x06;
+
+/* tags */
+pub const DW_TAG_compile_unit  : u64 = 0x11;
+pub const DW_TAG_subprogram    : u64 = 0x2e;
+pub const DW_TAG_partial_unit  : u64 = 0x3c;
+pub const DW_TAG_skeleton_unit : u64 = 0x4a;
+
+/* attributes */
+pub const DW_AT_name              : u64 = 0x03;
+pub const DW_AT_stmt_list         : u64 = 0x10;
+pub const DW_AT_low_pc            : u64 = 0x11;
+pub const DW_AT_high_pc           : u64 = 0x12;
+pub const DW_AT_language          : u64 = 0x13;
+pub const DW_AT_comp_dir          : u64 = 0x1b;
+pub const DW_AT_producer          : u64 = 0x25;
+pub const DW_AT_abstract_origin   : u64 = 0x31;
+pub const DW_AT_decl_file         : u64 = 0x3a;
+pub const DW_AT_decl_line         : u64 = 0x3b;
+pub const DW_AT_declaration       : u64 = 0x3c;
+pub const DW_AT_specification     : u64 = 0x47;
+pub const DW_AT_ranges            : u64 = 0x55;
+pub const DW_AT_linkage_name      : u64 = 0x6e;
+pub const DW_AT_str_offsets_base  : u64 = 0x72;
+pub const DW_AT_addr_base         : u64 = 0x73;
+pub const DW_AT_rnglists_base     : u64 = 0x74;
+pub const DW_AT_MIPS_linkage_name : u64 = 0x2007;
+
+/* forms */
+pub const DW_FORM_addr           : u64 = 0x01;
+pub const DW_FORM_block2         : u64 = 0x03;
+pub const DW_FORM_block4         : u64 = 0x04;
+pub const DW_FORM_data2          : u64 = 0x05;
+pub const DW_FORM_data4          : u64 = 0x06;
+pub const DW_FORM_data8          : u64 = 0x07;
+pub const DW_FORM_string         : u64 = 0x08;
+pub const DW_FORM_block          : u64 = 0x09;
+pub const DW_FORM_block1         : u64 = 0x0a;
+pub const DW_FORM_data1          : u64 = 0x0b;
+pub const DW_FORM_flag           : u64 = 0x0c;
+pub const DW_FORM_sdata          : u64 = 0x0d;
+pub const DW_FORM_strp           : u64 = 0x0e;
+pub const DW_FORM_udata          : u64 = 0x0f;
+pub const DW_FORM_ref_addr       : u64 = 0x10;
+pub const DW_FORM_ref1           : u64 = 0x11;
+pub const DW_FORM_ref2           : u64 = 0x12;
+pub const DW_FORM_ref4           : u64 = 0x13;
+pub const DW_FORM_ref8           : u64 = 0x14;
+pub const DW_FORM_ref_udata      : u64 = 0x15;
+pub const DW_FORM_indirect       : u64 = 0x16;
+pub const DW_FORM_sec_offset     : u64 = 0x17;
+pub const DW_FORM_exprloc        : u64 = 0x18;
+pub const DW_FORM_flag_present   : u64 = 0x19;
+pub const DW_FORM_strx           : u64 = 0x1a;
+pub const DW_FORM_addrx          : u64 = 0x1b;
+pub const DW_FORM_ref_sup4       : u64 = 0x1c;
+pub const DW_FORM_strp_sup       : u64 = 0x1d;
+pub const DW_FORM_data16         : u64 = 0x1e;
+pub const DW_FORM_line_strp      : u64 = 0x1f;
+pub const DW_FORM_ref_sig8       : u64 = 0x20;
+pub const DW_FORM_implicit_const : u64 = 0x21;
+pub const DW_FORM_loclistx       : u64 = 0x22;
+pub const DW_FORM_rnglistx       : u64 = 0x23;
+pub const DW_FORM_ref_sup8       : u64 = 0x24;
+pub const DW_FORM_strx1          : u64 = 0x25;
+pub const DW_FORM_strx2          : u64 = 0x26;
+pub const DW_FORM_strx3          : u64 = 0x27;
+pub const DW_FORM_strx4          : u64 = 0x28;
+pub const DW_FORM_addrx1         : u64 = 0x29;
+pub const DW_FORM_addrx2         : u64 = 0x2a;
+pub const DW_FORM_addrx3         : u64 = 0x2b;
+pub const DW_FORM_addrx4         : u64 = 0x2c;
+pub const DW_FORM_GNU_addr_index : u64 = 0x1f01;
+pub const DW_FORM_GNU_str_index  : u64 = 0x1f02;
+pub const DW_FORM_GNU_ref_alt    : u64 = 0x1f20;
+pub const DW_FORM_GNU_strp_alt   : u64 = 0x1f21;
+
+/* range list entries (DWARF 5) */
+const DW_RLE_end_of_list   : u8 = 0x00;
+const DW_RLE_base_addressx : u8 = 0x01;
+const DW_RLE_startx_endx   : u8 = 0x02;
+const DW_RLE_startx_length : u8 = 0x03;
+const DW_RLE_offset_pair   : u8 = 0x04;
+const DW_RLE_base_address  : u8 = 0x05;
+const DW_RLE_start_end     : u8 = 0x06;
+const DW_RLE_start_length  : u8 = 0x07;
+
+#[derive(Debug, Clone)]
+pub enum AttrValue {
+    Addr(u64),
+    AddrIndex(u64),         /* index into .debug_addr, relative to DW_AT_addr_base */
+    Udata(u64),
+    Sdata(i64),
+    Str(String),
+    StrIndex(u64),          /* index into .debug_str_offsets */
+    Ref(u64),               /* absolute offset in .debug_info */
+    SecOffset(u64),
+    RngListIndex(u64),
+    Flag(bool),
+    Block,                  /* expressions and blocks are skipped */
+}
+
+#[derive(Debug)]
+pub struct UnitHeader {
+    pub offset        : u64,    /* offset of the unit in .debug_info */
+    pub version       : u16,
+    pub unit_type     : u8,
+    pub addr_size     : u8,
+    pub offset_size   : usize,
+    pub abbrev_offset : u64,
+    pub end           : usize,
+}
+
+#[derive(Debug)]
+pub struct Die {
+    pub offset : u64,
+    pub tag    : u64,
+    pub attrs  : Vec<(u64, AttrValue)>,
+}
+
+impl Die {
+    pub fn attr(&self, at : u64) -> Option<&AttrValue> {
+        self.attrs.iter().find(|(a, _)| *a == at).map(|(_, v)| v)
+    }
+}
+
+pub struct RawUnit {
+    pub header    : UnitHeader,
+    pub dies      : Vec<Die>,   /* DIEs with interesting tags only, the unit DIE first */
+    str_offsets_base : u64,
+    addr_base        : u64,
+    rnglists_base    : u64,
+}
+
+fn parse_header(r : &mut Reader) -> Option<UnitHeader> {
+
+    let offset = r.pos as u64;
+    let (length, offset_size) = r.initial_length()?;
+    let end = r.pos.checked_add(length as usize)?;
+    let version = r.u16()?;
+
+    let (unit_type, addr_size, abbrev_offset);
+    if version >= 5 {
+        unit_type = r.u8()?;
+        addr_size = r.u8()?;
+        abbrev_offset = r.offset(offset_size)?;
+        match unit_type {
+            DW_UT_skeleton | DW_UT_split_compile => { r.u64()?; }
+            DW_UT_type | DW_UT_split_type        => { r.u64()?; r.offset(offset_size)?; }
+            _ => {}
+        }
+    } else {
+        unit_type = DW_UT_compile;
+        abbrev_offset = r.offset(offset_size)?;
+        addr_size = r.u8()?;
+    }
+
+    Some(UnitHeader {
+        offset,
+        version,
+        unit_type,
+        addr_size,
+        offset_size,
+        abbrev_offset,
+        end,
+    })
+}
+
+fn read_attr(r : &mut Reader, form : u64, implicit_const : i64, unit : &UnitHeader, secs : &DwarfSections) -> Option<AttrValue> {
+
+    let osz = unit.offset_size;
+    let strp = |off : u64, sec : &[u8]| str_at(sec, off).map(AttrValue::Str).unwrap_or(AttrValue::SecOffset(off));
+
+    let v = match form {
+        DW_FORM_addr          => AttrValue::Addr(r.uint(unit.addr_size as usize)?),
+        DW_FORM_data1         => AttrValue::Udata(r.u8()? as u64),
+        DW_FORM_data2         => AttrValue::Udata(r.u16()? as u64),
+        DW_FORM_data4         => AttrValue::Udata(r.u32()? as u64),
+        DW_FORM_data8         => AttrValue::Udata(r.u64()?),
+        DW_FORM_data16        => { r.skip(16)?; AttrValue::Block }
+        DW_FORM_sdata         => AttrValue::Sdata(r.sleb()?),
+        DW_FORM_udata         => AttrValue::Udata(r.uleb()?),
+        DW_FORM_implicit_const=> AttrValue::Sdata(implicit_const),
+        DW_FORM_string        => AttrValue::Str(r.cstr()?),
+        DW_FORM_strp          => strp(r.offset(osz)?, secs.str),
+        DW_FORM_line_strp     => strp(r.offset(osz)?, secs.line_str),
+        DW_FORM_strp_sup | DW_FORM_GNU_strp_alt
+                              => AttrValue::SecOffset(r.offset(osz)?),
+        DW_FORM_strx | DW_FORM_GNU_str_index
+                              => AttrValue::StrIndex(r.uleb()?),
+        DW_FORM_strx1         => AttrValue::StrIndex(r.uint(1)?),
+        DW_FORM_strx2         => AttrValue::StrIndex(r.uint(2)?),
+        DW_FORM_strx3         => AttrValue::StrIndex(r.uint(3)?),
+        DW_FORM_strx4         => AttrValue::StrIndex(r.uint(4)?),
+        DW_FORM_addrx | DW_FORM_GNU_addr_index
+                              => AttrValue::AddrIndex(r.uleb()?),
+        DW_FORM_addrx1        => AttrValue::AddrIndex(r.uint(1)?),
+        DW_FORM_addrx2        => AttrValue::AddrIndex(r.uint(2)?),
+        DW_FORM_addrx3        => AttrValue::AddrIndex(r.uint(3)?),
+        DW_FORM_addrx4        => AttrValue::AddrIndex(r.uint(4)?),
+        DW_FORM_ref1          => AttrValue::Ref(unit.offset + r.uint(1)?),
+        DW_FORM_ref2          => AttrValue::Ref(unit.offset + r.uint(2)?),
+        DW_FORM_ref4          => AttrValue::Ref(unit.offset + r.uint(4)?),
+        DW_FORM_ref8          => AttrValue::Ref(unit.offset + r.uint(8)?),
+        DW_FORM_ref_udata     => AttrValue::Ref(unit.offset + r.uleb()?),
+        DW_FORM_ref_addr      => {
+            // DWARF 2 used address size here
+            let sz = if unit.version <= 2 { unit.addr_size as usize } else { osz };
+            AttrValue::Ref(r.uint(sz)?)
+        }
+        DW_FORM_ref_sup4      => AttrValue::SecOffset(r.uint(4)?),
+        DW_FORM_ref_sup8 | DW_FORM_ref_sig8
+                              => AttrValue::SecOffset(r.u64()?),
+        DW_FORM_GNU_ref_alt   => AttrValue::SecOffset(r.offset(osz)?),
+        DW_FORM_sec_offset    => AttrValue::SecOffset(r.offset(osz)?),
+        DW_FORM_loclistx      => AttrValue::Udata(r.uleb()?),
+        DW_FORM_rnglistx      => AttrValue::RngListIndex(r.uleb()?),
+        DW_FORM_flag          => AttrValue::Flag(r.u8()? != 0),
+        DW_FORM_flag_present  => AttrValue::Flag(true),
+        DW_FORM_block1        => { let n = r.u8()? as usize; r.skip(n)?; AttrValue::Block }
+        DW_FORM_block2        => { let n = r.u16()? as usize; r.skip(n)?; AttrValue::Block }
+        DW_FORM_block4        => { let n = r.u32()? as usize; r.skip(n)?; AttrValue::Block }
+        DW_FORM_block | DW_FORM_exprloc
+                              => { let n = r.uleb()? as usize; r.skip(n)?; AttrValue::Block }
+        DW_FORM_indirect      => {
+            let form = r.uleb()?;
+            return read_attr(r, form, 0, unit, secs);
+        }
+        _ => return None,   /* unknown form, size can't be determined */
+    };
+    Some(v)
+}
+
+fn interesting(tag : u64) -> bool {
+    matches!(tag, DW_TAG_compile_unit | DW_TAG_partial_unit | DW_TAG_skeleton_unit | DW_TAG_subprogram)
+}
+
+pub fn parse_units(secs : &DwarfSections) -> Vec<RawUnit> {
+    // walk all units in .debug_info, a malformed unit stops the walk
+    let mut units = vec![];
+    let mut r = Reader::new(secs.info, 0);
+
+    while !r.is_empty() {
+        let header = match parse_header(&mut r) {
+            Some(h) => h,
+            None    => break,
+        };
+        let end = header.end.min(secs.info.len());
+
+        // type units carry no code
+        let is_type = header.unit_type == DW_UT_type || header.unit_type == DW_UT_split_type;
+        if !is_type {
+            if let Some(unit) = parse_unit(&mut r, header, secs) {
+                units.push(unit);
+            }
+        }
+        r.pos = end;
+    }
+    units
+}
+
+fn parse_unit(r : &mut Reader, header : UnitHeader, secs : &DwarfSections) -> Option<RawUnit> {
+
+    let abbrevs = Abbrevs::new(secs.abbrev, header.abbrev_offset)?;
+    let mut dies = vec![];
+    let mut depth = 0;
+
+    while r.pos < header.end {
+        let offset = r.pos as u64;
+        let code = r.uleb()?;
+        if code == 0 {
+            if depth == 0 {
+                break;
+            }
+            depth -= 1;
+            continue;
+        }
+        let abbrev = abbrevs.get(code)?;
+
+        let keep = interesting(abbrev.tag);
+        let mut attrs = vec![];
+        for spec in &abbrev.attrs {
+            let v = read_attr(r, spec.form, spec.implicit_const, &header, secs)?;
+            if keep {
+                attrs.push((spec.at, v));
+            }
+        }
+        if keep {
+            dies.push(Die { offset, tag : abbrev.tag, attrs });
+        }
+        if abbrev.has_children {
+            depth += 1;
+        }
+    }
+
+    let base = |at| match dies.first().and_then(|d : &Die| d.attr(at)) {
+        Some(AttrValue::SecOffset(v)) | Some(AttrValue::Udata(v)) => *v,
+        _ => 0,
+    };
+    // str_offsets_base defaults to just past the .debug_str_offsets header
+    let str_offsets_base = match base(DW_AT_str_offsets_base) {
+        0 if header.version >= 5 => 8,
+        v => v,
+    };
+    let addr_base = base(DW_AT_addr_base);
+    let rnglists_base = base(DW_AT_rnglists_base);
+
+    Some(RawUnit {
+        header,
+        dies,
+        str_offsets_base,
+        addr_base,
+        rnglists_base,
+    })
+}
+
+impl RawUnit {
+    pub fn string(&self, v : &AttrValue, secs : &DwarfSections) -> Option<String> {
+        // resolve a string class attribute
+        match v {
+            AttrValue::Str(s)        => Some(s.clone()),
+            AttrValue::StrIndex(idx) => {
+                let osz = self.header.offset_size;
+                let pos = self.str_offsets_base + idx * osz as u64;
+                let off = Reader::new(secs.str_offsets, pos as usize).offset(osz)?;
+                str_at(secs.str, off)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn address(&self, v : &AttrValue, secs : &DwarfSections) -> Option<u64> {
+        // resolve an address class attribute
+        match v {
+            AttrValue::Addr(a)        => Some(*a),
+            AttrValue::AddrIndex(idx) => self.addr_index(*idx, secs),
+            _ => None,
+        }
+    }
+
+    fn addr_index(&self, idx : u64, secs : &DwarfSections) -> Option<u64> {
+        let sz = self.header.addr_size as usize;
+        let pos = self.addr_base + idx * sz as u64;
+        Reader::new(secs.addr, pos as usize).uint(sz)
+    }
+
+    pub fn udata(&self, v : &AttrValue) -> Option<u64> {
+        match v {
+            AttrValue::Udata(v) | AttrValue::SecOffset(v) => Some(*v),
+            AttrValue::Sdata(v) => Some(*v as u64),
+            _ => None,
+        }
+    }
+
+    pub fn ranges(&self, die : &Die, secs : &DwarfSections) -> Vec<(u64, u64)> {
+        // [low, high) pairs from DW_AT_low_pc/DW_AT_high_pc or DW_AT_ranges
+        let low = die.attr(DW_AT_low_pc).and_then(|v| self.address(v, secs));
+
+        if let (Some(low), Some(high)) = (low, die.attr(DW_AT_high_pc)) {
+            let high = match high {
+                // high_pc of constant class is an offset from low_pc
+                AttrValue::Addr(_) | AttrValue::AddrIndex(_) => self.address(high, secs),
+                v => self.udata(v).map(|len| low + len),
+            };
+            return match high {
+                Some(high) if high > low => vec![(low, high)],
+                _ => vec![],
+            };
+        }
+
+        // base address for range lists is the unit's low_pc
+        let base = self.dies.first()
+            .and_then(|d| d.attr(DW_AT_low_pc))
+            .and_then(|v| self.address(v, secs))
+            .unwrap_or(0);
+
+        let ranges = match die.attr(DW_AT_ranges) {
+            Some(AttrValue::RngListIndex(idx)) => {
+                let osz = self.header.offset_size;
+                let pos = self.rnglists_base + idx * osz as u64;
+                Reader::new(secs.rnglists, pos as usize)
+                    .offset(osz)
+                    .and_then(|off| self.rnglist(self.rnglists_base + off, base, secs))
+            }
+            Some(AttrValue::SecOffset(off)) | Some(AttrValue::Udata(off)) => {
+                if self.header.version >= 5 {
+                    self.rnglist(*off, base, secs)
+                } else {
+                    self.debug_ranges(*off, base, secs)
+                }
+            }
+            _ => None,
+        };
+        ranges.unwrap_or_default()
+            .into_iter()
+            .filter(|(l, h)| h > l)
+            .collect()
+    }
+
+    fn debug_ranges(&self, offset : u64, mut base : u64, secs : &DwarfSections) -> Option<Vec<(u64, u64)>> {
+        // DWARF 2-4 .debug_ranges
+        let sz = self.header.addr_size as usize;
+        let max = if sz == 8 { u64::MAX } else { u32::MAX as u64 };
+        let mut r = Reader::new(secs.ranges, offset as usize);
+        let mut out = vec![];
+
+        loop {
+            let start = r.uint(sz)?;
+            let end = r.uint(sz)?;
+            if start == 0 && end == 0 {
+                break;
+            }
+            if start == max {
+                base = end;
+                continue;
+            }
+            out.push((base + start, base + end));
+        }
+        Some(out)
+    }
+
+    fn rnglist(&self, offset : u64, mut base : u64, secs : &DwarfSections) -> Option<Vec<(u64, u64)>> {
+        // DWARF 5 .debug_rnglists
+        let sz = self.header.addr_size as usize;
+        let mut r = Reader::new(secs.rnglists, offset as usize);
+        let mut out = vec![];
+
+        loop {
+            match r.u8()? {
+                DW_RLE_end_of_list   => break,
+                DW_RLE_base_addressx => base = self.addr_index(r.uleb()?, secs)?,
+                DW_RLE_startx_endx   => {
+                    let start = self.addr_index(r.uleb()?, secs)?;
+                    let end = self.addr_index(r.uleb()?, secs)?;
+                    out.push((start, end));
+                }
+                DW_RLE_startx_length => {
+                    let start = self.addr_index(r.uleb()?, secs)?;
+                    out.push((start, start + r.uleb()?));
+                }
+                DW_RLE_offset_pair   => {
+                    let start = r.uleb()?;
+                    let end = r.uleb()?;
+                    out.push((base + start, base + end));
+                }
+                DW_RLE_base_address  => base = r.uint(sz)?,
+                DW_RLE_start_end     => {
+                    let start = r.uint(sz)?;
+                    out.push((start, r.uint(sz)?));
+                }
+                DW_RLE_start_length  => {
+                    let start = r.uint(sz)?;
+                    out.push((start, start + r.uleb()?));
+                }
+                _ => return None,
+            }
+        }
+        Some(out)
+    }
+}
@@ -0,0 +1,280 @@
+#![allow(non_upper_case_globals)]
+
+use crate::parser::dwarf::DwarfSections;
+use crate::parser::dwarf::info::{DW_FORM_string, DW_FORM_line_strp, DW_FORM_strp, DW_FORM_udata};
+use crate::parser::dwarf::info::{DW_FORM_data1, DW_FORM_data2, DW_FORM_data4, DW_FORM_data8, DW_FORM_data16};
+use crate::parser::dwarf::info::{DW_FORM_block, DW_FORM_strx, DW_FORM_strx1, DW_FORM_strx2, DW_FORM_strx3, DW_FORM_strx4};
+use crate::parser::dwarf::reader::{Reader, str_at};
+
+/* standard opcodes */
+const DW_LNS_copy               : u8 = 0x01;
+const DW_LNS_advance_pc         : u8 = 0x02;
+const DW_LNS_advance_line       : u8 = 0x03;
+const DW_LNS_set_file           : u8 = 0x04;
+const DW_LNS_set_column         : u8 = 0x05;
+const DW_LNS_negate_stmt        : u8 = 0x06;
+const DW_LNS_set_basic_block    : u8 = 0x07;
+const DW_LNS_const_add_pc       : u8 = 0x08;
+const DW_LNS_fixed_advance_pc   : u8 = 0x09;
+const DW_LNS_set_prologue_end   : u8 = 0x0a;
+const DW_LNS_set_epilogue_begin : u8 = 0x0b;
+const DW_LNS_set_isa            : u8 = 0x0c;
+
+/* extended opcodes */
+const DW_LNE_end_sequence      : u8 = 0x01;
+const DW_LNE_set_address       : u8 = 0x02;
+const DW_LNE_define_file       : u8 = 0x03;
+const DW_LNE_set_discriminator : u8 = 0x04;
+
+/* line number header entry formats (DWARF 5) */
+const DW_LNCT_path            : u64 = 0x1;
+const DW_LNCT_directory_index : u64 = 0x2;
+
+#[derive(Debug, Clone)]
+pub struct LineRow {
+    pub addr         : u64,
+    pub file         : u64,
+    pub line         : u64,
+    pub column       : u64,
+    pub end_sequence : bool,
+}
+
+#[derive(Debug)]
+pub struct LineProgram {
+    pub files : Vec<String>,    /* file table, joined with its directory */
+    pub rows  : Vec<LineRow>,
+}
+
+struct Header {
+    min_insn_len    : u8,
+    line_base       : i8,
+    line_range      : u8,
+    opcode_base     : u8,
+    std_opcode_lens : Vec<u8>,
+    program_end     : usize,
+}
+
+impl LineProgram {
+    // comp_dir and cu_name come from the unit DIE, they are entry 0 of the tables before DWARF 5
+    pub fn new(secs : &DwarfSections, offset : u64, comp_dir : &str, cu_name : &str) -> Option<Self> {
+
+        let mut r = Reader::new(secs.line, offset as usize);
+        let (length, offset_size) = r.initial_length()?;
+        let unit_end = r.pos.checked_add(length as usize)?;
+        let version = r.u16()?;
+
+        if version >= 5 {
+            r.u8()?;    /* address_size */
+            r.u8()?;    /* segment_selector_size */
+        }
+        let header_length = r.offset(offset_size)?;
+        let program_start = r.pos + header_length as usize;
+
+        let min_insn_len = r.u8()?;
+        if version >= 4 {
+            r.u8()?;    /* maximum_operations_per_instruction, VLIW only */
+        }
+        r.u8()?;        /* default_is_stmt */
+        let line_base = r.i8()?;
+        let line_range = r.u8()?;
+        let opcode_base = r.u8()?;
+        let mut std_opcode_lens = vec![];
+        for _ in 1..opcode_base {
+            std_opcode_lens.push(r.u8()?);
+        }
+
+        let files = if version >= 5 {
+            Self::files_v5(&mut r, secs, offset_size)?
+        } else {
+            Self::files_v4(&mut r, comp_dir, cu_name)?
+        };
+
+        let header = Header {
+            min_insn_len,
+            line_base,
+            line_range,
+            opcode_base,
+            std_opcode_lens,
+            program_end : unit_end.min(secs.line.len()),
+        };
+
+        let mut program = LineProgram { files, rows : vec![] };
+        r.pos = program_start;
+        program.run(&mut r, &header)?;
+
+        Some(program)
+    }
+
+    fn files_v4(r : &mut Reader, comp_dir : &str, cu_name : &str) -> Option<Vec<String>> {
+        // include_directories and file_names are sequences ended by an empty string
+        let mut dirs = vec![comp_dir.to_string()];
+        loop {
+            let dir = r.cstr()?;
+            if dir.is_empty() {
+                break;
+            }
+            dirs.push(dir);
+        }
+
+        let mut files = vec![join_path(comp_dir, cu_name)];
+        loop {
+            let name = r.cstr()?;
+            if name.is_empty() {
+                break;
+            }
+            let dir = r.uleb()? as usize;
+            r.uleb()?;  /* mtime */
+            r.uleb()?;  /* length */
+            let dir = dirs.get(dir).map(|d| d.as_str()).unwrap_or("");
+            files.push(join_path(dir, &name));
+        }
+        Some(files)
+    }
+
+    fn files_v5(r : &mut Reader, secs : &DwarfSections, offset_size : usize) -> Option<Vec<String>> {
+        // directories and files are described by (content type, form) pairs
+        let dirs = Self::entries_v5(r, secs, offset_size)?
+            .into_iter()
+            .map(|(path, _)| path)
+            .collect::<Vec<String>>();
+
+        let files = Self::entries_v5(r, secs, offset_size)?
+            .into_iter()
+            .map(|(path, dir)| {
+                let dir = dirs.get(dir as usize).map(|d| d.as_str()).unwrap_or("");
+                join_path(dir, &path)
+            })
+            .collect();
+        Some(files)
+    }
+
+    fn entries_v5(r : &mut Reader, secs : &DwarfSections, offset_size : usize) -> Option<Vec<(String, u64)>> {
+
+        let format_count = r.u8()?;
+        let mut formats = vec![];
+        for _ in 0..format_count {
+            formats.push((r.uleb()?, r.uleb()?));
+        }
+
+        let count = r.uleb()?;
+        let mut entries = vec![];
+        for _ in 0..count {
+            let mut path = String::new();
+            let mut dir = 0;
+            for &(content, form) in &formats {
+                let mut num = 0;
+                let mut s = None;
+                match form {
+                    DW_FORM_string    => s = Some(r.cstr()?),
+                    DW_FORM_line_strp => s = str_at(secs.line_str, r.offset(offset_size)?),
+                    DW_FORM_strp      => s = str_at(secs.str, r.offset(offset_size)?),
+                    DW_FORM_udata     => num = r.uleb()?,
+                    DW_FORM_data1     => num = r.uint(1)?,
+                    DW_FORM_data2     => num = r.uint(2)?,
+                    DW_FORM_data4     => num = r.uint(4)?,
+                    DW_FORM_data8     => num = r.uint(8)?,
+                    DW_FORM_data16    => r.skip(16)?,
+                    DW_FORM_block     => { let n = r.uleb()? as usize; r.skip(n)?; }
+                    /* string offsets need the unit's str_offsets_base, not known here */
+                    DW_FORM_strx      => { r.uleb()?; }
+                    DW_FORM_strx1     => r.skip(1)?,
+                    DW_FORM_strx2     => r.skip(2)?,
+                    DW_FORM_strx3     => r.skip(3)?,
+                    DW_FORM_strx4     => r.skip(4)?,
+                    _ => return None,
+                }
+                match content {
+                    DW_LNCT_path            => path = s.unwrap_or_default(),
+                    DW_LNCT_directory_index => dir = num,
+                    _ => {}
+                }
+            }
+            entries.push((path, dir));
+        }
+        Some(entries)
+    }
+
+    fn run(&mut self, r : &mut Reader, h : &Header) -> Option<()> {
+        // execute the line number program, recording a row on every copy/special opcode
+        let reset = || LineRow {
+            addr         : 0,
+            file         : 1,
+            line         : 1,
+            column       : 0,
+            end_sequence : false,
+        };
+        let mut state = reset();
+        let min_len = h.min_insn_len as u64;
+
+        while r.pos < h.program_end {
+            let op = r.u8()?;
+
+            if op >= h.opcode_base {
+                // special opcode: advance address and line at once, then append a row
+                let adj = (op - h.opcode_base) as u64;
+                state.addr += (adj / h.line_range as u64) * min_len;
+                state.line = (state.line as i64 + h.line_base as i64 + (adj % h.line_range as u64) as i64) as u64;
+                self.rows.push(state.clone());
+                continue;
+            }
+
+            match op {
+                0 => {
+                    let len = r.uleb()? as usize;
+                    let next = r.pos + len;
+                    match r.u8()? {
+                        DW_LNE_end_sequence => {
+                            state.end_sequence = true;
+                            self.rows.push(state.clone());
+                            state = reset();
+                        }
+                        DW_LNE_set_address => {
+                            state.addr = r.uint(len - 1)?;
+                        }
+                        DW_LNE_define_file => {
+                            let name = r.cstr()?;
+                            self.files.push(name);
+                        }
+                        DW_LNE_set_discriminator => {}
+                        _ => {}
+                    }
+                    r.pos = next;
+                }
+                DW_LNS_copy               => self.rows.push(state.clone()),
+                DW_LNS_advance_pc         => state.addr += r.uleb()? * min_len,
+                DW_LNS_advance_line       => state.line = (state.line as i64 + r.sleb()?) as u64,
+                DW_LNS_set_file           => state.file = r.uleb()?,
+                DW_LNS_set_column         => state.column = r.uleb()?,
+                DW_LNS_negate_stmt | DW_LNS_set_basic_block => {}
+                DW_LNS_const_add_pc       => {
+                    let adj = (255 - h.opcode_base) as u64;
+                    state.addr += (adj / h.line_range as u64) * min_len;
+                }
+                DW_LNS_fixed_advance_pc   => state.addr += r.u16()? as u64,
+                DW_LNS_set_prologue_end | DW_LNS_set_epilogue_begin => {}
+                DW_LNS_set_isa            => { r.uleb()?; }
+                _ => {
+                    // unknown standard opcode, skip its uleb operands
+                    for _ in 0..h.std_opcode_lens[op as usize - 1] {
+                        r.uleb()?;
+                    }
+                }
+            }
+        }
+
+        Some(())
+    }
+
+    pub fn file_name(&self, idx : u64) -> Option<&str> {
+        // file indexes are 0-based since DWARF 5, and 1-based before (0 is the unit itself)
+        self.files.get(idx as usize).map(|f| f.as_str())
+    }
+}
+
+fn join_path(dir : &str, name : &str) -> String {
+    if name.starts_with('/') || dir.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}/{}", dir.trim_end_matches('/'), name)
+    }
+}
@@ -0,0 +1,145 @@
+// little-endian cursor over a debug section, every read returns None on truncation
+
+#[derive(Clone)]
+pub struct Reader<'a> {
+    pub buf : &'a [u8],
+    pub pos : usize,
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(buf : &'a [u8], pos : usize) -> Self {
+        Reader { buf, pos }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pos >= self.buf.len()
+    }
+
+    pub fn bytes(&mut self, n : usize) -> Option<&'a [u8]> {
+        let end = self.pos.checked_add(n)?;
+        let s = self.buf.get(self.pos..end)?;
+        self.pos = end;
+        Some(s)
+    }
+
+    pub fn skip(&mut self, n : usize) -> Option<()> {
+        self.bytes(n).map(|_| ())
+    }
+
+    pub fn u8(&mut self) -> Option<u8> {
+        self.bytes(1).map(|b| b[0])
+    }
+
+    pub fn i8(&mut self) -> Option<i8> {
+        self.u8().map(|b| b as i8)
+    }
+
+    pub fn u16(&mut self) -> Option<u16> {
+        self.bytes(2).map(|b| u16::from_le_bytes(b.try_into().unwrap()))
+    }
+
+    pub fn u32(&mut self) -> Option<u32> {
+        self.bytes(4).map(|b| u32::from_le_bytes(b.try_into().unwrap()))
+    }
+
+    pub fn u64(&mut self) -> Option<u64> {
+        self.bytes(8).map(|b| u64::from_le_bytes(b.try_into().unwrap()))
+    }
+
+    pub fn uint(&mut self, size : usize) -> Option<u64> {
+        // unsigned integer of 1/2/3/4/8 bytes
+        match size {
+            1 => self.u8().map(u64::from),
+            2 => self.u16().map(u64::from),
+            3 => self.bytes(3).map(|b| b[0] as u64 | (b[1] as u64) << 8 | (b[2] as u64) << 16),
+            4 => self.u32().map(u64::from),
+            8 => self.u64(),
+            _ => None,
+        }
+    }
+
+    pub fn uleb(&mut self) -> Option<u64> {
+        let mut result = 0u64;
+        let mut shift = 0;
+        loop {
+            let b = self.u8()?;
+            if shift < 64 {
+                result |= ((b & 0x7f) as u64) << shift;
+            }
+            shift += 7;
+            if b & 0x80 == 0 {
+                return Some(result);
+            }
+        }
+    }
+
+    pub fn sleb(&mut self) -> Option<i64> {
+        let mut result = 0i64;
+        let mut shift = 0;
+        loop {
+            let b = self.u8()?;
+            if shift < 64 {
+                result |= ((b & 0x7f) as i64) << shift;
+            }
+            shift += 7;
+            if b & 0x80 == 0 {
+                if shift < 64 && b & 0x40 != 0 {
+                    result |= -1i64 << shift;
+                }
+                return Some(result);
+            }
+        }
+    }
+
+    pub fn cstr(&mut self) -> Option<String> {
+        // Null-terminated string
+        let rest = self.buf.get(self.pos..)?;
+        let len = rest.iter().position(|&c| c == 0)?;
+        self.pos += len + 1;
+        Some(String::from_utf8_lossy(&rest[..len]).into_owned())
+    }
+
+    pub fn initial_length(&mut self) -> Option<(u64, usize)> {
+        // unit length and offset size (4 for 32-bit dwarf, 8 for 64-bit dwarf)
+        let len = self.u32()?;
+        if len == 0xffffffff {
+            Some((self.u64()?, 8))
+        } else {
+            Some((len as u64, 4))
+        }
+    }
+
+    pub fn offset(&mut self, offset_size : usize) -> Option<u64> {
+        self.uint(offset_size)
+    }
+}
+
+pub fn str_at(buf : &[u8], offset : u64) -> Option<String> {
+    // Null-terminated string at offset of a string section
+    Reader::new(buf, offset as usize).cstr()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn test_leb128() {
+        // examples from DWARF 5 spec, 7.6
+        let buf = [0x02, 0x7f, 0x80, 0x01, 0xe5, 0x8e, 0x26];
+        let mut r = Reader::new(&buf, 0);
+        assert_eq!(r.uleb(), Some(2));
+        assert_eq!(r.uleb(), Some(127));
+        assert_eq!(r.uleb(), Some(128));
+        assert_eq!(r.uleb(), Some(624485));
+        assert!(r.is_empty());
+
+        let buf = [0x02, 0x7e, 0xff, 0x00, 0x81, 0x7f, 0x80, 0x7f];
+        let mut r = Reader::new(&buf, 0);
+        assert_eq!(r.sleb(), Some(2));
+        assert_eq!(r.sleb(), Some(-2));
+        assert_eq!(r.sleb(), Some(127));
+        assert_eq!(r.sleb(), Some(-127));
+        assert_eq!(r.sleb(), Some(-128));
+        assert_eq!(r.sleb(), None);
+    }
+}
@@ -0,0 +1,619 @@
+#![allow(non_upper_case_globals)]
+
+use std::collections::{HashMap, HashSet};
+use colored::Colorize;
+
+use crate::parser::Parser;
+use crate::parser::dwarf::reader::Reader;
+use crate::parser::dwarf::expr::{describe, dwarf_reg_name};
+use crate::parser::elf::segments::segtype::SegmentType;
+
+/*
+.eh_frame is a sequence of records, each one is a CIE or an FDE
+
++--------+------------------+-------------------------------------------+
+| length | CIE id (0)       | version, augmentation, alignment, program | : CIE
++--------+------------------+-------------------------------------------+
+| length | CIE pointer (!0) | pc_begin, pc_range, augmentation, program | : FDE
++--------+------------------+-------------------------------------------+
+| 0      |                                                                : terminator
+
+.eh_frame_hdr holds a table of (initial_loc, fde_address) sorted by initial_loc
+for binary search at unwind time (PT_GNU_EH_FRAME)
+ */
+
+/* pointer encodings */
+pub const DW_EH_PE_absptr   : u8 = 0x00;
+pub const DW_EH_PE_uleb128  : u8 = 0x01;
+pub const DW_EH_PE_udata2   : u8 = 0x02;
+pub const DW_EH_PE_udata4   : u8 = 0x03;
+pub const DW_EH_PE_udata8   : u8 = 0x04;
+pub const DW_EH_PE_sleb128  : u8 = 0x09;
+pub const DW_EH_PE_sdata2   : u8 = 0x0a;
+pub const DW_EH_PE_sdata4   : u8 = 0x0b;
+pub const DW_EH_PE_sdata8   : u8 = 0x0c;
+pub const DW_EH_PE_pcrel    : u8 = 0x10;
+pub const DW_EH_PE_datarel  : u8 = 0x30;
+pub const DW_EH_PE_indirect : u8 = 0x80;
+pub const DW_EH_PE_omit     : u8 = 0xff;
+
+#[derive(Debug, Clone)]
+pub enum CfaInsn {
+    AdvanceLoc(u64),                /* already multiplied by code_align */
+    SetLoc(u64),
+    Offset(u64, i64),               /* reg saved at cfa+off */
+    ValOffset(u64, i64),
+    Restore(u64),
+    Undefined(u64),
+    SameValue(u64),
+    Register(u64, u64),
+    RememberState,
+    RestoreState,
+    DefCfa(u64, i64),
+    DefCfaRegister(u64),
+    DefCfaOffset(i64),
+    DefCfaExpression(Vec<u8>),
+    Expression(u64, Vec<u8>),
+    ValExpression(u64, Vec<u8>),
+    GnuArgsSize(u64),
+    Nop,
+    Unknown(u8),
+}
+
+#[derive(Debug)]
+pub struct Cie {
+    pub offset        : usize,  /* offset in .eh_frame */
+    pub version       : u8,
+    pub augmentation  : String,
+    pub code_align    : u64,
+    pub data_align    : i64,
+    pub ra_reg        : u64,
+    pub aug_data      : Vec<u8>,
+    pub fde_encoding  : u8,
+    pub lsda_encoding : u8,
+    pub personality   : Option<u64>,
+    pub signal_frame  : bool,
+    pub instructions  : Vec<CfaInsn>,
+}
+
+#[derive(Debug)]
+pub struct Fde {
+    pub offset       : usize,   /* offset in .eh_frame */
+    pub cie_offset   : usize,
+    pub pc_begin     : u64,
+    pub pc_range     : u64,
+    pub aug_data     : Vec<u8>,
+    pub lsda         : Option<u64>,
+    pub instructions : Vec<CfaInsn>,
+}
+
+pub struct EhFrame {
+    pub vaddr : u64,
+    pub cies  : Vec<Cie>,
+    pub fdes  : Vec<Fde>,
+}
+
+pub struct EhFrameHdr {
+    pub vaddr        : u64,
+    pub version      : u8,
+    pub eh_frame_ptr : u64,
+    pub fde_count    : u64,
+    pub table        : Vec<(u64, u64)>,     /* (initial_loc, fde vaddr) */
+}
+
+fn read_encoded(r : &mut Reader, enc : u8, vaddr : u64, datarel : u64) -> Option<u64> {
+    // read a pointer with DW_EH_PE_* encoding, vaddr is the address of the buffer start
+    if enc == DW_EH_PE_omit {
+        return None;
+    }
+    let pos = vaddr.wrapping_add(r.pos as u64);
+
+    let v = match enc & 0x0f {
+        DW_EH_PE_absptr  => r.u64()?,
+        DW_EH_PE_uleb128 => r.uleb()?,
+        DW_EH_PE_udata2  => r.u16()? as u64,
+        DW_EH_PE_udata4  => r.u32()? as u64,
+        DW_EH_PE_udata8  => r.u64()?,
+        DW_EH_PE_sleb128 => r.sleb()? as u64,
+        DW_EH_PE_sdata2  => r.u16()? as i16 as u64,
+        DW_EH_PE_sdata4  => r.u32()? as i32 as u64,
+        DW_EH_PE_sdata8  => r.u64()?,
+        _ => return None,
+    };
+
+    let base = match enc & 0x70 {
+        DW_EH_PE_pcrel   => pos,
+        DW_EH_PE_datarel => datarel,
+        _                => 0,
+    };
+    Some(base.wrapping_add(v))
+}
+
+fn parse_insns(buf : &[u8], cie : &Cie, vaddr : u64) -> Vec<CfaInsn> {
+    // decode a CFA program, stop at the first malformed instruction or factored operand that overflows
+    let mut r = Reader::new(buf, 0);
+    let mut insns = vec![];
+    let advance = |delta : u64| delta.checked_mul(cie.code_align);
+    let factored = |off : i64| off.checked_mul(cie.data_align);
+
+    while !r.is_empty() {
+        let insn = (|| {
+            let op = r.u8()?;
+            let low = (op & 0x3f) as u64;
+            let insn = match op >> 6 {
+                1 => CfaInsn::AdvanceLoc(advance(low)?),
+                2 => CfaInsn::Offset(low, factored(r.uleb()? as i64)?),
+                3 => CfaInsn::Restore(low),
+                _ => match op {
+                    0x00 => CfaInsn::Nop,
+                    0x01 => CfaInsn::SetLoc(read_encoded(&mut r, cie.fde_encoding, vaddr, 0)?),
+                    0x02 => CfaInsn::AdvanceLoc(advance(r.u8()? as u64)?),
+                    0x03 => CfaInsn::AdvanceLoc(advance(r.u16()? as u64)?),
+                    0x04 => CfaInsn::AdvanceLoc(advance(r.u32()? as u64)?),
+                    0x05 => CfaInsn::Offset(r.uleb()?, factored(r.uleb()? as i64)?),
+                    0x06 => CfaInsn::Restore(r.uleb()?),
+                    0x07 => CfaInsn::Undefined(r.uleb()?),
+                    0x08 => CfaInsn::SameValue(r.uleb()?),
+                    0x09 => CfaInsn::Register(r.uleb()?, r.uleb()?),
+                    0x0a => CfaInsn::RememberState,
+                    0x0b => CfaInsn::RestoreState,
+                    0x0c => CfaInsn::DefCfa(r.uleb()?, r.uleb()? as i64),
+                    0x0d => CfaInsn::DefCfaRegister(r.uleb()?),
+                    0x0e => CfaInsn::DefCfaOffset(r.uleb()? as i64),
+                    0x0f => {
+                        let n = r.uleb()? as usize;
+                        CfaInsn::DefCfaExpression(r.bytes(n)?.to_vec())
+                    }
+                    0x10 => {
+                        let reg = r.uleb()?;
+                        let n = r.uleb()? as usize;
+                        CfaInsn::Expression(reg, r.bytes(n)?.to_vec())
+                    }
+                    0x11 => CfaInsn::Offset(r.uleb()?, factored(r.sleb()?)?),
+                    0x12 => CfaInsn::DefCfa(r.uleb()?, factored(r.sleb()?)?),
+                    0x13 => CfaInsn::DefCfaOffset(factored(r.sleb()?)?),
+                    0x14 => CfaInsn::ValOffset(r.uleb()?, factored(r.uleb()? as i64)?),
+                    0x15 => CfaInsn::ValOffset(r.uleb()?, factored(r.sleb()?)?),
+                    0x16 => {
+                        let reg = r.uleb()?;
+                        let n = r.uleb()? as usize;
+                        CfaInsn::ValExpression(reg, r.bytes(n)?.to_vec())
+                    }
+                    0x2e => CfaInsn::GnuArgsSize(r.uleb()?),
+                    0x2f => CfaInsn::Offset(r.uleb()?, factored((r.uleb()? as i64).checked_neg()?)?),
+                    op   => CfaInsn::Unknown(op),
+                },
+            };
+            Some(insn)
+        })();
+
+        match insn {
+            Some(CfaInsn::Unknown(op)) => {
+                insns.push(CfaInsn::Unknown(op));
+                break;
+            }
+            Some(insn) => insns.push(insn),
+            None       => break,
+        }
+    }
+    insns
+}
+
+impl CfaInsn {
+    pub fn describe(&self, loc : &mut u64, machine : u16) -> String {
+        // readelf --debug-dump=frames style, advance_* updates loc
+        let reg = |r : &u64| dwarf_reg_name(machine, *r);
+        match self {
+            CfaInsn::AdvanceLoc(d)          => { *loc = loc.wrapping_add(*d); format!("DW_CFA_advance_loc: {} to {:016x}", d, loc) }
+            CfaInsn::SetLoc(a)              => { *loc = *a; format!("DW_CFA_set_loc: {:016x}", a) }
+            CfaInsn::Offset(r, off)         => format!("DW_CFA_offset: {} at cfa{:+}", reg(r), off),
+            CfaInsn::ValOffset(r, off)      => format!("DW_CFA_val_offset: {} is cfa{:+}", reg(r), off),
+            CfaInsn::Restore(r)             => format!("DW_CFA_restore: {}", reg(r)),
+            CfaInsn::Undefined(r)           => format!("DW_CFA_undefined: {}", reg(r)),
+            CfaInsn::SameValue(r)           => format!("DW_CFA_same_value: {}", reg(r)),
+            CfaInsn::Register(r1, r2)       => format!("DW_CFA_register: {} in {}", reg(r1), reg(r2)),
+            CfaInsn::RememberState          => "DW_CFA_remember_state".to_string(),
+            CfaInsn::RestoreState           => "DW_CFA_restore_state".to_string(),
+            CfaInsn::DefCfa(r, off)         => format!("DW_CFA_def_cfa: {} ofs {}", reg(r), off),
+            CfaInsn::DefCfaRegister(r)      => format!("DW_CFA_def_cfa_register: {}", reg(r)),
+            CfaInsn::DefCfaOffset(off)      => format!("DW_CFA_def_cfa_offset: {}", off),
+            CfaInsn::DefCfaExpression(e)    => format!("DW_CFA_def_cfa_expression ({})", describe(e, machine)),
+            CfaInsn::Expression(r, e)       => format!("DW_CFA_expression: {} ({})", reg(r), describe(e, machine)),
+            CfaInsn::ValExpression(r, e)    => format!("DW_CFA_val_expression: {} ({})", reg(r), describe(e, machine)),
+            CfaInsn::GnuArgsSize(n)         => format!("DW_CFA_GNU_args_size: {}", n),
+            CfaInsn::Nop                    => "DW_CFA_nop".to_string(),
+            CfaInsn::Unknown(op)            => format!("DW_CFA_<0x{:x}> (stop decoding)", op),
+        }
+    }
+}
+
+impl EhFrame {
+    // vaddr is the virtual address of buf[0], needed by pc-relative pointers
+    pub fn new(buf : &[u8], vaddr : u64) -> Self {
+
+        let mut cies : Vec<Cie> = vec![];
+        let mut fdes = vec![];
+        let mut cie_index = HashMap::new();
+        let mut r = Reader::new(buf, 0);
+
+        while !r.is_empty() {
+            let offset = r.pos;
+            let (length, offset_size) = match r.initial_length() {
+                Some((0, _)) | None => break,   /* terminator */
+                Some(l) => l,
+            };
+            let end = match r.pos.checked_add(length as usize) {
+                Some(end) if end <= buf.len() => end,
+                _ => break,
+            };
+            let id_pos = r.pos;
+            let id = match r.offset(offset_size) {
+                Some(id) => id,
+                None     => break,
+            };
+
+            if id == 0 {
+                if let Some(cie) = Self::parse_cie(&mut r, offset, end, vaddr) {
+                    cie_index.insert(offset, cies.len());
+                    cies.push(cie);
+                }
+            } else {
+                // CIE pointer is relative to the position of the id field itself
+                let cie_offset = id_pos.wrapping_sub(id as usize);
+                if let Some(cie) = cie_index.get(&cie_offset).map(|&i| &cies[i]) {
+                    if let Some(fde) = Self::parse_fde(&mut r, cie, offset, cie_offset, end, vaddr) {
+                        fdes.push(fde);
+                    }
+                }
+            }
+            r.pos = end;
+        }
+
+        EhFrame { vaddr, cies, fdes }
+    }
+
+    fn parse_cie(r : &mut Reader, offset : usize, end : usize, vaddr : u64) -> Option<Cie> {
+
+        let version = r.u8()?;
+        let augmentation = r.cstr()?;
+        if augmentation.contains("eh") {
+            r.u64()?;
+        }
+        let code_align = r.uleb()?;
+        let data_align = r.sleb()?;
+        let ra_reg = if version == 1 { r.u8()? as u64 } else { r.uleb()? };
+
+        let mut cie = Cie {
+            offset,
+            version,
+            augmentation : augmentation.clone(),
+            code_align,
+            data_align,
+            ra_reg,
+            aug_data      : vec![],
+            fde_encoding  : DW_EH_PE_absptr,
+            lsda_encoding : DW_EH_PE_omit,
+            personality   : None,
+            signal_frame  : false,
+            instructions  : vec![],
+        };
+
+        if augmentation.starts_with('z') {
+            let len = r.uleb()? as usize;
+            let aug_start = r.pos;
+            cie.aug_data = r.bytes(len)?.to_vec();
+            r.pos = aug_start;
+
+            for c in augmentation.chars().skip(1) {
+                match c {
+                    'L' => cie.lsda_encoding = r.u8()?,
+                    'R' => cie.fde_encoding = r.u8()?,
+                    'P' => {
+                        let enc = r.u8()?;
+                        cie.personality = read_encoded(r, enc & !DW_EH_PE_indirect, vaddr, 0);
+                    }
+                    'S' => cie.signal_frame = true,
+                    _   => break,
+                }
+            }
+            r.pos = aug_start + len;
+        }
+
+        cie.instructions = parse_insns(r.buf.get(r.pos..end)?, &cie, vaddr.wrapping_add(r.pos as u64));
+        Some(cie)
+    }
+
+    fn parse_fde(r : &mut Reader, cie : &Cie, offset : usize, cie_offset : usize, end : usize, vaddr : u64) -> Option<Fde> {
+
+        let pc_begin = read_encoded(r, cie.fde_encoding, vaddr, 0)?;
+        // pc_range has the same size but is never relative
+        let pc_range = read_encoded(r, cie.fde_encoding & 0x0f, vaddr, 0)?;
+
+        let mut aug_data = vec![];
+        let mut lsda = None;
+        if cie.augmentation.starts_with('z') {
+            let len = r.uleb()? as usize;
+            let aug_start = r.pos;
+            aug_data = r.bytes(len)?.to_vec();
+            if cie.augmentation.contains('L') {
+                r.pos = aug_start;
+                lsda = read_encoded(r, cie.lsda_encoding & !DW_EH_PE_indirect, vaddr, 0).filter(|&v| v != 0);
+            }
+            r.pos = aug_start + len;
+        }
+
+        Some(Fde {
+            offset,
+            cie_offset,
+            pc_begin,
+            pc_range,
+            aug_data,
+            lsda,
+            instructions : parse_insns(r.buf.get(r.pos..end)?, cie, vaddr.wrapping_add(r.pos as u64)),
+        })
+    }
+}
+
+impl EhFrameHdr {
+    pub fn new(buf : &[u8], vaddr : u64) -> Option<Self> {
+
+        let mut r = Reader::new(buf, 0);
+        let version = r.u8()?;
+        let eh_frame_ptr_enc = r.u8()?;
+        let fde_count_enc = r.u8()?;
+        let table_enc = r.u8()?;
+
+        let eh_frame_ptr = read_encoded(&mut r, eh_frame_ptr_enc, vaddr, vaddr)?;
+        let fde_count = read_encoded(&mut r, fde_count_enc, vaddr, vaddr).unwrap_or(0);
+
+        let mut table = vec![];
+        if table_enc != DW_EH_PE_omit {
+            for _ in 0..fde_count {
+                let loc = read_encoded(&mut r, table_enc, vaddr, vaddr)?;
+                let fde = read_encoded(&mut r, table_enc, vaddr, vaddr)?;
+                table.push((loc, fde));
+            }
+        }
+
+        Some(EhFrameHdr {
+            vaddr,
+            version,
+            eh_frame_ptr,
+            fde_count,
+            table,
+        })
+    }
+
+    pub fn validate(&self, eh_frame : &EhFrame) -> Vec<String> {
+        // the search table must be sorted and describe exactly the FDEs of .eh_frame
+        let mut problems = vec![];
+
+        if self.eh_frame_ptr != eh_frame.vaddr {
+            problems.push(format!("eh_frame_ptr 0x{:x} != .eh_frame at 0x{:x}", self.eh_frame_ptr, eh_frame.vaddr));
+        }
+        if self.fde_count != eh_frame.fdes.len() as u64 {
+            problems.push(format!("fde_count {} != {} FDEs in .eh_frame", self.fde_count, eh_frame.fdes.len()));
+        }
+        for (i, w) in self.table.windows(2).enumerate() {
+            if w[0].0 > w[1].0 {
+                problems.push(format!("table not sorted at entry {}: 0x{:x} > 0x{:x}", i + 1, w[0].0, w[1].0));
+            }
+        }
+
+        let by_vaddr : HashMap<u64, &Fde> = eh_frame.fdes.iter()
+            .map(|f| (eh_frame.vaddr.wrapping_add(f.offset as u64), f))
+            .collect();
+
+        for &(loc, fde_vaddr) in &self.table {
+            match by_vaddr.get(&fde_vaddr) {
+                Some(fde) if fde.pc_begin == loc => {}
+                Some(fde) => problems.push(format!(
+                    "entry 0x{:x} points to FDE at 0x{:x} which starts at 0x{:x}", loc, fde_vaddr, fde.pc_begin)),
+                None => problems.push(format!("entry 0x{:x} points to 0x{:x}, not an FDE", loc, fde_vaddr)),
+            }
+        }
+
+        let covered : HashSet<u64> = self.table.iter().map(|&(_, f)| f).collect();
+        for fde in &eh_frame.fdes {
+            let fde_vaddr = eh_frame.vaddr.wrapping_add(fde.offset as u64);
+            if !covered.contains(&fde_vaddr) {
+                problems.push(format!("FDE at 0x{:x} (pc=0x{:x}) missing from table", fde_vaddr, fde.pc_begin));
+            }
+        }
+        problems
+    }
+}
+
+impl Parser {
+    fn vaddr_slice(&self, vaddr : u64) -> Option<&[u8]> {
+        // bytes from vaddr to the end of the file-backed part of its PT_LOAD
+        for seg in self.segments.loadable() {
+            let p = &seg.phdr;
+            if vaddr >= p.p_vaddr && vaddr - p.p_vaddr < p.p_filesz {
+                let start = p.p_offset.checked_add(vaddr - p.p_vaddr)? as usize;
+                let end = p.p_offset.checked_add(p.p_filesz)? as usize;
+                return self.binbuf.buf.get(start..end);
+            }
+        }
+        None
+    }
+
+    pub fn eh_frame_hdr(&self) -> Option<EhFrameHdr> {
+        // from .eh_frame_hdr, or PT_GNU_EH_FRAME when section headers are gone
+        if let Some(sec) = self.sections.get_section(".eh_frame_hdr") {
+            return EhFrameHdr::new(self.section_data(".eh_frame_hdr")?, sec.shdr.sh_addr);
+        }
+        let seg = self.segments.get_segment(SegmentType::PT_GNU_EH_FRAME)?;
+        let start = seg.phdr.p_offset as usize;
+        let buf = self.binbuf.buf.get(start..start.checked_add(seg.phdr.p_filesz as usize)?)?;
+        EhFrameHdr::new(buf, seg.phdr.p_vaddr)
+    }
+
+    pub fn eh_frame(&self) -> Option<EhFrame> {
+        // from .eh_frame, or located through eh_frame_ptr of the header
+        if let Some(sec) = self.sections.get_section(".eh_frame") {
+            return Some(EhFrame::new(self.section_data(".eh_frame")?, sec.shdr.sh_addr));
+        }
+        let hdr = self.eh_frame_hdr()?;
+        Some(EhFrame::new(self.vaddr_slice(hdr.eh_frame_ptr)?, hdr.eh_frame_ptr))
+    }
+
+    pub fn show_eh_frame(&self) -> &Self {
+
+        let eh_frame = match self.eh_frame() {
+            Some(eh_frame) => eh_frame,
+            None => {
+                println!("no .eh_frame");
+                return self;
+            }
+        };
+        let machine = self.ehdr.e_machine;
+        let hex = |d : &[u8]| d.iter().map(|b| format!("{:02x}", b)).collect::<Vec<_>>().join(" ");
+
+        // records in file order, like readelf
+        enum Record<'a> {
+            Cie(&'a Cie),
+            Fde(&'a Fde),
+        }
+        let mut records : Vec<(usize, Record)> = vec![];
+        records.extend(eh_frame.cies.iter().map(|c| (c.offset, Record::Cie(c))));
+        records.extend(eh_frame.fdes.iter().map(|f| (f.offset, Record::Fde(f))));
+        records.sort_by_key(|r| r.0);
+
+        for (_, record) in records {
+            if let Record::Cie(cie) = record {
+                println!("{} {}", format!("{:08x}", cie.offset).yellow(), "CIE".red());
+                println!("  {:<24}{}", "Version:", cie.version);
+                println!("  {:<24}\"{}\"", "Augmentation:", cie.augmentation);
+                println!("  {:<24}{}", "Code alignment factor:", cie.code_align);
+                println!("  {:<24}{}", "Data alignment factor:", cie.data_align);
+                println!("  {:<24}{}", "Return address column:", cie.ra_reg);
+                if !cie.aug_data.is_empty() {
+                    println!("  {:<24}{}", "Augmentation data:", hex(&cie.aug_data));
+                }
+                if let Some(p) = cie.personality {
+                    println!("  {:<24}0x{:x}", "Personality:", p);
+                }
+                let mut loc = 0;
+                for insn in &cie.instructions {
+                    println!("  {}", insn.describe(&mut loc, machine));
+                }
+                println!();
+            }
+            if let Record::Fde(fde) = record {
+                println!(
+                    "{} {} cie={:08x} pc={}",
+                    format!("{:08x}", fde.offset).yellow(),
+                    "FDE".blue(),
+                    fde.cie_offset,
+                    format!("{:016x}..{:016x}", fde.pc_begin, fde.pc_begin.wrapping_add(fde.pc_range)).green(),
+                );
+                if !fde.aug_data.is_empty() {
+                    println!("  {:<24}{}", "Augmentation data:", hex(&fde.aug_data));
+                }
+                if let Some(lsda) = fde.lsda {
+                    println!("  {:<24}0x{:x}", "LSDA:", lsda);
+                }
+                let mut loc = fde.pc_begin;
+                for insn in &fde.instructions {
+                    println!("  {}", insn.describe(&mut loc, machine));
+                }
+                println!();
+            }
+        }
+
+        match self.eh_frame_hdr() {
+            Some(hdr) => {
+                println!(
+                    "{} at 0x{:x}: version {}, eh_frame_ptr 0x{:x}, {} entries",
+                    ".eh_frame_hdr".red(), hdr.vaddr, hdr.version, hdr.eh_frame_ptr, hdr.fde_count
+                );
+                let problems = hdr.validate(&eh_frame);
+                if problems.is_empty() {
+                    println!("  {}", "search table is sorted and covers every FDE".green());
+                }
+                for p in problems {
+                    println!("  {}", p.red());
+                }
+            }
+            None => println!("no .eh_frame_hdr"),
+        }
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::elf::elf_struct::EM_X86_64;
+    #[test]
+    fn test_eh_frame_testgot() {
+        /*
+        readelf --debug-dump=frames test/testgot
+        00000000 0000000000000014 00000000 CIE "zR" cf=1 df=-8 ra=16
+        00000018 0000000000000010 00000030 FDE cie=00000000 pc=0000000000401050..000000000040107f
+        ... 7 FDEs in total
+         */
+        let parser = Parser::new("test/testgot");
+        let eh_frame = parser.eh_frame().unwrap();
+
+        assert_eq!(eh_frame.cies.len(), 1);
+        let cie = &eh_frame.cies[0];
+        assert_eq!(cie.augmentation, "zR");
+        assert_eq!(cie.data_align, -8);
+        assert_eq!(cie.ra_reg, 16);
+
+        assert_eq!(eh_frame.fdes.len(), 7);
+        let fde = &eh_frame.fdes[0];
+        assert_eq!((fde.pc_begin, fde.pc_range), (0x401050, 0x2f));
+
+        let mut loc = fde.pc_begin;
+        let insns : Vec<String> = fde.instructions.iter().map(|i| i.describe(&mut loc, parser.ehdr.e_machine)).collect();
+        assert_eq!(insns, vec![
+            "DW_CFA_advance_loc: 4 to 0000000000401054",
+            "DW_CFA_undefined: r16 (rip)",
+        ]);
+    }
+    #[test]
+    fn test_eh_frame_hdr_validate() {
+        let parser = Parser::new("/bin/ls");
+        let eh_frame = parser.eh_frame().unwrap();
+        let hdr = parser.eh_frame_hdr().unwrap();
+        assert_eq!(hdr.fde_count as usize, eh_frame.fdes.len());
+        assert!(hdr.validate(&eh_frame).is_empty());
+    }
+    #[test]
+    fn test_eh_frame_hdr_unsorted() {
+        let parser = Parser::new("test/testgot");
+        let eh_frame = parser.eh_frame().unwrap();
+        let mut hdr = parser.eh_frame_hdr().unwrap();
+        hdr.table.swap(0, 1);
+        assert!(hdr.validate(&eh_frame).iter().any(|p| p.starts_with("table not sorted")));
+    }
+    #[test]
+    fn test_eh_frame_huge_length() {
+        // a 64-bit initial length of u64::MAX, the end of the entry would overflow
+        let mut buf = vec![0xff; 12];
+        buf.extend([0; 8]);
+        let eh_frame = EhFrame::new(&buf, 0x1000);
+        assert!(eh_frame.cies.is_empty() && eh_frame.fdes.is_empty());
+    }
+
+    #[test]
+    fn test_cfa_program_overflow() {
+        // code_align of u64::MAX: def_cfa decodes, the advance_loc after it overflows and ends the program
+        let mut body = vec![0, 0, 0, 0, 1, 0];
+        body.extend([0xff; 9]);
+        body.extend([0x01, 0x78, 0x10]);
+        body.extend([0x0c, 0x07, 0x08, 0x42, 0x00]);
+        let mut buf = (body.len() as u32).to_le_bytes().to_vec();
+        buf.extend(body);
+        let eh_frame = EhFrame::new(&buf, u64::MAX - 4);
+        assert_eq!(eh_frame.cies.len(), 1);
+        let insns = &eh_frame.cies[0].instructions;
+        assert!(matches!(insns[..], [CfaInsn::DefCfa(7, 8)]), "{:?}", insns);
+
+        let mut loc = u64::MAX;
+        assert!(CfaInsn::AdvanceLoc(2).describe(&mut loc, EM_X86_64).ends_with("to 0000000000000001"));
+    }
+}
@@ -1,6 +1,11 @@
 pub mod elf_struct;
 pub mod segments;
 pub mod sections;
-pub mod dyntable;
 pub mod symtable;
-pub mod relocations;
\ No newline at end of file
+pub mod symindex;
+pub mod relocations;
+pub mod dynamic;
+pub mod notes;
+pub mod rela;
+pub mod group;
+pub mod version;
\ No newline at end of file
@@ -0,0 +1,152 @@
+use crate::binbuf::c_str_at;
+use crate::parser::elf::elf_struct::Elf64Dyn;
+
+/* d_tag */
+pub const DT_NULL         : i64 = 0;            /* Marks end of dynamic section */
+pub const DT_NEEDED       : i64 = 1;            /* Name of needed library */
+pub const DT_PLTRELSZ     : i64 = 2;            /* Size in bytes of PLT relocs */
+pub const DT_PLTGOT       : i64 = 3;            /* Processor defined value */
+pub const DT_HASH         : i64 = 4;            /* Address of symbol hash table */
+pub const DT_STRTAB       : i64 = 5;            /* Address of string table */
+pub const DT_SYMTAB       : i64 = 6;            /* Address of symbol table */
+pub const DT_RELA         : i64 = 7;            /* Address of Rela relocs */
+pub const DT_RELASZ       : i64 = 8;            /* Total size of Rela relocs */
+pub const DT_RELAENT      : i64 = 9;            /* Size of one Rela reloc */
+pub const DT_STRSZ        : i64 = 10;           /* Size of string table */
+pub const DT_SYMENT       : i64 = 11;           /* Size of one symbol table entry */
+pub const DT_INIT         : i64 = 12;           /* Address of init function */
+pub const DT_FINI         : i64 = 13;           /* Address of termination function */
+pub const DT_SONAME       : i64 = 14;           /* Name of shared object */
+pub const DT_RPATH        : i64 = 15;           /* Library search path (deprecated) */
+pub const DT_SYMBOLIC     : i64 = 16;           /* Start symbol search here */
+pub const DT_REL          : i64 = 17;           /* Address of Rel relocs */
+pub const DT_RELSZ        : i64 = 18;           /* Total size of Rel relocs */
+pub const DT_RELENT       : i64 = 19;           /* Size of one Rel reloc */
+pub const DT_PLTREL       : i64 = 20;           /* Type of reloc in PLT */
+pub const DT_DEBUG        : i64 = 21;           /* For debugging; unspecified */
+pub const DT_TEXTREL      : i64 = 22;           /* Reloc might modify .text */
+pub const DT_JMPREL       : i64 = 23;           /* Address of PLT relocs */
+pub const DT_BIND_NOW     : i64 = 24;           /* Process relocations of object */
+pub const DT_INIT_ARRAY   : i64 = 25;           /* Array with addresses of init fct */
+pub const DT_FINI_ARRAY   : i64 = 26;           /* Array with addresses of fini fct */
+pub const DT_INIT_ARRAYSZ : i64 = 27;           /* Size in bytes of DT_INIT_ARRAY */
+pub const DT_FINI_ARRAYSZ : i64 = 28;           /* Size in bytes of DT_FINI_ARRAY */
+pub const DT_RUNPATH      : i64 = 29;           /* Library search path */
+pub const DT_FLAGS        : i64 = 30;           /* Flags for the object being loaded */
+pub const DT_GNU_HASH     : i64 = 0x6ffffef5;   /* GNU-style hash table */
+pub const DT_VERSYM       : i64 = 0x6ffffff0;
+pub const DT_RELACOUNT    : i64 = 0x6ffffff9;
+pub const DT_RELCOUNT     : i64 = 0x6ffffffa;
+pub const DT_FLAGS_1      : i64 = 0x6ffffffb;   /* State flags, see DF_1_* below */
+pub const DT_VERDEF       : i64 = 0x6ffffffc;   /* Address of version definition table */
+pub const DT_VERDEFNUM    : i64 = 0x6ffffffd;   /* Number of version definitions */
+pub const DT_VERNEED      : i64 = 0x6ffffffe;   /* Address of table with needed versions */
+pub const DT_VERNEEDNUM   : i64 = 0x6fffffff;   /* Number of needed versions */
+
+/* DT_FLAGS */
+pub const DF_BIND_NOW     : u64 = 0x8;          /* No lazy binding for this object */
+
+/* DT_FLAGS_1 */
+pub const DF_1_NOW        : u64 = 0x1;          /* Set RTLD_NOW for this object */
+pub const DF_1_NODEFLIB   : u64 = 0x800;        /* Ignore default lib search path */
+pub const DF_1_PIE        : u64 = 0x08000000;   /* Object is a position-independent executable */
+
+pub fn get_dyn_tag_str(d_tag : i64) -> &'static str {
+    // get dynamic tag str
+    match d_tag {
+        DT_NULL         => "NULL",
+        DT_NEEDED       => "NEEDED",
+        DT_PLTRELSZ     => "PLTRELSZ",
+        DT_PLTGOT       => "PLTGOT",
+        DT_HASH         => "HASH",
+        DT_STRTAB       => "STRTAB",
+        DT_SYMTAB       => "SYMTAB",
+        DT_RELA         => "RELA",
+        DT_RELASZ       => "RELASZ",
+        DT_RELAENT      => "RELAENT",
+        DT_STRSZ        => "STRSZ",
+        DT_SYMENT       => "SYMENT",
+        DT_INIT         => "INIT",
+        DT_FINI         => "FINI",
+        DT_SONAME       => "SONAME",
+        DT_RPATH        => "RPATH",
+        DT_SYMBOLIC     => "SYMBOLIC",
+        DT_REL          => "REL",
+        DT_RELSZ        => "RELSZ",
+        DT_RELENT       => "RELENT",
+        DT_PLTREL       => "PLTREL",
+        DT_DEBUG        => "DEBUG",
+        DT_TEXTREL      => "TEXTREL",
+        DT_JMPREL       => "JMPREL",
+        DT_BIND_NOW     => "BIND_NOW",
+        DT_INIT_ARRAY   => "INIT_ARRAY",
+        DT_FINI_ARRAY   => "FINI_ARRAY",
+        DT_INIT_ARRAYSZ => "INIT_ARRAYSZ",
+        DT_FINI_ARRAYSZ => "FINI_ARRAYSZ",
+        DT_RUNPATH      => "RUNPATH",
+        DT_FLAGS        => "FLAGS",
+        DT_GNU_HASH     => "GNU_HASH",
+        DT_VERSYM       => "VERSYM",
+        DT_RELACOUNT    => "RELACOUNT",
+        DT_RELCOUNT     => "RELCOUNT",
+        DT_FLAGS_1      => "FLAGS_1",
+        DT_VERDEF       => "VERDEF",
+        DT_VERDEFNUM    => "VERDEFNUM",
+        DT_VERNEED      => "VERNEED",
+        DT_VERNEEDNUM   => "VERNEEDNUM",
+        _               => "UNKNOWN",
+    }
+}
+
+#[derive(Debug)]
+pub struct DynEntry {
+    // wrapper of Elf64Dyn, val_str is filled for tags whose value is a dynstr offset
+    pub entry   : Elf64Dyn,
+    pub name    : String,
+    pub val_str : Option<String>,
+}
+
+pub struct DynEntries {
+    // manage all entries in PT_DYNAMIC
+    pub entries : Vec<DynEntry>,
+}
+
+impl DynEntries {
+    // dynstr is the content of DT_STRTAB, if it can be located
+    pub fn new(dyns : Vec<Elf64Dyn>, dynstr : Option<&[u8]>) -> Self {
+
+        let mut entries = vec![];
+
+        for entry in dyns {
+            let val_str = match entry.d_tag {
+                DT_NEEDED | DT_SONAME | DT_RPATH | DT_RUNPATH => {
+                    dynstr.and_then(|buf| c_str_at(buf, entry.d_val as usize))
+                }
+                _ => None,
+            };
+
+            entries.push(DynEntry {
+                name : get_dyn_tag_str(entry.d_tag).to_string(),
+                entry,
+                val_str,
+            });
+        }
+
+        DynEntries { entries }
+    }
+
+    pub fn get(&self, d_tag : i64) -> Option<&DynEntry> {
+        // get the first entry with given tag
+        self.entries.iter().find(|e| e.entry.d_tag == d_tag)
+    }
+
+    pub fn get_all(&self, d_tag : i64) -> Vec<&DynEntry> {
+        // get all entries with given tag (e.g. DT_NEEDED)
+        self.entries.iter().filter(|e| e.entry.d_tag == d_tag).collect()
+    }
+
+    pub fn has_flag(&self, d_tag : i64, flag : u64) -> bool {
+        // test a bit of DT_FLAGS / DT_FLAGS_1
+        self.get(d_tag).is_some_and(|e| e.entry.d_val & flag != 0)
+    }
+}
@@ -26,18 +26,18 @@ type Elf64Section = u16;
 /* Elf Header */
 #[repr(C)]   /* for forbid rearrange */
 pub struct Elf64Ehdr {
-        e_ident     : [u8; EI_NIDENT], /* Magic number and other info */
-        e_type      : Elf64Half,       /* Object file type */
-        e_machine   : Elf64Half,       /* Architecture */
-        e_version   : Elf64Word,       /* Object file version */
+    pub e_ident     : [u8; EI_NIDENT], /* Magic number and other info */
+    pub e_type      : Elf64Half,       /* Object file type */
+    pub e_machine   : Elf64Half,       /* Architecture */
+    pub e_version   : Elf64Word,       /* Object file version */
     pub e_entry     : Elf64Addr,       /* Entry point virtual address */
     pub e_phoff     : Elf64Off,        /* Program header table file offset */
     pub e_shoff     : Elf64Off,        /* Section header table file offset */
-        e_flags     : Elf64Word,       /* Processor-specific flags */
-        e_ehsize    : Elf64Half,       /* ELF header size in bytes */
-        e_phentsize : Elf64Half,       /* Program header table entry size */
+    pub e_flags     : Elf64Word,       /* Processor-specific flags */
+    pub e_ehsize    : Elf64Half,       /* ELF header size in bytes */
+    pub e_phentsize : Elf64Half,       /* Program header table entry size */
     pub e_phnum     : Elf64Half,       /* Program header table entry count */
-        e_shentsize : Elf64Half,       /* Section header table entry size */
+    pub e_shentsize : Elf64Half,       /* Section header table entry size */
     pub e_shnum     : Elf64Half,       /* Section header table entry count */
     pub e_shstrndx  : Elf64Half,       /* Section header string table index */
 }
@@ -100,8 +100,53 @@ impl Elf64Ehdr {
         // dbg!(&header);
         header
     }
+
+    fn shdr0(&self, buf : &[u8]) -> Option<Elf64Shdr> {
+        // section header 0 carries the counts that overflow the 16-bit fields
+        let off = self.e_shoff as usize;
+        if off == 0 || off + mem::size_of::<Elf64Shdr>() > buf.len() {
+            return None;
+        }
+        Some(Elf64Shdr::new(&buf[off..]))
+    }
+
+    pub fn phnum(&self, buf : &[u8]) -> usize {
+        match self.e_phnum {
+            PN_XNUM => self.shdr0(buf).map(|s| s.sh_info as usize).unwrap_or(PN_XNUM as usize),
+            n       => n as usize,
+        }
+    }
+
+    pub fn shnum(&self, buf : &[u8]) -> usize {
+        match self.e_shnum {
+            0 => self.shdr0(buf).map(|s| s.sh_size as usize).unwrap_or(0),
+            n => n as usize,
+        }
+    }
+
+    pub fn shstrndx(&self, buf : &[u8]) -> usize {
+        match self.e_shstrndx {
+            SHN_XINDEX => self.shdr0(buf).map(|s| s.sh_link as usize).unwrap_or(0),
+            n          => n as usize,
+        }
+    }
 }
 
+pub const PN_XNUM : Elf64Half = 0xffff;     /* e_phnum overflowed, real count in sh_info of section 0 */
+
+/* e_type */
+pub const ET_NONE : Elf64Half = 0;      /* No file type */
+pub const ET_REL  : Elf64Half = 1;      /* Relocatable file */
+pub const ET_EXEC : Elf64Half = 2;      /* Executable file */
+pub const ET_DYN  : Elf64Half = 3;      /* Shared object file */
+pub const ET_CORE : Elf64Half = 4;      /* Core file */
+
+/* e_machine */
+pub const EM_386     : Elf64Half = 3;       /* Intel 80386 */
+pub const EM_X86_64  : Elf64Half = 62;      /* AMD x86-64 architecture */
+pub const EM_AARCH64 : Elf64Half = 183;     /* ARM AARCH64 */
+pub const EM_RISCV   : Elf64Half = 243;     /* RISC-V */
+
 #[repr(C)]
 pub struct EIdent {
     ei_mag0 : u8,
@@ -123,15 +168,20 @@ pub struct EIdent {
 pub struct Elf64Phdr
 {
     pub p_type   : Elf64Word,			/* Segment type */
-        p_flags  : Elf64Word,			/* Segment flags */
+    pub p_flags  : Elf64Word,			/* Segment flags */
     pub p_offset : Elf64Off,		    /* Segment file offset */
     pub p_vaddr  : Elf64Addr,		    /* Segment virtual address */
   	pub p_paddr  : Elf64Addr,		    /* Segment physical address */
     pub p_filesz : Elf64Xword,		    /* Segment size in file */
     pub p_memsz  : Elf64Xword,		    /* Segment size in memory */
-  	    p_align  : Elf64Xword,		    /* Segment alignment */
+  	pub p_align  : Elf64Xword,		    /* Segment alignment */
 }
 
+/* p_flags */
+pub const PF_X : Elf64Word = 1 << 0;    /* Segment is executable */
+pub const PF_W : Elf64Word = 1 << 1;    /* Segment is writable */
+pub const PF_R : Elf64Word = 1 << 2;    /* Segment is readable */
+
 impl Elf64Phdr {
     pub fn new(buf : &[u8]) -> Self {
 
@@ -168,19 +218,38 @@ impl Elf64Phdr {
 pub struct Elf64Shdr
 {
     pub sh_name      : Elf64Word,		/* Section name (string tbl index) */
-        sh_type      : Elf64Word,		/* Section type */
-        sh_flags     : Elf64Xword,		/* Section flags */
+    pub sh_type      : Elf64Word,		/* Section type */
+    pub sh_flags     : Elf64Xword,		/* Section flags */
     pub sh_addr      : Elf64Addr,		/* Section virtual addr at execution */
     pub sh_offset    : Elf64Off,		/* Section file offset */
     pub sh_size      : Elf64Xword,		/* Section size in bytes */
-        sh_link      : Elf64Word,		/* Link to another section */
-        sh_info      : Elf64Word,		/* Additional section information */
-        sh_addralign : Elf64Xword,		/* Section alignment */
-        sh_entsize   : Elf64Xword,		/* Entry size if section holds table */
+    pub sh_link      : Elf64Word,		/* Link to another section */
+    pub sh_info      : Elf64Word,		/* Additional section information */
+    pub sh_addralign : Elf64Xword,		/* Section alignment */
+    pub sh_entsize   : Elf64Xword,		/* Entry size if section holds table */
 }
-// TEMP:
-const SHT_SYMTAB : Elf64Word = 3;
-const SHT_DYNSYM : Elf64Word = 11;
+pub const SHT_SYMTAB     : Elf64Word = 2;    /* Symbol table */
+pub const SHT_STRTAB     : Elf64Word = 3;    /* String table */
+pub const SHT_RELA       : Elf64Word = 4;    /* Relocation entries with addends */
+pub const SHT_HASH       : Elf64Word = 5;    /* Symbol hash table */
+pub const SHT_DYNAMIC    : Elf64Word = 6;    /* Dynamic linking information */
+pub const SHT_NOBITS     : Elf64Word = 8;    /* Program space with no data (bss) */
+pub const SHT_REL        : Elf64Word = 9;    /* Relocation entries, no addends */
+pub const SHT_DYNSYM     : Elf64Word = 11;   /* Dynamic linker symbol table */
+pub const SHT_INIT_ARRAY : Elf64Word = 14;   /* Array of constructors */
+pub const SHT_FINI_ARRAY : Elf64Word = 15;   /* Array of destructors */
+pub const SHT_GROUP      : Elf64Word = 17;   /* Section group */
+pub const SHT_SYMTAB_SHNDX : Elf64Word = 18; /* Extended section indices */
+pub const SHT_GNU_HASH   : Elf64Word = 0x6ffffff6;   /* GNU-style hash table */
+
+pub const SHF_WRITE     : Elf64Xword = 1 << 0;   /* Writable */
+pub const SHF_ALLOC     : Elf64Xword = 1 << 1;   /* Occupies memory during execution */
+pub const SHF_EXECINSTR : Elf64Xword = 1 << 2;   /* Executable */
+pub const SHF_INFO_LINK : Elf64Xword = 1 << 6;   /* sh_info contains SHT index */
+pub const SHF_GROUP     : Elf64Xword = 1 << 9;   /* Section is member of a group */
+pub const SHF_TLS       : Elf64Xword = 1 << 10;  /* Section holds thread-local data */
+
+pub const GRP_COMDAT : Elf64Word = 1;       /* Mark group as COMDAT */
 #[deprecated]
 pub fn find_symtab_in_shdrs(shdrs : &Vec<Elf64Shdr>) -> Elf64Shdr {
     for shdr in shdrs {
@@ -240,11 +309,11 @@ impl fmt::Debug for Elf64Shdr {
 }
 
 #[repr(C)]
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub struct Elf64Sym
 {
     pub st_name  : Elf64Word,		/* Symbol name (string tbl index) */
-        st_info  : u8,      		/* Symbol type and binding */
+    pub st_info  : u8,      		/* Symbol type and binding */
         st_other : u8,		        /* Symbol visibility */
     pub st_shndx : Elf64Section,	/* Section index */
     pub st_value : Elf64Addr,		/* Symbol value */
@@ -274,7 +343,54 @@ impl Elf64Sym {
 
         header
     }
+
+    pub fn with_info(st_info : u8, st_shndx : Elf64Section, st_value : Elf64Addr, st_size : Elf64Xword) -> Self {
+        // symbol made up by us (e.g. recovered function), not read from file
+        Elf64Sym {
+            st_name  : 0,
+            st_info,
+            st_other : 0,
+            st_shndx,
+            st_value,
+            st_size,
+        }
+    }
+
+    pub fn st_bind(&self) -> u8 {
+        self.st_info >> 4
+    }
+
+    pub fn st_type(&self) -> u8 {
+        self.st_info & 0xf
+    }
+
+    pub fn st_visibility(&self) -> u8 {
+        self.st_other & 0x3
+    }
 }
+
+pub const STB_LOCAL  : u8 = 0;      /* Local symbol */
+pub const STB_GLOBAL : u8 = 1;      /* Global symbol */
+pub const STB_WEAK   : u8 = 2;      /* Weak symbol */
+pub const STB_GNU_UNIQUE : u8 = 10; /* Unique symbol */
+
+pub const STV_DEFAULT   : u8 = 0;   /* Default symbol visibility rules */
+pub const STV_PROTECTED : u8 = 3;   /* Not preemptible, not exported */
+
+pub const STT_NOTYPE  : u8 = 0;     /* Symbol type is unspecified */
+pub const STT_OBJECT  : u8 = 1;     /* Symbol is a data object */
+pub const STT_FUNC    : u8 = 2;     /* Symbol is a code object */
+pub const STT_SECTION : u8 = 3;     /* Symbol associated with a section */
+pub const STT_FILE    : u8 = 4;     /* Symbol's name is file name */
+pub const STT_TLS     : u8 = 6;     /* Symbol is thread-local data object */
+pub const STT_GNU_IFUNC : u8 = 10;  /* Symbol is indirect code object */
+
+pub const SHN_UNDEF : Elf64Section = 0;     /* Undefined section */
+pub const SHN_LORESERVE : Elf64Section = 0xff00;    /* Start of reserved indices */
+pub const SHN_ABS   : Elf64Section = 0xfff1;    /* Associated symbol is absolute */
+pub const SHN_COMMON : Elf64Section = 0xfff2;   /* Associated symbol is common */
+pub const SHN_XINDEX : Elf64Section = 0xffff;   /* Index is in extra table */
+
 impl fmt::Display for Elf64Sym {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
 
@@ -290,3 +406,71 @@ impl fmt::Display for Elf64Sym {
     }
 }
 
+
+/* Dynamic section entry */
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct Elf64Dyn
+{
+    pub d_tag : i64,                /* Dynamic entry type */
+    pub d_val : Elf64Xword,         /* Integer or address value */
+}
+
+impl Elf64Dyn {
+    pub fn new(buf : &[u8]) -> Self {
+
+        let mut header = Elf64Dyn {
+            d_tag : 0,
+            d_val : 0,
+        };
+
+        let sz = mem::size_of::<Elf64Dyn>();
+        assert!(buf.len() >= sz);
+
+        unsafe {
+            // &mut header as *mut _ make header as rawptr
+            ptr::copy_nonoverlapping(buf.as_ptr(), &mut header as *mut _ as *mut u8, sz);
+        }
+
+        header
+    }
+}
+
+/* Relocation table entry with addend (in section of type SHT_RELA) */
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct Elf64Rela
+{
+    pub r_offset : Elf64Addr,       /* Address */
+    pub r_info   : Elf64Xword,      /* Relocation type and symbol index */
+    pub r_addend : i64,             /* Addend */
+}
+
+impl Elf64Rela {
+    pub fn new(buf : &[u8]) -> Self {
+
+        let mut header = Elf64Rela {
+            r_offset : 0,
+            r_info   : 0,
+            r_addend : 0,
+        };
+
+        let sz = mem::size_of::<Elf64Rela>();
+        assert!(buf.len() >= sz);
+
+        unsafe {
+            // &mut header as *mut _ make header as rawptr
+            ptr::copy_nonoverlapping(buf.as_ptr(), &mut header as *mut _ as *mut u8, sz);
+        }
+
+        header
+    }
+
+    pub fn r_sym(&self) -> u32 {
+        (self.r_info >> 32) as u32
+    }
+
+    pub fn r_type(&self) -> u32 {
+        (self.r_info & 0xffffffff) as u32
+    }
+}
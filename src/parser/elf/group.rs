@@ -0,0 +1,36 @@
+/*
++--------------------------+
+|  flags (GRP_COMDAT)      | : u32
++--------------------------+
+|  member section index    | : u32 x n
++--------------------------+
+sh_link of the SHT_GROUP section is the symbol table, sh_info the signature symbol
+*/
+use crate::parser::elf::elf_struct::GRP_COMDAT;
+
+#[derive(Debug)]
+pub struct Group {
+    pub index     : usize,      /* of the SHT_GROUP section */
+    pub signature : String,
+    pub flags     : u32,
+    pub members   : Vec<u32>,
+}
+
+impl Group {
+    // buf is the content of the SHT_GROUP section
+    pub fn new(index : usize, signature : String, buf : &[u8]) -> Self {
+        let mut words = buf.chunks_exact(4).map(|w| u32::from_le_bytes(w.try_into().unwrap()));
+        let flags = words.next().unwrap_or(0);
+
+        Group {
+            index,
+            signature,
+            flags,
+            members : words.collect(),
+        }
+    }
+
+    pub fn is_comdat(&self) -> bool {
+        self.flags & GRP_COMDAT != 0
+    }
+}
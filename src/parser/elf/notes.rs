@@ -0,0 +1,240 @@
+use crate::binbuf::{u16_at, u32_at, u64_at, c_str};
+
+/*
++--------------------------+
+|  namesz | descsz | type  | : 3 x u32
++--------------------------+
+|  name (padded to 4)      |
++--------------------------+
+|  desc (padded to 4)      |
++--------------------------+
+*/
+
+/* n_type for "GNU" notes */
+pub const NT_GNU_PROPERTY_TYPE_0 : u32 = 5;
+
+/* n_type for "CORE" notes of a core dump */
+pub const NT_PRSTATUS : u32 = 1;            /* one per thread, registers and pending signal */
+pub const NT_PRPSINFO : u32 = 3;            /* process name and arguments */
+pub const NT_AUXV     : u32 = 6;            /* auxiliary vector */
+pub const NT_SIGINFO  : u32 = 0x53494749;   /* siginfo_t of the fatal signal */
+pub const NT_FILE     : u32 = 0x46494c45;   /* file-backed mappings */
+
+/* a_type of auxv entries */
+pub const AT_NULL   : u64 = 0;
+pub const AT_PHDR   : u64 = 3;
+pub const AT_PAGESZ : u64 = 6;
+pub const AT_BASE   : u64 = 7;
+pub const AT_ENTRY  : u64 = 9;
+pub const AT_EXECFN : u64 = 31;     /* pointer to the executable path on the stack */
+
+/* 64-bit elf_prstatus: siginfo, cursig, sigpend/hold, pids, 4 timevals, then pr_reg */
+const PRSTATUS_REG_OFFSET : usize = 112;
+
+/* pr_type in NT_GNU_PROPERTY_TYPE_0 */
+pub const GNU_PROPERTY_X86_FEATURE_1_AND   : u32 = 0xc0000002;
+pub const GNU_PROPERTY_X86_FEATURE_1_IBT   : u32 = 1 << 0;
+pub const GNU_PROPERTY_X86_FEATURE_1_SHSTK : u32 = 1 << 1;
+
+#[derive(Debug)]
+pub struct Note {
+    pub n_type : u32,
+    pub name   : String,
+    pub desc   : Vec<u8>,
+}
+
+pub struct Notes {
+    pub notes : Vec<Note>,
+}
+
+impl Notes {
+    // buf is the content of a PT_NOTE segment or SHT_NOTE section
+    pub fn new(buf : &[u8]) -> Self {
+
+        let mut notes = vec![];
+        let mut idx = 0;
+
+        while let (Some(namesz), Some(descsz), Some(n_type)) = (u32_at(buf, idx), u32_at(buf, idx + 4), u32_at(buf, idx + 8)) {
+
+            let (namesz, descsz) = (namesz as usize, descsz as usize);
+
+            let name_start = idx + 12;
+            let desc_start = name_start + align4(namesz);
+            let next       = desc_start + align4(descsz);
+
+            if desc_start + descsz > buf.len() {
+                break;
+            }
+
+            let name = c_str(&buf[name_start..name_start + namesz]);
+
+            notes.push(Note {
+                n_type,
+                name,
+                desc : buf[desc_start..desc_start + descsz].to_vec(),
+            });
+
+            idx = next;
+        }
+
+        Notes { notes }
+    }
+
+    pub fn find(&self, name : &str, n_type : u32) -> Option<&Note> {
+        self.notes.iter().find(|n| n.name == name && n.n_type == n_type)
+    }
+}
+
+pub struct PrStatus {
+    pub cursig : u16,
+    pub pid    : i32,
+    pub regs   : Vec<u64>,    /* elf_gregset_t, layout depends on e_machine */
+}
+
+pub struct PrPsInfo {
+    pub state  : u8,
+    pub uid    : u32,
+    pub gid    : u32,
+    pub pid    : i32,
+    pub ppid   : i32,
+    pub fname  : String,
+    pub psargs : String,    /* command line, truncated to 80 bytes by the kernel */
+}
+
+pub struct SigInfo {
+    pub signo : i32,
+    pub errno : i32,
+    pub code  : i32,
+    pub addr  : u64,        /* faulting address, meaningful for SIGSEGV/SIGBUS/SIGILL/SIGFPE */
+}
+
+pub struct FileMapping {
+    pub start  : u64,
+    pub end    : u64,
+    pub offset : u64,       /* in bytes, NT_FILE stores pages */
+    pub path   : String,
+}
+
+impl Note {
+    pub fn prstatus(&self) -> Option<PrStatus> {
+        let d = &self.desc;
+        if d.len() < PRSTATUS_REG_OFFSET + 8 {
+            return None;
+        }
+        // pr_reg is followed by the 4-byte pr_fpvalid and padding
+        let nregs = (d.len() - PRSTATUS_REG_OFFSET - 8) / 8;
+        Some(PrStatus {
+            cursig : u16_at(d, 12)?,
+            pid    : u32_at(d, 32)? as i32,
+            regs   : (0..nregs).map(|i| u64_at(d, PRSTATUS_REG_OFFSET + i * 8)).collect::<Option<_>>()?,
+        })
+    }
+
+    pub fn prpsinfo(&self) -> Option<PrPsInfo> {
+        let d = &self.desc;
+        if d.len() < 136 {
+            return None;
+        }
+        Some(PrPsInfo {
+            state  : d[0],
+            uid    : u32_at(d, 16)?,
+            gid    : u32_at(d, 20)?,
+            pid    : u32_at(d, 24)? as i32,
+            ppid   : u32_at(d, 28)? as i32,
+            fname  : c_str(&d[40..56]),
+            psargs : c_str(&d[56..136]).trim_end().to_string(),
+        })
+    }
+
+    pub fn siginfo(&self) -> Option<SigInfo> {
+        let d = &self.desc;
+        if d.len() < 24 {
+            return None;
+        }
+        Some(SigInfo {
+            signo : u32_at(d, 0)? as i32,
+            errno : u32_at(d, 4)? as i32,
+            code  : u32_at(d, 8)? as i32,
+            addr  : u64_at(d, 16)?,
+        })
+    }
+
+    pub fn auxv(&self) -> Vec<(u64, u64)> {
+        self.desc.chunks_exact(16)
+            .filter_map(|e| Some((u64_at(e, 0)?, u64_at(e, 8)?)))
+            .take_while(|&(a_type, _)| a_type != AT_NULL)
+            .collect()
+    }
+
+    pub fn file_mappings(&self) -> Vec<FileMapping> {
+        // count, page size, count x (start, end, page offset), then count NUL-terminated paths
+        let d = &self.desc;
+        let (count, page_size) = match (u64_at(d, 0), u64_at(d, 8)) {
+            (Some(count), Some(page_size)) => (count as usize, page_size),
+            _ => return vec![],
+        };
+        let names = match count.checked_mul(24).and_then(|n| n.checked_add(16)) {
+            Some(names) if names <= d.len() => names,
+            _ => return vec![],
+        };
+
+        d[names..].split(|&c| c == 0)
+            .take(count)
+            .enumerate()
+            // a page offset that overflows in bytes is not a real mapping, leave it out
+            .filter_map(|(i, path)| Some(FileMapping {
+                start  : u64_at(d, 16 + i * 24)?,
+                end    : u64_at(d, 24 + i * 24)?,
+                offset : u64_at(d, 32 + i * 24)?.checked_mul(page_size)?,
+                path   : String::from_utf8_lossy(path).to_string(),
+            }))
+            .collect()
+    }
+
+    pub fn gnu_properties(&self) -> Vec<(u32, Vec<u8>)> {
+        // split NT_GNU_PROPERTY_TYPE_0 desc into (pr_type, pr_data), entries are 8-aligned on 64-bit
+        let mut props = vec![];
+        let mut idx = 0;
+
+        while let (Some(pr_type), Some(pr_datasz)) = (u32_at(&self.desc, idx), u32_at(&self.desc, idx + 4)) {
+            let pr_datasz = pr_datasz as usize;
+            let start = idx + 8;
+
+            if start + pr_datasz > self.desc.len() {
+                break;
+            }
+            props.push((pr_type, self.desc[start..start + pr_datasz].to_vec()));
+
+            idx = start + ((pr_datasz + 7) & !7);
+        }
+        props
+    }
+}
+
+fn align4(n : usize) -> usize {
+    (n + 3) & !3
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn test_file_mappings_overflow() {
+        // a page offset that does not fit in bytes drops its mapping, the others stay
+        let mut desc = vec![];
+        for v in [2, 0x1000, 0x400000, 0x401000, 1, 0x500000, 0x501000, u64::MAX / 0x1000 + 1] {
+            desc.extend(u64::to_le_bytes(v));
+        }
+        desc.extend(b"/bin/a\0/bin/b\0");
+        let note = Note { n_type : NT_FILE, name : "CORE".to_string(), desc };
+
+        let maps = note.file_mappings();
+        assert_eq!(maps.len(), 1);
+        assert_eq!((maps[0].start, maps[0].offset, maps[0].path.as_str()), (0x400000, 0x1000, "/bin/a"));
+
+        // a count whose entries would run past the desc is not read at all
+        let mut huge = note.desc.clone();
+        huge[..8].copy_from_slice(&u64::MAX.to_le_bytes());
+        assert!(Note { desc : huge, ..note }.file_mappings().is_empty());
+    }
+}
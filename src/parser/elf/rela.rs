@@ -0,0 +1,418 @@
+use crate::parser::elf::elf_struct::Elf64Rela;
+use crate::parser::elf::elf_struct::{EM_X86_64, EM_AARCH64, EM_RISCV};
+
+/* x86-64 relocation types */
+pub const R_X86_64_NONE      : u32 = 0;
+pub const R_X86_64_64        : u32 = 1;     /* Direct 64 bit  */
+pub const R_X86_64_PC32      : u32 = 2;     /* PC relative 32 bit signed */
+pub const R_X86_64_GOT32     : u32 = 3;     /* 32 bit GOT entry */
+pub const R_X86_64_PLT32     : u32 = 4;     /* 32 bit PLT address */
+pub const R_X86_64_COPY      : u32 = 5;     /* Copy symbol at runtime */
+pub const R_X86_64_GLOB_DAT  : u32 = 6;     /* Create GOT entry */
+pub const R_X86_64_JUMP_SLOT : u32 = 7;     /* Create PLT entry */
+pub const R_X86_64_RELATIVE  : u32 = 8;     /* Adjust by program base */
+pub const R_X86_64_GOTPCREL  : u32 = 9;     /* 32 bit signed PC relative offset to GOT */
+pub const R_X86_64_32        : u32 = 10;    /* Direct 32 bit zero extended */
+pub const R_X86_64_32S       : u32 = 11;    /* Direct 32 bit sign extended */
+pub const R_X86_64_DTPMOD64  : u32 = 16;    /* ID of module containing symbol */
+pub const R_X86_64_DTPOFF64  : u32 = 17;    /* Offset in module's TLS block */
+pub const R_X86_64_TPOFF64   : u32 = 18;    /* Offset in initial TLS block */
+pub const R_X86_64_IRELATIVE : u32 = 37;    /* Adjust indirectly by program base */
+pub const R_X86_64_GOTPCRELX : u32 = 41;    /* Load from 32 bit signed pc relative offset to GOT entry without REX prefix, relaxable */
+
+/* x86-64 static relocation types, found in relocatable objects */
+pub const R_X86_64_16        : u32 = 12;    /* Direct 16 bit zero extended */
+pub const R_X86_64_PC16      : u32 = 13;    /* 16 bit sign extended pc relative */
+pub const R_X86_64_8         : u32 = 14;    /* Direct 8 bit sign extended */
+pub const R_X86_64_PC8       : u32 = 15;    /* 8 bit sign extended pc relative */
+pub const R_X86_64_TLSGD     : u32 = 19;    /* 32 bit signed PC relative offset to two GOT entries for GD symbol */
+pub const R_X86_64_TLSLD     : u32 = 20;    /* 32 bit signed PC relative offset to two GOT entries for LD symbol */
+pub const R_X86_64_DTPOFF32  : u32 = 21;    /* Offset in TLS block */
+pub const R_X86_64_GOTTPOFF  : u32 = 22;    /* 32 bit signed PC relative offset to GOT entry for IE symbol */
+pub const R_X86_64_TPOFF32   : u32 = 23;    /* Offset in initial TLS block */
+pub const R_X86_64_PC64      : u32 = 24;    /* PC relative 64 bit */
+pub const R_X86_64_GOTOFF64  : u32 = 25;    /* 64 bit offset to GOT */
+pub const R_X86_64_GOTPC32   : u32 = 26;    /* 32 bit signed pc relative offset to GOT */
+pub const R_X86_64_GOT64     : u32 = 27;    /* 64-bit GOT entry offset */
+pub const R_X86_64_GOTPCREL64 : u32 = 28;    /* 64-bit PC relative offset to GOT entry */
+pub const R_X86_64_GOTPC64   : u32 = 29;    /* 64-bit PC relative offset to GOT */
+pub const R_X86_64_GOTPLT64  : u32 = 30;    /* like GOT64, says PLT entry needed */
+pub const R_X86_64_PLTOFF64  : u32 = 31;    /* 64-bit GOT relative offset to PLT entry */
+pub const R_X86_64_SIZE32    : u32 = 32;    /* Size of symbol plus 32-bit addend */
+pub const R_X86_64_SIZE64    : u32 = 33;    /* Size of symbol plus 64-bit addend */
+pub const R_X86_64_GOTPC32_TLSDESC : u32 = 34;    /* GOT offset for TLS descriptor */
+pub const R_X86_64_TLSDESC_CALL : u32 = 35;    /* Marker for call through TLS descriptor */
+pub const R_X86_64_TLSDESC   : u32 = 36;    /* TLS descriptor */
+pub const R_X86_64_RELATIVE64 : u32 = 38;    /* 64-bit adjust by program base */
+pub const R_X86_64_REX_GOTPCRELX : u32 = 42;    /* Load from 32 bit signed pc relative offset to GOT entry with REX prefix, relaxable */
+
+/* AArch64 dynamic relocation types */
+pub const R_AARCH64_NONE         : u32 = 0;
+pub const R_AARCH64_ABS64        : u32 = 257;   /* Direct 64 bit */
+pub const R_AARCH64_COPY         : u32 = 1024;  /* Copy symbol at runtime */
+pub const R_AARCH64_GLOB_DAT     : u32 = 1025;  /* Create GOT entry */
+pub const R_AARCH64_JUMP_SLOT    : u32 = 1026;  /* Create PLT entry */
+pub const R_AARCH64_RELATIVE     : u32 = 1027;  /* Adjust by program base */
+pub const R_AARCH64_TLS_DTPMOD64 : u32 = 1028;  /* Module number */
+pub const R_AARCH64_TLS_DTPREL64 : u32 = 1029;  /* Module-relative offset */
+pub const R_AARCH64_TLS_TPREL64  : u32 = 1030;  /* TP-relative offset */
+pub const R_AARCH64_TLSDESC      : u32 = 1031;  /* TLS descriptor */
+pub const R_AARCH64_IRELATIVE    : u32 = 1032;  /* STT_GNU_IFUNC relocation */
+
+/* AArch64 static relocation types, found in relocatable objects */
+pub const R_AARCH64_ABS32        : u32 = 258;   /* Direct 32 bit */
+pub const R_AARCH64_ABS16        : u32 = 259;   /* Direct 16-bit */
+pub const R_AARCH64_PREL64       : u32 = 260;   /* PC-relative 64-bit */
+pub const R_AARCH64_PREL32       : u32 = 261;   /* PC-relative 32-bit */
+pub const R_AARCH64_PREL16       : u32 = 262;   /* PC-relative 16-bit */
+pub const R_AARCH64_MOVW_UABS_G0 : u32 = 263;   /* Dir. MOVZ imm. from bits 15:0 */
+pub const R_AARCH64_MOVW_UABS_G0_NC : u32 = 264;   /* Likewise for MOVK; no check */
+pub const R_AARCH64_MOVW_UABS_G1 : u32 = 265;   /* Dir. MOVZ imm. from bits 31:16 */
+pub const R_AARCH64_MOVW_UABS_G1_NC : u32 = 266;   /* Likewise for MOVK; no check */
+pub const R_AARCH64_MOVW_UABS_G2 : u32 = 267;   /* Dir. MOVZ imm. from bits 47:32 */
+pub const R_AARCH64_MOVW_UABS_G2_NC : u32 = 268;   /* Likewise for MOVK; no check */
+pub const R_AARCH64_MOVW_UABS_G3 : u32 = 269;   /* Dir. MOV{K,Z} imm. from 63:48 */
+pub const R_AARCH64_LD_PREL_LO19 : u32 = 273;   /* PC-rel. LD imm. from bits 20:2 */
+pub const R_AARCH64_ADR_PREL_LO21 : u32 = 274;   /* PC-rel. ADR imm. from bits 20:0 */
+pub const R_AARCH64_ADR_PREL_PG_HI21 : u32 = 275;   /* Page-rel. ADRP imm. from 32:12 */
+pub const R_AARCH64_ADR_PREL_PG_HI21_NC : u32 = 276;   /* Likewise; no overflow check */
+pub const R_AARCH64_ADD_ABS_LO12_NC : u32 = 277;   /* Dir. ADD imm. from bits 11:0 */
+pub const R_AARCH64_LDST8_ABS_LO12_NC : u32 = 278;   /* Likewise for LD/ST; no check */
+pub const R_AARCH64_TSTBR14      : u32 = 279;   /* PC-rel. TBZ/TBNZ imm. from 15:2 */
+pub const R_AARCH64_CONDBR19     : u32 = 280;   /* PC-rel. cond. br. imm. from 20:2 */
+pub const R_AARCH64_JUMP26       : u32 = 282;   /* PC-rel. B imm. from bits 27:2 */
+pub const R_AARCH64_CALL26       : u32 = 283;   /* Likewise for CALL */
+pub const R_AARCH64_LDST16_ABS_LO12_NC : u32 = 284;   /* Dir. ADD imm. from bits 11:1 */
+pub const R_AARCH64_LDST32_ABS_LO12_NC : u32 = 285;   /* Likewise for bits 11:2 */
+pub const R_AARCH64_LDST64_ABS_LO12_NC : u32 = 286;   /* Likewise for bits 11:3 */
+pub const R_AARCH64_LDST128_ABS_LO12_NC : u32 = 299;   /* Dir. ADD imm. from bits 11:4 */
+pub const R_AARCH64_GOT_LD_PREL19 : u32 = 309;   /* PC-rel. GOT off. load imm. 20:2 */
+pub const R_AARCH64_ADR_GOT_PAGE : u32 = 311;   /* P-page-rel. GOT off. ADRP 32:12 */
+pub const R_AARCH64_LD64_GOT_LO12_NC : u32 = 312;   /* Dir. GOT off. LD/ST imm. 11:3 */
+pub const R_AARCH64_LD64_GOTPAGE_LO15 : u32 = 313;   /* GOT-page-rel. GOT off. LD/ST 14:3 */
+pub const R_AARCH64_TLSGD_ADR_PAGE21 : u32 = 513;   /* page-rel. ADRP imm. 32:12 */
+pub const R_AARCH64_TLSGD_ADD_LO12_NC : u32 = 514;   /* direct ADD imm. from 11:0 */
+pub const R_AARCH64_TLSLD_ADR_PAGE21 : u32 = 518;   /* Like 513; local dynamic model */
+pub const R_AARCH64_TLSLD_ADD_LO12_NC : u32 = 519;   /* Like 514; local dynamic model */
+pub const R_AARCH64_TLSLD_ADD_DTPREL_HI12 : u32 = 528;   /* DTP-rel. ADD imm. from 23:12 */
+pub const R_AARCH64_TLSLD_ADD_DTPREL_LO12 : u32 = 529;   /* DTP-rel. ADD imm. from 11:0 */
+pub const R_AARCH64_TLSLD_ADD_DTPREL_LO12_NC : u32 = 530;   /* Likewise; no ovfl. check */
+pub const R_AARCH64_TLSIE_ADR_GOTTPREL_PAGE21 : u32 = 541;   /* Page-rel. ADRP 32:12 */
+pub const R_AARCH64_TLSIE_LD64_GOTTPREL_LO12_NC : u32 = 542;   /* Direct LD off. 11:3 */
+pub const R_AARCH64_TLSLE_ADD_TPREL_HI12 : u32 = 549;   /* TP-rel. ADD imm. 23:12 */
+pub const R_AARCH64_TLSLE_ADD_TPREL_LO12 : u32 = 550;   /* TP-rel. ADD imm. 11:0 */
+pub const R_AARCH64_TLSLE_ADD_TPREL_LO12_NC : u32 = 551;   /* Likewise; no ovfl. check */
+pub const R_AARCH64_TLSDESC_ADR_PAGE21 : u32 = 562;   /* Page-rel. ADRP imm. 32:12 */
+pub const R_AARCH64_TLSDESC_LD64_LO12 : u32 = 563;   /* Direct LD off. from 11:3 */
+pub const R_AARCH64_TLSDESC_ADD_LO12 : u32 = 564;   /* Direct ADD imm. from 11:0 */
+pub const R_AARCH64_TLSDESC_LDR  : u32 = 567;   /* Relax LDR */
+pub const R_AARCH64_TLSDESC_ADD  : u32 = 568;   /* Relax ADD */
+pub const R_AARCH64_TLSDESC_CALL : u32 = 569;   /* Relax BLR */
+
+/* RISC-V dynamic relocation types */
+pub const R_RISCV_NONE         : u32 = 0;
+pub const R_RISCV_64           : u32 = 2;       /* Direct 64 bit */
+pub const R_RISCV_RELATIVE     : u32 = 3;       /* Adjust by program base */
+pub const R_RISCV_COPY         : u32 = 4;       /* Copy symbol at runtime */
+pub const R_RISCV_JUMP_SLOT    : u32 = 5;       /* Create PLT entry */
+pub const R_RISCV_TLS_DTPMOD64 : u32 = 7;       /* Module number */
+pub const R_RISCV_TLS_DTPREL64 : u32 = 9;       /* Module-relative offset */
+pub const R_RISCV_TLS_TPREL64  : u32 = 11;      /* TP-relative offset */
+pub const R_RISCV_IRELATIVE    : u32 = 58;      /* STT_GNU_IFUNC relocation */
+
+/* RISC-V static relocation types, found in relocatable objects */
+pub const R_RISCV_32           : u32 = 1;       /* Direct 32 bit */
+pub const R_RISCV_BRANCH       : u32 = 16;      /* PC-relative conditional branch */
+pub const R_RISCV_JAL          : u32 = 17;      /* PC-relative jump */
+pub const R_RISCV_CALL         : u32 = 18;      /* PC-relative call, AUIPC+JALR pair */
+pub const R_RISCV_CALL_PLT     : u32 = 19;      /* PC-relative call through the PLT */
+pub const R_RISCV_GOT_HI20     : u32 = 20;      /* PC-relative GOT entry, high 20 bits */
+pub const R_RISCV_TLS_GOT_HI20 : u32 = 21;      /* PC-relative GOT entry of an IE TLS symbol */
+pub const R_RISCV_TLS_GD_HI20  : u32 = 22;      /* PC-relative GOT entry of a GD TLS symbol */
+pub const R_RISCV_PCREL_HI20   : u32 = 23;      /* PC-relative, high 20 bits */
+pub const R_RISCV_PCREL_LO12_I : u32 = 24;      /* PC-relative, low 12 bits, I-type */
+pub const R_RISCV_PCREL_LO12_S : u32 = 25;      /* PC-relative, low 12 bits, S-type */
+pub const R_RISCV_HI20         : u32 = 26;      /* Absolute, high 20 bits */
+pub const R_RISCV_LO12_I       : u32 = 27;      /* Absolute, low 12 bits, I-type */
+pub const R_RISCV_LO12_S       : u32 = 28;      /* Absolute, low 12 bits, S-type */
+pub const R_RISCV_TPREL_HI20   : u32 = 29;      /* TP-relative, high 20 bits */
+pub const R_RISCV_TPREL_LO12_I : u32 = 30;      /* TP-relative, low 12 bits, I-type */
+pub const R_RISCV_TPREL_LO12_S : u32 = 31;      /* TP-relative, low 12 bits, S-type */
+pub const R_RISCV_TPREL_ADD    : u32 = 32;      /* Marks the TP add of a TP-relative access */
+pub const R_RISCV_ADD8         : u32 = 33;      /* 8 bit label addition */
+pub const R_RISCV_ADD16        : u32 = 34;      /* 16 bit label addition */
+pub const R_RISCV_ADD32        : u32 = 35;      /* 32 bit label addition */
+pub const R_RISCV_ADD64        : u32 = 36;      /* 64 bit label addition */
+pub const R_RISCV_SUB8         : u32 = 37;      /* 8 bit label subtraction */
+pub const R_RISCV_SUB16        : u32 = 38;      /* 16 bit label subtraction */
+pub const R_RISCV_SUB32        : u32 = 39;      /* 32 bit label subtraction */
+pub const R_RISCV_SUB64        : u32 = 40;      /* 64 bit label subtraction */
+pub const R_RISCV_ALIGN        : u32 = 43;      /* Alignment padding the linker may delete */
+pub const R_RISCV_RVC_BRANCH   : u32 = 44;      /* PC-relative compressed branch */
+pub const R_RISCV_RVC_JUMP     : u32 = 45;      /* PC-relative compressed jump */
+pub const R_RISCV_RELAX        : u32 = 51;      /* The preceding relocation may be relaxed */
+pub const R_RISCV_SUB6         : u32 = 52;      /* 6 bit label subtraction */
+pub const R_RISCV_SET6         : u32 = 53;      /* 6 bit label assignment */
+pub const R_RISCV_SET8         : u32 = 54;      /* 8 bit label assignment */
+pub const R_RISCV_SET16        : u32 = 55;      /* 16 bit label assignment */
+pub const R_RISCV_SET32        : u32 = 56;      /* 32 bit label assignment */
+pub const R_RISCV_32_PCREL     : u32 = 57;      /* PC-relative 32 bit */
+
+pub fn is_got_slot(machine : u16, r_type : u32) -> bool {
+    // relocation filling a GOT slot with a symbol address
+    match machine {
+        EM_X86_64 => matches!(r_type, R_X86_64_JUMP_SLOT | R_X86_64_GLOB_DAT),
+        EM_AARCH64 => matches!(r_type, R_AARCH64_JUMP_SLOT | R_AARCH64_GLOB_DAT),
+        EM_RISCV => matches!(r_type, R_RISCV_JUMP_SLOT | R_RISCV_64),
+        _ => false,
+    }
+}
+
+pub fn is_relative(machine : u16, r_type : u32) -> bool {
+    // base + addend relocation
+    match machine {
+        EM_X86_64 => r_type == R_X86_64_RELATIVE,
+        EM_AARCH64 => r_type == R_AARCH64_RELATIVE,
+        EM_RISCV => r_type == R_RISCV_RELATIVE,
+        _ => false,
+    }
+}
+
+pub fn is_copy(machine : u16, r_type : u32) -> bool {
+    // the executable holds a copy of a shared library's data object
+    match machine {
+        EM_X86_64 => r_type == R_X86_64_COPY,
+        EM_AARCH64 => r_type == R_AARCH64_COPY,
+        EM_RISCV => r_type == R_RISCV_COPY,
+        _ => false,
+    }
+}
+
+pub fn get_reloc_type_str(machine : u16, r_type : u32) -> String {
+    let s = match machine {
+        EM_X86_64 => match r_type {
+            R_X86_64_NONE      => "R_X86_64_NONE",
+            R_X86_64_64        => "R_X86_64_64",
+            R_X86_64_PC32      => "R_X86_64_PC32",
+            R_X86_64_GOT32     => "R_X86_64_GOT32",
+            R_X86_64_PLT32     => "R_X86_64_PLT32",
+            R_X86_64_COPY      => "R_X86_64_COPY",
+            R_X86_64_GLOB_DAT  => "R_X86_64_GLOB_DAT",
+            R_X86_64_JUMP_SLOT => "R_X86_64_JUMP_SLOT",
+            R_X86_64_RELATIVE  => "R_X86_64_RELATIVE",
+            R_X86_64_GOTPCREL  => "R_X86_64_GOTPCREL",
+            R_X86_64_32        => "R_X86_64_32",
+            R_X86_64_32S       => "R_X86_64_32S",
+            R_X86_64_DTPMOD64  => "R_X86_64_DTPMOD64",
+            R_X86_64_DTPOFF64  => "R_X86_64_DTPOFF64",
+            R_X86_64_TPOFF64   => "R_X86_64_TPOFF64",
+            R_X86_64_IRELATIVE => "R_X86_64_IRELATIVE",
+            R_X86_64_GOTPCRELX => "R_X86_64_GOTPCRELX",
+            R_X86_64_REX_GOTPCRELX => "R_X86_64_REX_GOTPCRELX",
+            R_X86_64_16        => "R_X86_64_16",
+            R_X86_64_PC16      => "R_X86_64_PC16",
+            R_X86_64_8         => "R_X86_64_8",
+            R_X86_64_PC8       => "R_X86_64_PC8",
+            R_X86_64_TLSGD     => "R_X86_64_TLSGD",
+            R_X86_64_TLSLD     => "R_X86_64_TLSLD",
+            R_X86_64_DTPOFF32  => "R_X86_64_DTPOFF32",
+            R_X86_64_GOTTPOFF  => "R_X86_64_GOTTPOFF",
+            R_X86_64_TPOFF32   => "R_X86_64_TPOFF32",
+            R_X86_64_PC64      => "R_X86_64_PC64",
+            R_X86_64_GOTOFF64  => "R_X86_64_GOTOFF64",
+            R_X86_64_GOTPC32   => "R_X86_64_GOTPC32",
+            R_X86_64_GOT64     => "R_X86_64_GOT64",
+            R_X86_64_GOTPCREL64 => "R_X86_64_GOTPCREL64",
+            R_X86_64_GOTPC64   => "R_X86_64_GOTPC64",
+            R_X86_64_GOTPLT64  => "R_X86_64_GOTPLT64",
+            R_X86_64_PLTOFF64  => "R_X86_64_PLTOFF64",
+            R_X86_64_SIZE32    => "R_X86_64_SIZE32",
+            R_X86_64_SIZE64    => "R_X86_64_SIZE64",
+            R_X86_64_GOTPC32_TLSDESC => "R_X86_64_GOTPC32_TLSDESC",
+            R_X86_64_TLSDESC_CALL => "R_X86_64_TLSDESC_CALL",
+            R_X86_64_TLSDESC   => "R_X86_64_TLSDESC",
+            R_X86_64_RELATIVE64 => "R_X86_64_RELATIVE64",
+            _ => "",
+        },
+        EM_AARCH64 => match r_type {
+            R_AARCH64_NONE         => "R_AARCH64_NONE",
+            R_AARCH64_ABS64        => "R_AARCH64_ABS64",
+            R_AARCH64_COPY         => "R_AARCH64_COPY",
+            R_AARCH64_GLOB_DAT     => "R_AARCH64_GLOB_DAT",
+            R_AARCH64_JUMP_SLOT    => "R_AARCH64_JUMP_SLOT",
+            R_AARCH64_RELATIVE     => "R_AARCH64_RELATIVE",
+            R_AARCH64_TLS_DTPMOD64 => "R_AARCH64_TLS_DTPMOD64",
+            R_AARCH64_TLS_DTPREL64 => "R_AARCH64_TLS_DTPREL64",
+            R_AARCH64_TLS_TPREL64  => "R_AARCH64_TLS_TPREL64",
+            R_AARCH64_TLSDESC      => "R_AARCH64_TLSDESC",
+            R_AARCH64_IRELATIVE    => "R_AARCH64_IRELATIVE",
+            R_AARCH64_ABS32        => "R_AARCH64_ABS32",
+            R_AARCH64_ABS16        => "R_AARCH64_ABS16",
+            R_AARCH64_PREL64       => "R_AARCH64_PREL64",
+            R_AARCH64_PREL32       => "R_AARCH64_PREL32",
+            R_AARCH64_PREL16       => "R_AARCH64_PREL16",
+            R_AARCH64_MOVW_UABS_G0 => "R_AARCH64_MOVW_UABS_G0",
+            R_AARCH64_MOVW_UABS_G0_NC => "R_AARCH64_MOVW_UABS_G0_NC",
+            R_AARCH64_MOVW_UABS_G1 => "R_AARCH64_MOVW_UABS_G1",
+            R_AARCH64_MOVW_UABS_G1_NC => "R_AARCH64_MOVW_UABS_G1_NC",
+            R_AARCH64_MOVW_UABS_G2 => "R_AARCH64_MOVW_UABS_G2",
+            R_AARCH64_MOVW_UABS_G2_NC => "R_AARCH64_MOVW_UABS_G2_NC",
+            R_AARCH64_MOVW_UABS_G3 => "R_AARCH64_MOVW_UABS_G3",
+            R_AARCH64_LD_PREL_LO19 => "R_AARCH64_LD_PREL_LO19",
+            R_AARCH64_ADR_PREL_LO21 => "R_AARCH64_ADR_PREL_LO21",
+            R_AARCH64_ADR_PREL_PG_HI21 => "R_AARCH64_ADR_PREL_PG_HI21",
+            R_AARCH64_ADR_PREL_PG_HI21_NC => "R_AARCH64_ADR_PREL_PG_HI21_NC",
+            R_AARCH64_ADD_ABS_LO12_NC => "R_AARCH64_ADD_ABS_LO12_NC",
+            R_AARCH64_LDST8_ABS_LO12_NC => "R_AARCH64_LDST8_ABS_LO12_NC",
+            R_AARCH64_TSTBR14      => "R_AARCH64_TSTBR14",
+            R_AARCH64_CONDBR19     => "R_AARCH64_CONDBR19",
+            R_AARCH64_JUMP26       => "R_AARCH64_JUMP26",
+            R_AARCH64_CALL26       => "R_AARCH64_CALL26",
+            R_AARCH64_LDST16_ABS_LO12_NC => "R_AARCH64_LDST16_ABS_LO12_NC",
+            R_AARCH64_LDST32_ABS_LO12_NC => "R_AARCH64_LDST32_ABS_LO12_NC",
+            R_AARCH64_LDST64_ABS_LO12_NC => "R_AARCH64_LDST64_ABS_LO12_NC",
+            R_AARCH64_LDST128_ABS_LO12_NC => "R_AARCH64_LDST128_ABS_LO12_NC",
+            R_AARCH64_GOT_LD_PREL19 => "R_AARCH64_GOT_LD_PREL19",
+            R_AARCH64_ADR_GOT_PAGE => "R_AARCH64_ADR_GOT_PAGE",
+            R_AARCH64_LD64_GOT_LO12_NC => "R_AARCH64_LD64_GOT_LO12_NC",
+            R_AARCH64_LD64_GOTPAGE_LO15 => "R_AARCH64_LD64_GOTPAGE_LO15",
+            R_AARCH64_TLSGD_ADR_PAGE21 => "R_AARCH64_TLSGD_ADR_PAGE21",
+            R_AARCH64_TLSGD_ADD_LO12_NC => "R_AARCH64_TLSGD_ADD_LO12_NC",
+            R_AARCH64_TLSLD_ADR_PAGE21 => "R_AARCH64_TLSLD_ADR_PAGE21",
+            R_AARCH64_TLSLD_ADD_LO12_NC => "R_AARCH64_TLSLD_ADD_LO12_NC",
+            R_AARCH64_TLSLD_ADD_DTPREL_HI12 => "R_AARCH64_TLSLD_ADD_DTPREL_HI12",
+            R_AARCH64_TLSLD_ADD_DTPREL_LO12 => "R_AARCH64_TLSLD_ADD_DTPREL_LO12",
+            R_AARCH64_TLSLD_ADD_DTPREL_LO12_NC => "R_AARCH64_TLSLD_ADD_DTPREL_LO12_NC",
+            R_AARCH64_TLSIE_ADR_GOTTPREL_PAGE21 => "R_AARCH64_TLSIE_ADR_GOTTPREL_PAGE21",
+            R_AARCH64_TLSIE_LD64_GOTTPREL_LO12_NC => "R_AARCH64_TLSIE_LD64_GOTTPREL_LO12_NC",
+            R_AARCH64_TLSLE_ADD_TPREL_HI12 => "R_AARCH64_TLSLE_ADD_TPREL_HI12",
+            R_AARCH64_TLSLE_ADD_TPREL_LO12 => "R_AARCH64_TLSLE_ADD_TPREL_LO12",
+            R_AARCH64_TLSLE_ADD_TPREL_LO12_NC => "R_AARCH64_TLSLE_ADD_TPREL_LO12_NC",
+            R_AARCH64_TLSDESC_ADR_PAGE21 => "R_AARCH64_TLSDESC_ADR_PAGE21",
+            R_AARCH64_TLSDESC_LD64_LO12 => "R_AARCH64_TLSDESC_LD64_LO12",
+            R_AARCH64_TLSDESC_ADD_LO12 => "R_AARCH64_TLSDESC_ADD_LO12",
+            R_AARCH64_TLSDESC_LDR  => "R_AARCH64_TLSDESC_LDR",
+            R_AARCH64_TLSDESC_ADD  => "R_AARCH64_TLSDESC_ADD",
+            R_AARCH64_TLSDESC_CALL => "R_AARCH64_TLSDESC_CALL",
+            _ => "",
+        },
+        EM_RISCV => match r_type {
+            R_RISCV_NONE         => "R_RISCV_NONE",
+            R_RISCV_64           => "R_RISCV_64",
+            R_RISCV_RELATIVE     => "R_RISCV_RELATIVE",
+            R_RISCV_COPY         => "R_RISCV_COPY",
+            R_RISCV_JUMP_SLOT    => "R_RISCV_JUMP_SLOT",
+            R_RISCV_TLS_DTPMOD64 => "R_RISCV_TLS_DTPMOD64",
+            R_RISCV_TLS_DTPREL64 => "R_RISCV_TLS_DTPREL64",
+            R_RISCV_TLS_TPREL64  => "R_RISCV_TLS_TPREL64",
+            R_RISCV_IRELATIVE    => "R_RISCV_IRELATIVE",
+            R_RISCV_32           => "R_RISCV_32",
+            R_RISCV_BRANCH       => "R_RISCV_BRANCH",
+            R_RISCV_JAL          => "R_RISCV_JAL",
+            R_RISCV_CALL         => "R_RISCV_CALL",
+            R_RISCV_CALL_PLT     => "R_RISCV_CALL_PLT",
+            R_RISCV_GOT_HI20     => "R_RISCV_GOT_HI20",
+            R_RISCV_TLS_GOT_HI20 => "R_RISCV_TLS_GOT_HI20",
+            R_RISCV_TLS_GD_HI20  => "R_RISCV_TLS_GD_HI20",
+            R_RISCV_PCREL_HI20   => "R_RISCV_PCREL_HI20",
+            R_RISCV_PCREL_LO12_I => "R_RISCV_PCREL_LO12_I",
+            R_RISCV_PCREL_LO12_S => "R_RISCV_PCREL_LO12_S",
+            R_RISCV_HI20         => "R_RISCV_HI20",
+            R_RISCV_LO12_I       => "R_RISCV_LO12_I",
+            R_RISCV_LO12_S       => "R_RISCV_LO12_S",
+            R_RISCV_TPREL_HI20   => "R_RISCV_TPREL_HI20",
+            R_RISCV_TPREL_LO12_I => "R_RISCV_TPREL_LO12_I",
+            R_RISCV_TPREL_LO12_S => "R_RISCV_TPREL_LO12_S",
+            R_RISCV_TPREL_ADD    => "R_RISCV_TPREL_ADD",
+            R_RISCV_ADD8         => "R_RISCV_ADD8",
+            R_RISCV_ADD16        => "R_RISCV_ADD16",
+            R_RISCV_ADD32        => "R_RISCV_ADD32",
+            R_RISCV_ADD64        => "R_RISCV_ADD64",
+            R_RISCV_SUB8         => "R_RISCV_SUB8",
+            R_RISCV_SUB16        => "R_RISCV_SUB16",
+            R_RISCV_SUB32        => "R_RISCV_SUB32",
+            R_RISCV_SUB64        => "R_RISCV_SUB64",
+            R_RISCV_ALIGN        => "R_RISCV_ALIGN",
+            R_RISCV_RVC_BRANCH   => "R_RISCV_RVC_BRANCH",
+            R_RISCV_RVC_JUMP     => "R_RISCV_RVC_JUMP",
+            R_RISCV_RELAX        => "R_RISCV_RELAX",
+            R_RISCV_SUB6         => "R_RISCV_SUB6",
+            R_RISCV_SET6         => "R_RISCV_SET6",
+            R_RISCV_SET8         => "R_RISCV_SET8",
+            R_RISCV_SET16        => "R_RISCV_SET16",
+            R_RISCV_SET32        => "R_RISCV_SET32",
+            R_RISCV_32_PCREL     => "R_RISCV_32_PCREL",
+            _ => "",
+        },
+        _ => "",
+    };
+    if s.is_empty() {
+        format!("<unknown>: {:#x}", r_type)
+    } else {
+        s.to_string()
+    }
+}
+
+#[derive(Debug)]
+pub struct Rela {
+    // wrapper of rela entry and the name of its symbol
+    pub rela     : Elf64Rela,
+    pub sym_name : String,
+}
+
+#[derive(Debug)]
+pub struct RelaTable {
+    pub name   : String,    /* e.g. .rela.plt */
+    pub target : u32,       /* sh_info, index of section the relocations apply to */
+    pub relas  : Vec<Rela>,
+}
+
+#[derive(Debug)]
+pub struct RelaTables {
+    pub tables : Vec<RelaTable>,
+}
+
+impl RelaTables {
+    pub fn new(tables : Vec<RelaTable>) -> Self {
+        RelaTables { tables }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Rela> {
+        // every entry of every table
+        self.tables.iter().flat_map(|t| t.relas.iter())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::Parser;
+    /*
+    test/testtls.o is gcc -c -O1 -fPIC test/testtls.c, one TLS variable per access model
+     */
+    #[test]
+    fn test_tls_relocations() {
+        let parser = Parser::new("test/testtls.o");
+        let names : Vec<String> = parser.relas.iter()
+            .map(|r| get_reloc_type_str(parser.ehdr.e_machine, r.rela.r_type()))
+            .collect();
+        for name in ["R_X86_64_TLSGD", "R_X86_64_TLSLD", "R_X86_64_DTPOFF32", "R_X86_64_GOTTPOFF", "R_X86_64_TPOFF32"] {
+            assert!(names.iter().any(|n| n == name), "{} missing", name);
+        }
+        assert!(!names.iter().any(|n| n.starts_with("<unknown>")));
+    }
+
+    #[test]
+    fn test_static_reloc_names() {
+        assert_eq!(get_reloc_type_str(EM_X86_64, R_X86_64_PLTOFF64), "R_X86_64_PLTOFF64");
+        assert_eq!(get_reloc_type_str(EM_AARCH64, 283), "R_AARCH64_CALL26");
+        assert_eq!(get_reloc_type_str(EM_AARCH64, 275), "R_AARCH64_ADR_PREL_PG_HI21");
+        assert_eq!(get_reloc_type_str(EM_AARCH64, 277), "R_AARCH64_ADD_ABS_LO12_NC");
+        assert_eq!(get_reloc_type_str(EM_RISCV, 19), "R_RISCV_CALL_PLT");
+        assert_eq!(get_reloc_type_str(EM_RISCV, 23), "R_RISCV_PCREL_HI20");
+        assert_eq!(get_reloc_type_str(EM_RISCV, 16), "R_RISCV_BRANCH");
+        // same number, different machine
+        assert_eq!(get_reloc_type_str(EM_X86_64, 19), "R_X86_64_TLSGD");
+        assert_eq!(get_reloc_type_str(EM_RISCV, 47), "<unknown>: 0x2f");
+    }
+}
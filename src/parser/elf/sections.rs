@@ -1,14 +1,14 @@
-use crate::parser::elf::elf_struct::Elf64Shdr;
+use crate::parser::elf::elf_struct::{Elf64Shdr, SHF_ALLOC, SHF_TLS, SHT_NOBITS};
 use colored::Colorize;
 use std::{collections::HashMap, vec};
 
 pub struct Sections {
     // manage all sections
     pub secs : Vec<Section>,
+    pub synthetic : bool,   /* rebuilt from PT_DYNAMIC, the file's own section headers are missing or unusable */
     shstrndx : usize,
     secnames : Vec<String>, /* store all section names */
     offset_to_secname : HashMap<usize, String>,
-    pos : usize
 }
 
 #[derive(Debug)]
@@ -35,15 +35,15 @@ impl Sections {
             1   p        n
             2            p     n
              */
+            // an unterminated last name runs to the end of the table, corrupt bytes become U+FFFD
             let next = match shstrtab_buf[prev..].iter().position(|&x| x == b'\x00') {
                 Some(pos) => pos + 1,
-                None      => break,
+                None      => shstrtab_buf.len() - prev,
             };
 
             let s = match shstrtab_buf[prev] {
                 b'\x00' => String::from("\x00"),
-                _       => String::from_utf8(shstrtab_buf[prev..prev+next].to_vec()).unwrap()
-                
+                _       => String::from_utf8_lossy(&shstrtab_buf[prev..prev+next]).into_owned()
             };
 
             offset_to_secname.insert(prev, s);
@@ -69,13 +69,19 @@ impl Sections {
         
         Sections { 
             secs, 
+            synthetic : false,
             shstrndx,
             secnames ,
             offset_to_secname,
-            pos : 0,
         }
     }
     
+    pub fn synthetic(shdrs : Vec<Elf64Shdr>, shstrtab_buf : &[u8]) -> Self {
+        let mut sections = Self::new(shdrs, shstrtab_buf, 0);
+        sections.synthetic = true;
+        sections
+    }
+    
     fn get_sec_name(&self, offset : usize) -> String {
         // get section name by sh_name(offset in shstrtab) in shdr
         self.offset_to_secname[&offset].clone()
@@ -124,12 +130,37 @@ impl Sections {
     }
     pub fn get_section(&self, name : &str) -> Option<&Section> {
         // get section's ref by name
-        for sec in &self.secs {
-            if sec.name.as_str().trim_end_matches('\x00') == name {
-                return Some(sec);
-            }
-        }
-        None
+        self.index_of(name).map(|i| &self.secs[i])
+    }
+
+    pub fn index_of(&self, name : &str) -> Option<usize> {
+        // index of the first section with that name, names are stored with their NUL
+        self.secs.iter().position(|sec| sec.name.trim_end_matches('\x00') == name)
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, Section> {
+        self.secs.iter()
+    }
+
+    pub fn iter_mut(&mut self) -> std::slice::IterMut<'_, Section> {
+        self.secs.iter_mut()
+    }
+
+    pub fn by_type(&self, sh_type : u32) -> impl Iterator<Item = &Section> {
+        self.secs.iter().filter(move |sec| sec.shdr.sh_type == sh_type)
+    }
+
+    pub fn by_flag(&self, flag : u64) -> impl Iterator<Item = &Section> {
+        // sections with every bit of flag set, e.g. SHF_ALLOC | SHF_EXECINSTR
+        self.secs.iter().filter(move |sec| sec.shdr.sh_flags & flag == flag)
+    }
+
+    pub fn index_at(&self, vaddr : u64) -> Option<usize> {
+        // allocated section whose [sh_addr, sh_addr+sh_size) holds vaddr, .tbss overlaps what follows it and is skipped
+        self.secs.iter().position(|sec| {
+            let tbss = sec.shdr.sh_flags & SHF_TLS != 0 && sec.shdr.sh_type == SHT_NOBITS;
+            sec.shdr.sh_flags & SHF_ALLOC != 0 && !tbss && sec.shdr.sh_addr <= vaddr && vaddr < sec.shdr.sh_addr + sec.shdr.sh_size
+        })
     }
 }
 
@@ -142,12 +173,23 @@ impl std::ops::Index<usize> for Sections {
         &self.secs[index]
     }
 }
-// TODO: problem
-impl<'a> Iterator for &'a Sections {
+
+impl std::ops::Index<&str> for Sections {
+
+    type Output = Section;
+
+    fn index(&self, name: &str) -> &Self::Output {
+        self.get_section(name).unwrap_or_else(|| panic!("no section named {}", name))
+    }
+}
+
+impl<'a> std::iter::IntoIterator for &'a Sections {
+
     type Item = &'a Section;
-    
-    fn next(&mut self) -> Option<Self::Item> {
-        self.secs.iter().next()
+    type IntoIter = std::slice::Iter<'a, Section>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.secs.iter()
     }
 }
 
@@ -161,5 +203,28 @@ impl<'a> std::iter::IntoIterator for &'a mut Sections {
     }
 }
 
-mod test {
+#[cfg(test)]
+mod tests {
+    use crate::parser::Parser;
+    use crate::parser::elf::elf_struct::{SHT_SYMTAB, SHF_ALLOC, SHF_EXECINSTR};
+    #[test]
+    fn test_iter_sections() {
+        let mut parser = Parser::new("test/test");
+        let sections = &parser.sections;
+
+        // every section exactly once, in header order
+        let names : Vec<&str> = sections.into_iter().map(|s| s.name.trim_end_matches('\x00')).collect();
+        assert_eq!(names.len(), sections.len());
+        assert_eq!(names[sections.index_of(".text").unwrap()], ".text");
+        assert_eq!(sections[".text"].shdr.sh_addr, sections.get_section(".text").unwrap().shdr.sh_addr);
+
+        assert_eq!(sections.by_type(SHT_SYMTAB).count(), 1);
+        let code : Vec<&str> = sections.by_flag(SHF_ALLOC | SHF_EXECINSTR).map(|s| s.name.trim_end_matches('\x00')).collect();
+        assert!(code.contains(&".text") && !code.contains(&".data"));
+
+        for sec in parser.sections.iter_mut() {
+            sec.shdr.sh_addralign = 0;
+        }
+        assert!(parser.sections.iter().all(|s| s.shdr.sh_addralign == 0));
+    }
 }
\ No newline at end of file
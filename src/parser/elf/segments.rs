@@ -1,12 +1,13 @@
 use crate::parser::elf::elf_struct::Elf64Phdr;
 use colored::Colorize;
+use self::segtype::SegmentType;
 
-mod segtype {
+pub mod segtype {
 
     // TODO: try struct enum???
     #[allow(non_camel_case_types)]
-    #[derive(Debug)]
-    enum SegmentType {
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub enum SegmentType {
         PT_NULL          = 0,           // Program header table entry unused
         PT_LOAD          = 1,           // Loadable program segment
         PT_DYNAMIC       = 2,           // Dynamic linking information
@@ -20,6 +21,7 @@ mod segtype {
         PT_GNU_EH_FRAME  = 0x6474e550,  // GCC .eh_frame_hdr segment
         PT_GNU_STACK     = 0x6474e551,  // Indicates stack executability
         PT_GNU_RELRO     = 0x6474e552,  // Read-only after relocation
+        PT_GNU_PROPERTY  = 0x6474e553,  // GNU property notes
         // PT_LOSUNW        = 0x6ffffffa,
         // PT_SUNWBSS       = 0x6ffffffa,  // Sun Specific segment
         // PT_SUNWSTACK     = 0x6ffffffb,  // Stack segment
@@ -46,6 +48,7 @@ mod segtype {
                 0x6474e550 => SegmentType::PT_GNU_EH_FRAME,
                 0x6474e551 => SegmentType::PT_GNU_STACK,
                 0x6474e552 => SegmentType::PT_GNU_RELRO,
+                0x6474e553 => SegmentType::PT_GNU_PROPERTY,
                 _ => SegmentType::PT_UNKNOWN,
             }
         }
@@ -68,6 +71,7 @@ mod segtype {
             SegmentType::PT_GNU_EH_FRAME  => "GNU_EH_FRAME",
             SegmentType::PT_GNU_STACK     => "GNU_STACK",
             SegmentType::PT_GNU_RELRO     => "GNU_RELRO",
+            SegmentType::PT_GNU_PROPERTY  => "GNU_PROPERTY",
             SegmentType::PT_UNKNOWN       => "UNKNOWN",
             _ => panic!("unknown type"),
         }
@@ -77,7 +81,6 @@ mod segtype {
 pub struct Segments {
     // manage all segments
     pub segs  : Vec<Segment>,
-    pub pos   : usize,
 }
 #[derive(Debug)]
 pub struct Segment {
@@ -101,7 +104,6 @@ impl Segments {
 
         Segments { 
             segs ,
-            pos : 0,
         }
     }
 
@@ -130,6 +132,49 @@ impl Segments {
     pub fn len(&self) -> usize {
         return self.segs.len();
     }
+
+    pub fn get_segment(&self, seg_type : SegmentType) -> Option<&Segment> {
+        // get the first segment of given type
+        self.segs.iter().find(|seg| seg.seg_type() == seg_type)
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, Segment> {
+        self.segs.iter()
+    }
+
+    pub fn iter_mut(&mut self) -> std::slice::IterMut<'_, Segment> {
+        self.segs.iter_mut()
+    }
+
+    pub fn by_type(&self, seg_type : SegmentType) -> impl Iterator<Item = &Segment> {
+        self.segs.iter().filter(move |seg| seg.seg_type() == seg_type)
+    }
+
+    pub fn by_flag(&self, flag : u32) -> impl Iterator<Item = &Segment> {
+        // segments with every bit of flag set, e.g. PF_R | PF_X
+        self.segs.iter().filter(move |seg| seg.phdr.p_flags & flag == flag)
+    }
+
+    pub fn loadable(&self) -> impl Iterator<Item = &Segment> {
+        self.by_type(SegmentType::PT_LOAD)
+    }
+
+    pub fn vaddr_to_offset(&self, vaddr : u64) -> Option<u64> {
+        // translate a virtual address to file offset through file-backed part of PT_LOAD
+        for seg in self.loadable() {
+            let start = seg.phdr.p_vaddr;
+            if vaddr >= start && vaddr < start + seg.phdr.p_filesz {
+                return Some(seg.phdr.p_offset + (vaddr - start));
+            }
+        }
+        None
+    }
+}
+
+impl Segment {
+    pub fn seg_type(&self) -> SegmentType {
+        SegmentType::from(self.phdr.p_type)
+    }
 }
 
 impl std::ops::Index<usize> for Segments {
@@ -141,11 +186,13 @@ impl std::ops::Index<usize> for Segments {
     }
 }
 
-impl<'a> Iterator for &'a Segments {
+impl<'a> std::iter::IntoIterator for &'a Segments {
+
     type Item = &'a Segment;
+    type IntoIter = std::slice::Iter<'a, Segment>;
 
-    fn next(&mut self) -> Option<Self::Item> {
-        self.segs.iter().next()
+    fn into_iter(self) -> Self::IntoIter {
+        self.segs.iter()
     }
 }
 
@@ -159,5 +206,24 @@ impl<'a> std::iter::IntoIterator for &'a mut Segments {
     }
 }
 
-mod test {
+#[cfg(test)]
+mod tests {
+    use crate::parser::Parser;
+    use crate::parser::elf::elf_struct::{PF_R, PF_X};
+    use super::segtype::SegmentType;
+    #[test]
+    fn test_iter_segments() {
+        let parser = Parser::new("test/test");
+        let segments = &parser.segments;
+
+        assert_eq!(segments.into_iter().count(), segments.len());
+        assert!(segments.loadable().all(|s| s.seg_type() == SegmentType::PT_LOAD));
+        assert_eq!(segments.by_type(SegmentType::PT_INTERP).count(), 1);
+
+        // the text segment, entry point inside it
+        let entry = parser.ehdr.e_entry;
+        let text : Vec<_> = segments.loadable().filter(|s| s.phdr.p_flags & PF_X != 0).collect();
+        assert_eq!(segments.by_flag(PF_R | PF_X).filter(|s| s.seg_type() == SegmentType::PT_LOAD).count(), text.len());
+        assert!(text.iter().any(|s| entry >= s.phdr.p_vaddr && entry < s.phdr.p_vaddr + s.phdr.p_memsz));
+    }
 }
\ No newline at end of file
@@ -0,0 +1,139 @@
+use crate::parser::elf::symtable::SymSource;
+
+#[derive(Debug, Clone)]
+pub struct IndexedSymbol {
+    pub start  : u64,
+    pub end    : u64,     /* start + st_size, equal to start for unsized symbols */
+    pub name   : String,
+    pub source : SymSource,
+}
+
+impl IndexedSymbol {
+    pub fn size(&self) -> u64 {
+        self.end - self.start
+    }
+
+    pub fn contains(&self, addr : u64) -> bool {
+        self.start <= addr && addr < self.end
+    }
+}
+
+pub struct SymbolIndex {
+    syms    : Vec<IndexedSymbol>,   /* sorted by start, one per address */
+    max_end : Vec<u64>,             /* max_end[i] = largest end of syms[..=i] */
+}
+
+impl SymbolIndex {
+    pub fn new(mut syms : Vec<IndexedSymbol>) -> Self {
+        // aliases keep the sized entry, then whichever was pushed first
+        syms.sort_by(|a, b| a.start.cmp(&b.start).then(b.end.cmp(&a.end)));
+        syms.dedup_by(|a, b| a.start == b.start);
+
+        let mut max_end = Vec::with_capacity(syms.len());
+        let mut end = 0;
+        for s in &syms {
+            end = end.max(s.end);
+            max_end.push(end);
+        }
+        SymbolIndex { syms, max_end }
+    }
+
+    pub fn len(&self) -> usize {
+        self.syms.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.syms.is_empty()
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, IndexedSymbol> {
+        self.syms.iter()
+    }
+
+    pub fn at(&self, addr : u64) -> Option<&IndexedSymbol> {
+        // the symbol starting exactly at addr
+        let i = self.syms.partition_point(|s| s.start < addr);
+        self.syms.get(i).filter(|s| s.start == addr)
+    }
+
+    pub fn containing(&self, addr : u64) -> Option<&IndexedSymbol> {
+        // innermost sized symbol with addr in [start, end), unsized ones never contain anything
+        let mut i = self.syms.partition_point(|s| s.start <= addr);
+        while i > 0 && self.max_end[i - 1] > addr {
+            i -= 1;
+            if self.syms[i].contains(addr) {
+                return Some(&self.syms[i]);
+            }
+        }
+        None
+    }
+
+    pub fn in_range(&self, start : u64, end : u64) -> &[IndexedSymbol] {
+        // symbols starting in [start, end)
+        let lo = self.syms.partition_point(|s| s.start < start);
+        let hi = self.syms.partition_point(|s| s.start < end).max(lo);
+        &self.syms[lo..hi]
+    }
+
+    pub fn next(&self, addr : u64) -> Option<&IndexedSymbol> {
+        // first symbol starting after addr
+        let i = self.syms.partition_point(|s| s.start <= addr);
+        self.syms.get(i)
+    }
+
+    pub fn prev(&self, addr : u64) -> Option<&IndexedSymbol> {
+        // last symbol starting at or before addr, sized or not
+        let i = self.syms.partition_point(|s| s.start <= addr);
+        self.syms.get(i.checked_sub(1)?)
+    }
+}
+
+impl<'a> std::iter::IntoIterator for &'a SymbolIndex {
+
+    type Item = &'a IndexedSymbol;
+    type IntoIter = std::slice::Iter<'a, IndexedSymbol>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.syms.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sym(start : u64, size : u64, name : &str) -> IndexedSymbol {
+        IndexedSymbol { start, end : start + size, name : name.to_string(), source : SymSource::Symtab }
+    }
+
+    #[test]
+    fn test_symbol_index() {
+        // outer [0x100, 0x200) holds inner [0x120, 0x140), label at 0x150 is unsized
+        let index = SymbolIndex::new(vec![
+            sym(0x150, 0, "label"),
+            sym(0x120, 0x20, "inner"),
+            sym(0x100, 0x100, "outer"),
+            sym(0x100, 0, "alias"),
+            sym(0x300, 0x10, "last"),
+        ]);
+        let name = |s : Option<&IndexedSymbol>| s.map(|s| s.name.clone());
+
+        assert_eq!(index.len(), 4);
+        assert_eq!(name(index.at(0x100)), Some("outer".into()));
+        assert_eq!(name(index.containing(0x130)), Some("inner".into()));
+        assert_eq!(name(index.containing(0x140)), Some("outer".into()));
+        assert_eq!(name(index.containing(0x150)), Some("outer".into()));
+        assert_eq!(name(index.containing(0x200)), None);
+        assert_eq!(name(index.containing(0x30f)), Some("last".into()));
+        assert_eq!(name(index.containing(0x50)), None);
+
+        let range : Vec<&str> = index.in_range(0x110, 0x300).iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(range, ["inner", "label"]);
+        assert!(index.in_range(0x400, 0x100).is_empty());
+
+        assert_eq!(name(index.next(0x120)), Some("label".into()));
+        assert_eq!(name(index.next(0x300)), None);
+        assert_eq!(name(index.prev(0x160)), Some("label".into()));
+        assert_eq!(name(index.prev(0xff)), None);
+    }
+}
@@ -1,27 +1,172 @@
-use crate::parser::elf::elf_struct::Elf64Sym;
-// TODO: maybe can merge with dyn sym table
+use std::collections::{BTreeMap, HashMap};
+use std::mem;
+
+use crate::binbuf::{BinBuf, u32_at};
+use crate::parser::elf::elf_struct::{Elf64Sym, SHN_XINDEX, SHT_DYNSYM, SHT_SYMTAB_SHNDX};
+use crate::parser::elf::sections::Sections;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SymSource {
+    Symtab,     /* .symtab, everything the linker knew */
+    Dynsym,     /* .dynsym, what the dynamic loader sees */
+    Recovered,  /* rebuilt from .eh_frame/.dynsym/PLT/init arrays of a stripped binary */
+    Plt,        /* a PLT stub named after its GOT slot's relocation */
+}
+
+impl SymSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SymSource::Symtab    => "symtab",
+            SymSource::Dynsym    => "dynsym",
+            SymSource::Recovered => "recovered",
+            SymSource::Plt       => "plt",
+        }
+    }
+}
 
 #[derive(Debug)]
 pub struct Symbol {
-    // wrapper of dynsym and dynstr
+    // an entry of a symbol table with its name resolved
     pub sym : Elf64Sym,
     pub str : String,
+    pub shndx : usize,  /* st_shndx, or its .symtab_shndx entry when that is SHN_XINDEX */
 }
 
 impl Symbol {
     pub fn new(sym : Elf64Sym, str : String) -> Self {
-        Symbol { sym, str }
+        let shndx = sym.st_shndx as usize;
+        Symbol { sym, str, shndx }
     }
 }
 
-pub struct SymTables {
-    pub syms : Vec<Symbol>,
+pub struct SymbolTable {
+    pub source  : SymSource,
+    pub section : usize,    /* the SHT_SYMTAB/SHT_DYNSYM it was read from, 0 if recovered */
+    syms    : Vec<Symbol>,  /* private, every change goes through update() to keep the indexes right */
+    by_name : HashMap<String, Vec<usize>>,
+    by_addr : BTreeMap<u64, Vec<usize>>,
 }
 
-impl SymTables {
-    pub fn new(tables : Vec<Symbol>) -> Self{
-        SymTables {  
-            syms: tables,
+impl SymbolTable {
+    pub fn new(syms : Vec<Symbol>, source : SymSource, section : usize) -> Self {
+        let mut table = SymbolTable { source, section, syms, by_name : HashMap::new(), by_addr : BTreeMap::new() };
+        table.reindex();
+        table
+    }
+
+    fn reindex(&mut self) {
+        self.by_name.clear();
+        self.by_addr.clear();
+        for (i, s) in self.syms.iter().enumerate() {
+            if !s.str.is_empty() {
+                self.by_name.entry(s.str.clone()).or_default().push(i);
+            }
+            if s.sym.st_value != 0 {
+                self.by_addr.entry(s.sym.st_value).or_default().push(i);
+            }
         }
     }
+
+    pub fn update(&mut self, f : impl FnMut(&mut Symbol)) {
+        // change entries in place, e.g. move addresses after an insertion, then rebuild the indexes
+        self.syms.iter_mut().for_each(f);
+        self.reindex();
+    }
+
+    pub fn synthetic(syms : Vec<Symbol>) -> Self {
+        Self::new(syms, SymSource::Recovered, 0)
+    }
+
+    pub fn from_section(binbuf : &BinBuf, sections : &Sections, idx : usize) -> Option<Self> {
+        // entries of section idx, names from the string table its sh_link points at
+        let shdr = sections.secs.get(idx)?.shdr;
+        let source = if shdr.sh_type == SHT_DYNSYM { SymSource::Dynsym } else { SymSource::Symtab };
+        let strtab = sections.secs.get(shdr.sh_link as usize).map(|s| s.shdr);
+        let sz = mem::size_of::<Elf64Sym>();
+        let buf = &binbuf.buf;
+
+        let mut syms = vec![];
+        for i in 0..shdr.sh_size as usize / sz {
+            let off = shdr.sh_offset as usize + i * sz;
+            if off + sz > buf.len() {
+                break;
+            }
+            let sym = Elf64Sym::new(&buf[off..]);
+            let name = match strtab {
+                Some(s) if (sym.st_name as u64) < s.sh_size && ((s.sh_offset + sym.st_name as u64) as usize) < buf.len() => {
+                    binbuf.idx_to_string((s.sh_offset + sym.st_name as u64) as usize)
+                }
+                _ => String::new(),
+            };
+            let mut symbol = Symbol::new(sym, name);
+            if symbol.sym.st_shndx == SHN_XINDEX {
+                symbol.shndx = extended_shndx(buf, sections, idx, i).unwrap_or(0);
+            }
+            syms.push(symbol);
+        }
+        Some(Self::new(syms, source, idx))
+    }
+
+    pub fn is_synthetic(&self) -> bool {
+        self.source == SymSource::Recovered
+    }
+
+    pub fn len(&self) -> usize {
+        self.syms.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.syms.is_empty()
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, Symbol> {
+        self.syms.iter()
+    }
+
+    pub fn get(&self, idx : usize) -> Option<&Symbol> {
+        // entry idx of the table, what st_shndx-style indexes (relocations, groups) refer to
+        self.syms.get(idx)
+    }
+
+    pub fn by_name(&self, name : &str) -> impl Iterator<Item = &Symbol> {
+        // every entry with that name, e.g. a local and a global static of the same name
+        self.by_name.get(name).into_iter().flatten().map(|&i| &self.syms[i])
+    }
+
+    pub fn by_addr(&self, addr : u64) -> impl Iterator<Item = &Symbol> {
+        // entries whose st_value is exactly addr, aliases included
+        self.by_addr.get(&addr).into_iter().flatten().map(|&i| &self.syms[i])
+    }
+}
+
+impl<'a> std::iter::IntoIterator for &'a SymbolTable {
+
+    type Item = &'a Symbol;
+    type IntoIter = std::slice::Iter<'a, Symbol>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.syms.iter()
+    }
+}
+
+pub fn extended_shndx(buf : &[u8], sections : &Sections, symtab : usize, sym : usize) -> Option<usize> {
+    // st_shndx of SHN_XINDEX, the real index is entry sym of the SHT_SYMTAB_SHNDX section linked to symtab
+    let table = sections.by_type(SHT_SYMTAB_SHNDX).find(|s| s.shdr.sh_link as usize == symtab)?;
+    let off = (table.shdr.sh_offset as usize).checked_add(sym.checked_mul(4)?)?;
+    u32_at(buf, off).map(|shndx| shndx as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::Parser;
+    #[test]
+    fn test_update_reindexes() {
+        let mut parser = Parser::new("test/testgot");
+        let symtab = parser.symtab.as_mut().unwrap();
+        let main = symtab.by_name("main").next().unwrap().sym.st_value;
+        symtab.update(|s| if s.sym.st_value >= main { s.sym.st_value += 0x1000 });
+        assert!(symtab.by_addr(main).next().is_none());
+        assert_eq!(symtab.by_addr(main + 0x1000).next().unwrap().str, "main");
+        assert_eq!(symtab.by_name("main").next().unwrap().sym.st_value, main + 0x1000);
+    }
 }
@@ -0,0 +1,129 @@
+use crate::binbuf::{BinBuf, u16_at, u32_at, c_str_at};
+use crate::parser::elf::dynamic::{DynEntries, DT_STRTAB, DT_STRSZ, DT_VERSYM, DT_VERDEF, DT_VERDEFNUM, DT_VERNEED, DT_VERNEEDNUM};
+use crate::parser::elf::segments::Segments;
+
+/*
+.gnu.version   (DT_VERSYM)  --> u16 per .dynsym entry, index of its version, bit 15 hides it
+.gnu.version_d (DT_VERDEF)  --> Verdef  { version, flags, ndx, cnt, hash, aux, next } + Verdaux { name, next }
+.gnu.version_r (DT_VERNEED) --> Verneed { version, cnt, file, aux, next } + Vernaux { hash, flags, other, name, next }
+aux/next are byte offsets from the current record, names are offsets into .dynstr
+ */
+pub const VER_NDX_LOCAL  : u16 = 0;         /* Symbol is local */
+pub const VER_NDX_GLOBAL : u16 = 1;         /* Symbol is global and unversioned */
+pub const VERSYM_HIDDEN  : u16 = 0x8000;    /* Not the default version, only explicit references bind */
+pub const VER_FLG_BASE   : u16 = 0x1;       /* Version of the file itself */
+pub const VER_FLG_WEAK   : u16 = 0x2;       /* Weak version reference */
+
+#[derive(Debug)]
+pub struct VersionDef {
+    pub ndx   : u16,
+    pub flags : u16,
+    pub name  : String,
+}
+
+#[derive(Debug)]
+pub struct VersionNeed {
+    pub ndx   : u16,        /* vna_other, what .gnu.version entries refer to */
+    pub flags : u16,
+    pub file  : String,     /* soname expected to define it */
+    pub name  : String,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum SymVersion<'a> {
+    Local,
+    Global,                                     /* unversioned */
+    Defined { name : &'a str, hidden : bool },  /* name@@VER, or name@VER when hidden */
+    Needed { name : &'a str, file : &'a str, weak : bool },
+}
+
+#[derive(Default)]
+pub struct Versions {
+    pub versym : Vec<u16>,
+    pub defs   : Vec<VersionDef>,
+    pub needs  : Vec<VersionNeed>,
+}
+
+impl Versions {
+    pub fn new(binbuf : &BinBuf, segments : &Segments, dynamic : &DynEntries, nsyms : usize) -> Option<Self> {
+        // located through PT_DYNAMIC so stripped section headers do not matter, None if unversioned
+        let buf = &binbuf.buf;
+        let val = |tag| dynamic.get(tag).map(|e| e.entry.d_val);
+        let off = |tag| val(tag).and_then(|a| segments.vaddr_to_offset(a)).map(|o| o as usize);
+
+        let strtab = off(DT_STRTAB)?;
+        let strsz = val(DT_STRSZ).unwrap_or(0) as usize;
+        let str_at = |name : u32| -> String {
+            if name as usize >= strsz {
+                return String::new();
+            }
+            c_str_at(buf, strtab + name as usize).unwrap_or_default()
+        };
+
+        let versym_off = off(DT_VERSYM)?;
+        let mut versions = Versions::default();
+
+        if let (Some(mut vd), Some(num)) = (off(DT_VERDEF), val(DT_VERDEFNUM)) {
+            for _ in 0..num {
+                let (flags, ndx, aux, next) = (u16_at(buf, vd + 2)?, u16_at(buf, vd + 4)?, u32_at(buf, vd + 12)?, u32_at(buf, vd + 16)?);
+                // the first Verdaux names the version, the others its parents
+                let name = str_at(u32_at(buf, vd + aux as usize)?);
+                versions.defs.push(VersionDef { ndx, flags, name });
+                if next == 0 {
+                    break;
+                }
+                vd += next as usize;
+            }
+        }
+
+        if let (Some(mut vn), Some(num)) = (off(DT_VERNEED), val(DT_VERNEEDNUM)) {
+            for _ in 0..num {
+                let (cnt, file, aux, next) = (u16_at(buf, vn + 2)?, u32_at(buf, vn + 4)?, u32_at(buf, vn + 8)?, u32_at(buf, vn + 12)?);
+                let file = str_at(file);
+                let mut va = vn + aux as usize;
+                for _ in 0..cnt {
+                    let (flags, ndx, name, vnext) = (u16_at(buf, va + 4)?, u16_at(buf, va + 6)?, u32_at(buf, va + 8)?, u32_at(buf, va + 12)?);
+                    versions.needs.push(VersionNeed { ndx, flags, file : file.clone(), name : str_at(name) });
+                    if vnext == 0 {
+                        break;
+                    }
+                    va += vnext as usize;
+                }
+                if next == 0 {
+                    break;
+                }
+                vn += next as usize;
+            }
+        }
+
+        // DT_VERSYM has no size of its own, one entry per .dynsym symbol
+        for i in 0..nsyms {
+            versions.versym.push(u16_at(buf, versym_off + i * 2)?);
+        }
+        Some(versions)
+    }
+
+    pub fn of(&self, sym : usize) -> SymVersion<'_> {
+        let v = match self.versym.get(sym) {
+            Some(&v) => v,
+            None     => return SymVersion::Global,
+        };
+        let ndx = v & !VERSYM_HIDDEN;
+        match ndx {
+            VER_NDX_LOCAL  => return SymVersion::Local,
+            VER_NDX_GLOBAL => return SymVersion::Global,
+            _ => {}
+        }
+        if let Some(d) = self.defs.iter().find(|d| d.ndx == ndx) {
+            // the base version is the file's own name, symbols of it count as unversioned
+            if d.flags & VER_FLG_BASE != 0 {
+                return SymVersion::Global;
+            }
+            return SymVersion::Defined { name : &d.name, hidden : v & VERSYM_HIDDEN != 0 };
+        }
+        match self.needs.iter().find(|n| n.ndx == ndx) {
+            Some(n) => SymVersion::Needed { name : &n.name, file : &n.file, weak : n.flags & VER_FLG_WEAK != 0 },
+            None    => SymVersion::Global,
+        }
+    }
+}
@@ -0,0 +1,291 @@
+use colored::Colorize;
+
+use crate::binbuf::{u64_at, c_str};
+use crate::parser::Parser;
+
+/* appended by scripts/sign-file after the ELF image */
+const SIG_MAGIC    : &[u8] = b"~Module signature appended~\n";
+const SIG_INFO_LEN : usize = 12;     /* struct module_signature */
+
+/* struct modversion_info in __versions */
+const MODVERSION_LEN : usize = 64;
+
+/* offset of name in struct module, after state and the list_head */
+const THIS_MODULE_NAME : usize = 24;
+const MODULE_NAME_LEN  : usize = 56;
+
+/* id_type of struct module_signature */
+const PKEY_ID_PGP   : u8 = 0;
+const PKEY_ID_X509  : u8 = 1;
+const PKEY_ID_PKCS7 : u8 = 2;
+
+pub struct ModuleSignature {
+    pub id_type : u8,
+    pub offset  : usize,    /* where the signature starts, i.e. the end of the signed ELF */
+    pub data    : Vec<u8>,
+}
+
+pub struct Signer {
+    pub issuer    : Vec<(String, String)>,    /* (attribute, value) of the issuer name */
+    pub serial    : String,
+    pub digest    : String,
+    pub algorithm : String,
+}
+
+pub struct ThisModule {
+    pub name  : String,
+    pub hooks : Vec<(u64, String)>,     /* relocated function pointers, init_module/cleanup_module */
+}
+
+impl Parser {
+    pub fn is_kernel_module(&self) -> bool {
+        self.is_relocatable() && self.sections.get_section(".gnu.linkonce.this_module").is_some()
+    }
+
+    pub fn modinfo(&self) -> Vec<(String, String)> {
+        // NUL separated key=value strings
+        let data = match self.section_data(".modinfo") {
+            Some(data) => data,
+            None => return vec![],
+        };
+        data.split(|&c| c == 0)
+            .filter_map(|kv| {
+                let kv = String::from_utf8_lossy(kv);
+                kv.split_once('=').map(|(k, v)| (k.to_string(), v.to_string()))
+            })
+            .collect()
+    }
+
+    pub fn modversions(&self) -> Vec<(u64, String)> {
+        // (crc, symbol) the module was built against
+        let data = match self.section_data("__versions") {
+            Some(data) => data,
+            None => return vec![],
+        };
+        data.chunks_exact(MODVERSION_LEN)
+            .filter_map(|v| Some((u64_at(v, 0)?, c_str(&v[8..]))))
+            .collect()
+    }
+
+    pub fn this_module(&self) -> Option<ThisModule> {
+        let data = self.section_data(".gnu.linkonce.this_module")?;
+        let name = c_str(data.get(THIS_MODULE_NAME..THIS_MODULE_NAME + MODULE_NAME_LEN)?);
+
+        // init/exit are only known through the relocations against the section, their offsets move between kernels
+        let idx = self.sections.index_of(".gnu.linkonce.this_module")?;
+        let hooks = self.relas_for(idx).map(|r| (r.rela.r_offset, r.sym_name.clone())).collect();
+
+        Some(ThisModule { name, hooks })
+    }
+
+    pub fn module_signature(&self) -> Option<ModuleSignature> {
+        let buf = &self.binbuf.buf;
+        let info_end = buf.len().checked_sub(SIG_MAGIC.len())?;
+        if &buf[info_end..] != SIG_MAGIC {
+            return None;
+        }
+        let info = &buf[info_end.checked_sub(SIG_INFO_LEN)?..info_end];
+        let sig_len = u32::from_be_bytes(info[8..12].try_into().unwrap()) as usize;
+        let offset = (info_end - SIG_INFO_LEN).checked_sub(sig_len)?;
+
+        Some(ModuleSignature {
+            id_type : info[2],
+            offset,
+            data    : buf[offset..offset + sig_len].to_vec(),
+        })
+    }
+
+    pub fn show_module(&self) -> &Self {
+        if !self.is_kernel_module() {
+            println!("not a kernel module");
+            return self;
+        }
+
+        if let Some(this) = self.this_module() {
+            println!("{:<16}{}", "Module:".red(), this.name.blue());
+            for (off, sym) in &this.hooks {
+                println!("{:<16}{} at this_module+{:#x}", "", self.display_name(sym).green(), off);
+            }
+        }
+
+        let info = self.modinfo();
+        if !info.is_empty() {
+            println!();
+            println!("{} contains {} entries:", ".modinfo".red(), info.len());
+            let width = info.iter().map(|(k, _)| k.len()).max().unwrap_or(0) + 1;
+            for (key, value) in &info {
+                println!("  {:<width$} {}", format!("{}:", key).yellow(), value, width = width);
+            }
+        }
+
+        let versions = self.modversions();
+        if !versions.is_empty() {
+            println!();
+            println!("{} contains {} entries:", "__versions".red(), versions.len());
+            for (crc, name) in &versions {
+                println!("  {:#010x}  {}", crc, name);
+            }
+        }
+
+        println!();
+        match self.module_signature() {
+            Some(sig) => {
+                let kind = match sig.id_type {
+                    PKEY_ID_PGP   => "PGP",
+                    PKEY_ID_X509  => "X.509",
+                    PKEY_ID_PKCS7 => "PKCS#7",
+                    _             => "unknown",
+                };
+                println!("{:<16}{} {} bytes, signed image ends at {:#x}", "Signature:".red(), kind, sig.data.len(), sig.offset);
+                if let Some(signer) = sig.signer() {
+                    let issuer : Vec<String> = signer.issuer.iter().map(|(k, v)| format!("{}={}", k, v)).collect();
+                    println!("{:<16}{}", "Signer:".red(), issuer.join(", ").blue());
+                    println!("{:<16}{}", "Serial:".red(), signer.serial);
+                    println!("{:<16}{} with {}", "Algorithm:".red(), signer.digest, signer.algorithm);
+                }
+            }
+            None => println!("{:<16}{}", "Signature:".red(), "unsigned".yellow()),
+        }
+        self
+    }
+}
+
+impl ModuleSignature {
+    pub fn signer(&self) -> Option<Signer> {
+        // first SignerInfo of a PKCS#7 SignedData, as written by sign-file
+        if self.id_type != PKEY_ID_PKCS7 {
+            return None;
+        }
+        let content_info = der_children(der(&self.data, 0)?.1)?;
+        let signed_data = der_children(der(content_info.get(1)?.1, 0)?.1)?;
+        // version, digestAlgorithms, contentInfo, [0] certificates, [1] crls, signerInfos
+        let signer_infos = signed_data.last()?;
+        let signer_info = der_children(der(signer_infos.1, 0)?.1)?;
+
+        let issuer_and_serial = der_children(signer_info.get(1)?.1)?;
+        let mut issuer = vec![];
+        for rdn in der_children(issuer_and_serial.first()?.1)? {
+            for attr in der_children(rdn.1)? {
+                let attr = der_children(attr.1)?;
+                let value = String::from_utf8_lossy(attr.get(1)?.1).to_string();
+                issuer.push((oid_name(attr.first()?.1), value));
+            }
+        }
+        let serial = issuer_and_serial.get(1)?.1.iter().map(|b| format!("{:02x}", b)).collect();
+        let algo = |i : usize| -> Option<String> { Some(oid_name(der_children(signer_info.get(i)?.1)?.first()?.1)) };
+
+        Some(Signer {
+            issuer,
+            serial,
+            digest    : algo(2)?,
+            // signedAttrs [0] sits between the digest and signature algorithm when present
+            algorithm : algo(if signer_info.get(3)?.0 == 0xa0 { 4 } else { 3 })?,
+        })
+    }
+}
+
+fn der(buf : &[u8], pos : usize) -> Option<(u8, &[u8], usize)> {
+    // (tag, content, next) of the DER element at pos
+    let tag = *buf.get(pos)?;
+    let first = *buf.get(pos + 1)? as usize;
+    let (len, start) = if first & 0x80 == 0 {
+        (first, pos + 2)
+    } else {
+        let n = first & 0x7f;
+        let len = buf.get(pos + 2..pos + 2 + n)?.iter().fold(0usize, |acc, &b| acc << 8 | b as usize);
+        (len, pos + 2 + n)
+    };
+    let content = buf.get(start..start + len)?;
+    Some((tag, content, start + len))
+}
+
+fn der_children(buf : &[u8]) -> Option<Vec<(u8, &[u8])>> {
+    let mut out = vec![];
+    let mut pos = 0;
+    while pos < buf.len() {
+        let (tag, content, next) = der(buf, pos)?;
+        out.push((tag, content));
+        pos = next;
+    }
+    Some(out)
+}
+
+fn oid_name(oid : &[u8]) -> String {
+    // dotted form, named when it is one of the usual signing OIDs
+    let mut arcs = vec![];
+    if let Some(&first) = oid.first() {
+        arcs.push((first / 40) as u64);
+        arcs.push((first % 40) as u64);
+    }
+    let mut v : u64 = 0;
+    for &b in oid.iter().skip(1) {
+        v = v << 7 | (b & 0x7f) as u64;
+        if b & 0x80 == 0 {
+            arcs.push(v);
+            v = 0;
+        }
+    }
+    let dotted = arcs.iter().map(|a| a.to_string()).collect::<Vec<_>>().join(".");
+
+    let name = match dotted.as_str() {
+        "2.5.4.3"                => "CN",
+        "2.5.4.6"                => "C",
+        "2.5.4.10"               => "O",
+        "2.5.4.11"               => "OU",
+        "1.2.840.113549.1.9.1"   => "emailAddress",
+        "1.3.14.3.2.26"          => "sha1",
+        "2.16.840.1.101.3.4.2.1" => "sha256",
+        "2.16.840.1.101.3.4.2.2" => "sha384",
+        "2.16.840.1.101.3.4.2.3" => "sha512",
+        "2.16.840.1.101.3.4.2.4" => "sha224",
+        "2.16.840.1.101.3.4.2.8" => "sha3-256",
+        "1.2.840.113549.1.1.1"   => "rsaEncryption",
+        "1.2.840.113549.1.1.11"  => "sha256WithRSAEncryption",
+        "1.2.840.10045.2.1"      => "ecPublicKey",
+        "1.2.840.10045.4.3.2"    => "ecdsa-with-SHA256",
+        "1.2.840.10045.4.3.3"    => "ecdsa-with-SHA384",
+        "1.3.101.112"            => "ed25519",
+        _                        => return dotted,
+    };
+    name.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    /*
+    test/testmod.ko is test/testmod.c built with gcc -O1 -c, then signed like sign-file does:
+    openssl cms -sign -binary -noattr -nocerts -outform DER -md sha256 with an RSA key
+    whose subject is O=ExParser, CN=ExParser test signing key
+     */
+    #[test]
+    fn test_kernel_module() {
+        let parser = Parser::new("test/testmod.ko");
+        assert!(parser.is_kernel_module());
+        assert!(!Parser::new("test/testrel.o").is_kernel_module());
+
+        let info = parser.modinfo();
+        let get = |key : &str| info.iter().filter(|(k, _)| k == key).map(|(_, v)| v.as_str()).collect::<Vec<_>>();
+        assert_eq!(get("license"), ["GPL"]);
+        assert_eq!(get("depends"), ["usbcore,hid"]);
+        assert_eq!(get("alias").len(), 2);
+        assert_eq!(get("vermagic"), ["6.1.0-18-amd64 SMP preempt mod_unload modversions "]);
+
+        let versions = parser.modversions();
+        assert_eq!(versions[1], (0x92997ed8, "_printk".to_string()));
+
+        let this = parser.this_module().unwrap();
+        assert_eq!(this.name, "testmod");
+        let hooks : Vec<&str> = this.hooks.iter().map(|h| h.1.as_str()).collect();
+        assert!(hooks.contains(&"init_module") && hooks.contains(&"cleanup_module"));
+
+        let sig = parser.module_signature().unwrap();
+        assert_eq!(sig.id_type, PKEY_ID_PKCS7);
+        assert_eq!(sig.offset, 3072);
+        assert_eq!(&parser.binbuf.buf[..4], b"\x7fELF");
+        let signer = sig.signer().unwrap();
+        assert_eq!(signer.issuer, [("O".to_string(), "ExParser".to_string()), ("CN".to_string(), "ExParser test signing key".to_string())]);
+        assert_eq!(signer.digest, "sha256");
+        assert_eq!(signer.algorithm, "rsaEncryption");
+    }
+}
@@ -0,0 +1,342 @@
+use colored::Colorize;
+
+use crate::binbuf::{u32_at, u64_at, c_str_at};
+use crate::parser::elf::elf_struct::{EM_X86_64, EM_AARCH64, EM_RISCV};
+
+/*
+/etc/ld.so.cache as written by ldconfig, three layouts exist:
+  old       "ld.so-1.7.0", nlibs, file_entry[nlibs], strings        (glibc < 2.32 with --format=old)
+  compat    the old one followed by the new one at an 8-byte boundary (glibc < 2.32 default)
+  new       "glibc-ld.so.cache1.1" header, file_entry_new[nlibs], strings (glibc >= 2.32)
+
+old entries  --> flags, key (soname), value (path), offsets relative to the end of the entries
+new header   --> nlibs, len_strings, flags, extension_offset
+new entries  --> flags, key, value, osversion, hwcap, offsets relative to the new header
+extension    --> magic, count, (tag, flags, offset, size)[count], tag 0 names the generator,
+                 tag 1 lists the glibc-hwcaps subdirectories entries refer to
+ */
+const MAGIC_OLD : &[u8] = b"ld.so-1.7.0";
+const MAGIC_NEW : &[u8] = b"glibc-ld.so.cache1.1";
+const HEADER_OLD : usize = 16;
+const ENTRY_OLD : usize = 12;
+const HEADER_NEW : usize = 48;
+const ENTRY_NEW : usize = 24;
+
+const EXTENSION_MAGIC : u32 = 0xeaa42174;
+const EXTENSION_TAG_GENERATOR : u32 = 0;
+const EXTENSION_TAG_GLIBC_HWCAPS : u32 = 1;
+
+/* hwcap of a new entry: bit 62 says the low 32 bits index the glibc-hwcaps subdirectories */
+const HWCAP_EXTENSION : u64 = 1 << 62;
+
+/* entry flags, FLAG_TYPE_MASK selects the libc, FLAG_REQUIRED_MASK the ABI */
+pub const FLAG_TYPE_MASK      : i32 = 0x00ff;
+pub const FLAG_LIBC4          : i32 = 0x0000;
+pub const FLAG_ELF            : i32 = 0x0001;
+pub const FLAG_ELF_LIBC5      : i32 = 0x0002;
+pub const FLAG_ELF_LIBC6      : i32 = 0x0003;
+pub const FLAG_REQUIRED_MASK  : i32 = 0xff00;
+pub const FLAG_X8664_LIB64    : i32 = 0x0300;
+pub const FLAG_AARCH64_LIB64  : i32 = 0x0a00;
+pub const FLAG_RISCV_FLOAT_ABI_SOFT   : i32 = 0x0f00;
+pub const FLAG_RISCV_FLOAT_ABI_DOUBLE : i32 = 0x1000;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CacheFormat {
+    Old,
+    Compat,
+    New,
+}
+
+#[derive(Debug)]
+pub struct CacheEntry {
+    pub flags     : i32,
+    pub key       : String,         /* soname */
+    pub path      : String,
+    pub osversion : u32,            /* minimum kernel from the ABI note, 0 if none */
+    pub hwcap     : u64,
+    pub hwcaps    : Option<String>, /* glibc-hwcaps subdirectory, e.g. x86-64-v3 */
+}
+
+pub struct LdCache {
+    pub format    : CacheFormat,
+    pub entries   : Vec<CacheEntry>,
+    pub generator : Option<String>, /* ldconfig version that wrote it */
+}
+
+impl LdCache {
+    pub fn open(path : &str) -> Option<Self> {
+        Self::parse(&std::fs::read(path).ok()?)
+    }
+
+    pub fn parse(buf : &[u8]) -> Option<Self> {
+        if buf.starts_with(MAGIC_NEW) {
+            return Self::parse_new(buf, 0, CacheFormat::New);
+        }
+        if !buf.starts_with(MAGIC_OLD) {
+            return None;
+        }
+        // the new layout behind the old entries is the complete one when present
+        let nlibs = u32_at(buf, 12)? as usize;
+        let end = HEADER_OLD + nlibs * ENTRY_OLD;
+        let aligned = (end + 7) & !7;
+        if buf.get(aligned..).is_some_and(|b| b.starts_with(MAGIC_NEW)) {
+            return Self::parse_new(buf, aligned, CacheFormat::Compat);
+        }
+
+        let mut entries = vec![];
+        for i in 0..nlibs {
+            let off = HEADER_OLD + i * ENTRY_OLD;
+            entries.push(CacheEntry {
+                flags     : u32_at(buf, off)? as i32,
+                key       : c_str_at(buf, end + u32_at(buf, off + 4)? as usize)?,
+                path      : c_str_at(buf, end + u32_at(buf, off + 8)? as usize)?,
+                osversion : 0,
+                hwcap     : 0,
+                hwcaps    : None,
+            });
+        }
+        Some(LdCache { format : CacheFormat::Old, entries, generator : None })
+    }
+
+    fn parse_new(buf : &[u8], base : usize, format : CacheFormat) -> Option<Self> {
+        let nlibs = u32_at(buf, base + 20)? as usize;
+        let ext = u32_at(buf, base + 32)? as usize;
+
+        // the extension is optional, glibc 2.32 does not write one
+        let mut generator = None;
+        let mut subdirs = vec![];
+        if ext != 0 && u32_at(buf, base + ext) == Some(EXTENSION_MAGIC) {
+            for i in 0..u32_at(buf, base + ext + 4)? as usize {
+                let sec = base + ext + 8 + i * 16;
+                let (tag, off, size) = (u32_at(buf, sec)?, u32_at(buf, sec + 8)? as usize, u32_at(buf, sec + 12)? as usize);
+                match tag {
+                    EXTENSION_TAG_GENERATOR => {
+                        let bytes = buf.get(base + off..base + off + size)?;
+                        generator = Some(String::from_utf8_lossy(bytes).into_owned());
+                    }
+                    EXTENSION_TAG_GLIBC_HWCAPS => {
+                        for j in 0..size / 4 {
+                            subdirs.push(c_str_at(buf, base + u32_at(buf, base + off + j * 4)? as usize)?);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        let mut entries = vec![];
+        for i in 0..nlibs {
+            let off = base + HEADER_NEW + i * ENTRY_NEW;
+            let hwcap = u64_at(buf, off + 16)?;
+            let hwcaps = match hwcap & HWCAP_EXTENSION {
+                0 => None,
+                _ => subdirs.get((hwcap & 0xffff_ffff) as usize).cloned(),
+            };
+            entries.push(CacheEntry {
+                flags     : u32_at(buf, off)? as i32,
+                key       : c_str_at(buf, base + u32_at(buf, off + 4)? as usize)?,
+                path      : c_str_at(buf, base + u32_at(buf, off + 8)? as usize)?,
+                osversion : u32_at(buf, off + 12)?,
+                hwcap,
+                hwcaps,
+            });
+        }
+        Some(LdCache { format, entries, generator })
+    }
+
+    pub fn lookup(&self, soname : &str, machine : u16) -> Option<&str> {
+        // baseline entry of the right ABI, hwcaps subdirectories depend on the CPU it runs on
+        let abis = abi_flags(machine);
+        self.entries.iter()
+            .filter(|e| e.key == soname && e.flags & FLAG_TYPE_MASK == FLAG_ELF_LIBC6)
+            .filter(|e| abis.contains(&(e.flags & FLAG_REQUIRED_MASK)))
+            .min_by_key(|e| e.hwcap != 0)
+            .map(|e| e.path.as_str())
+    }
+
+    pub fn show(&self, path : &str) {
+        // ldconfig -p layout
+        let format = match self.format {
+            CacheFormat::Old    => "ld.so-1.7.0",
+            CacheFormat::Compat => "ld.so-1.7.0 + glibc-ld.so.cache1.1",
+            CacheFormat::New    => "glibc-ld.so.cache1.1",
+        };
+        println!("{} libs found in cache `{}' ({})", self.entries.len(), path, format.yellow());
+        for e in &self.entries {
+            println!("\t{} ({}) => {}", e.key.blue(), entry_desc(e), e.path);
+        }
+        if let Some(generator) = &self.generator {
+            println!("Cache generated by: {}", generator);
+        }
+    }
+}
+
+pub fn get_flags_str(flags : i32) -> String {
+    let kind = match flags & FLAG_TYPE_MASK {
+        FLAG_LIBC4     => "libc4",
+        FLAG_ELF       => "ELF",
+        FLAG_ELF_LIBC5 => "libc5",
+        FLAG_ELF_LIBC6 => "libc6",
+        _              => "unknown",
+    };
+    let abi = match flags & FLAG_REQUIRED_MASK {
+        0x0000 => "",
+        0x0100 | 0x0400 | 0x0500 | 0x0700 => ",64bit",
+        0x0200 => ",IA-64",
+        0x0300 => ",x86-64",
+        0x0600 => ",N32",
+        0x0800 => ",x32",
+        0x0900 => ",hard-float",
+        0x0a00 => ",AArch64",
+        0x0b00 | 0x0f00 | 0x1100 => ",soft-float",
+        0x0c00 => ",nan2008",
+        0x0d00 => ",N32,nan2008",
+        0x0e00 => ",64bit,nan2008",
+        0x1000 | 0x1200 => ",double-float",
+        _      => ",unknown",
+    };
+    format!("{}{}", kind, abi)
+}
+
+fn entry_desc(e : &CacheEntry) -> String {
+    // flags, then the hwcaps and OS ABI ldconfig -p shows
+    let mut desc = get_flags_str(e.flags);
+    match &e.hwcaps {
+        Some(dir) => desc += &format!(", hwcap: \"{}\"", dir),
+        None if e.hwcap != 0 => desc += &format!(", hwcap: {:#018x}", e.hwcap),
+        None => {}
+    }
+    if e.osversion != 0 {
+        let os = ["Linux", "Hurd", "Solaris", "FreeBSD", "kNetBSD", "Syllable"].get((e.osversion >> 24) as usize).unwrap_or(&"Unknown OS");
+        desc += &format!(", OS ABI: {} {}.{}.{}", os, (e.osversion >> 16) & 0xff, (e.osversion >> 8) & 0xff, e.osversion & 0xff);
+    }
+    desc
+}
+
+fn abi_flags(machine : u16) -> &'static [i32] {
+    // FLAG_REQUIRED_MASK bits ldconfig gives 64-bit libraries of a machine
+    match machine {
+        EM_X86_64  => &[FLAG_X8664_LIB64],
+        EM_AARCH64 => &[FLAG_AARCH64_LIB64],
+        EM_RISCV   => &[FLAG_RISCV_FLOAT_ABI_SOFT, FLAG_RISCV_FLOAT_ABI_DOUBLE],
+        _          => &[],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn old_cache(entries : &[(i32, &str, &str)]) -> Vec<u8> {
+        // "ld.so-1.7.0" layout, strings right after the entries
+        let mut buf = MAGIC_OLD.to_vec();
+        buf.push(0);
+        buf.extend((entries.len() as u32).to_le_bytes());
+        let mut strings = vec![];
+        for (flags, key, path) in entries {
+            buf.extend(flags.to_le_bytes());
+            for s in [key, path] {
+                buf.extend((strings.len() as u32).to_le_bytes());
+                strings.extend(s.as_bytes());
+                strings.push(0);
+            }
+        }
+        buf.extend(strings);
+        buf
+    }
+
+    fn new_cache(entries : &[(i32, &str, &str, Option<&str>)], generator : &str) -> Vec<u8> {
+        // "glibc-ld.so.cache1.1" layout: header, entries, strings, then the extension naming generator and hwcaps
+        let subdirs : Vec<&str> = entries.iter().filter_map(|e| e.3).collect();
+        let strings_at = HEADER_NEW + entries.len() * ENTRY_NEW;
+        let mut strings = vec![];
+        let mut add = |s : &str| -> u32 {
+            let off = (strings_at + strings.len()) as u32;
+            strings.extend(s.as_bytes());
+            strings.push(0);
+            off
+        };
+
+        let mut table = vec![];
+        for (i, (flags, key, path, hwcaps)) in entries.iter().enumerate() {
+            table.extend(flags.to_le_bytes());
+            table.extend(add(key).to_le_bytes());
+            table.extend(add(path).to_le_bytes());
+            table.extend(0u32.to_le_bytes());
+            let hwcap = if hwcaps.is_some() { HWCAP_EXTENSION | subdirs.iter().position(|&d| Some(d) == entries[i].3).unwrap() as u64 } else { 0 };
+            table.extend(hwcap.to_le_bytes());
+        }
+        let dirs : Vec<u32> = subdirs.iter().map(|d| add(d)).collect();
+        let len_strings = strings.len();
+        strings.resize(len_strings.next_multiple_of(4), 0);
+
+        // extension: magic, count, (tag, flags, offset, size) per section, then the section bodies
+        let ext = strings_at + strings.len();
+        let body = ext + 8 + 2 * 16;
+        let mut extension = vec![];
+        extension.extend(EXTENSION_MAGIC.to_le_bytes());
+        extension.extend(2u32.to_le_bytes());
+        for (tag, off, size) in [(EXTENSION_TAG_GENERATOR, body, generator.len()), (EXTENSION_TAG_GLIBC_HWCAPS, body + generator.len(), dirs.len() * 4)] {
+            for v in [tag, 0, off as u32, size as u32] {
+                extension.extend(v.to_le_bytes());
+            }
+        }
+        extension.extend(generator.as_bytes());
+        dirs.iter().for_each(|d| extension.extend(d.to_le_bytes()));
+
+        let mut buf = MAGIC_NEW.to_vec();
+        buf.extend((entries.len() as u32).to_le_bytes());
+        buf.extend((len_strings as u32).to_le_bytes());
+        buf.extend([0u8; 4]);
+        buf.extend((ext as u32).to_le_bytes());
+        buf.extend([0u8; 12]);
+        buf.extend(table);
+        buf.extend(strings);
+        buf.extend(extension);
+        buf
+    }
+
+    #[test]
+    fn test_old_format() {
+        let buf = old_cache(&[(0x303, "libc.so.6", "/lib64/libc.so.6"), (0x3, "libc.so.6", "/lib/libc.so.6")]);
+        let cache = LdCache::parse(&buf).unwrap();
+        assert_eq!(cache.format, CacheFormat::Old);
+        assert_eq!(cache.entries.len(), 2);
+        assert_eq!(cache.entries[1].path, "/lib/libc.so.6");
+        assert_eq!(cache.lookup("libc.so.6", EM_X86_64), Some("/lib64/libc.so.6"));
+        assert_eq!(cache.lookup("libm.so.6", EM_X86_64), None);
+        assert_eq!(get_flags_str(cache.entries[0].flags), "libc6,x86-64");
+        assert_eq!(get_flags_str(cache.entries[1].flags), "libc6");
+        assert!(LdCache::parse(b"not a cache").is_none());
+    }
+    #[test]
+    fn test_new_format() {
+        // a cache pulled from another machine: x86-64 with an x86-64-v3 build of libc, and an aarch64 libc
+        let entries = [
+            (0x303, "libc.so.6", "/usr/lib/x86_64-linux-gnu/glibc-hwcaps/x86-64-v3/libc.so.6", Some("x86-64-v3")),
+            (0x303, "libc.so.6", "/lib/x86_64-linux-gnu/libc.so.6", None),
+            (0xa03, "libc.so.6", "/lib/aarch64-linux-gnu/libc.so.6", None),
+        ];
+        let buf = new_cache(&entries, "ldconfig (GNU libc) 2.36");
+        let cache = LdCache::parse(&buf).unwrap();
+        assert_eq!(cache.format, CacheFormat::New);
+        assert_eq!(cache.generator.as_deref(), Some("ldconfig (GNU libc) 2.36"));
+        assert_eq!(cache.entries[0].hwcaps.as_deref(), Some("x86-64-v3"));
+        assert_eq!(entry_desc(&cache.entries[0]), "libc6,x86-64, hwcap: \"x86-64-v3\"");
+        assert_eq!(cache.entries[1].hwcaps, None);
+        // the baseline build, not the one that depends on the CPU
+        assert_eq!(cache.lookup("libc.so.6", EM_X86_64), Some("/lib/x86_64-linux-gnu/libc.so.6"));
+        assert_eq!(cache.lookup("libc.so.6", EM_AARCH64), Some("/lib/aarch64-linux-gnu/libc.so.6"));
+
+        // compat: the new cache after the old entries at an 8-byte boundary wins
+        let mut compat = old_cache(&[(0x303, "libold.so.1", "/lib/libold.so.1")]);
+        compat.truncate(HEADER_OLD + ENTRY_OLD);
+        compat.resize(compat.len().next_multiple_of(8), 0);
+        compat.extend(&buf);
+        let cache = LdCache::parse(&compat).unwrap();
+        assert_eq!(cache.format, CacheFormat::Compat);
+        assert_eq!(cache.entries.len(), 3);
+        assert_eq!(cache.lookup("libc.so.6", EM_AARCH64), Some("/lib/aarch64-linux-gnu/libc.so.6"));
+    }
+}
@@ -0,0 +1,240 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use colored::Colorize;
+
+use crate::parser::Parser;
+use crate::parser::elf::dynamic::DT_SONAME;
+use crate::parser::elf::elf_struct::{SHN_UNDEF, STB_LOCAL, STB_GLOBAL, STB_WEAK, STB_GNU_UNIQUE, STV_DEFAULT, STV_PROTECTED};
+use crate::parser::elf::rela::is_copy;
+use crate::parser::elf::version::SymVersion;
+
+#[derive(Debug, PartialEq)]
+pub enum Finding {
+    // no loaded object exports the symbol
+    Unresolved { object : String, symbol : String, version : Option<String> },
+    // the symbol is there, but not with the version the reference was linked against
+    VersionMismatch { object : String, symbol : String, version : String, file : String, available : Vec<String> },
+    // several objects export it, the first in load order wins
+    Interposed { symbol : String, providers : Vec<String> },
+}
+
+impl Finding {
+    pub fn is_error(&self) -> bool {
+        !matches!(self, Finding::Interposed { .. })
+    }
+
+    pub fn object(&self) -> &str {
+        match self {
+            Finding::Unresolved { object, .. } | Finding::VersionMismatch { object, .. } => object,
+            Finding::Interposed { providers, .. } => &providers[0],
+        }
+    }
+
+    pub fn symbol(&self) -> String {
+        // name@VERSION as the reference spells it
+        match self {
+            Finding::Unresolved { symbol, version : Some(v), .. } => format!("{}@{}", symbol, v),
+            Finding::Unresolved { symbol, .. } | Finding::Interposed { symbol, .. } => symbol.clone(),
+            Finding::VersionMismatch { symbol, version, .. } => format!("{}@{}", symbol, version),
+        }
+    }
+}
+
+pub struct LinkReport {
+    pub findings : Vec<Finding>,
+}
+
+impl LinkReport {
+    pub fn errors(&self) -> usize {
+        self.findings.iter().filter(|f| f.is_error()).count()
+    }
+
+    pub fn show(&self) {
+        for f in &self.findings {
+            match f {
+                Finding::Unresolved { object, .. } => {
+                    println!("{:<12} {:<40} {}", "unresolved".red(), f.symbol(), object);
+                }
+                Finding::VersionMismatch { object, file, available, .. } => {
+                    let have = if available.is_empty() { "unversioned".to_string() } else { available.join(", ") };
+                    println!("{:<12} {:<40} {} ({} has {})", "version".red(), f.symbol(), object, file, have);
+                }
+                Finding::Interposed { providers, .. } => {
+                    println!("{:<12} {:<40} {} {}", "interposed".yellow(), f.symbol(), providers[0], format!("over {}", providers[1..].join(", ")).yellow());
+                }
+            }
+        }
+        let count = |p : fn(&Finding) -> bool| self.findings.iter().filter(|f| p(f)).count();
+        println!("{} unresolved, {} version mismatches, {} interposed",
+            count(|f| matches!(f, Finding::Unresolved { .. })),
+            count(|f| matches!(f, Finding::VersionMismatch { .. })),
+            count(|f| matches!(f, Finding::Interposed { .. })));
+    }
+}
+
+struct Export<'a> {
+    object  : usize,
+    version : Option<&'a str>,
+    hidden  : bool,     /* name@VER, only references asking for VER bind to it */
+    copy    : bool,     /* the executable's copy of a library object (R_*_COPY) */
+}
+
+pub fn check_links(objects : &[(&str, &Parser)]) -> LinkReport {
+    // like ld.so with every object in the global scope: objects[0] is the executable, the rest in load order
+    let exported = |b : u8| matches!(b, STB_GLOBAL | STB_WEAK | STB_GNU_UNIQUE);
+    let visible = |v : u8| matches!(v, STV_DEFAULT | STV_PROTECTED);
+    let versions : Vec<_> = objects.iter().map(|(_, p)| p.versions()).collect();
+
+    let mut exports : HashMap<&str, Vec<Export>> = HashMap::new();
+    for (i, (_, parser)) in objects.iter().enumerate() {
+        let copies : HashSet<&str> = parser.relas.iter()
+            .filter(|r| is_copy(parser.ehdr.e_machine, r.rela.r_type()))
+            .map(|r| r.sym_name.as_str())
+            .collect();
+        for (j, s) in parser.dynsym.iter().enumerate() {
+            if s.sym.st_shndx == SHN_UNDEF || s.str.is_empty() || !exported(s.sym.st_bind()) || !visible(s.sym.st_visibility()) {
+                continue;
+            }
+            let (version, hidden) = match versions[i].as_ref().map(|v| v.of(j)) {
+                Some(SymVersion::Local) => continue,
+                Some(SymVersion::Defined { name, hidden }) => (Some(name), hidden),
+                _ => (None, false),
+            };
+            exports.entry(s.str.as_str()).or_default().push(Export { object : i, version, hidden, copy : copies.contains(s.str.as_str()) });
+        }
+    }
+
+    // DT_SONAME (or the file name) of each object, what version requirements name
+    let sonames : Vec<String> = objects.iter()
+        .map(|(path, p)| p.dyn_strs(DT_SONAME).pop().unwrap_or_else(|| path.rsplit('/').next().unwrap_or(path).to_string()))
+        .collect();
+
+    let mut findings = vec![];
+    let mut interposed : BTreeMap<&str, Vec<usize>> = BTreeMap::new();
+    for (i, (path, parser)) in objects.iter().enumerate() {
+        for (j, s) in parser.dynsym.iter().enumerate() {
+            if s.sym.st_shndx != SHN_UNDEF || s.str.is_empty() || s.sym.st_bind() == STB_LOCAL {
+                continue;
+            }
+            let weak = s.sym.st_bind() == STB_WEAK;
+            let candidates : Vec<&Export> = exports.get(s.str.as_str()).into_iter().flatten().collect();
+
+            let providers : Vec<&Export> = match versions[i].as_ref().map(|v| v.of(j)) {
+                Some(SymVersion::Needed { name, file, weak : weak_version }) => {
+                    // any definition of that version binds, the default one or not, and like glibc's check_match
+                    // so does an unversioned one from another object (a preloaded malloc in front of libc)
+                    let good : Vec<&Export> = candidates.iter().copied()
+                        .filter(|e| e.version == Some(name) || (e.version.is_none() && sonames[e.object] != file))
+                        .collect();
+                    if good.is_empty() && !candidates.is_empty() && !weak_version {
+                        let mut available : Vec<String> = candidates.iter()
+                            .filter(|e| sonames[e.object] == file)
+                            .filter_map(|e| e.version.map(|v| v.to_string()))
+                            .collect();
+                        available.dedup();
+                        findings.push(Finding::VersionMismatch {
+                            object : path.to_string(), symbol : s.str.clone(), version : name.to_string(), file : file.to_string(), available,
+                        });
+                        continue;
+                    }
+                    if good.is_empty() && !weak {
+                        findings.push(Finding::Unresolved { object : path.to_string(), symbol : s.str.clone(), version : Some(name.to_string()) });
+                    }
+                    good
+                }
+                _ => {
+                    let good : Vec<&Export> = candidates.iter().copied().filter(|e| !e.hidden).collect();
+                    if good.is_empty() && !weak {
+                        findings.push(Finding::Unresolved { object : path.to_string(), symbol : s.str.clone(), version : None });
+                    }
+                    good
+                }
+            };
+
+            // a copy relocation is the library's own object moved into the executable
+            let mut owners : Vec<usize> = providers.iter().filter(|e| !e.copy).map(|e| e.object).collect();
+            owners.dedup();
+            if owners.len() > 1 {
+                interposed.entry(s.str.as_str()).or_insert(owners);
+            }
+        }
+    }
+
+    for (symbol, owners) in interposed {
+        findings.push(Finding::Interposed { symbol : symbol.to_string(), providers : owners.iter().map(|&o| objects[o].0.to_string()).collect() });
+    }
+    LinkReport { findings }
+}
+
+impl Parser {
+    pub fn check_links(&self, libs : &[Parser]) -> LinkReport {
+        let mut objects : Vec<(&str, &Parser)> = vec![(self.binbuf.name(), self)];
+        objects.extend(libs.iter().map(|p| (p.binbuf.name(), p)));
+        check_links(&objects)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::binbuf::BinBuf;
+    use crate::parser::elf::dynamic::DT_STRTAB;
+
+    // test/testlink needs base_fn and base_alloc@TESTBASE_1.0 and base_new@TESTBASE_2.0 from libtestbase.so.1,
+    // which needs leaf_fn from libtestleaf.so, libtestpre.so has an unversioned base_alloc
+    fn libs(names : &[&str]) -> Vec<Parser> {
+        names.iter().map(|n| Parser::new(&format!("test/{}", n))).collect()
+    }
+
+    #[test]
+    fn test_check_links() {
+        let exe = Parser::new("test/testlink");
+        let unresolved = |symbol : &str, version : &str| Finding::Unresolved {
+            object : "test/testlink".to_string(), symbol : symbol.to_string(), version : Some(version.to_string()),
+        };
+        assert_eq!(exe.check_links(&[]).findings, [
+            unresolved("base_alloc", "TESTBASE_1.0"), unresolved("base_fn", "TESTBASE_1.0"), unresolved("base_new", "TESTBASE_2.0"),
+        ]);
+        assert!(exe.check_links(&libs(&["libtestbase.so.1", "libtestleaf.so"])).findings.is_empty());
+
+        // libtestbase.so.1 needs the leaf library
+        let report = exe.check_links(&libs(&["libtestbase.so.1"]));
+        assert_eq!(report.findings.len(), 1);
+        assert_eq!((report.findings[0].object(), report.findings[0].symbol()), ("test/libtestbase.so.1", "leaf_fn".to_string()));
+
+        // the same library twice exports everything twice
+        let report = exe.check_links(&libs(&["libtestbase.so.1", "libtestbase.so.1", "libtestleaf.so"]));
+        assert_eq!(report.errors(), 0);
+        assert!(report.findings.iter().any(|f| matches!(f, Finding::Interposed { symbol, .. } if symbol == "base_fn")));
+    }
+    #[test]
+    fn test_unversioned_interposer() {
+        // an unversioned definition satisfies a versioned reference when it is not in the file the version names
+        let exe = Parser::new("test/testlink");
+        let report = exe.check_links(&libs(&["libtestpre.so", "libtestbase.so.1", "libtestleaf.so"]));
+        assert_eq!(report.findings, [Finding::Interposed {
+            symbol : "base_alloc".to_string(), providers : vec!["test/libtestpre.so".to_string(), "test/libtestbase.so.1".to_string()],
+        }]);
+
+        // without the real library, the interposer alone still binds it
+        let report = exe.check_links(&libs(&["libtestpre.so"]));
+        assert!(report.findings.iter().all(|f| matches!(f, Finding::Unresolved { symbol, .. } if symbol != "base_alloc")));
+        assert_eq!(report.errors(), 2);
+    }
+    #[test]
+    fn test_version_mismatch() {
+        // rename the TESTBASE_2.0 requirement to a version libtestbase.so.1 does not define
+        let exe = Parser::new("test/testlink");
+        let strtab = exe.dynamic.as_ref().unwrap().get(DT_STRTAB).unwrap().entry.d_val;
+        let strtab = exe.segments.vaddr_to_offset(strtab).unwrap() as usize;
+        let mut buf = exe.binbuf.buf.clone();
+        let pos = strtab + buf[strtab..].windows(12).position(|w| w == b"TESTBASE_2.0").unwrap();
+        buf[pos..pos + 12].copy_from_slice(b"TESTBASE_2.9");
+
+        let patched = Parser::from_binbuf(BinBuf::from_bytes("testlink", buf));
+        let report = patched.check_links(&libs(&["libtestbase.so.1", "libtestleaf.so"]));
+        assert_eq!(report.findings, [Finding::VersionMismatch {
+            object : "testlink".to_string(), symbol : "base_new".to_string(), version : "TESTBASE_2.9".to_string(),
+            file : "libtestbase.so.1".to_string(), available : vec!["TESTBASE_2.0".to_string()],
+        }]);
+    }
+}
@@ -0,0 +1,150 @@
+use colored::Colorize;
+use std::mem;
+
+use crate::parser::Parser;
+use crate::parser::elf::elf_struct::{Elf64Ehdr, Elf64Phdr, Elf64Shdr, SHT_NOBITS};
+
+/* well-known magics seen at the start of an overlay */
+const OVERLAY_MAGICS : [(&[u8], &str); 9] = [
+    (b"PK\x03\x04",                   "zip archive"),
+    (b"7z\xbc\xaf\x27\x1c",           "7z archive"),
+    (b"\x1f\x8b",                     "gzip data"),
+    (b"\xfd7zXZ\x00",                 "xz data"),
+    (b"BZh",                          "bzip2 data"),
+    (b"\x28\xb5\x2f\xfd",             "zstd data"),
+    (b"\x7fELF",                      "ELF image"),
+    (b"!<arch>\n",                    "ar archive"),
+    (b"\x30\x82",                     "DER data (signature?)"),
+];
+
+pub struct Extent {
+    pub start : usize,
+    pub end   : usize,
+    pub what  : String,
+}
+
+impl Parser {
+    pub fn extents(&self) -> Vec<Extent> {
+        // every file range something in the headers accounts for
+        let ehdr = &self.ehdr;
+        let mut extents = vec![Extent { start : 0, end : mem::size_of::<Elf64Ehdr>(), what : "ELF header".to_string() }];
+
+        if !self.segments.segs.is_empty() {
+            let start = ehdr.e_phoff as usize;
+            extents.push(Extent { start, end : start + self.segments.segs.len() * mem::size_of::<Elf64Phdr>(), what : "program headers".to_string() });
+        }
+        if !self.sections.secs.is_empty() && !self.sections.synthetic {
+            let start = ehdr.e_shoff as usize;
+            extents.push(Extent { start, end : start + self.sections.secs.len() * mem::size_of::<Elf64Shdr>(), what : "section headers".to_string() });
+        }
+        for seg in &self.segments.segs {
+            if seg.phdr.p_filesz != 0 {
+                let start = seg.phdr.p_offset as usize;
+                extents.push(Extent { start, end : start + seg.phdr.p_filesz as usize, what : seg.name.clone() });
+            }
+        }
+        for (i, sec) in self.sections.secs.iter().enumerate() {
+            if sec.shdr.sh_type != SHT_NOBITS && sec.shdr.sh_size != 0 {
+                let start = sec.shdr.sh_offset as usize;
+                extents.push(Extent { start, end : start + sec.shdr.sh_size as usize, what : self.section_name(i).to_string() });
+            }
+        }
+        extents.sort_by_key(|e| (e.start, e.end));
+        extents
+    }
+
+    pub fn covered_end(&self) -> usize {
+        self.extents().iter().map(|e| e.end).max().unwrap_or(0)
+    }
+
+    pub fn gaps(&self) -> Vec<(usize, usize)> {
+        // [start, end) ranges below the covered end that nothing describes
+        let mut gaps = vec![];
+        let mut pos = 0;
+        for e in self.extents() {
+            if e.start > pos {
+                gaps.push((pos, e.start.min(self.binbuf.buf.len())));
+            }
+            pos = pos.max(e.end);
+        }
+        gaps.retain(|g| g.0 < g.1);
+        gaps
+    }
+
+    pub fn overlay(&self) -> Option<&[u8]> {
+        let start = self.covered_end();
+        self.binbuf.buf.get(start..).filter(|o| !o.is_empty())
+    }
+
+    pub fn show_overlay(&self) -> &Self {
+        let len = self.binbuf.buf.len();
+        let end = self.covered_end();
+        let last = self.extents().into_iter().max_by_key(|e| e.end).map(|e| e.what).unwrap_or_default();
+        println!("{:<16}{:#x} bytes, described up to {:#x} (end of {})", "File size:".red(), len, end, last);
+        if end > len {
+            println!("{:<16}{}", "", format!("truncated, {:#x} bytes described past the end of file", end - len).yellow());
+        }
+
+        let gaps = self.gaps();
+        if !gaps.is_empty() {
+            println!();
+            println!("{} ({}):", "Gaps".red(), gaps.len());
+            println!("  {:<12}{:<12}{}", "Offset".green(), "Size".cyan(), "Content".yellow());
+            for (start, end) in &gaps {
+                let bytes = &self.binbuf.buf[*start..*end];
+                let content = if bytes.iter().all(|&b| b == 0) { "zero padding".normal() } else { "non-zero data".yellow() };
+                println!("  {:<#12x}{:<#12x}{}", start, end - start, content);
+            }
+        }
+
+        println!();
+        match self.overlay() {
+            Some(overlay) => {
+                let kind = if self.module_signature().is_some_and(|s| s.offset == end) {
+                    "kernel module signature"
+                } else {
+                    OVERLAY_MAGICS.iter().find(|(m, _)| overlay.starts_with(m)).map(|(_, k)| *k).unwrap_or("unknown data")
+                };
+                println!("{:<16}{:#x} bytes at {:#x}, {}", "Overlay:".red(), overlay.len(), end, kind.blue());
+                let head : Vec<String> = overlay.iter().take(16).map(|b| format!("{:02x}", b)).collect();
+                println!("{:<16}{}", "", head.join(" "));
+            }
+            None => println!("{:<16}{}", "Overlay:".red(), "none".green()),
+        }
+        self
+    }
+
+    pub fn extract_overlay(&self, path : &str) -> &Self {
+        match self.overlay() {
+            Some(overlay) => match std::fs::write(path, overlay) {
+                Ok(_)  => println!("wrote {:#x} bytes of overlay to {}", overlay.len(), path),
+                Err(e) => eprintln!("{}: {}", path, e),
+            },
+            None => eprintln!("no overlay to extract"),
+        }
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::binbuf::BinBuf;
+    #[test]
+    fn test_overlay() {
+        let parser = Parser::new("test/test");
+        assert!(parser.overlay().is_none());
+        assert_eq!(parser.covered_end(), parser.binbuf.buf.len());
+
+        // a zip glued to the end, as self-extracting installers do
+        let mut buf = parser.binbuf.buf.clone();
+        buf.extend_from_slice(b"PK\x03\x04 payload");
+        let packed = Parser::from_binbuf(BinBuf::from_bytes("packed", buf));
+        assert_eq!(packed.overlay().unwrap(), b"PK\x03\x04 payload");
+        assert_eq!(packed.gaps(), parser.gaps());
+
+        // the signature of a signed module starts right after the section headers
+        let module = Parser::new("test/testmod.ko");
+        assert_eq!(module.covered_end(), module.module_signature().unwrap().offset);
+    }
+}
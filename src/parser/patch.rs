@@ -0,0 +1,151 @@
+use crate::parser::Parser;
+use crate::parser::elf::elf_struct::SHN_UNDEF;
+
+#[derive(Debug)]
+pub struct Patch {
+    pub vaddr  : u64,
+    pub offset : usize,     /* file offset the bytes went to */
+    pub old    : Vec<u8>,
+    pub new    : Vec<u8>,
+}
+
+pub fn parse_addr(s : &str) -> Option<u64> {
+    // accept both 0x-prefixed hex and plain decimal
+    match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        Some(hex) => u64::from_str_radix(hex, 16).ok(),
+        None      => s.parse().ok(),
+    }
+}
+
+pub fn parse_bytes(s : &str) -> Option<Vec<u8>> {
+    // "90 90", "9090" or "0x9090", whitespace between bytes is ignored
+    let hex : String = s.split_whitespace().map(|b| b.strip_prefix("0x").unwrap_or(b)).collect();
+    if hex.is_empty() || !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len()).step_by(2).map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok()).collect()
+}
+
+impl Parser {
+    pub fn resolve_target(&self, target : &str) -> Result<u64, String> {
+        // a vaddr, or a symbol name with an optional +offset (operator+ and friends keep their '+')
+        if self.is_relocatable() {
+            return Err("relocatable objects have no virtual addresses, use a section offset".to_string());
+        }
+        if let Some(vaddr) = parse_addr(target) {
+            return Ok(vaddr);
+        }
+        let (name, off) = match target.rsplit_once('+') {
+            Some((name, off)) if parse_addr(off).is_some() => (name, parse_addr(off).unwrap()),
+            _ => (target, 0),
+        };
+        // a defined symbol of either table, a PLT stub (write@plt) only when none matches
+        let start = self.symbols_named(name).iter()
+            .map(|(_, s)| s.sym)
+            .find(|s| s.st_shndx != SHN_UNDEF && s.st_value != 0)
+            .map(|s| s.st_value)
+            .or_else(|| self.plt_stubs().into_iter().find(|(_, _, stub)| stub == name).map(|(vaddr, _, _)| vaddr))
+            .ok_or(format!("no symbol named {}", name))?;
+        start.checked_add(off).ok_or(format!("invalid offset: {}", target))
+    }
+
+    pub fn patch_offset(&self, vaddr : u64, len : usize) -> Result<usize, String> {
+        // the whole write must land in the file-backed part of one PT_LOAD
+        let end = vaddr.checked_add(len as u64).ok_or(format!("{:#x}: address overflow", vaddr))?;
+        let seg = self.segments.loadable()
+            .find(|s| vaddr >= s.phdr.p_vaddr && s.phdr.p_vaddr.checked_add(s.phdr.p_memsz).is_some_and(|e| vaddr < e))
+            .ok_or(format!("{:#x} is not in any PT_LOAD segment", vaddr))?;
+
+        // p_filesz <= p_memsz, so neither end overflows once p_vaddr + p_memsz did not
+        let mem_end = seg.phdr.p_vaddr + seg.phdr.p_memsz;
+        let file_end = seg.phdr.p_vaddr + seg.phdr.p_filesz.min(seg.phdr.p_memsz);
+        if end > file_end {
+            if end > mem_end {
+                return Err(format!("{:#x}..{:#x} runs past the end of its segment", vaddr, end));
+            }
+            return Err(format!("{:#x}..{:#x} crosses into bss at {:#x}, which has no bytes in the file", vaddr, end, file_end));
+        }
+        seg.phdr.p_offset.checked_add(vaddr - seg.phdr.p_vaddr)
+            .map(|o| o as usize)
+            .filter(|o| o.checked_add(len).is_some_and(|e| e <= self.binbuf.buf.len()))
+            .ok_or(format!("{:#x}..{:#x} is past the end of the file", vaddr, end))
+    }
+
+    pub fn patch(&mut self, vaddr : u64, bytes : &[u8]) -> Result<Patch, String> {
+        // in place on the buffer, the parsed tables are not refreshed
+        let offset = self.patch_offset(vaddr, bytes.len())?;
+        let old = self.binbuf.buf[offset..offset + bytes.len()].to_vec();
+        self.binbuf.buf[offset..offset + bytes.len()].copy_from_slice(bytes);
+        Ok(Patch { vaddr, offset, old, new : bytes.to_vec() })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::binbuf::BinBuf;
+    use crate::parser::elf::segments::segtype::SegmentType;
+    #[test]
+    fn test_patch() {
+        let mut parser = Parser::new("test/testgot");
+        let main = parser.resolve_target("main").unwrap();
+        assert_eq!(main, 0x401136);
+        assert_eq!(parser.resolve_target("main+0x4"), Ok(0x40113a));
+        assert_eq!(parser.resolve_target("0x401136"), Ok(0x401136));
+        assert!(parser.resolve_target("nosuchsym").is_err());
+        // only a PLT stub has that name
+        let stub = parser.plt_stubs().into_iter().find(|(_, _, n)| n == "write@plt").unwrap().0;
+        assert_eq!(parser.resolve_target("write@plt+1"), Ok(stub + 1));
+        assert_eq!(parse_bytes("90 90"), Some(vec![0x90, 0x90]));
+        assert_eq!(parse_bytes("0xcc90"), Some(vec![0xcc, 0x90]));
+        assert_eq!(parse_bytes("909"), None);
+        // symbol values of a .o are section offsets, not addresses
+        let rel = Parser::new("test/testrel.o");
+        assert!(rel.resolve_target("_Z1fi").unwrap_err().contains("relocatable"));
+
+        let before = parser.binbuf.buf[0x1136..0x1138].to_vec();
+        let patch = parser.patch(main, &[0x90, 0x90]).unwrap();
+        assert_eq!((patch.offset, patch.old), (0x1136, before));
+        assert_eq!(parser.binbuf.buf[0x1136..0x1138], [0x90, 0x90]);
+
+        // the last file byte of .data is fine, one more runs into .bss
+        assert!(parser.patch(0x40402f, &[0]).is_ok());
+        assert!(parser.patch(0x40402f, &[0, 0]).unwrap_err().contains("bss"));
+        assert!(parser.patch(0x404038, &[0]).is_err());
+        assert!(parser.patch(0x10, &[0]).is_err());
+
+        let out = std::env::temp_dir().join(format!("exparser_patch_{}", std::process::id()));
+        let out = out.to_str().unwrap();
+        parser.writeback(out, false).unwrap();
+        let patched = Parser::new(out);
+        std::fs::remove_file(out).unwrap();
+        assert_eq!(patched.binbuf.buf[0x1136..0x1138], [0x90, 0x90]);
+    }
+
+    #[test]
+    fn test_patch_overflow() {
+        // a PT_LOAD ending at or past u64::MAX, or a p_offset near it, is an error rather than a wrap
+        let orig = Parser::new("test/testgot");
+        let i = orig.segments.iter().rposition(|s| s.seg_type() == SegmentType::PT_LOAD).unwrap();
+        let phdr = orig.ehdr.e_phoff as usize + i * 56;
+        let with = |fields : &[(usize, u64)]| {
+            let mut buf = orig.binbuf.buf.clone();
+            for (at, val) in fields {
+                buf[phdr + at..phdr + at + 8].copy_from_slice(&val.to_le_bytes());
+            }
+            Parser::from_binbuf(BinBuf::from_bytes("crafted", buf))
+        };
+
+        // p_vaddr, p_filesz, p_memsz: the last byte of the address space
+        let top = with(&[(0x10, u64::MAX - 0xf), (0x20, 0xf), (0x28, 0xf)]);
+        assert!(top.patch_offset(u64::MAX - 1, 1).is_ok());
+        assert!(top.patch_offset(u64::MAX - 1, 2).unwrap_err().contains("overflow"));
+
+        let wraps = with(&[(0x10, u64::MAX - 0xf), (0x28, 0x100)]);
+        assert!(wraps.patch_offset(u64::MAX - 1, 1).unwrap_err().contains("not in any PT_LOAD"));
+
+        let vaddr = orig.segments.iter().nth(i).unwrap().phdr.p_vaddr;
+        let far = with(&[(0x08, u64::MAX - 4)]);
+        assert!(far.patch_offset(vaddr + 8, 1).unwrap_err().contains("past the end of the file"));
+    }
+}
@@ -0,0 +1,124 @@
+use colored::Colorize;
+
+use crate::parser::Parser;
+use crate::parser::elf::elf_struct::{ET_REL, SHF_WRITE, SHF_ALLOC, SHF_EXECINSTR, SHF_GROUP, SHT_GROUP, SHT_NOBITS, STT_SECTION};
+use crate::parser::elf::group::Group;
+use crate::parser::elf::rela::Rela;
+
+impl Parser {
+    pub fn is_relocatable(&self) -> bool {
+        self.ehdr.e_type == ET_REL
+    }
+
+    pub fn section_name(&self, idx : usize) -> &str {
+        self.sections.secs.get(idx).map(|s| s.name.trim_end_matches('\x00')).unwrap_or("")
+    }
+
+    pub fn relas_for(&self, idx : usize) -> impl Iterator<Item = &Rela> {
+        // entries of every SHT_RELA section whose sh_info points at section idx
+        self.relas.tables.iter()
+            .filter(move |t| t.target as usize == idx)
+            .flat_map(|t| t.relas.iter())
+    }
+
+    pub fn groups(&self) -> Vec<Group> {
+        let mut groups = vec![];
+        for (i, sec) in self.sections.secs.iter().enumerate() {
+            if sec.shdr.sh_type != SHT_GROUP {
+                continue;
+            }
+            // signature is symbol sh_info of the linked .symtab, a section symbol names it by its section
+            let sym = self.symtab.as_ref().and_then(|s| s.get(sec.shdr.sh_info as usize));
+            let signature = match sym {
+                Some(s) if s.sym.st_type() == STT_SECTION => self.section_name(s.shndx).to_string(),
+                Some(s) => s.str.clone(),
+                None => String::new(),
+            };
+            let start = sec.shdr.sh_offset as usize;
+            let end = (start + sec.shdr.sh_size as usize).min(self.binbuf.buf.len());
+            groups.push(Group::new(i, signature, self.binbuf.buf.get(start..end).unwrap_or_default()));
+        }
+        groups
+    }
+
+    pub fn show_groups(&self) -> &Self {
+        let groups = self.groups();
+        if groups.is_empty() {
+            println!("no section groups");
+        }
+        for group in &groups {
+            let kind = if group.is_comdat() { "COMDAT group" } else { "group" };
+            println!("{} [{:>5}] '{}' [{}] contains {} sections:",
+                kind.red(),
+                group.index,
+                self.section_name(group.index).blue(),
+                self.display_name(&group.signature).green(),
+                group.members.len());
+            println!("   [Index]    Name");
+            for &idx in &group.members {
+                println!("   [{:>5}]   {}", idx, self.section_name(idx as usize));
+            }
+            println!();
+        }
+        self
+    }
+
+    pub fn show_section_layout(&self) -> &Self {
+        // without program headers there is nothing to map, show where each section sits in the file
+        let groups = self.groups();
+        let width = self.sections.secs.iter().map(|s| s.name.len()).max().unwrap_or(0).max(20);
+
+        println!("{}", "no program headers, sections by file offset:".yellow());
+        for (i, sec) in self.sections.secs.iter().enumerate().skip(1) {
+            let start = sec.shdr.sh_offset;
+            let end = if sec.shdr.sh_type == SHT_NOBITS { start } else { start + sec.shdr.sh_size };
+
+            let flags : String = [(SHF_WRITE, 'W'), (SHF_ALLOC, 'A'), (SHF_EXECINSTR, 'X'), (SHF_GROUP, 'G')]
+                .iter()
+                .filter(|(f, _)| sec.shdr.sh_flags & f != 0)
+                .map(|(_, c)| *c)
+                .collect();
+
+            let mut line = format!("[{:>2}] {:<width$} {} {:<4}",
+                i,
+                self.section_name(i).blue(),
+                format!("0x{:x}-0x{:x}", start, end).yellow(),
+                flags,
+                width = width);
+
+            let relocs = self.relas_for(i).count();
+            if relocs != 0 {
+                line += &format!(" {} relocs", relocs);
+            }
+            if let Some(group) = groups.iter().find(|g| g.members.contains(&(i as u32))) {
+                line += &format!(" [{}]", self.display_name(&group.signature).green());
+            }
+            println!("{}", line);
+        }
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    /*
+    test/testrel.o is g++ -c test/testrel.cpp, the template and the inline function
+    land in COMDAT groups
+     */
+    #[test]
+    fn test_relocatable() {
+        let parser = Parser::new("test/testrel.o");
+        assert!(parser.is_relocatable());
+        assert!(parser.dynsym.is_empty());
+
+        let groups = parser.groups();
+        let sigs : Vec<&str> = groups.iter().map(|g| g.signature.as_str()).collect();
+        assert!(sigs.contains(&"_Z2twIiET_S0_"));
+        assert!(groups.iter().all(|g| g.is_comdat() && !g.members.is_empty()));
+
+        // .rela.text applies to .text, its call to the undefined ext() included
+        let text = parser.sections.secs.iter().position(|s| s.name.starts_with(".text\x00")).unwrap();
+        assert!(parser.relas_for(text).any(|r| r.sym_name == "_Z3exti"));
+    }
+}
@@ -0,0 +1,329 @@
+use std::mem;
+
+use crate::binbuf::{BinBuf, u32_at};
+use crate::parser::Parser;
+use crate::parser::elf::elf_struct::{Elf64Ehdr, Elf64Shdr, Elf64Sym, Elf64Rela, Elf64Dyn};
+use crate::parser::elf::elf_struct::{EM_RISCV, STB_LOCAL, SHF_WRITE, SHF_ALLOC, SHF_INFO_LINK, SHT_NOBITS, SHT_STRTAB};
+use crate::parser::elf::elf_struct::{SHT_RELA, SHT_HASH, SHT_DYNAMIC, SHT_DYNSYM, SHT_INIT_ARRAY, SHT_FINI_ARRAY, SHT_GNU_HASH};
+use crate::parser::elf::dynamic::{DT_PLTRELSZ, DT_PLTGOT, DT_HASH, DT_STRTAB, DT_SYMTAB, DT_RELA, DT_RELASZ, DT_STRSZ};
+use crate::parser::elf::dynamic::{DT_PLTREL, DT_JMPREL, DT_INIT_ARRAY, DT_FINI_ARRAY, DT_INIT_ARRAYSZ, DT_FINI_ARRAYSZ, DT_GNU_HASH};
+use crate::parser::elf::sections::Sections;
+use crate::parser::elf::segments::Segments;
+use crate::parser::elf::segments::segtype::SegmentType;
+
+const SHT_PROGBITS : u32 = 1;
+
+/* GOT entries reserved ahead of the PLT slots (link map, resolver...) */
+const GOT_RESERVED       : u64 = 3;
+const GOT_RESERVED_RISCV : u64 = 2;
+
+/* a section to rebuild: name, header, and the names its sh_link/sh_info refer to */
+struct Rebuilt {
+    name : &'static str,
+    shdr : Elf64Shdr,
+    link : Option<&'static str>,
+    info : Option<&'static str>,
+}
+
+impl Parser {
+    pub fn section_headers_ok(ehdr : &Elf64Ehdr, buf : &[u8]) -> bool {
+        // every header in the file, the name table present, every name and every body inside it
+        let shnum = ehdr.shnum(buf);
+        let sz = mem::size_of::<Elf64Shdr>();
+        if shnum == 0 || ehdr.e_shentsize as usize != sz {
+            return false;
+        }
+        let table_end = shnum.checked_mul(sz).and_then(|size| (ehdr.e_shoff as usize).checked_add(size));
+        if ehdr.e_shoff == 0 || table_end.is_none_or(|end| end > buf.len()) {
+            return false;
+        }
+        let shdrs : Vec<Elf64Shdr> = (0..shnum).map(|i| Elf64Shdr::new(&buf[ehdr.e_shoff as usize + i * sz..])).collect();
+
+        let in_file = |s : &Elf64Shdr| s.sh_type == SHT_NOBITS || s.sh_offset.checked_add(s.sh_size).is_some_and(|end| end <= buf.len() as u64);
+        let shstrtab = match shdrs.get(ehdr.shstrndx(buf)) {
+            Some(s) if ehdr.shstrndx(buf) != 0 && s.sh_type == SHT_STRTAB && in_file(s) => s,
+            _ => return false,
+        };
+        shdrs.iter().all(|s| in_file(s) && (s.sh_name as u64) < shstrtab.sh_size)
+    }
+
+    pub fn sections_rebuilt(&self) -> bool {
+        self.sections.synthetic
+    }
+
+    pub fn sections_from_dynamic(binbuf : &BinBuf, ehdr : &Elf64Ehdr, segments : &Segments) -> Sections {
+        // what the loader needs is reachable from PT_DYNAMIC, lay the usual sections over it
+        let mut rebuilt = vec![];
+        let buf = &binbuf.buf;
+        let at = |vaddr : u64| segments.vaddr_to_offset(vaddr);
+        let read_u32 = |vaddr : u64| -> Option<u32> {
+            u32_at(buf, at(vaddr)? as usize)
+        };
+        let shdr = |sh_type : u32, sh_flags : u64, sh_addr : u64, sh_size : u64, sh_entsize : u64| Elf64Shdr {
+            sh_name      : 0,
+            sh_type,
+            sh_flags,
+            sh_addr,
+            sh_offset    : at(sh_addr).unwrap_or(0),
+            sh_size,
+            sh_link      : 0,
+            sh_info      : 0,
+            sh_addralign : if sh_entsize != 0 { 8 } else { 1 },
+            sh_entsize,
+        };
+
+        if let Some(interp) = segments.get_segment(SegmentType::PT_INTERP) {
+            let s = shdr(SHT_PROGBITS, SHF_ALLOC, interp.phdr.p_vaddr, interp.phdr.p_filesz, 0);
+            rebuilt.push(Rebuilt { name : ".interp", shdr : s, link : None, info : None });
+        }
+
+        let dynamic = match segments.get_segment(SegmentType::PT_DYNAMIC) {
+            Some(seg) => seg,
+            None => return Self::assemble(rebuilt),
+        };
+        let mut dyns = vec![];
+        let start = dynamic.phdr.p_offset as usize;
+        let end = (dynamic.phdr.p_offset.saturating_add(dynamic.phdr.p_filesz) as usize).min(buf.len());
+        for chunk in buf.get(start..end).unwrap_or_default().chunks_exact(mem::size_of::<Elf64Dyn>()) {
+            let d = Elf64Dyn::new(chunk);
+            if d.d_tag == 0 {
+                break;
+            }
+            dyns.push(d);
+        }
+        let find = |tag| dyns.iter().find(|d| d.d_tag == tag).map(|d| d.d_val);
+
+        let mut s = shdr(SHT_DYNAMIC, SHF_WRITE | SHF_ALLOC, dynamic.phdr.p_vaddr, dynamic.phdr.p_filesz, mem::size_of::<Elf64Dyn>() as u64);
+        s.sh_addralign = dynamic.phdr.p_align;
+        rebuilt.push(Rebuilt { name : ".dynamic", shdr : s, link : Some(".dynstr"), info : None });
+
+        // DT_HASH is the only place the number of dynamic symbols is written down, .gnu.hash gives a lower bound
+        let mut nsyms = None;
+        let mut hashed = 0;
+        if let Some(hash) = find(DT_HASH) {
+            if let (Some(nbucket), Some(nchain)) = (read_u32(hash), hash.checked_add(4).and_then(read_u32)) {
+                nsyms = Some(nchain as u64);
+                let s = shdr(SHT_HASH, SHF_ALLOC, hash, (2 + nbucket as u64 + nchain as u64) * 4, 4);
+                rebuilt.push(Rebuilt { name : ".hash", shdr : s, link : Some(".dynsym"), info : None });
+            }
+        }
+        if let Some(hash) = find(DT_GNU_HASH) {
+            if let Some(count) = gnu_hash_symbols(hash, &read_u32) {
+                hashed = count;
+                let (nbuckets, symoffset, bloom) = (read_u32(hash).unwrap() as u64, read_u32(hash + 4).unwrap() as u64, read_u32(hash + 8).unwrap() as u64);
+                let size = 16 + bloom * 8 + nbuckets * 4 + count.saturating_sub(symoffset) * 4;
+                let s = shdr(SHT_GNU_HASH, SHF_ALLOC, hash, size, 0);
+                rebuilt.push(Rebuilt { name : ".gnu.hash", shdr : s, link : Some(".dynsym"), info : None });
+            }
+        }
+
+        if let (Some(strtab), Some(strsz)) = (find(DT_STRTAB), find(DT_STRSZ)) {
+            rebuilt.push(Rebuilt { name : ".dynstr", shdr : shdr(SHT_STRTAB, SHF_ALLOC, strtab, strsz, 0), link : None, info : None });
+        }
+        if let Some(symtab) = find(DT_SYMTAB) {
+            let symsz = mem::size_of::<Elf64Sym>() as u64;
+            let sym = |i : u64| symtab.checked_add(i * symsz).and_then(at).and_then(|off| buf.get(off as usize..off as usize + symsz as usize)).map(Elf64Sym::new);
+            // otherwise .dynstr conventionally follows .dynsym, count the entries in between that look like symbols
+            let count = nsyms.unwrap_or_else(|| {
+                let strsz = find(DT_STRSZ).unwrap_or(0);
+                let gap = find(DT_STRTAB).filter(|&s| s > symtab).map_or(0, |s| (s - symtab) / symsz);
+                let plausible = (0..gap).take_while(|&i| sym(i).is_some_and(|s| (s.st_name as u64) < strsz)).count() as u64;
+                plausible.max(hashed)
+            });
+            let mut s = shdr(SHT_DYNSYM, SHF_ALLOC, symtab, count * symsz, symsz);
+            s.sh_info = (1..count).find(|&i| sym(i).is_some_and(|s| s.st_bind() != STB_LOCAL)).unwrap_or(count) as u32;
+            rebuilt.push(Rebuilt { name : ".dynsym", shdr : s, link : Some(".dynstr"), info : None });
+        }
+
+        let relasz = mem::size_of::<Elf64Rela>() as u64;
+        let jmprel = find(DT_JMPREL).filter(|_| find(DT_PLTREL) == Some(DT_RELA as u64));
+        if let (Some(rela), Some(size)) = (find(DT_RELA), find(DT_RELASZ)) {
+            // some linkers count the PLT relocations in DT_RELASZ as well
+            let size = match jmprel {
+                Some(j) if j > rela && j < rela.saturating_add(size) => j - rela,
+                _ => size,
+            };
+            rebuilt.push(Rebuilt { name : ".rela.dyn", shdr : shdr(SHT_RELA, SHF_ALLOC, rela, size, relasz), link : Some(".dynsym"), info : None });
+        }
+        if let (Some(jmprel), Some(size)) = (jmprel, find(DT_PLTRELSZ)) {
+            let s = shdr(SHT_RELA, SHF_ALLOC | SHF_INFO_LINK, jmprel, size, relasz);
+            rebuilt.push(Rebuilt { name : ".rela.plt", shdr : s, link : Some(".dynsym"), info : Some(".got.plt") });
+
+            if let Some(got) = find(DT_PLTGOT) {
+                let reserved = if ehdr.e_machine == EM_RISCV { GOT_RESERVED_RISCV } else { GOT_RESERVED };
+                let s = shdr(SHT_PROGBITS, SHF_WRITE | SHF_ALLOC, got, (reserved + size / relasz) * 8, 8);
+                rebuilt.push(Rebuilt { name : ".got.plt", shdr : s, link : None, info : None });
+            }
+        }
+
+        for (name, sh_type, addr, size) in [(".init_array", SHT_INIT_ARRAY, DT_INIT_ARRAY, DT_INIT_ARRAYSZ), (".fini_array", SHT_FINI_ARRAY, DT_FINI_ARRAY, DT_FINI_ARRAYSZ)] {
+            if let (Some(addr), Some(size)) = (find(addr), find(size)) {
+                rebuilt.push(Rebuilt { name, shdr : shdr(sh_type, SHF_WRITE | SHF_ALLOC, addr, size, 8), link : None, info : None });
+            }
+        }
+
+        Self::assemble(rebuilt)
+    }
+
+    fn assemble(mut rebuilt : Vec<Rebuilt>) -> Sections {
+        // address order behind the null section, then resolve names to indices
+        rebuilt.sort_by_key(|r| r.shdr.sh_addr);
+        let index = |name : Option<&str>| name.and_then(|n| rebuilt.iter().position(|r| r.name == n)).map_or(0, |i| i as u32 + 1);
+
+        let mut shstrtab = vec![0u8];
+        let mut shdrs = vec![Elf64Shdr::new(&[0; mem::size_of::<Elf64Shdr>()])];
+        for r in &rebuilt {
+            let mut shdr = r.shdr;
+            shdr.sh_name = shstrtab.len() as u32;
+            shdr.sh_link = index(r.link);
+            shdr.sh_info = if r.info.is_some() { index(r.info) } else { shdr.sh_info };
+            shstrtab.extend_from_slice(r.name.as_bytes());
+            shstrtab.push(0);
+            shdrs.push(shdr);
+        }
+        Sections::synthetic(shdrs, &shstrtab)
+    }
+
+    pub fn writeback(&self, path : &str, with_sections : bool) -> std::io::Result<()> {
+        // the file as parsed, plus the rebuilt section headers and their names when asked to
+        let mut out = self.binbuf.buf.clone();
+
+        if with_sections && self.sections.synthetic {
+            let mut names = vec![0u8];
+            let mut shdrs : Vec<Elf64Shdr> = self.sections.secs.iter().map(|s| s.shdr).collect();
+            for (shdr, sec) in shdrs.iter_mut().zip(&self.sections.secs).skip(1) {
+                let name = sec.name.trim_end_matches('\x00');
+                shdr.sh_name = names.len() as u32;
+                names.extend_from_slice(name.as_bytes());
+                names.push(0);
+            }
+
+            let mut shstrtab = Elf64Shdr::new(&[0; mem::size_of::<Elf64Shdr>()]);
+            shstrtab.sh_type = SHT_STRTAB;
+            shstrtab.sh_name = names.len() as u32;
+            shstrtab.sh_offset = out.len() as u64;
+            names.extend_from_slice(b".shstrtab\x00");
+            shstrtab.sh_size = names.len() as u64;
+            shstrtab.sh_addralign = 1;
+            shdrs.push(shstrtab);
+
+            out.extend_from_slice(&names);
+            out.resize(out.len().next_multiple_of(8), 0);
+            let shoff = out.len() as u64;
+            for shdr in &shdrs {
+                let fields = [
+                    (shdr.sh_name as u64, 4), (shdr.sh_type as u64, 4), (shdr.sh_flags, 8), (shdr.sh_addr, 8), (shdr.sh_offset, 8),
+                    (shdr.sh_size, 8), (shdr.sh_link as u64, 4), (shdr.sh_info as u64, 4), (shdr.sh_addralign, 8), (shdr.sh_entsize, 8),
+                ];
+                for (v, n) in fields {
+                    out.extend_from_slice(&v.to_le_bytes()[..n]);
+                }
+            }
+
+            // e_shoff, e_shentsize, e_shnum, e_shstrndx
+            out[0x28..0x30].copy_from_slice(&shoff.to_le_bytes());
+            out[0x3a..0x3c].copy_from_slice(&(mem::size_of::<Elf64Shdr>() as u16).to_le_bytes());
+            out[0x3c..0x3e].copy_from_slice(&(shdrs.len() as u16).to_le_bytes());
+            out[0x3e..0x40].copy_from_slice(&(shdrs.len() as u16 - 1).to_le_bytes());
+        }
+
+        std::fs::write(path, out)
+    }
+}
+
+fn gnu_hash_symbols(hash : u64, read_u32 : &dyn Fn(u64) -> Option<u32>) -> Option<u64> {
+    // one past the last symbol in the highest bucket's chain, the end of a chain has its low bit set
+    let nbuckets = read_u32(hash)? as u64;
+    let symoffset = read_u32(hash.checked_add(4)?)? as u64;
+    let bloom = read_u32(hash.checked_add(8)?)? as u64;
+    let buckets = hash.checked_add(16 + bloom * 8)?;
+    let chains = buckets.checked_add(nbuckets * 4)?;
+
+    let last = (0..nbuckets).filter_map(|i| read_u32(buckets.checked_add(i * 4)?)).max().unwrap_or(0) as u64;
+    if last < symoffset {
+        return Some(symoffset);
+    }
+    let mut idx = last;
+    loop {
+        if read_u32(chains.checked_add((idx - symoffset) * 4)?)? & 1 != 0 {
+            return Some(idx + 1);
+        }
+        idx += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::binbuf::BinBuf;
+
+    fn strip_section_headers(buf : &mut [u8]) {
+        // what sstrip and some packers do
+        buf[0x28..0x30].fill(0);
+        buf[0x3c..0x40].fill(0);
+    }
+
+    #[test]
+    fn test_segment_only() {
+        let orig = Parser::new("test/testgot");
+        let mut buf = orig.binbuf.buf.clone();
+        strip_section_headers(&mut buf);
+        assert!(!Parser::section_headers_ok(&Elf64Ehdr::new(&buf), &buf));
+
+        let parser = Parser::from_binbuf(BinBuf::from_bytes("stripped", buf));
+        assert!(parser.sections.synthetic);
+
+        // the same dynamic symbols and relocations as with the real headers
+        let names = |p : &Parser| p.dynsym.iter().map(|s| s.str.clone()).collect::<Vec<_>>();
+        assert_eq!(names(&parser), names(&orig));
+        let relocs = |p : &Parser| p.relas.iter().map(|r| (r.rela.r_offset, r.sym_name.clone())).collect::<Vec<_>>();
+        assert_eq!(relocs(&parser), relocs(&orig));
+        for name in [".dynsym", ".dynstr", ".rela.plt", ".got.plt", ".dynamic"] {
+            let (a, b) = (parser.sections.get_section(name).unwrap(), orig.sections.get_section(name).unwrap());
+            assert_eq!((a.shdr.sh_addr, a.shdr.sh_offset, a.shdr.sh_size), (b.shdr.sh_addr, b.shdr.sh_offset, b.shdr.sh_size), "{}", name);
+        }
+
+        // written back with headers it parses like a normal file again
+        let out = std::env::temp_dir().join("exparser_segonly");
+        parser.writeback(out.to_str().unwrap(), true).unwrap();
+        let rebuilt = Parser::new(out.to_str().unwrap());
+        assert!(!rebuilt.sections.synthetic);
+        assert_eq!(names(&rebuilt), names(&orig));
+        std::fs::remove_file(out).unwrap();
+    }
+
+    #[test]
+    fn test_corrupt_headers() {
+        // a non-UTF-8 section name is kept, not fatal
+        let orig = Parser::new("test/testgot");
+        let shstrtab = &orig.sections.secs[orig.ehdr.shstrndx(&orig.binbuf.buf)];
+        let mut buf = orig.binbuf.buf.clone();
+        buf[shstrtab.shdr.sh_offset as usize + 1] = 0xff;
+        let parser = Parser::from_binbuf(BinBuf::from_bytes("badname", buf));
+        assert_eq!(parser.sections.secs.len(), orig.sections.secs.len());
+        assert!(parser.sections.secs.iter().any(|s| s.name.starts_with('\u{fffd}')));
+        parser.checksec();
+
+        // a program header table running past EOF keeps the entries that fit
+        let mut buf = orig.binbuf.buf.clone();
+        let phoff = buf.len() as u64 - 0x40;
+        buf[0x20..0x28].copy_from_slice(&phoff.to_le_bytes());
+        let parser = Parser::from_binbuf(BinBuf::from_bytes("badphoff", buf));
+        assert_eq!(parser.segments.len(), 1);
+        parser.checksec();
+
+        // e_shnum 0 defers to shdr0.sh_size, large enough that the table size overflows
+        let mut buf = Parser::new("test/test").binbuf.buf.clone();
+        let shoff = Elf64Ehdr::new(&buf).e_shoff as usize;
+        buf[0x3c..0x3e].fill(0);
+        buf[shoff + 0x20..shoff + 0x28].copy_from_slice(&(1u64 << 60).to_le_bytes());
+        assert!(!Parser::section_headers_ok(&Elf64Ehdr::new(&buf), &buf));
+        let parser = Parser::from_binbuf(BinBuf::from_bytes("bigshnum", buf));
+        assert!(parser.sections.synthetic);
+        parser.checksec();
+
+        // a .gnu.hash at the top of the address space
+        assert_eq!(gnu_hash_symbols(u64::MAX - 8, &|_| Some(u32::MAX)), None);
+        assert_eq!(gnu_hash_symbols(u64::MAX - 16, &|_| Some(1)), None);
+    }
+}
@@ -0,0 +1,490 @@
+use colored::Colorize;
+use std::collections::{BTreeMap, HashMap};
+
+use crate::binbuf::u32_at;
+use crate::parser::Parser;
+use crate::parser::elf::dynamic::{DT_INIT, DT_FINI};
+use crate::parser::elf::elf_struct::Elf64Sym;
+use crate::parser::elf::elf_struct::{EM_X86_64, EM_AARCH64, EM_RISCV, SHT_INIT_ARRAY, SHT_FINI_ARRAY, SHN_ABS, SHN_UNDEF, SHN_LORESERVE};
+use crate::parser::elf::elf_struct::{STB_LOCAL, STB_GLOBAL, STB_WEAK, STB_GNU_UNIQUE, STT_NOTYPE, STT_OBJECT, STT_FUNC, STT_SECTION, STT_FILE, STT_TLS, STT_GNU_IFUNC};
+use crate::parser::elf::rela::get_reloc_type_str;
+use crate::parser::elf::rela::{is_got_slot, is_relative};
+use crate::parser::elf::symtable::{SymbolTable, SymSource, Symbol};
+use crate::parser::elf::symindex::{IndexedSymbol, SymbolIndex};
+use crate::parser::elf::version::Versions;
+
+/* sections holding PLT stubs */
+const PLT_SECTIONS : [&str; 3] = [".plt", ".plt.sec", ".plt.got"];
+
+pub fn get_sym_type_str(st_type : u8) -> String {
+    match st_type {
+        STT_NOTYPE    => "NOTYPE".to_string(),
+        STT_OBJECT    => "OBJECT".to_string(),
+        STT_FUNC      => "FUNC".to_string(),
+        STT_SECTION   => "SECTION".to_string(),
+        STT_FILE      => "FILE".to_string(),
+        STT_TLS       => "TLS".to_string(),
+        STT_GNU_IFUNC => "IFUNC".to_string(),
+        _             => format!("<{}>", st_type),
+    }
+}
+
+pub fn get_sym_bind_str(st_bind : u8) -> String {
+    match st_bind {
+        STB_LOCAL  => "LOCAL".to_string(),
+        STB_GLOBAL => "GLOBAL".to_string(),
+        STB_WEAK   => "WEAK".to_string(),
+        STB_GNU_UNIQUE => "UNIQUE".to_string(),
+        _          => format!("<{}>", st_bind),
+    }
+}
+
+fn plt_slot_x86_64(entry : &[u8], vaddr : u64) -> Option<u64> {
+    // GOT slot of `jmp *disp32(%rip)` (ff 25), possibly behind endbr64 and bnd prefix
+    let pos = entry.windows(2).position(|w| w == [0xff, 0x25])?;
+    let disp = entry.get(pos + 2..pos + 6)?;
+    let disp = i32::from_le_bytes(disp.try_into().unwrap()) as i64;
+    Some(vaddr.wrapping_add((pos + 6) as u64).wrapping_add(disp as u64))
+}
+
+fn plt_slot_aarch64(entry : &[u8], vaddr : u64) -> Option<u64> {
+    // GOT slot of `adrp x16, page; ldr x17, [x16, #off]`
+    let word = |i : usize| u32_at(entry, i * 4);
+    let (adrp, ldr) = (word(0)?, word(1)?);
+    if adrp & 0x9f00_001f != 0x9000_0010 || ldr & 0xffc0_03ff != 0xf940_0211 {
+        return None;
+    }
+    let imm = (((adrp >> 5) & 0x7ffff) << 2) | ((adrp >> 29) & 3);
+    let page = ((imm as i64) << 43 >> 31) as u64;
+    let off = ((ldr >> 10) & 0xfff) as u64 * 8;
+    Some((vaddr & !0xfff).wrapping_add(page).wrapping_add(off))
+}
+
+fn plt_slot_riscv(entry : &[u8], vaddr : u64) -> Option<u64> {
+    // GOT slot of `auipc t3, hi; ld t3, lo(t3)`
+    let word = |i : usize| u32_at(entry, i * 4);
+    let (auipc, ld) = (word(0)?, word(1)?);
+    if auipc & 0xfff == 0xe17 && ld & 0xfffff == 0xe3e03 {
+        let hi = (auipc & 0xffff_f000) as i32 as i64;
+        let lo = (ld as i32 >> 20) as i64;
+        return Some(vaddr.wrapping_add((hi + lo) as u64));
+    }
+    None
+}
+
+#[derive(Default)]
+struct Candidate {
+    name : Option<String>,
+    size : u64,
+    info : Option<u8>,
+}
+
+impl Parser {
+    pub fn got_names(&self) -> HashMap<u64, String> {
+        // GOT slot vaddr -> name of the symbol the dynamic linker puts there
+        self.relas
+            .iter()
+            .filter(|r| is_got_slot(self.ehdr.e_machine, r.rela.r_type()))
+            .filter(|r| !r.sym_name.is_empty())
+            .map(|r| (r.rela.r_offset, r.sym_name.clone()))
+            .collect()
+    }
+
+    pub fn plt_stubs(&self) -> Vec<(u64, u64, String)> {
+        // (vaddr, size, name@plt) of every PLT stub whose GOT slot has a named relocation
+        let slot_of = match self.ehdr.e_machine {
+            EM_X86_64 => plt_slot_x86_64,
+            EM_AARCH64 => plt_slot_aarch64,
+            EM_RISCV => plt_slot_riscv,
+            _ => return vec![],
+        };
+        let got = self.got_names();
+        let mut stubs = vec![];
+
+        for name in PLT_SECTIONS {
+            let (sec, data) = match (self.sections.get_section(name), self.section_data(name)) {
+                (Some(sec), Some(data)) => (sec, data),
+                _ => continue,
+            };
+            // .plt.got entries are 8 bytes without IBT, everything else 16 (AArch64 and RISC-V too)
+            let entsize = match sec.shdr.sh_entsize {
+                8 | 16 => sec.shdr.sh_entsize as usize,
+                _ => 16,
+            };
+            for (i, entry) in data.chunks(entsize).enumerate() {
+                let vaddr = sec.shdr.sh_addr + (i * entsize) as u64;
+                if let Some(name) = slot_of(entry, vaddr).and_then(|slot| got.get(&slot)) {
+                    stubs.push((vaddr, entsize as u64, format!("{}@plt", name)));
+                }
+            }
+        }
+        stubs
+    }
+
+    fn array_pointers(&self, sh_type : u32) -> Vec<u64> {
+        // function pointers of .init_array/.fini_array, the RELATIVE addend if not prelinked in file
+        let mut ptrs = vec![];
+        for sec in self.sections.by_type(sh_type) {
+            let start = sec.shdr.sh_offset as usize;
+            let data = match self.binbuf.buf.get(start..start + sec.shdr.sh_size as usize) {
+                Some(data) => data,
+                None => continue,
+            };
+            for (i, p) in data.chunks_exact(8).enumerate() {
+                let slot = sec.shdr.sh_addr + i as u64 * 8;
+                let mut ptr = u64::from_le_bytes(p.try_into().unwrap());
+                if ptr == 0 {
+                    if let Some(r) = self.relas.iter().find(|r| r.rela.r_offset == slot && is_relative(self.ehdr.e_machine, r.rela.r_type())) {
+                        ptr = r.rela.r_addend as u64;
+                    }
+                }
+                // 0 and -1 are terminators of the old .ctors layout
+                if ptr != 0 && ptr != u64::MAX {
+                    ptrs.push(ptr);
+                }
+            }
+        }
+        ptrs
+    }
+
+    fn section_index_of(&self, vaddr : u64) -> u16 {
+        // index of the allocated section holding vaddr
+        self.sections.index_at(vaddr)
+            .map(|i| i as u16)
+            .unwrap_or(SHN_ABS)
+    }
+
+    pub fn recover_functions(&self) -> SymbolTable {
+        /*
+         function boundaries of a stripped binary, weakest source first so stronger ones override:
+         1. .eh_frame FDEs          -> sub_<addr> with the FDE range as size
+         2. .init_array/.fini_array -> _INIT_<n>/_FINI_<n>
+         3. DT_INIT/DT_FINI, entry  -> _init/_fini/_start
+         4. PLT stubs               -> name@plt
+         5. defined .dynsym funcs   -> exported name, size and binding
+         */
+        let mut funcs : BTreeMap<u64, Candidate> = BTreeMap::new();
+
+        let plt_ranges : Vec<(u64, u64)> = PLT_SECTIONS
+            .iter()
+            .filter_map(|n| self.sections.get_section(n))
+            .map(|s| (s.shdr.sh_addr, s.shdr.sh_addr + s.shdr.sh_size))
+            .collect();
+        let in_plt = |addr : u64| plt_ranges.iter().any(|&(s, e)| s <= addr && addr < e);
+
+        if let Some(eh_frame) = self.eh_frame() {
+            // FDEs of the PLT cover the whole section, stubs are named below
+            for fde in eh_frame.fdes.iter().filter(|f| !in_plt(f.pc_begin)) {
+                funcs.entry(fde.pc_begin).or_default().size = fde.pc_range;
+            }
+        }
+
+        let mut name = |addr : u64, name : String| {
+            if addr != 0 {
+                funcs.entry(addr).or_default().name = Some(name);
+            }
+        };
+
+        for (i, addr) in self.array_pointers(SHT_INIT_ARRAY).into_iter().enumerate() {
+            name(addr, format!("_INIT_{}", i));
+        }
+        for (i, addr) in self.array_pointers(SHT_FINI_ARRAY).into_iter().enumerate() {
+            name(addr, format!("_FINI_{}", i));
+        }
+        if let Some(dynamic) = &self.dynamic {
+            if let Some(d) = dynamic.get(DT_INIT) {
+                name(d.entry.d_val, "_init".to_string());
+            }
+            if let Some(d) = dynamic.get(DT_FINI) {
+                name(d.entry.d_val, "_fini".to_string());
+            }
+        }
+        name(self.ehdr.e_entry, "_start".to_string());
+
+        for (addr, size, stub) in self.plt_stubs() {
+            let f = funcs.entry(addr).or_default();
+            f.name = Some(stub);
+            f.size = size;
+        }
+
+        for dynsym in &self.dynsym {
+            let sym = &dynsym.sym;
+            if sym.st_shndx == SHN_UNDEF || sym.st_value == 0 || dynsym.str.is_empty() {
+                continue;
+            }
+            if sym.st_type() != STT_FUNC && sym.st_type() != STT_GNU_IFUNC {
+                continue;
+            }
+            let f = funcs.entry(sym.st_value).or_default();
+            f.name = Some(dynsym.str.clone());
+            f.info = Some(sym.st_info);
+            if sym.st_size != 0 {
+                f.size = sym.st_size;
+            }
+        }
+
+        let syms = funcs
+            .into_iter()
+            .map(|(addr, f)| {
+                let info = f.info.unwrap_or(STB_LOCAL << 4 | STT_FUNC);
+                let sym = Elf64Sym::with_info(info, self.section_index_of(addr), addr, f.size);
+                Symbol::new(sym, f.name.unwrap_or_else(|| format!("sub_{:x}", addr)))
+            })
+            .collect();
+
+        SymbolTable::synthetic(syms)
+    }
+
+    pub fn symtab_or_recovered(&self) -> &SymbolTable {
+        // .symtab, or for a stripped file the functions recovered the first time anyone asks
+        match &self.symtab {
+            Some(symtab) => symtab,
+            None         => self.recovered.get_or_init(|| self.recover_functions()),
+        }
+    }
+
+    pub fn symbol_tables(&self) -> impl Iterator<Item = &SymbolTable> {
+        // .symtab (or the recovered functions) first, then .dynsym
+        std::iter::once(self.symtab_or_recovered()).chain(std::iter::once(&self.dynsym))
+    }
+
+    pub fn symbols_named(&self, name : &str) -> Vec<(SymSource, &Symbol)> {
+        self.symbol_tables().flat_map(|t| t.by_name(name).map(move |s| (t.source, s))).collect()
+    }
+
+    pub fn symbols_at(&self, addr : u64) -> Vec<(SymSource, &Symbol)> {
+        self.symbol_tables().flat_map(|t| t.by_addr(addr).map(move |s| (t.source, s))).collect()
+    }
+
+    pub fn versions(&self) -> Option<Versions> {
+        // symbol versions of the .dynsym entries, None for an unversioned file
+        Versions::new(&self.binbuf, &self.segments, self.dynamic.as_ref()?, self.dynsym.len())
+    }
+
+    pub fn symbol_index(&self) -> SymbolIndex {
+        // every defined code/data symbol of both tables plus the PLT stubs, by address
+        self.index_symbols(None)
+    }
+
+    pub fn section_symbol_index(&self, shndx : usize) -> SymbolIndex {
+        // the symbols of one section, what offsets into a section of a relocatable object resolve against
+        self.index_symbols(Some(shndx))
+    }
+
+    fn index_symbols(&self, section : Option<usize>) -> SymbolIndex {
+        let wanted = |t : u8| matches!(t, STT_FUNC | STT_NOTYPE | STT_OBJECT | STT_GNU_IFUNC);
+        // $x/$d mapping symbols and .L assembler locals are not labels
+        let named = |name : &str| !name.is_empty() && !name.starts_with('$') && !name.starts_with(".L");
+        // defined in a real section, at any value: ABS and COMMON values are not addresses, offset 0 of a .o is
+        let defined = |sym : &Elf64Sym| {
+            sym.st_shndx != SHN_UNDEF && sym.st_shndx < SHN_LORESERVE && section.is_none_or(|i| sym.st_shndx as usize == i)
+        };
+
+        let mut syms = vec![];
+        for table in self.symbol_tables() {
+            for s in table.iter().filter(|s| defined(&s.sym)) {
+                if wanted(s.sym.st_type()) && named(&s.str) {
+                    syms.push(IndexedSymbol {
+                        start  : s.sym.st_value,
+                        end    : s.sym.st_value.saturating_add(s.sym.st_size),
+                        name   : s.str.clone(),
+                        source : table.source,
+                    });
+                }
+            }
+        }
+        if section.is_none() {
+            for (start, size, name) in self.plt_stubs() {
+                syms.push(IndexedSymbol { start, end : start.saturating_add(size), name, source : SymSource::Plt });
+            }
+        }
+        SymbolIndex::new(syms)
+    }
+
+    fn show_sym_rows<'a>(&self, syms : impl Iterator<Item = (&'a Elf64Sym, usize, &'a str)>) {
+
+        let ndx = |sym : &Elf64Sym, shndx : usize| match sym.st_shndx {
+            SHN_UNDEF => "UND".to_string(),
+            SHN_ABS   => "ABS".to_string(),
+            // values of a relocatable object are offsets into their section, name it
+            _ if self.is_relocatable() => self.section_name(shndx).to_string(),
+            _         => shndx.to_string(),
+        };
+        let rows : Vec<_> = syms.map(|(sym, shndx, name)| (sym, ndx(sym, shndx), name)).collect();
+        let width = rows.iter().map(|r| r.1.len()).max().unwrap_or(0).max(6);
+
+        print!("{:>6}", "Num".red());
+        print!("{:>18}", "Value".green());
+        print!("{:>7}", "Size".cyan());
+        print!(" {:<8}", "Type".yellow());
+        print!("{:<7}", "Bind".yellow());
+        print!("{:>width$}", "Ndx".red(), width = width);
+        println!(" {}", "Name".blue());
+
+        for (i, (sym, ndx, name)) in rows.into_iter().enumerate() {
+            println!("{:>5}: {:016x} {:>6} {:<8}{:<7}{:>width$} {}",
+                i,
+                sym.st_value,
+                sym.st_size,
+                get_sym_type_str(sym.st_type()),
+                get_sym_bind_str(sym.st_bind()),
+                ndx,
+                self.display_name(name).blue(),
+                width = width);
+        }
+    }
+
+    pub fn show_symbols(&self) -> &Self {
+        let symtab = self.symtab_or_recovered();
+        if symtab.is_synthetic() {
+            println!("{}", "no .symtab, functions recovered from .eh_frame/.dynsym/PLT/init arrays".yellow());
+        }
+        self.show_sym_rows(symtab.iter().map(|s| (&s.sym, s.shndx, s.str.as_str())));
+        self
+    }
+
+    pub fn show_lookup(&self, name : &str, addr : Option<u64>) -> &Self {
+        // by address when the query parses as one, every table that has it
+        let found = match addr {
+            Some(addr) => self.symbols_at(addr),
+            None       => self.symbols_named(name),
+        };
+        if found.is_empty() {
+            println!("no symbol {}", name);
+        }
+        for (source, s) in found {
+            println!("{:<10} {:016x} {:>6} {:<8}{:<7}{}",
+                source.as_str().yellow(),
+                s.sym.st_value,
+                s.sym.st_size,
+                get_sym_type_str(s.sym.st_type()),
+                get_sym_bind_str(s.sym.st_bind()),
+                self.display_name(&s.str).blue());
+        }
+        self
+    }
+
+    pub fn show_dyn_syms(&self) -> &Self {
+        self.show_sym_rows(self.dynsym.iter().map(|s| (&s.sym, s.shndx, s.str.as_str())));
+        self
+    }
+
+    pub fn show_relocs(&self) -> &Self {
+        if self.relas.tables.is_empty() {
+            println!("no relocations");
+        }
+        for table in &self.relas.tables {
+            if table.target != 0 {
+                println!("{} '{}' applies to '{}', contains {} entries:", "Relocation section".red(), table.name.blue(), self.section_name(table.target as usize).blue(), table.relas.len());
+            } else {
+                println!("{} '{}' contains {} entries:", "Relocation section".red(), table.name.blue(), table.relas.len());
+            }
+            println!("{:<18}{:<18}{:<24}{}", "Offset".green(), "Info".cyan(), "Type".yellow(), "Sym. Name + Addend".blue());
+            for r in &table.relas {
+                let sym_name = self.display_name(&r.sym_name);
+                let target = if sym_name.is_empty() {
+                    format!("{:x}", r.rela.r_addend)
+                } else if r.rela.r_addend < 0 {
+                    format!("{} - {:x}", sym_name, -r.rela.r_addend)
+                } else {
+                    format!("{} + {:x}", sym_name, r.rela.r_addend)
+                };
+                println!("{:016x}  {:016x}  {:<24}{}",
+                    r.rela.r_offset,
+                    r.rela.r_info,
+                    get_reloc_type_str(self.ehdr.e_machine, r.rela.r_type()),
+                    target);
+            }
+            println!();
+        }
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn test_recover_stripped() {
+        // every sized function of the unstripped binary must start a recovered function,
+        // crtstuff helpers like register_tm_clones have no FDE and are only reached by direct calls
+        let parser = Parser::new("test/testgot");
+        let stripped = Parser::new("test/testgot_stripped");
+
+        let orig = parser.symtab.as_ref().unwrap();
+        assert!(stripped.symtab.is_none());
+        let recovered = stripped.symtab_or_recovered();
+        assert!(!orig.is_synthetic());
+        assert!(recovered.is_synthetic());
+
+        let addrs : Vec<u64> = recovered.iter().map(|s| s.sym.st_value).collect();
+        for sym in orig.iter().filter(|s| s.sym.st_type() == STT_FUNC && s.sym.st_size != 0) {
+            assert!(addrs.contains(&sym.sym.st_value), "{} at {:#x} not recovered", sym.str, sym.sym.st_value);
+        }
+
+        let find = |name : &str| recovered.iter().find(|s| s.str == name).map(|s| (s.sym.st_value, s.sym.st_size));
+        assert_eq!(find("_start"), Some((0x401050, 0x2f)));
+        assert_eq!(find("_init").map(|s| s.0), Some(0x401000));
+        assert_eq!(find("write@plt"), Some((0x401040, 0x10)));
+        assert_eq!(find("sub_401136"), Some((0x401136, 0x25)));   /* main */
+        assert_eq!(find("_INIT_0").map(|s| s.0), Some(0x401130));  /* frame_dummy */
+    }
+    #[test]
+    fn test_recover_lazily() {
+        // parsing a stripped file recovers nothing until a symbol is asked for, then only once
+        let stripped = Parser::new("test/testgot_stripped");
+        assert!(stripped.recovered.get().is_none());
+        let first = stripped.symtab_or_recovered();
+        assert!(stripped.recovered.get().is_some_and(|r| !r.is_empty()));
+        assert!(std::ptr::eq(first, stripped.symtab_or_recovered()));
+
+        // a file with its .symtab never recovers
+        let parser = Parser::new("test/testgot");
+        parser.symtab_or_recovered();
+        assert!(parser.recovered.get().is_none());
+    }
+    #[test]
+    fn test_plt_stubs() {
+        let parser = Parser::new("/bin/ls");
+        let stubs = parser.plt_stubs();
+        assert!(stubs.iter().any(|(_, _, n)| n == "free@plt"));
+
+        let symtab = parser.symtab_or_recovered();
+        assert!(symtab.is_synthetic());
+        let start = symtab.by_name("_start").next().unwrap();
+        assert_eq!(start.sym.st_value, parser.ehdr.e_entry);
+    }
+    #[test]
+    fn test_symbol_queries() {
+        let parser = Parser::new("test/testgot");
+        let symtab = parser.symtab.as_ref().unwrap();
+        assert_eq!((symtab.source, parser.dynsym.source), (SymSource::Symtab, SymSource::Dynsym));
+        assert_eq!(parser.sections.secs[symtab.section].name, ".symtab\x00");
+
+        // write is imported in both tables, .symtab spells it with its version
+        let sources = |name : &str| parser.symbols_named(name).iter().map(|(src, _)| *src).collect::<Vec<_>>();
+        assert_eq!(sources("write"), [SymSource::Dynsym]);
+        assert_eq!(sources("write@@GLIBC_2.2.5"), [SymSource::Symtab]);
+
+        let main = symtab.by_name("main").next().unwrap();
+        let at : Vec<&str> = parser.symbols_at(main.sym.st_value).iter().map(|(_, s)| s.str.as_str()).collect();
+        assert!(at.contains(&"main"));
+        assert!(parser.symbols_named("no_such_symbol").is_empty());
+    }
+
+    #[test]
+    fn test_symbol_index_relocatable() {
+        // every function of a .o starts at offset 0 of its own section
+        let parser = Parser::new("test/testrel.o");
+        let text = parser.sections.index_of(".text").unwrap();
+        let index = parser.section_symbol_index(text);
+        let names : Vec<&str> = index.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, ["_Z1fi"]);
+        assert_eq!(index.containing(0x46).map(|s| s.name.as_str()), Some("_Z1fi"));
+
+        let ili = parser.section_symbol_index(parser.sections.index_of(".text._Z2ili").unwrap());
+        assert_eq!(ili.at(0).map(|s| s.name.as_str()), Some("_Z2ili"));
+        assert!(parser.symbol_index().at(0).is_some());
+        assert!(parser.symbol_index().iter().all(|s| s.name != "testrel.cpp"));
+    }
+}
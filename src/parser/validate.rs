@@ -0,0 +1,424 @@
+use colored::Colorize;
+use std::mem;
+
+use crate::json::json_str;
+use crate::parser::elf::elf_struct::{Elf64Ehdr, Elf64Phdr, Elf64Shdr, Elf64Sym};
+use crate::parser::elf::elf_struct::{ET_EXEC, ET_DYN, PF_X, PN_XNUM, SHN_UNDEF, SHN_XINDEX, STB_LOCAL};
+use crate::parser::elf::elf_struct::{SHT_SYMTAB, SHT_STRTAB, SHT_RELA, SHT_HASH, SHT_DYNAMIC, SHT_NOBITS, SHT_REL, SHT_DYNSYM, SHT_GNU_HASH, SHT_SYMTAB_SHNDX};
+use crate::parser::elf::elf_struct::SHF_INFO_LINK;
+use crate::parser::elf::segments::segtype::get_seg_type_str;
+
+const PT_LOAD : u32 = 1;
+
+/* st_shndx from here up are reserved (ABS, COMMON, XINDEX, processor/OS specific) */
+const SHN_LORESERVE : u16 = 0xff00;
+
+/* bad symbols reported per table before the rest are only counted */
+const MAX_SYM_FINDINGS : usize = 16;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Severity {
+    Error,      /* a loader or linker would reject or misread it */
+    Warning,    /* legal but unusual, often a sign of tampering */
+}
+
+#[derive(Debug)]
+pub struct Finding {
+    pub severity : Severity,
+    pub offset   : u64,         /* file offset of the offending field */
+    pub message  : String,
+}
+
+pub struct Validation {
+    pub findings : Vec<Finding>,
+}
+
+struct Checker<'a> {
+    buf      : &'a [u8],
+    findings : Vec<Finding>,
+}
+
+pub fn validate(buf : &[u8]) -> Validation {
+    // works on raw bytes, nothing here trusts a header before checking it against the file
+    let mut c = Checker { buf, findings : vec![] };
+    if let Some(ehdr) = c.header() {
+        let phdrs = c.segments(&ehdr);
+        let shdrs = c.sections(&ehdr);
+        c.string_tables(&shdrs);
+        c.symbols(&ehdr, &shdrs);
+        c.entry(&ehdr, &phdrs);
+    }
+    c.findings.sort_by_key(|f| f.offset);
+    Validation { findings : c.findings }
+}
+
+fn pow2(align : u64) -> bool {
+    align <= 1 || align.is_power_of_two()
+}
+
+impl<'a> Checker<'a> {
+    fn error(&mut self, offset : u64, message : String) {
+        self.findings.push(Finding { severity : Severity::Error, offset, message });
+    }
+
+    fn warn(&mut self, offset : u64, message : String) {
+        self.findings.push(Finding { severity : Severity::Warning, offset, message });
+    }
+
+    fn in_file(&self, offset : u64, size : u64) -> bool {
+        offset.checked_add(size).is_some_and(|end| end <= self.buf.len() as u64)
+    }
+
+    fn header(&mut self) -> Option<Elf64Ehdr> {
+        let len = self.buf.len();
+        if len < mem::size_of::<Elf64Ehdr>() {
+            self.error(0, format!("file is {:#x} bytes, too small for an ELF header", len));
+            return None;
+        }
+        if &self.buf[..4] != b"\x7fELF" {
+            self.error(0, "bad magic, not an ELF file".to_string());
+            return None;
+        }
+        if self.buf[4] != 2 || self.buf[5] != 1 {
+            self.error(4, format!("EI_CLASS {} / EI_DATA {}, only little-endian ELF64 is checked", self.buf[4], self.buf[5]));
+            return None;
+        }
+        if self.buf[6] != 1 {
+            self.warn(6, format!("EI_VERSION is {}, expected 1", self.buf[6]));
+        }
+
+        let ehdr = Elf64Ehdr::new(self.buf);
+        if ehdr.e_ehsize as usize != mem::size_of::<Elf64Ehdr>() {
+            self.warn(0x34, format!("e_ehsize is {:#x}, expected {:#x}", ehdr.e_ehsize, mem::size_of::<Elf64Ehdr>()));
+        }
+        // counts that overflow the 16-bit fields live in section header 0
+        let phnum = ehdr.phnum(self.buf) as u64;
+        let shnum = ehdr.shnum(self.buf) as u64;
+        if phnum != 0 && ehdr.e_phentsize as usize != mem::size_of::<Elf64Phdr>() {
+            self.error(0x36, format!("e_phentsize is {:#x}, expected {:#x}", ehdr.e_phentsize, mem::size_of::<Elf64Phdr>()));
+        }
+        if shnum != 0 && ehdr.e_shentsize as usize != mem::size_of::<Elf64Shdr>() {
+            self.error(0x3a, format!("e_shentsize is {:#x}, expected {:#x}", ehdr.e_shentsize, mem::size_of::<Elf64Shdr>()));
+        }
+        match phnum.checked_mul(mem::size_of::<Elf64Phdr>() as u64) {
+            None => self.error(0x38, format!("program header count {:#x} overflows the table size", phnum)),
+            Some(size) if !self.in_file(ehdr.e_phoff, size) =>
+                self.error(0x20, format!("program header table at {:#x} ({} entries) runs past end of file", ehdr.e_phoff, phnum)),
+            _ => {}
+        }
+        match shnum.checked_mul(mem::size_of::<Elf64Shdr>() as u64) {
+            None => self.error(0x3c, format!("section header count {:#x} overflows the table size", shnum)),
+            Some(size) if !self.in_file(ehdr.e_shoff, size) =>
+                self.error(0x28, format!("section header table at {:#x} ({} entries) runs past end of file", ehdr.e_shoff, shnum)),
+            _ => {}
+        }
+        if ehdr.e_phnum == PN_XNUM && shnum == 0 {
+            self.error(0x38, "e_phnum is PN_XNUM but there is no section header 0 to hold the count".to_string());
+        }
+        Some(ehdr)
+    }
+
+    fn segments(&mut self, ehdr : &Elf64Ehdr) -> Vec<Elf64Phdr> {
+        let sz = mem::size_of::<Elf64Phdr>() as u64;
+        let phnum = ehdr.phnum(self.buf) as u64;
+        if ehdr.e_phentsize as u64 != sz || !phnum.checked_mul(sz).is_some_and(|size| self.in_file(ehdr.e_phoff, size)) {
+            return vec![];
+        }
+
+        let mut phdrs = vec![];
+        let mut prev_load : Option<(u64, u64)> = None;     /* p_vaddr, p_memsz */
+        for i in 0..phnum {
+            let off = ehdr.e_phoff + i * sz;
+            let p = Elf64Phdr::new(&self.buf[off as usize..]);
+            let name = format!("segment {} ({})", i, get_seg_type_str(p.p_type));
+
+            if p.p_filesz != 0 && !self.in_file(p.p_offset, p.p_filesz) {
+                self.error(off + 0x8, format!("{} [{:#x}, {:#x}) runs past end of file", name, p.p_offset, p.p_offset.saturating_add(p.p_filesz)));
+            }
+            if !pow2(p.p_align) {
+                self.error(off + 0x30, format!("{} p_align {:#x} is not a power of two", name, p.p_align));
+            }
+
+            if p.p_type == PT_LOAD {
+                if p.p_filesz > p.p_memsz {
+                    self.error(off + 0x20, format!("{} p_filesz {:#x} is larger than p_memsz {:#x}", name, p.p_filesz, p.p_memsz));
+                }
+                if p.p_align > 1 && pow2(p.p_align) && p.p_offset % p.p_align != p.p_vaddr % p.p_align {
+                    self.error(off + 0x8, format!("{} p_offset {:#x} and p_vaddr {:#x} disagree modulo p_align {:#x}", name, p.p_offset, p.p_vaddr, p.p_align));
+                }
+                if let Some((vaddr, memsz)) = prev_load {
+                    if p.p_vaddr < vaddr {
+                        self.error(off + 0x10, format!("{} at {:#x} is not sorted by p_vaddr after {:#x}", name, p.p_vaddr, vaddr));
+                    } else if p.p_vaddr < vaddr.saturating_add(memsz) {
+                        self.error(off + 0x10, format!("{} at {:#x} overlaps the previous PT_LOAD ending at {:#x}", name, p.p_vaddr, vaddr.saturating_add(memsz)));
+                    }
+                }
+                prev_load = Some((p.p_vaddr, p.p_memsz));
+            }
+            phdrs.push(p);
+        }
+        phdrs
+    }
+
+    fn sections(&mut self, ehdr : &Elf64Ehdr) -> Vec<Elf64Shdr> {
+        let sz = mem::size_of::<Elf64Shdr>() as u64;
+        let shnum = ehdr.shnum(self.buf) as u64;
+        if ehdr.e_shentsize as u64 != sz || !shnum.checked_mul(sz).is_some_and(|size| self.in_file(ehdr.e_shoff, size)) {
+            return vec![];
+        }
+        let shdrs : Vec<Elf64Shdr> = (0..shnum)
+            .map(|i| Elf64Shdr::new(&self.buf[(ehdr.e_shoff + i * sz) as usize..]))
+            .collect();
+        let shnum = shdrs.len();
+        let at = |i : usize| ehdr.e_shoff + i as u64 * sz;
+
+        // the name table everything else is reported against
+        let shstrndx = ehdr.shstrndx(self.buf);
+        let shstrtab = if shstrndx == SHN_UNDEF as usize {
+            self.warn(0x3e, "e_shstrndx is SHN_UNDEF, sections have no names".to_string());
+            None
+        } else if shstrndx >= shnum {
+            self.error(0x3e, format!("e_shstrndx {} is out of range ({} sections)", shstrndx, shnum));
+            None
+        } else if shdrs[shstrndx].sh_type != SHT_STRTAB {
+            self.error(0x3e, format!("e_shstrndx {} is not a SHT_STRTAB section", shstrndx));
+            None
+        } else {
+            Some(shdrs[shstrndx])
+        };
+
+        let mut ranges = vec![];
+        for (i, s) in shdrs.iter().enumerate().skip(1) {
+            let off = at(i);
+            let name = format!("section {}", i);
+
+            if let Some(strtab) = shstrtab {
+                if s.sh_name as u64 >= strtab.sh_size {
+                    self.error(off, format!("{} sh_name {:#x} is past the end of the name table", name, s.sh_name));
+                }
+            }
+            if s.sh_type != SHT_NOBITS && s.sh_size != 0 {
+                if self.in_file(s.sh_offset, s.sh_size) {
+                    ranges.push((s.sh_offset, s.sh_offset + s.sh_size, i));
+                } else {
+                    self.error(off + 0x18, format!("{} [{:#x}, {:#x}) runs past end of file", name, s.sh_offset, s.sh_offset.saturating_add(s.sh_size)));
+                }
+            }
+            if !pow2(s.sh_addralign) {
+                self.warn(off + 0x30, format!("{} sh_addralign {:#x} is not a power of two", name, s.sh_addralign));
+            } else if s.sh_addralign > 1 && s.sh_addr % s.sh_addralign != 0 {
+                self.warn(off + 0x10, format!("{} sh_addr {:#x} is not aligned to {:#x}", name, s.sh_addr, s.sh_addralign));
+            }
+
+            // sh_link must name a section of the kind the type needs
+            let link = s.sh_link as usize;
+            let wanted : &[u32] = match s.sh_type {
+                SHT_SYMTAB | SHT_DYNSYM | SHT_DYNAMIC => &[SHT_STRTAB],
+                SHT_HASH | SHT_GNU_HASH               => &[SHT_SYMTAB, SHT_DYNSYM],
+                SHT_SYMTAB_SHNDX                      => &[SHT_SYMTAB],
+                SHT_REL | SHT_RELA if link != 0       => &[SHT_SYMTAB, SHT_DYNSYM],
+                _                                     => &[],
+            };
+            if link >= shnum {
+                self.error(off + 0x28, format!("{} sh_link {} is out of range ({} sections)", name, link, shnum));
+            } else if !wanted.is_empty() && !wanted.contains(&shdrs[link].sh_type) {
+                self.error(off + 0x28, format!("{} sh_link {} points at a section of type {:#x}", name, link, shdrs[link].sh_type));
+            }
+            if s.sh_flags & SHF_INFO_LINK != 0 && s.sh_info as usize >= shnum {
+                self.error(off + 0x2c, format!("{} sh_info {} is out of range ({} sections)", name, s.sh_info, shnum));
+            }
+        }
+
+        ranges.sort();
+        for w in ranges.windows(2) {
+            let ((_, end, a), (start, _, b)) = (w[0], w[1]);
+            if start < end {
+                self.warn(at(b) + 0x18, format!("section {} at {:#x} overlaps section {} ending at {:#x}", b, start, a, end));
+            }
+        }
+        shdrs
+    }
+
+    fn string_tables(&mut self, shdrs : &[Elf64Shdr]) {
+        for (i, s) in shdrs.iter().enumerate() {
+            if s.sh_type != SHT_STRTAB || s.sh_size == 0 || !self.in_file(s.sh_offset, s.sh_size) {
+                continue;
+            }
+            let data = &self.buf[s.sh_offset as usize..(s.sh_offset + s.sh_size) as usize];
+            if data[0] != 0 {
+                self.warn(s.sh_offset, format!("string table {} does not start with NUL", i));
+            }
+            if data[data.len() - 1] != 0 {
+                self.error(s.sh_offset + s.sh_size - 1, format!("string table {} is not NUL terminated", i));
+            }
+        }
+    }
+
+    fn symbols(&mut self, ehdr : &Elf64Ehdr, shdrs : &[Elf64Shdr]) {
+        let sz = mem::size_of::<Elf64Sym>() as u64;
+        for (i, s) in shdrs.iter().enumerate() {
+            if !matches!(s.sh_type, SHT_SYMTAB | SHT_DYNSYM) || !self.in_file(s.sh_offset, s.sh_size) {
+                continue;
+            }
+            let shdr_off = ehdr.e_shoff + i as u64 * mem::size_of::<Elf64Shdr>() as u64;
+            if s.sh_entsize != sz {
+                self.error(shdr_off + 0x38, format!("symbol table {} sh_entsize is {:#x}, expected {:#x}", i, s.sh_entsize, sz));
+            }
+            if s.sh_size % sz != 0 {
+                self.warn(shdr_off + 0x20, format!("symbol table {} size {:#x} is not a multiple of {:#x}", i, s.sh_size, sz));
+            }
+            let strsz = shdrs.get(s.sh_link as usize).map(|t| t.sh_size);
+            let xindex = shdrs.iter().find(|t| t.sh_type == SHT_SYMTAB_SHNDX && t.sh_link as usize == i && self.in_file(t.sh_offset, t.sh_size));
+
+            let mut bad = 0;
+            for j in 0..s.sh_size / sz {
+                let off = s.sh_offset + j * sz;
+                let sym = Elf64Sym::new(&self.buf[off as usize..]);
+                let mut found = vec![];
+
+                if strsz.is_some_and(|n| sym.st_name as u64 >= n) {
+                    found.push((Severity::Error, off, format!("symbol {} of table {} st_name {:#x} is past its string table", j, i, sym.st_name)));
+                }
+                if sym.st_shndx < SHN_LORESERVE && sym.st_shndx as usize >= shdrs.len() {
+                    found.push((Severity::Error, off + 0x6, format!("symbol {} of table {} st_shndx {} is out of range ({} sections)", j, i, sym.st_shndx, shdrs.len())));
+                }
+                if sym.st_shndx == SHN_XINDEX {
+                    // the real index is entry j of the SHT_SYMTAB_SHNDX section linked to this table
+                    match xindex.filter(|t| (j + 1) * 4 <= t.sh_size) {
+                        Some(t) => {
+                            let at = (t.sh_offset + j * 4) as usize;
+                            let shndx = u32::from_le_bytes(self.buf[at..at + 4].try_into().unwrap()) as usize;
+                            if shndx >= shdrs.len() {
+                                found.push((Severity::Error, at as u64, format!("symbol {} of table {} extended index {} is out of range ({} sections)", j, i, shndx, shdrs.len())));
+                            }
+                        }
+                        None => found.push((Severity::Error, off + 0x6, format!("symbol {} of table {} is SHN_XINDEX but has no SHT_SYMTAB_SHNDX entry", j, i))),
+                    }
+                }
+                // sh_info is one past the last local symbol
+                let local = sym.st_bind() == STB_LOCAL;
+                if j != 0 && local != (j < s.sh_info as u64) {
+                    found.push((Severity::Warning, off + 0x4, format!("symbol {} of table {} is {} but sh_info is {}", j, i, if local { "local" } else { "global" }, s.sh_info)));
+                }
+
+                for (severity, off, message) in found {
+                    bad += 1;
+                    if bad <= MAX_SYM_FINDINGS {
+                        self.findings.push(Finding { severity, offset : off, message });
+                    }
+                }
+            }
+            if bad > MAX_SYM_FINDINGS {
+                self.warn(s.sh_offset, format!("{} more findings in symbol table {} not shown", bad - MAX_SYM_FINDINGS, i));
+            }
+        }
+    }
+
+    fn entry(&mut self, ehdr : &Elf64Ehdr, phdrs : &[Elf64Phdr]) {
+        if !matches!(ehdr.e_type, ET_EXEC | ET_DYN) || ehdr.e_entry == 0 || !phdrs.iter().any(|p| p.p_type == PT_LOAD) {
+            return;
+        }
+        let executable = phdrs.iter().any(|p| {
+            p.p_type == PT_LOAD && p.p_flags & PF_X != 0 && ehdr.e_entry >= p.p_vaddr && p.p_vaddr.checked_add(p.p_memsz).is_some_and(|end| ehdr.e_entry < end)
+        });
+        if !executable {
+            self.warn(0x18, format!("e_entry {:#x} is not inside an executable PT_LOAD", ehdr.e_entry));
+        }
+    }
+}
+
+impl Validation {
+    pub fn count(&self, severity : Severity) -> usize {
+        self.findings.iter().filter(|f| f.severity == severity).count()
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.count(Severity::Error) == 0
+    }
+
+    pub fn show(&self) -> &Self {
+        for f in &self.findings {
+            let severity = match f.severity {
+                Severity::Error   => "error".red(),
+                Severity::Warning => "warning".yellow(),
+            };
+            println!("{:<8} {:#010x}  {}", severity, f.offset, f.message);
+        }
+        println!("{} errors, {} warnings", self.count(Severity::Error), self.count(Severity::Warning));
+        self
+    }
+
+    pub fn to_json(&self) -> String {
+        let findings = self.findings.iter().map(|f| {
+            let severity = match f.severity {
+                Severity::Error   => "error",
+                Severity::Warning => "warning",
+            };
+            format!("{{\"severity\":\"{}\",\"offset\":{},\"message\":{}}}", severity, f.offset, json_str(&f.message))
+        }).collect::<Vec<String>>();
+
+        format!("{{\"valid\":{},\"errors\":{},\"warnings\":{},\"findings\":[{}]}}",
+            self.is_valid(),
+            self.count(Severity::Error),
+            self.count(Severity::Warning),
+            findings.join(","))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn errors_at(buf : &[u8]) -> Vec<u64> {
+        validate(buf).findings.iter().filter(|f| f.severity == Severity::Error).map(|f| f.offset).collect()
+    }
+
+    #[test]
+    fn test_validate_clean() {
+        for path in ["test/test", "test/testgot", "test/testrel.o", "test/testcore.core", "test/testplt_aarch64"] {
+            let v = validate(&std::fs::read(path).unwrap());
+            assert!(v.is_valid(), "{}: {:?}", path, v.findings);
+        }
+    }
+
+    #[test]
+    fn test_validate_broken() {
+        let orig = std::fs::read("test/test").unwrap();
+        let ehdr = Elf64Ehdr::new(&orig);
+
+        let mut buf = orig.clone();
+        buf[0x36] = 0x40;   /* e_phentsize */
+        assert_eq!(errors_at(&buf), [0x36]);
+
+        let mut buf = orig.clone();
+        buf[0x3e..0x40].copy_from_slice(&0x200u16.to_le_bytes());
+        assert_eq!(errors_at(&buf), [0x3e]);
+
+        // second PT_LOAD moved off its page alignment
+        let mut buf = orig.clone();
+        let phdrs : Vec<Elf64Phdr> = (0..ehdr.e_phnum as usize).map(|i| Elf64Phdr::new(&orig[ehdr.e_phoff as usize + i * 56..])).collect();
+        let i = phdrs.iter().enumerate().filter(|(_, p)| p.p_type == PT_LOAD).nth(1).unwrap().0;
+        let off = ehdr.e_phoff as usize + i * 56 + 0x8;
+        buf[off..off + 8].copy_from_slice(&(phdrs[i].p_offset + 8).to_le_bytes());
+        assert!(errors_at(&buf).contains(&(off as u64)));
+
+        // .shstrtab without its final NUL
+        let mut buf = orig.clone();
+        let shstrtab = Elf64Shdr::new(&orig[ehdr.e_shoff as usize + ehdr.e_shstrndx as usize * 64..]);
+        let last = (shstrtab.sh_offset + shstrtab.sh_size - 1) as usize;
+        buf[last] = b'x';
+        assert_eq!(errors_at(&buf), [last as u64]);
+
+        // e_shnum 0 defers to shdr0.sh_size, which is large enough to overflow the table size
+        let mut buf = orig.clone();
+        buf[0x3c..0x3e].copy_from_slice(&0u16.to_le_bytes());
+        let size = ehdr.e_shoff as usize + 0x20;
+        buf[size..size + 8].copy_from_slice(&(1u64 << 60).to_le_bytes());
+        assert_eq!(errors_at(&buf), [0x3c]);
+
+        // cut inside the section header table
+        let buf = &orig[..ehdr.e_shoff as usize + 10];
+        assert_eq!(errors_at(buf), [0x28]);
+        assert_eq!(errors_at(&orig[..16]), [0]);
+    }
+}
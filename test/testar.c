@@ -0,0 +1,16 @@
+int counter;
+
+int add(int a, int b)
+{
+    return a + b;
+}
+
+static int twice(int x)
+{
+    return add(x, x);
+}
+
+int bump(void)
+{
+    return counter = twice(counter);
+}
@@ -0,0 +1,8 @@
+extern int add(int a, int b);
+
+const char greeting[] = "hello";
+
+int sum3(int a, int b, int c)
+{
+    return add(add(a, b), c);
+}
@@ -0,0 +1,2 @@
+int depth(int n){ if(n==0){ volatile int *p=0; return *p; } return depth(n-1)+1; }
+int main(int argc, char **argv){ return depth(argc); }
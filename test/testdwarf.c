@@ -0,0 +1,15 @@
+int add(int a, int b)
+{
+    return a + b;
+}
+
+static int square(int x)
+{
+    return x * x;
+}
+
+int main()
+{
+    int s = add(1, 2);
+    return square(s);
+}
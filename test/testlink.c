@@ -0,0 +1,5 @@
+// gcc -nostdlib -no-pie -Wl,--enable-new-dtags,-rpath,'$ORIGIN' testlink.c -L. -l:libtestbase.so.1 -o testlink
+extern int base_fn(int x);
+extern void *base_alloc(int n);
+extern int base_new(void);
+void _start(void) { base_fn(base_new()); base_alloc(0); for (;;); }
@@ -0,0 +1,7 @@
+// gcc -shared -fPIC -nostdlib -Wl,-soname,libtestbase.so.1 -Wl,--version-script=testlink_base.map \
+//     -Wl,--enable-new-dtags,-rpath,'$ORIGIN' testlink_base.c -L. -ltestleaf -o libtestbase.so.1
+extern int leaf_fn(int x);
+static char heap[64];
+int base_fn(int x) { return leaf_fn(x); }
+void *base_alloc(int n) { return heap + n; }
+int base_new(void) { return 2; }
@@ -0,0 +1,2 @@
+// gcc -shared -fPIC -nostdlib -Wl,-soname,libtestleaf.so testlink_leaf.c -o libtestleaf.so
+int leaf_fn(int x) { return x + 1; }
@@ -0,0 +1,4 @@
+// gcc -shared -fPIC -nostdlib -Wl,-soname,libtestpre.so testlink_pre.c -o libtestpre.so
+// unversioned, like a malloc replacement preloaded in front of libc
+static char heap[64];
+void *base_alloc(int n) { return heap + n; }
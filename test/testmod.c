@@ -0,0 +1,47 @@
+/* stand-in for a kernel module, only the sections a .ko is inspected for */
+struct list_head { void *next, *prev; };
+
+struct module {
+    int state;
+    struct list_head list;
+    char name[56];
+    char pad0[0x100];
+    int (*init)(void);
+    char pad1[0x200];
+    void (*exit)(void);
+};
+
+static int testmod_init(void) { return 0; }
+static void testmod_exit(void) { }
+int init_module(void) __attribute__((alias("testmod_init")));
+void cleanup_module(void) __attribute__((alias("testmod_exit")));
+
+struct module __this_module __attribute__((section(".gnu.linkonce.this_module"))) = {
+    .name = "testmod",
+    .init = init_module,
+    .exit = cleanup_module,
+};
+
+static const char modinfo[] __attribute__((section(".modinfo"), used, aligned(1))) =
+    "license=GPL\0"
+    "author=ExParser\0"
+    "description=test module\0"
+    "parm=debug:enable debug output\0"
+    "parmtype=debug:int\0"
+    "alias=usb:v046DpC52Bd*dc*dsc*dp*ic*isc*ip*in*\0"
+    "alias=hid:b0003g*v0000046Dp0000C52B\0"
+    "depends=usbcore,hid\0"
+    "retpoline=Y\0"
+    "name=testmod\0"
+    "vermagic=6.1.0-18-amd64 SMP preempt mod_unload modversions ";
+
+struct modversion_info {
+    unsigned long crc;
+    char name[64 - sizeof(unsigned long)];
+};
+
+static const struct modversion_info versions[] __attribute__((section("__versions"), used, aligned(8))) = {
+    { 0x2cfde6ac, "module_layout" },
+    { 0x92997ed8, "_printk" },
+    { 0x5b8239ca, "__x86_return_thunk" },
+};
@@ -0,0 +1,29 @@
+// llvm-mc -triple aarch64 -filetype=obj testplt_aarch64.s -o testplt_aarch64.o
+// ld.lld testplt_aarch64.o libc.so.6 -o testplt_aarch64 (libc.so.6 only needs to export write)
+    .text
+    .globl  _start
+    .type   _start, %function
+_start:
+    bl      main
+    mov     x8, #93
+    svc     #0
+    .size   _start, .-_start
+
+    .globl  main
+    .type   main, %function
+main:
+    stp     x29, x30, [sp, #-16]!
+    mov     x29, sp
+    mov     x0, #1
+    adrp    x1, msg
+    add     x1, x1, :lo12:msg
+    mov     x2, #14
+    bl      write
+    mov     w0, #0
+    ldp     x29, x30, [sp], #16
+    ret
+    .size   main, .-main
+
+    .section .rodata
+msg:
+    .ascii  "Hello, World!\n"
@@ -0,0 +1,29 @@
+# llvm-mc -triple riscv64 -mattr=+c,-relax -filetype=obj testplt_riscv64.s -o testplt_riscv64.o
+# ld.lld testplt_riscv64.o libc.so.6 -o testplt_riscv64 (libc.so.6 only needs to export write)
+    .text
+    .globl  _start
+    .type   _start, @function
+_start:
+    call    main
+    li      a7, 93
+    ecall
+    .size   _start, .-_start
+
+    .globl  main
+    .type   main, @function
+main:
+    addi    sp, sp, -16
+    sd      ra, 8(sp)
+    li      a0, 1
+    lla     a1, msg
+    li      a2, 14
+    call    write
+    li      a0, 0
+    ld      ra, 8(sp)
+    addi    sp, sp, 16
+    ret
+    .size   main, .-main
+
+    .section .rodata
+msg:
+    .ascii  "Hello, World!\n"
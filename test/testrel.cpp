@@ -0,0 +1,4 @@
+template<typename T> T tw(T x){ return x*2; }
+inline int il(int a){ static int n; return a+n++; }
+extern int ext(int);
+int f(int a){ return tw(a)+il(a)+ext(a)+tw<long>(a); }
@@ -0,0 +1,8 @@
+// gcc -c -O1 -fPIC test/testtls.c -o test/testtls.o
+// one access per TLS model, each leaves its own relocations in .rela.text
+extern __thread int gd;
+static __thread int ld __attribute__((tls_model("local-dynamic")));
+extern __thread int ie __attribute__((tls_model("initial-exec")));
+__thread int le __attribute__((tls_model("local-exec")));
+void set(int v){ ld = v; }
+int get(void){ return gd + ld + ie + le; }